@@ -0,0 +1,50 @@
+//! Session ID comparison throughput, as exercised by sorted routing tables.
+//!
+//! Run with `cargo bench --bench compare`.
+use std::hint::black_box;
+use std::time::Instant;
+use verse_session_id::{new_session_id_pair, ISessionIdPair, SessionId};
+
+const ROUNDS: u32 = 20;
+
+fn bench(name: &str, mut f: impl FnMut()) {
+    f();
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        f();
+    }
+    println!("{:<24} {:>10.2?}/iter", name, start.elapsed() / ROUNDS);
+}
+
+fn main() {
+    let ids: Vec<SessionId> = (0..10_000)
+        .map(|_| new_session_id_pair().unwrap().get_id())
+        .collect();
+    let raw: Vec<Vec<u8>> = ids.iter().map(|id| id.to_vec()).collect();
+
+    bench("sort by Ord", || {
+        let mut v = ids.clone();
+        v.sort_unstable();
+        black_box(v);
+    });
+    bench("sort by cmp_slice", || {
+        let mut v = ids.clone();
+        v.sort_unstable_by(|a, b| a.cmp_slice(b));
+        black_box(v);
+    });
+    bench("eq_slice against raw", || {
+        let n = ids
+            .iter()
+            .zip(raw.iter().rev())
+            .filter(|(id, raw)| id.eq_slice(raw))
+            .count();
+        black_box(n);
+    });
+    let mut sorted = ids.clone();
+    sorted.sort_unstable();
+    bench("binary search", || {
+        for id in &ids {
+            black_box(sorted.binary_search_by(|probe| probe.cmp_slice(id)).is_ok());
+        }
+    });
+}
@@ -0,0 +1,67 @@
+/* C interface of verse-session-id. Build the library with
+ * `cargo rustc --release --features ffi --crate-type cdylib` (or `staticlib`). */
+#ifndef VERSE_SESSION_ID_H
+#define VERSE_SESSION_ID_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define VERSE_OK 0
+#define VERSE_SESSION_ID_STR_SIZE 45
+#define VERSE_SIGNATURE_SET_STR_SIZE 97
+#define VERSE_SESSION_ID_PAIR_SIZE 64
+
+/* Error codes (see SessionIdError::code) */
+#define VERSE_ERR_MALFORMED_PUBLIC_KEY 101
+#define VERSE_ERR_MALFORMED_SIGNATURE 102
+#define VERSE_ERR_VERIFICATION_FAILED 104
+#define VERSE_ERR_SIGNING_FAILED 105
+#define VERSE_ERR_INVALID_LENGTH 201
+#define VERSE_ERR_BASE64 202
+#define VERSE_ERR_INVALID_FORMAT 203
+#define VERSE_ERR_RANDOM 301
+#define VERSE_ERR_REQUIRED 401
+#define VERSE_ERR_OUT_OF_RANGE 402
+#define VERSE_ERR_INVALID_ARGUMENT 403
+#define VERSE_ERR_MERKLE_PROOF 501
+#define VERSE_ERR_EXPIRED 601
+#define VERSE_ERR_NOT_YET_VALID 602
+#define VERSE_ERR_INVALID_CLAIM 603
+#define VERSE_ERR_REPLAYED 604
+#define VERSE_ERR_KEYSTORE 701
+#define VERSE_ERR_DECRYPTION 801
+
+typedef struct VerseSessionId {
+  uint8_t bytes[32];
+} VerseSessionId;
+
+/* Secret key followed by public key. Treat as secret. */
+typedef struct VerseSessionIdPair {
+  uint8_t bytes[VERSE_SESSION_ID_PAIR_SIZE];
+} VerseSessionIdPair;
+
+typedef struct VerseSignatureSet {
+  uint8_t signature[64];
+  uint8_t salt[8];
+} VerseSignatureSet;
+
+uint32_t verse_session_id_pair_generate(VerseSessionIdPair *out);
+uint32_t verse_session_id_pair_get_id(const VerseSessionIdPair *pair, VerseSessionId *out);
+uint32_t verse_session_id_pair_sign(const VerseSessionIdPair *pair, const uint8_t *data,
+                                    size_t data_len, VerseSignatureSet *out);
+uint32_t verse_session_id_verify(const VerseSessionId *session_id, const uint8_t *data,
+                                 size_t data_len, const VerseSignatureSet *sigset);
+uint32_t verse_session_id_from_str(const char *s, VerseSessionId *out);
+uint32_t verse_session_id_to_str(const VerseSessionId *session_id, char *buf, size_t buf_len);
+uint32_t verse_signature_set_from_str(const char *s, VerseSignatureSet *out);
+uint32_t verse_signature_set_to_str(const VerseSignatureSet *sigset, char *buf, size_t buf_len);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* VERSE_SESSION_ID_H */
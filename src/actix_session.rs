@@ -0,0 +1,134 @@
+//! actix-web integration. Enabled with the `actix` feature.
+//!
+//! [`AuthenticatedSessionId`] checks the headers added by [`sign_body`](crate::sign_body)
+//! against the method, path, query and body of the request and rejects with 401 on failure.
+//!
+//! ```rust,ignore
+//! #[post("/")]
+//! async fn index(auth: AuthenticatedSessionId) -> String {
+//!     auth.session_id.to_string()
+//! }
+//! ```
+use crate::{
+    verify_body, SessionId, SignedRequest, VERSE_CREATED_HEADER, VERSE_SESSION_HEADER,
+    VERSE_SIGNATURE_HEADER,
+};
+use actix_web::dev::Payload;
+use actix_web::error::ErrorUnauthorized;
+use actix_web::web::Bytes;
+use actix_web::{FromRequest, HttpRequest};
+use std::future::Future;
+use std::pin::Pin;
+
+/// Signer and body of a request signed with [`sign_body`](crate::sign_body).
+/// The body is consumed by the extractor, so it is returned here.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AuthenticatedSessionId {
+    pub session_id: SessionId,
+    pub body: Bytes,
+}
+
+fn header(req: &HttpRequest, name: &str) -> Option<String> {
+    req.headers()
+        .get(name)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.to_string())
+}
+
+impl FromRequest for AuthenticatedSessionId {
+    type Error = actix_web::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self, Self::Error>>>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let session = header(req, VERSE_SESSION_HEADER);
+        let signature = header(req, VERSE_SIGNATURE_HEADER);
+        let created = header(req, VERSE_CREATED_HEADER);
+        let method = req.method().clone();
+        let path_and_query = req
+            .uri()
+            .path_and_query()
+            .map_or("/", |v| v.as_str())
+            .to_string();
+        // limited by `web::PayloadConfig`
+        let body = Bytes::from_request(req, payload);
+        Box::pin(async move {
+            let (Some(session), Some(signature), Some(created)) = (session, signature, created)
+            else {
+                return Err(ErrorUnauthorized("missing session headers"));
+            };
+            let body = body.await?;
+            let request = SignedRequest {
+                method: method.as_str(),
+                path_and_query: &path_and_query,
+                body: &body,
+            };
+            let session_id =
+                verify_body(&session, &signature, &created, &request).map_err(ErrorUnauthorized)?;
+            Ok(AuthenticatedSessionId { session_id, body })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{new_session_id_pair, sign_body, sign_body_at, ISessionIdPair, ManualClock};
+    use actix_web::http::StatusCode;
+    use actix_web::test::TestRequest;
+
+    async fn extract(req: TestRequest) -> Result<AuthenticatedSessionId, actix_web::Error> {
+        let (req, mut payload) = req.to_http_parts();
+        AuthenticatedSessionId::from_request(&req, &mut payload).await
+    }
+
+    #[tokio::test]
+    async fn test_actix_authenticated_session_id() {
+        let kp = new_session_id_pair().unwrap();
+        let request = SignedRequest {
+            method: "POST",
+            path_and_query: "/rooms?id=1",
+            body: b"hello",
+        };
+        let headers = sign_body(&kp, &request).unwrap();
+        let signed = |uri: &str, body: &'static str| {
+            TestRequest::post()
+                .uri(uri)
+                .insert_header((VERSE_SESSION_HEADER, headers.session.as_str()))
+                .insert_header((VERSE_SIGNATURE_HEADER, headers.signature.as_str()))
+                .insert_header((VERSE_CREATED_HEADER, headers.created.as_str()))
+                .set_payload(body)
+        };
+
+        let auth = extract(signed("/rooms?id=1", "hello")).await.unwrap();
+        assert_eq!(auth.session_id, kp.get_id());
+        assert_eq!(auth.body, "hello");
+
+        for req in [
+            signed("/rooms?id=1", "hellp"),
+            signed("/rooms?id=2", "hello"),
+            signed("/rooms?id=1", "hello").method(actix_web::http::Method::PUT),
+        ] {
+            let err = extract(req).await.unwrap_err();
+            assert_eq!(
+                err.as_response_error().status_code(),
+                StatusCode::UNAUTHORIZED
+            );
+        }
+
+        let stale = sign_body_at(&kp, &request, &ManualClock::new(1_700_000_000)).unwrap();
+        let req = TestRequest::post()
+            .uri("/rooms?id=1")
+            .insert_header((VERSE_SESSION_HEADER, stale.session))
+            .insert_header((VERSE_SIGNATURE_HEADER, stale.signature))
+            .insert_header((VERSE_CREATED_HEADER, stale.created))
+            .set_payload("hello");
+        assert!(extract(req).await.is_err());
+        let err = extract(TestRequest::post().set_payload("hello"))
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.as_response_error().status_code(),
+            StatusCode::UNAUTHORIZED
+        );
+    }
+}
@@ -0,0 +1,76 @@
+//! age (age-encryption.org/v1) X25519 keys. Enabled with the `age` feature.
+//!
+//! The recipient is the bech32 `age1...` form of [`SessionId::to_x25519_public`] and the
+//! identity is the `AGE-SECRET-KEY-1...` form of the matching X25519 secret, so files
+//! encrypted to a SessionId can be decrypted with `age -d -i` or `rage`.
+use crate::errors::{Result, SessionIdError};
+use crate::{SessionId, SessionIdPair, X25519KeyAgreement};
+use bech32::{Bech32, Hrp};
+use zeroize::Zeroizing;
+
+const RECIPIENT_HRP: &str = "age";
+const IDENTITY_HRP: &str = "age-secret-key-";
+
+fn age_error(e: impl std::fmt::Display) -> SessionIdError {
+    SessionIdError::InvalidFormat(format!("age: {}", e))
+}
+
+fn encode(hrp: &str, data: &[u8]) -> Result<String> {
+    let hrp = Hrp::parse(hrp).map_err(age_error)?;
+    bech32::encode::<Bech32>(hrp, data).map_err(age_error)
+}
+
+impl SessionId {
+    /// age X25519 recipient (`age1...`)
+    pub fn to_age_recipient(&self) -> Result<String> {
+        encode(RECIPIENT_HRP, &self.to_x25519_public()?)
+    }
+}
+
+/// Export of a SessionIdPair as an age identity
+pub trait AgeIdentity {
+    /// age X25519 identity (`AGE-SECRET-KEY-1...`). Anyone holding it can decrypt files
+    /// encrypted to this session ID.
+    fn to_age_identity(&self) -> Result<String>;
+}
+
+impl AgeIdentity for SessionIdPair {
+    fn to_age_identity(&self) -> Result<String> {
+        let secret = Zeroizing::new(self.to_x25519_secret());
+        let identity = Zeroizing::new(encode(IDENTITY_HRP, &secret[..])?);
+        Ok(identity.to_uppercase())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{new_session_id_pair, ISessionIdPair};
+    use curve25519_dalek::constants::X25519_BASEPOINT;
+    use curve25519_dalek::scalar::Scalar;
+
+    #[test]
+    fn test_age_keys() {
+        let pair = new_session_id_pair().unwrap();
+        let recipient = pair.get_id().to_age_recipient().unwrap();
+        assert!(recipient.starts_with("age1"));
+        assert_eq!(recipient.len(), 62);
+        let identity = pair.to_age_identity().unwrap();
+        assert!(identity.starts_with("AGE-SECRET-KEY-1"));
+        assert_eq!(identity.len(), 74);
+
+        // the identity's public key is the recipient
+        let (hrp, secret) = bech32::decode(&identity).unwrap();
+        assert_eq!(hrp.to_lowercase(), IDENTITY_HRP);
+        let secret: [u8; 32] = secret.try_into().unwrap();
+        let public = (X25519_BASEPOINT * Scalar::from_bits(secret)).to_bytes();
+        let (hrp, decoded) = bech32::decode(&recipient).unwrap();
+        assert_eq!(hrp.as_str(), RECIPIENT_HRP);
+        assert_eq!(decoded, public);
+
+        // not a curve point
+        let mut invalid = [0u8; 32];
+        invalid[0] = 2;
+        assert!(SessionId::from(invalid).to_age_recipient().is_err());
+    }
+}
@@ -0,0 +1,71 @@
+//! Fixed-size string buffers for formatting without allocation.
+use std::fmt;
+use std::ops::Deref;
+
+/// Characters of a base64 SessionId
+pub const SESSION_ID_BASE64_SIZE: usize = 44;
+/// Characters of a base64 SignatureSet
+pub const SIGNATURE_SET_BASE64_SIZE: usize = 96;
+
+/// ASCII string stored inline in `N` bytes
+#[derive(Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct ArrayString<const N: usize>([u8; N]);
+
+impl<const N: usize> ArrayString<N> {
+    /// Standard base64 of `bytes`, which must encode to exactly `N` characters
+    pub(crate) fn base64(bytes: &[u8]) -> Self {
+        let mut buf = [0u8; N];
+        let n = base64::encode_config_slice(bytes, base64::STANDARD, &mut buf);
+        debug_assert_eq!(n, N);
+        ArrayString(buf)
+    }
+    pub fn as_str(&self) -> &str {
+        // only constructed from base64 output
+        std::str::from_utf8(&self.0).unwrap_or_default()
+    }
+}
+
+impl<const N: usize> Deref for ArrayString<N> {
+    type Target = str;
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+impl<const N: usize> AsRef<str> for ArrayString<N> {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+impl<const N: usize> fmt::Display for ArrayString<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(self.as_str())
+    }
+}
+impl<const N: usize> fmt::Debug for ArrayString<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+impl<const N: usize> PartialEq<str> for ArrayString<N> {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+impl<const N: usize> PartialEq<&str> for ArrayString<N> {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_array_string() {
+        let s = ArrayString::<8>::base64(b"hello");
+        assert_eq!(s, "aGVsbG8=");
+        assert_eq!(s.len(), 8);
+        assert_eq!(format!("{:>10}|{:?}", s, s), "  aGVsbG8=|\"aGVsbG8=\"");
+    }
+}
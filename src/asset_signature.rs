@@ -0,0 +1,161 @@
+//! Provenance signatures for user-generated assets.
+//!
+//! The author signs the SHA-256 of the asset content together with its URI and license,
+//! and consuming clients check the signature against the bytes they downloaded.
+use crate::base64url;
+use crate::encoding::{write_str, Reader};
+use crate::errors::{Result, SessionIdError, SignatureErrorKind};
+use crate::{
+    ISessionIdPair, SessionId, SessionIdPair, SessionIdPublic, SignatureSet, SESSION_ID_SIZE,
+    SIGNATURE_SET_SIZE,
+};
+use ed25519_dalek::Digest;
+use sha2::Sha256;
+use std::fmt;
+
+const ASSET_CONTEXT: &[u8] = b"verse-session-id/asset/v1";
+const ASSET_VERSION: u8 = 1;
+
+/// Authorship signature of an asset
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AssetSignature {
+    pub author: SessionId,
+    /// SHA-256 of the asset content
+    pub content_hash: [u8; 32],
+    pub uri: String,
+    /// e.g. an SPDX identifier such as `CC-BY-4.0`
+    pub license: String,
+    pub signature: SignatureSet,
+}
+
+fn content_hash(content: &[u8]) -> [u8; 32] {
+    let mut v = [0u8; 32];
+    v.copy_from_slice(&Sha256::digest(content));
+    v
+}
+
+fn payload(content_hash: &[u8; 32], uri: &str, license: &str) -> Result<Vec<u8>> {
+    let mut buf = content_hash.to_vec();
+    write_str(&mut buf, uri)?;
+    write_str(&mut buf, license)?;
+    Ok(buf)
+}
+
+impl AssetSignature {
+    /// Sign `content` published at `uri` under `license`
+    pub fn sign(pair: &SessionIdPair, content: &[u8], uri: &str, license: &str) -> Result<Self> {
+        Self::sign_hash(pair, content_hash(content), uri, license)
+    }
+    /// Sign an asset by its SHA-256, for assets too large to hold in memory
+    pub fn sign_hash(
+        pair: &SessionIdPair,
+        content_hash: [u8; 32],
+        uri: &str,
+        license: &str,
+    ) -> Result<Self> {
+        let signature = pair.sign([ASSET_CONTEXT, &payload(&content_hash, uri, license)?])?;
+        Ok(AssetSignature {
+            author: pair.get_id(),
+            content_hash,
+            uri: uri.to_string(),
+            license: license.to_string(),
+            signature,
+        })
+    }
+    /// Check the signature and that it covers `content`
+    pub fn verify(&self, content: &[u8]) -> Result<()> {
+        self.verify_hash(&content_hash(content))
+    }
+    /// Check the signature and that it covers content with SHA-256 `content_hash`
+    pub fn verify_hash(&self, content_hash: &[u8; 32]) -> Result<()> {
+        if *content_hash != self.content_hash {
+            return Err(SessionIdError::Signature(
+                SignatureErrorKind::VerificationFailed,
+            ));
+        }
+        self.author.verify(
+            [
+                ASSET_CONTEXT,
+                &payload(&self.content_hash, &self.uri, &self.license)?,
+            ],
+            &self.signature,
+        )
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let mut buf = vec![ASSET_VERSION];
+        buf.extend_from_slice(self.author.as_ref());
+        buf.extend_from_slice(&payload(&self.content_hash, &self.uri, &self.license)?);
+        buf.extend_from_slice(&self.signature.to_bytes());
+        Ok(buf)
+    }
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let mut r = Reader(bytes);
+        if r.read(1)?[0] != ASSET_VERSION {
+            return Err(SessionIdError::InvalidFormat(
+                "asset: unsupported version".to_string(),
+            ));
+        }
+        let author = SessionId::from(r.read_array::<SESSION_ID_SIZE>()?);
+        let content_hash = r.read_array()?;
+        let uri = r.read_str()?;
+        let license = r.read_str()?;
+        let signature = SignatureSet::from_bytes(&r.read_array::<SIGNATURE_SET_SIZE>()?);
+        if !r.0.is_empty() {
+            return Err(SessionIdError::InvalidFormat(
+                "asset: trailing bytes".to_string(),
+            ));
+        }
+        Ok(AssetSignature {
+            author,
+            content_hash,
+            uri,
+            license,
+            signature,
+        })
+    }
+}
+
+impl fmt::Display for AssetSignature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let bytes = self.to_bytes().map_err(|_| fmt::Error)?;
+        write!(f, "{}", base64url::encode(bytes))
+    }
+}
+impl std::str::FromStr for AssetSignature {
+    type Err = SessionIdError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_bytes(&base64url::decode(s)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::new_session_id_pair;
+
+    #[test]
+    fn test_asset_signature() {
+        let pair = new_session_id_pair().unwrap();
+        let content = b"glTF binary";
+        let sig =
+            AssetSignature::sign(&pair, content, "https://example.com/a.glb", "CC-BY-4.0").unwrap();
+        assert_eq!(sig.author, pair.get_id());
+        assert!(sig.verify(content).is_ok());
+        assert!(sig.verify(b"other").is_err());
+
+        let decoded: AssetSignature = sig.to_string().parse().unwrap();
+        assert_eq!(decoded, sig);
+        assert!(decoded.verify(content).is_ok());
+
+        let mut relicensed = sig.clone();
+        relicensed.license = "CC0-1.0".to_string();
+        assert!(relicensed.verify(content).is_err());
+        let mut moved = sig.clone();
+        moved.uri = "https://evil.example/a.glb".to_string();
+        assert!(moved.verify(content).is_err());
+        let mut stolen = sig;
+        stolen.author = new_session_id_pair().unwrap().get_id();
+        assert!(stolen.verify(content).is_err());
+    }
+}
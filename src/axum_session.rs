@@ -0,0 +1,156 @@
+//! axum integration. Enabled with the `axum` feature.
+//!
+//! [`verify_session`] checks the headers added by [`sign_body`](crate::sign_body) against
+//! the method, path, query and body of the request and rejects with 401 on failure.
+//! Handlers behind it can extract the signer as [`VerifiedSession`].
+//! Apply it to the outermost router: inside `Router::nest` the path is stripped of the
+//! nesting prefix and no longer matches the signed one.
+//!
+//! ```rust,ignore
+//! let app = Router::new()
+//!     .route("/", post(|VerifiedSession(sid): VerifiedSession| async move { sid.to_string() }))
+//!     .layer(axum::middleware::from_fn(verify_session));
+//! ```
+use crate::{
+    verify_body, SessionId, SignedRequest, MAX_SIGNED_BODY_SIZE, VERSE_CREATED_HEADER,
+    VERSE_SESSION_HEADER, VERSE_SIGNATURE_HEADER,
+};
+use axum::body::{to_bytes, Body};
+use axum::extract::{FromRequestParts, Request};
+use axum::http::request::Parts;
+use axum::http::{HeaderMap, StatusCode};
+use axum::middleware::Next;
+use axum::response::Response;
+
+/// SessionId of a request verified by [`verify_session`]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct VerifiedSession(pub SessionId);
+
+impl<S: Send + Sync> FromRequestParts<S> for VerifiedSession {
+    type Rejection = StatusCode;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        parts
+            .extensions
+            .get::<VerifiedSession>()
+            .copied()
+            .ok_or(StatusCode::UNAUTHORIZED)
+    }
+}
+
+fn header<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers.get(name).and_then(|v| v.to_str().ok())
+}
+
+/// Middleware verifying the signed request. Use with `axum::middleware::from_fn`.
+pub async fn verify_session(request: Request, next: Next) -> Result<Response, StatusCode> {
+    let (mut parts, body) = request.into_parts();
+    let (Some(session), Some(signature), Some(created)) = (
+        header(&parts.headers, VERSE_SESSION_HEADER),
+        header(&parts.headers, VERSE_SIGNATURE_HEADER),
+        header(&parts.headers, VERSE_CREATED_HEADER),
+    ) else {
+        return Err(StatusCode::UNAUTHORIZED);
+    };
+    let body = to_bytes(body, MAX_SIGNED_BODY_SIZE)
+        .await
+        .map_err(|_| StatusCode::PAYLOAD_TOO_LARGE)?;
+    let request = SignedRequest {
+        method: parts.method.as_str(),
+        path_and_query: parts.uri.path_and_query().map_or("/", |v| v.as_str()),
+        body: &body,
+    };
+    let session_id =
+        verify_body(session, signature, created, &request).map_err(|_| StatusCode::UNAUTHORIZED)?;
+    parts.extensions.insert(VerifiedSession(session_id));
+    Ok(next.run(Request::from_parts(parts, Body::from(body))).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        new_session_id_pair, sign_body, sign_body_at, ISessionIdPair, ManualClock,
+        SignedBodyHeaders,
+    };
+    use axum::routing::post;
+    use axum::Router;
+    use tower::ServiceExt;
+
+    fn app() -> Router {
+        Router::new()
+            .route(
+                "/",
+                post(
+                    |VerifiedSession(sid): VerifiedSession, body: String| async move {
+                        format!("{}:{}", sid, body)
+                    },
+                ),
+            )
+            .layer(axum::middleware::from_fn(verify_session))
+    }
+
+    fn request(headers: &SignedBodyHeaders, uri: &str, body: &'static str) -> Request {
+        Request::post(uri)
+            .header(VERSE_SESSION_HEADER, &headers.session)
+            .header(VERSE_SIGNATURE_HEADER, &headers.signature)
+            .header(VERSE_CREATED_HEADER, &headers.created)
+            .body(Body::from(body))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_axum_verify_session() {
+        let kp = new_session_id_pair().unwrap();
+        let signed = SignedRequest {
+            method: "POST",
+            path_and_query: "/",
+            body: b"hello",
+        };
+        let headers = sign_body(&kp, &signed).unwrap();
+
+        let res = app()
+            .oneshot(request(&headers, "/", "hello"))
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(body, format!("{}:hello", kp.get_id()));
+
+        let res = app()
+            .oneshot(request(&headers, "/", "hellp"))
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+
+        // replayed against another path
+        let res = app()
+            .oneshot(request(&headers, "/?admin=1", "hello"))
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+
+        let stale = sign_body_at(&kp, &signed, &ManualClock::new(1_700_000_000)).unwrap();
+        let res = app().oneshot(request(&stale, "/", "hello")).await.unwrap();
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+
+        let res = app()
+            .oneshot(Request::post("/").body(Body::from("hello")).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_axum_extractor_without_middleware() {
+        let app = Router::new().route(
+            "/",
+            post(|VerifiedSession(sid): VerifiedSession| async move { sid.to_string() }),
+        );
+        let res = app
+            .oneshot(Request::post("/").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+    }
+}
@@ -0,0 +1,46 @@
+//! RFC 4648 base32 without padding, used by the strkey and onion address formats.
+use crate::errors::{Result, SessionIdError};
+
+const ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+/// Upper case
+pub(crate) fn encode(v: impl AsRef<[u8]>) -> String {
+    let v = v.as_ref();
+    let mut s = String::with_capacity((v.len() * 8).div_ceil(5));
+    let (mut buf, mut bits) = (0u16, 0);
+    for b in v {
+        buf = (buf << 8) | *b as u16;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            s.push(ALPHABET[((buf >> bits) & 0x1f) as usize] as char);
+        }
+    }
+    if bits > 0 {
+        s.push(ALPHABET[((buf << (5 - bits)) & 0x1f) as usize] as char);
+    }
+    s
+}
+
+/// Upper case only. Leftover bits must be zero.
+pub(crate) fn decode(s: &str) -> Result<Vec<u8>> {
+    let error = |msg: &str| SessionIdError::InvalidFormat(format!("base32: {}", msg));
+    let mut v = Vec::with_capacity(s.len() * 5 / 8);
+    let (mut buf, mut bits) = (0u16, 0);
+    for c in s.bytes() {
+        let digit = ALPHABET
+            .iter()
+            .position(|d| *d == c)
+            .ok_or_else(|| error("invalid character"))?;
+        buf = (buf << 5) | digit as u16;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            v.push((buf >> bits) as u8);
+        }
+    }
+    if bits >= 5 || buf & ((1 << bits) - 1) != 0 {
+        return Err(error("invalid length"));
+    }
+    Ok(v)
+}
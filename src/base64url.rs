@@ -0,0 +1,37 @@
+//! base64url without padding (RFC 4648 §5), used by the token formats.
+use crate::errors::Result;
+
+pub(crate) fn encode(v: impl AsRef<[u8]>) -> String {
+    base64::encode_config(v, base64::URL_SAFE_NO_PAD)
+}
+
+pub(crate) fn decode(s: &str) -> Result<Vec<u8>> {
+    Ok(base64::decode_config(s, base64::URL_SAFE_NO_PAD)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_base64url() {
+        // RFC 4648 §10, without padding
+        for (raw, encoded) in [
+            ("", ""),
+            ("f", "Zg"),
+            ("fo", "Zm8"),
+            ("foo", "Zm9v"),
+            ("foob", "Zm9vYg"),
+            ("fooba", "Zm9vYmE"),
+            ("foobar", "Zm9vYmFy"),
+        ] {
+            assert_eq!(encode(raw), encoded);
+            assert_eq!(decode(encoded).unwrap(), raw.as_bytes());
+        }
+        // url-safe alphabet
+        assert_eq!(encode([0xfb, 0xff]), "-_8");
+        assert_eq!(decode("-_8").unwrap(), [0xfb, 0xff]);
+        assert!(decode("+/8").is_err());
+        assert!(decode("Zm9v!").is_err());
+    }
+}
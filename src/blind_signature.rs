@@ -0,0 +1,316 @@
+//! Clause blind Schnorr signatures for anonymous tickets.
+//!
+//! The issuer signs a message without seeing it. Signing uses a dedicated [`BlindSigner`] key,
+//! derived from the session key with its own context or given explicitly, never the session
+//! key itself, and the challenge is domain-separated, so a blind signature never verifies as
+//! an Ed25519 signature by any key.
+//!
+//! 1. issuer: [`BlindSigner::commit`] and send the two commitments
+//! 2. user: [`blind`] the message and send the two blinded challenges
+//! 3. issuer: [`BlindSigner::sign_blinded`] and send the response for one random clause
+//! 4. user: [`unblind`] to get the signature, checked with [`verify_blind_signature`]
+//!
+//! Plain blind Schnorr signatures are broken by the ROS attack when an issuer runs sessions
+//! concurrently. In the clause variant (Fuchsbauer and Wolf, "Concurrently Secure Blind
+//! Schnorr Signatures", Eurocrypt 2024) the issuer answers only one of two challenges,
+//! chosen at random, which defeats the attack, so any number of sessions may be open at
+//! once with the same key. Each [`BlindSigningSession`] is consumed by its response.
+//! Signatures are ordinary Schnorr signatures under the blind public key.
+use crate::errors::{Result, SessionIdError, SignatureErrorKind};
+use crate::SessionIdPair;
+use curve25519_dalek::constants::ED25519_BASEPOINT_POINT;
+use curve25519_dalek::edwards::{CompressedEdwardsY, EdwardsPoint};
+use curve25519_dalek::scalar::Scalar;
+use ed25519_dalek::{Digest, Sha512};
+use zeroize::Zeroize;
+
+const BLIND_KEY_CONTEXT: &[u8] = b"verse-session-id/blind-key/v1";
+const BLIND_SIGNATURE_CONTEXT: &[u8] = b"verse-session-id/blind-signature/v1";
+/// Size of a public key, commitment, challenge or response element
+pub const BLIND_ELEMENT_SIZE: usize = 32;
+/// Size of the two commitments of step 1 and the two challenges of step 2
+pub const BLIND_PAIR_SIZE: usize = 2 * BLIND_ELEMENT_SIZE;
+/// Size of a response: the index of the answered clause and the response element
+pub const BLIND_RESPONSE_SIZE: usize = 1 + BLIND_ELEMENT_SIZE;
+/// Size of an unblinded signature
+pub const BLIND_SIGNATURE_SIZE: usize = 64;
+
+/// Public key of a [`BlindSigner`]. It is not a SessionId; an issuer publishes it, e.g.
+/// signed with its session key.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct BlindPublicKey(pub [u8; BLIND_ELEMENT_SIZE]);
+
+/// Issuer key. Signing sessions are independent and may run concurrently.
+pub struct BlindSigner {
+    key: Scalar,
+    public: BlindPublicKey,
+}
+
+impl Drop for BlindSigner {
+    fn drop(&mut self) {
+        self.key.zeroize();
+    }
+}
+
+/// Issuer state between [`BlindSigner::commit`] and [`BlindSigner::sign_blinded`].
+/// Dropping it aborts the session.
+pub struct BlindSigningSession {
+    nonces: [Scalar; 2],
+}
+
+impl Drop for BlindSigningSession {
+    fn drop(&mut self) {
+        self.nonces.iter_mut().for_each(Zeroize::zeroize);
+    }
+}
+
+/// User state between [`blind`] and [`unblind`]
+pub struct BlindingState {
+    clauses: [(Scalar, CompressedEdwardsY); 2],
+    issuer: BlindPublicKey,
+    message: Vec<u8>,
+}
+
+fn random_scalar() -> Result<Scalar> {
+    let mut v = [0u8; 64];
+    getrandom::getrandom(&mut v)?;
+    let s = Scalar::from_bytes_mod_order_wide(&v);
+    v.zeroize();
+    Ok(s)
+}
+
+fn decompress(bytes: &[u8], kind: SignatureErrorKind) -> Result<EdwardsPoint> {
+    CompressedEdwardsY::from_slice(bytes)
+        .decompress()
+        .filter(|p| !p.is_small_order())
+        .ok_or(SessionIdError::Signature(kind))
+}
+
+fn scalar(bytes: &[u8; BLIND_ELEMENT_SIZE]) -> Result<Scalar> {
+    Scalar::from_canonical_bytes(*bytes).ok_or(SessionIdError::Signature(
+        SignatureErrorKind::MalformedSignature,
+    ))
+}
+
+fn challenge(r: &CompressedEdwardsY, issuer: &BlindPublicKey, message: &[u8]) -> Scalar {
+    let hash = Sha512::new()
+        .chain(BLIND_SIGNATURE_CONTEXT)
+        .chain(r.as_bytes())
+        .chain(issuer.0)
+        .chain(message)
+        .finalize();
+    let mut wide = [0u8; 64];
+    wide.copy_from_slice(&hash);
+    Scalar::from_bytes_mod_order_wide(&wide)
+}
+
+impl BlindSigner {
+    /// Blind signing key derived from the session key with a dedicated context
+    pub fn derive(pair: &SessionIdPair) -> Self {
+        let mut hash = Sha512::new()
+            .chain(BLIND_KEY_CONTEXT)
+            .chain(pair.secret.as_bytes())
+            .finalize();
+        let mut wide = [0u8; 64];
+        wide.copy_from_slice(&hash);
+        let key = Scalar::from_bytes_mod_order_wide(&wide);
+        wide.zeroize();
+        hash[..].zeroize();
+        Self::with_scalar(key)
+    }
+    /// Blind signing key from 32 secret bytes, e.g. generated and stored separately
+    pub fn from_secret(secret: &[u8; 32]) -> Result<Self> {
+        let key = Scalar::from_bytes_mod_order(*secret);
+        if key == Scalar::zero() {
+            return Err(SessionIdError::InvalidArgument("zero blind signing key"));
+        }
+        Ok(Self::with_scalar(key))
+    }
+    fn with_scalar(key: Scalar) -> Self {
+        BlindSigner {
+            public: BlindPublicKey((key * ED25519_BASEPOINT_POINT).compress().to_bytes()),
+            key,
+        }
+    }
+    pub fn public_key(&self) -> BlindPublicKey {
+        self.public
+    }
+    /// Step 1: start a signing session. Returns the session to keep and the two
+    /// commitments to send.
+    pub fn commit(&self) -> Result<(BlindSigningSession, [u8; BLIND_PAIR_SIZE])> {
+        let session = BlindSigningSession {
+            nonces: [random_scalar()?, random_scalar()?],
+        };
+        let mut commitments = [0u8; BLIND_PAIR_SIZE];
+        for (k, out) in session.nonces.iter().zip(commitments.chunks_mut(32)) {
+            out.copy_from_slice((k * ED25519_BASEPOINT_POINT).compress().as_bytes());
+        }
+        Ok((session, commitments))
+    }
+    /// Step 3: respond to the blinded challenges of `session` for one random clause
+    pub fn sign_blinded(
+        &self,
+        session: BlindSigningSession,
+        blinded_challenges: &[u8; BLIND_PAIR_SIZE],
+    ) -> Result<[u8; BLIND_RESPONSE_SIZE]> {
+        let mut bit = [0u8; 1];
+        getrandom::getrandom(&mut bit)?;
+        let clause = (bit[0] & 1) as usize;
+        let c = scalar(blinded_challenges[clause * 32..][..32].try_into().unwrap())?;
+        let mut response = [0u8; BLIND_RESPONSE_SIZE];
+        response[0] = clause as u8;
+        response[1..].copy_from_slice((session.nonces[clause] + c * self.key).as_bytes());
+        Ok(response)
+    }
+}
+
+/// User, step 2: blind `message` for `issuer` under both `commitments`.
+/// Returns the state to keep and the two challenges to send.
+pub fn blind(
+    issuer: &BlindPublicKey,
+    commitments: &[u8; BLIND_PAIR_SIZE],
+    message: &[u8],
+) -> Result<(BlindingState, [u8; BLIND_PAIR_SIZE])> {
+    let x = decompress(&issuer.0, SignatureErrorKind::MalformedPublicKey)?;
+    let mut challenges = [0u8; BLIND_PAIR_SIZE];
+    let clause = |commitment: &[u8], out: &mut [u8]| -> Result<(Scalar, CompressedEdwardsY)> {
+        let r = decompress(commitment, SignatureErrorKind::MalformedSignature)?;
+        let alpha = random_scalar()?;
+        let beta = random_scalar()?;
+        let r = (r + alpha * ED25519_BASEPOINT_POINT + beta * x).compress();
+        out.copy_from_slice((challenge(&r, issuer, message) + beta).as_bytes());
+        Ok((alpha, r))
+    };
+    let (c0, c1) = challenges.split_at_mut(32);
+    let clauses = [
+        clause(&commitments[..32], c0)?,
+        clause(&commitments[32..], c1)?,
+    ];
+    Ok((
+        BlindingState {
+            clauses,
+            issuer: *issuer,
+            message: message.to_vec(),
+        },
+        challenges,
+    ))
+}
+
+/// User, step 4: unblind the response into a signature over the message
+pub fn unblind(
+    state: BlindingState,
+    blinded_response: &[u8; BLIND_RESPONSE_SIZE],
+) -> Result<[u8; BLIND_SIGNATURE_SIZE]> {
+    let Some((alpha, r)) = state.clauses.get(blinded_response[0] as usize) else {
+        return Err(SessionIdError::Signature(
+            SignatureErrorKind::MalformedSignature,
+        ));
+    };
+    let s = scalar(blinded_response[1..].try_into().unwrap())? + alpha;
+    let mut sig = [0u8; BLIND_SIGNATURE_SIZE];
+    sig[..32].copy_from_slice(r.as_bytes());
+    sig[32..].copy_from_slice(s.as_bytes());
+    verify_blind_signature(&state.issuer, &state.message, &sig)?;
+    Ok(sig)
+}
+
+/// Check an unblinded signature by `issuer` over `message`
+pub fn verify_blind_signature(
+    issuer: &BlindPublicKey,
+    message: &[u8],
+    signature: &[u8],
+) -> Result<()> {
+    if signature.len() != BLIND_SIGNATURE_SIZE {
+        return Err(crate::errors::invalid_length(
+            BLIND_SIGNATURE_SIZE,
+            signature.len(),
+        ));
+    }
+    let x = decompress(&issuer.0, SignatureErrorKind::MalformedPublicKey)?;
+    let r = CompressedEdwardsY::from_slice(&signature[..32]);
+    let s = scalar(signature[32..].try_into().unwrap())?;
+    let c = challenge(&r, issuer, message);
+    // s·B - c·X == R
+    let expected = EdwardsPoint::vartime_double_scalar_mul_basepoint(&-c, &x, &s);
+    if expected.compress() != r {
+        return Err(SessionIdError::Signature(
+            SignatureErrorKind::VerificationFailed,
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{new_session_id_pair, ISessionIdPair};
+
+    #[test]
+    fn test_blind_signature() {
+        let pair = new_session_id_pair().unwrap();
+        let issuer = BlindSigner::derive(&pair);
+        let issuer_key = issuer.public_key();
+        assert_ne!(issuer_key.0, *pair.get_id().as_raw());
+        assert_eq!(BlindSigner::derive(&pair).public_key(), issuer_key);
+        let ticket = b"ticket:8f3a";
+
+        // sessions may be open concurrently, even on separate signers with the same key
+        let (session, commitments) = issuer.commit().unwrap();
+        let (other_session, other_commitments) = BlindSigner::derive(&pair).commit().unwrap();
+        let (state, challenges) = blind(&issuer_key, &commitments, ticket).unwrap();
+        let (other_state, other_challenges) =
+            blind(&issuer_key, &other_commitments, b"ticket:other").unwrap();
+        let response = issuer.sign_blinded(session, &challenges).unwrap();
+        let other_response = issuer
+            .sign_blinded(other_session, &other_challenges)
+            .unwrap();
+        let sig = unblind(state, &response).unwrap();
+        let other_sig = unblind(other_state, &other_response).unwrap();
+
+        assert!(verify_blind_signature(&issuer_key, ticket, &sig).is_ok());
+        assert!(verify_blind_signature(&issuer_key, b"ticket:other", &other_sig).is_ok());
+        // the issuer never saw the ticket or the final signature
+        assert!(!commitments.chunks(32).any(|r| r == &sig[..32]));
+        assert!(verify_blind_signature(&issuer_key, b"ticket:other", &sig).is_err());
+        let other = BlindSigner::from_secret(&[7; 32]).unwrap().public_key();
+        assert!(verify_blind_signature(&other, ticket, &sig).is_err());
+
+        // not an Ed25519 signature by the session key or the blind key
+        for key in [*pair.get_id().as_raw(), issuer_key.0] {
+            let pk = ed25519_dalek::PublicKey::from_bytes(&key).unwrap();
+            let s = ed25519_dalek::Signature::from_bytes(&sig).unwrap();
+            assert!(ed25519_dalek::Verifier::verify(&pk, ticket, &s).is_err());
+        }
+
+        // a response from another key does not unblind
+        let wrong_issuer = BlindSigner::from_secret(&[9; 32]).unwrap();
+        let (_, commitments) = issuer.commit().unwrap();
+        let (state, challenges) = blind(&issuer_key, &commitments, ticket).unwrap();
+        let (wrong_session, _) = wrong_issuer.commit().unwrap();
+        let wrong = wrong_issuer
+            .sign_blinded(wrong_session, &challenges)
+            .unwrap();
+        assert!(unblind(state, &wrong).is_err());
+        assert!(BlindSigner::from_secret(&[0; 32]).is_err());
+    }
+
+    #[test]
+    fn test_blind_signature_clauses() {
+        let issuer = BlindSigner::from_secret(&[3; 32]).unwrap();
+        let issuer_key = issuer.public_key();
+        let mut answered = [false; 2];
+        for _ in 0..64 {
+            let (session, commitments) = issuer.commit().unwrap();
+            let (state, challenges) = blind(&issuer_key, &commitments, b"ticket").unwrap();
+            let response = issuer.sign_blinded(session, &challenges).unwrap();
+            answered[response[0] as usize] = true;
+            assert!(unblind(state, &response).is_ok());
+        }
+        let (session, commitments) = issuer.commit().unwrap();
+        let (state, challenges) = blind(&issuer_key, &commitments, b"ticket").unwrap();
+        let mut response = issuer.sign_blinded(session, &challenges).unwrap();
+        response[0] = 2;
+        assert!(unblind(state, &response).is_err());
+        assert_eq!(answered, [true, true]);
+    }
+}
@@ -0,0 +1,484 @@
+//! Signing of `Serialize` types over a canonical deterministic binary encoding.
+//!
+//! The encoding does not depend on the format a value is transported in, on field
+//! declaration order, or on the iteration order of hash maps:
+//!
+//! - integers and floats are fixed width little endian (NaN is canonicalized)
+//! - strings, bytes and sequences are prefixed with a u64 LE length
+//! - `Option` is a `0`/`1` byte followed by the value
+//! - enum variants are encoded by name, not by index
+//! - maps and structs are a u64 LE entry count followed by the entries sorted by the
+//!   encoding of their key (the field name for structs); duplicate keys are rejected
+use crate::errors::{Result, SessionIdError};
+use crate::{ISessionIdPair, SessionId, SessionIdPair, SessionIdPublic, SignatureSet};
+use serde::ser::{self, Serialize};
+use std::fmt;
+
+const CANONICAL_CONTEXT: &[u8] = b"verse-session-id/canonical/v1";
+
+#[derive(Debug)]
+struct Error(String);
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+impl std::error::Error for Error {}
+impl ser::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Error(msg.to_string())
+    }
+}
+impl From<Error> for SessionIdError {
+    fn from(e: Error) -> Self {
+        SessionIdError::InvalidFormat(format!("canonical: {}", e))
+    }
+}
+
+/// Canonical encoding of `value`
+pub fn canonical_encode<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>> {
+    Ok(encode(value)?)
+}
+
+fn encode<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>, Error> {
+    let mut ser = Encoder(Vec::new());
+    value.serialize(&mut ser)?;
+    Ok(ser.0)
+}
+
+struct Encoder(Vec<u8>);
+
+impl Encoder {
+    fn write_len(&mut self, len: usize) {
+        self.0.extend_from_slice(&(len as u64).to_le_bytes());
+    }
+    fn write_bytes(&mut self, v: &[u8]) {
+        self.write_len(v.len());
+        self.0.extend_from_slice(v);
+    }
+}
+
+struct SeqEncoder<'a> {
+    parent: &'a mut Encoder,
+    // sequences of unknown length are buffered to write the count first
+    buf: Encoder,
+    len: usize,
+    prefixed: bool,
+}
+
+impl SeqEncoder<'_> {
+    fn element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.len += 1;
+        value.serialize(&mut self.buf)
+    }
+    fn finish(self) {
+        if self.prefixed {
+            self.parent.write_len(self.len);
+        }
+        self.parent.0.extend_from_slice(&self.buf.0);
+    }
+}
+
+struct MapEncoder<'a> {
+    parent: &'a mut Encoder,
+    entries: Vec<(Vec<u8>, Vec<u8>)>,
+    key: Option<Vec<u8>>,
+}
+
+impl MapEncoder<'_> {
+    fn finish(mut self) -> Result<(), Error> {
+        self.entries.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+        if self.entries.windows(2).any(|w| w[0].0 == w[1].0) {
+            return Err(Error("duplicate map key".to_string()));
+        }
+        self.parent.write_len(self.entries.len());
+        for (k, v) in self.entries {
+            self.parent.0.extend_from_slice(&k);
+            self.parent.0.extend_from_slice(&v);
+        }
+        Ok(())
+    }
+}
+
+impl<'a> ser::Serializer for &'a mut Encoder {
+    type Ok = ();
+    type Error = Error;
+    type SerializeSeq = SeqEncoder<'a>;
+    type SerializeTuple = SeqEncoder<'a>;
+    type SerializeTupleStruct = SeqEncoder<'a>;
+    type SerializeTupleVariant = SeqEncoder<'a>;
+    type SerializeMap = MapEncoder<'a>;
+    type SerializeStruct = MapEncoder<'a>;
+    type SerializeStructVariant = MapEncoder<'a>;
+
+    fn serialize_bool(self, v: bool) -> Result<(), Error> {
+        self.0.push(v as u8);
+        Ok(())
+    }
+    fn serialize_i8(self, v: i8) -> Result<(), Error> {
+        self.0.extend_from_slice(&v.to_le_bytes());
+        Ok(())
+    }
+    fn serialize_i16(self, v: i16) -> Result<(), Error> {
+        self.0.extend_from_slice(&v.to_le_bytes());
+        Ok(())
+    }
+    fn serialize_i32(self, v: i32) -> Result<(), Error> {
+        self.0.extend_from_slice(&v.to_le_bytes());
+        Ok(())
+    }
+    fn serialize_i64(self, v: i64) -> Result<(), Error> {
+        self.0.extend_from_slice(&v.to_le_bytes());
+        Ok(())
+    }
+    fn serialize_i128(self, v: i128) -> Result<(), Error> {
+        self.0.extend_from_slice(&v.to_le_bytes());
+        Ok(())
+    }
+    fn serialize_u8(self, v: u8) -> Result<(), Error> {
+        self.0.push(v);
+        Ok(())
+    }
+    fn serialize_u16(self, v: u16) -> Result<(), Error> {
+        self.0.extend_from_slice(&v.to_le_bytes());
+        Ok(())
+    }
+    fn serialize_u32(self, v: u32) -> Result<(), Error> {
+        self.0.extend_from_slice(&v.to_le_bytes());
+        Ok(())
+    }
+    fn serialize_u64(self, v: u64) -> Result<(), Error> {
+        self.0.extend_from_slice(&v.to_le_bytes());
+        Ok(())
+    }
+    fn serialize_u128(self, v: u128) -> Result<(), Error> {
+        self.0.extend_from_slice(&v.to_le_bytes());
+        Ok(())
+    }
+    fn serialize_f32(self, v: f32) -> Result<(), Error> {
+        let v = if v.is_nan() { f32::NAN } else { v };
+        self.0.extend_from_slice(&v.to_le_bytes());
+        Ok(())
+    }
+    fn serialize_f64(self, v: f64) -> Result<(), Error> {
+        let v = if v.is_nan() { f64::NAN } else { v };
+        self.0.extend_from_slice(&v.to_le_bytes());
+        Ok(())
+    }
+    fn serialize_char(self, v: char) -> Result<(), Error> {
+        self.serialize_u32(v as u32)
+    }
+    fn serialize_str(self, v: &str) -> Result<(), Error> {
+        self.write_bytes(v.as_bytes());
+        Ok(())
+    }
+    fn serialize_bytes(self, v: &[u8]) -> Result<(), Error> {
+        self.write_bytes(v);
+        Ok(())
+    }
+    fn serialize_none(self) -> Result<(), Error> {
+        self.0.push(0);
+        Ok(())
+    }
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<(), Error> {
+        self.0.push(1);
+        value.serialize(self)
+    }
+    fn serialize_unit(self) -> Result<(), Error> {
+        Ok(())
+    }
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<(), Error> {
+        Ok(())
+    }
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+    ) -> Result<(), Error> {
+        self.serialize_str(variant)
+    }
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        value.serialize(self)
+    }
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.write_bytes(variant.as_bytes());
+        value.serialize(self)
+    }
+    fn serialize_seq(self, _len: Option<usize>) -> Result<SeqEncoder<'a>, Error> {
+        Ok(SeqEncoder {
+            parent: self,
+            buf: Encoder(Vec::new()),
+            len: 0,
+            prefixed: true,
+        })
+    }
+    fn serialize_tuple(self, _len: usize) -> Result<SeqEncoder<'a>, Error> {
+        Ok(SeqEncoder {
+            parent: self,
+            buf: Encoder(Vec::new()),
+            len: 0,
+            prefixed: false,
+        })
+    }
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<SeqEncoder<'a>, Error> {
+        self.serialize_tuple(len)
+    }
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<SeqEncoder<'a>, Error> {
+        self.write_bytes(variant.as_bytes());
+        self.serialize_tuple(len)
+    }
+    fn serialize_map(self, _len: Option<usize>) -> Result<MapEncoder<'a>, Error> {
+        Ok(MapEncoder {
+            parent: self,
+            entries: Vec::new(),
+            key: None,
+        })
+    }
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<MapEncoder<'a>, Error> {
+        self.serialize_map(Some(len))
+    }
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<MapEncoder<'a>, Error> {
+        self.write_bytes(variant.as_bytes());
+        self.serialize_map(Some(len))
+    }
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+}
+
+impl ser::SerializeSeq for SeqEncoder<'_> {
+    type Ok = ();
+    type Error = Error;
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.element(value)
+    }
+    fn end(self) -> Result<(), Error> {
+        self.finish();
+        Ok(())
+    }
+}
+impl ser::SerializeTuple for SeqEncoder<'_> {
+    type Ok = ();
+    type Error = Error;
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.element(value)
+    }
+    fn end(self) -> Result<(), Error> {
+        self.finish();
+        Ok(())
+    }
+}
+impl ser::SerializeTupleStruct for SeqEncoder<'_> {
+    type Ok = ();
+    type Error = Error;
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.element(value)
+    }
+    fn end(self) -> Result<(), Error> {
+        self.finish();
+        Ok(())
+    }
+}
+impl ser::SerializeTupleVariant for SeqEncoder<'_> {
+    type Ok = ();
+    type Error = Error;
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.element(value)
+    }
+    fn end(self) -> Result<(), Error> {
+        self.finish();
+        Ok(())
+    }
+}
+impl ser::SerializeMap for MapEncoder<'_> {
+    type Ok = ();
+    type Error = Error;
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), Error> {
+        self.key = Some(encode(key)?);
+        Ok(())
+    }
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        let key = self
+            .key
+            .take()
+            .ok_or_else(|| Error("value without key".to_string()))?;
+        self.entries.push((key, encode(value)?));
+        Ok(())
+    }
+    fn end(self) -> Result<(), Error> {
+        self.finish()
+    }
+}
+impl ser::SerializeStruct for MapEncoder<'_> {
+    type Ok = ();
+    type Error = Error;
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.entries.push((encode(key)?, encode(value)?));
+        Ok(())
+    }
+    fn end(self) -> Result<(), Error> {
+        self.finish()
+    }
+}
+impl ser::SerializeStructVariant for MapEncoder<'_> {
+    type Ok = ();
+    type Error = Error;
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.entries.push((encode(key)?, encode(value)?));
+        Ok(())
+    }
+    fn end(self) -> Result<(), Error> {
+        self.finish()
+    }
+}
+
+/// Signing of `Serialize` values with a SessionIdPair
+pub trait SerializableSigner {
+    /// Sign the [`canonical_encode`] encoding of `value`
+    fn sign_serializable<T: Serialize + ?Sized>(&self, value: &T) -> Result<SignatureSet>;
+}
+
+impl SerializableSigner for SessionIdPair {
+    fn sign_serializable<T: Serialize + ?Sized>(&self, value: &T) -> Result<SignatureSet> {
+        self.sign([CANONICAL_CONTEXT, &canonical_encode(value)?])
+    }
+}
+
+impl SessionId {
+    /// Verify a signature created by [`SerializableSigner::sign_serializable`]
+    pub fn verify_serializable<T: Serialize + ?Sized>(
+        &self,
+        value: &T,
+        signature: &SignatureSet,
+    ) -> Result<()> {
+        self.verify([CANONICAL_CONTEXT, &canonical_encode(value)?], signature)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::new_session_id_pair;
+    use serde::Serialize;
+    use std::collections::{BTreeMap, HashMap};
+
+    #[derive(Serialize)]
+    struct Message {
+        room: String,
+        seq: u64,
+        tags: HashMap<String, i32>,
+        reply_to: Option<u32>,
+        kind: Kind,
+    }
+    #[derive(Serialize)]
+    struct Reordered {
+        kind: Kind,
+        reply_to: Option<u32>,
+        tags: BTreeMap<String, i32>,
+        seq: u64,
+        room: String,
+    }
+    #[derive(Serialize, Clone, Copy)]
+    enum Kind {
+        #[allow(dead_code)]
+        Text,
+        Emote {
+            id: u16,
+        },
+    }
+
+    #[test]
+    fn test_canonical_encode() {
+        let tags: Vec<(String, i32)> = (0..20).map(|i| (format!("t{}", i), i)).collect();
+        let message = Message {
+            room: "lobby".to_string(),
+            seq: 7,
+            tags: tags.iter().cloned().collect(),
+            reply_to: None,
+            kind: Kind::Emote { id: 3 },
+        };
+        let reordered = Reordered {
+            kind: message.kind,
+            reply_to: None,
+            tags: tags.iter().cloned().collect(),
+            seq: 7,
+            room: "lobby".to_string(),
+        };
+        assert_eq!(
+            canonical_encode(&message).unwrap(),
+            canonical_encode(&reordered).unwrap()
+        );
+
+        assert_eq!(canonical_encode(&1u16).unwrap(), [1, 0]);
+        assert_eq!(
+            canonical_encode("ab").unwrap(),
+            [2, 0, 0, 0, 0, 0, 0, 0, b'a', b'b']
+        );
+        assert_eq!(
+            canonical_encode(&f64::NAN).unwrap(),
+            canonical_encode(&-f64::NAN).unwrap()
+        );
+        // a map serialized with duplicate keys has no canonical form
+        struct Dup;
+        impl Serialize for Dup {
+            fn serialize<S: ser::Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+                use ser::SerializeMap;
+                let mut m = s.serialize_map(None)?;
+                m.serialize_entry("a", &1)?;
+                m.serialize_entry("a", &2)?;
+                m.end()
+            }
+        }
+        assert!(canonical_encode(&Dup).is_err());
+
+        let pair = new_session_id_pair().unwrap();
+        let signature = pair.sign_serializable(&message).unwrap();
+        let id = pair.get_id();
+        assert!(id.verify_serializable(&reordered, &signature).is_ok());
+        assert!(id
+            .verify_serializable(
+                &Reordered {
+                    seq: 8,
+                    ..reordered
+                },
+                &signature
+            )
+            .is_err());
+    }
+}
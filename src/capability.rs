@@ -0,0 +1,364 @@
+//! Attenuable capability tokens.
+//!
+//! A token is a chain of blocks. The first block is signed by the issuer, and every block
+//! names the public key that must sign the next one. The token carries the secret key of the
+//! last named key, so a holder can append a narrower block offline and pass the result on.
+//! Anyone who knows the issuer's SessionId can verify the whole chain.
+use crate::encoding::{write_str, Reader};
+use crate::errors::{Result, SessionIdError, SignatureErrorKind};
+use crate::time::{Clock, SystemClock};
+use crate::{
+    base64url, new_session_id_pair, ISessionIdPair, SecretSessionKey, SessionId, SessionIdPair,
+    SessionIdPairSecret, SessionIdPublic, SignatureSet, SESSION_ID_SIZE, SIGNATURE_SET_SIZE,
+};
+use std::fmt;
+
+const CAPABILITY_CONTEXT: &[u8] = b"verse-session-id/capability/v1";
+const CAPABILITY_VERSION: u8 = 1;
+
+/// Rights granted by a capability token
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CapabilityGrant {
+    /// World the rights apply to
+    pub world_id: String,
+    /// Granted rights or roles (e.g. `"moderate"`)
+    pub rights: Vec<String>,
+    /// Expiration time (seconds since UNIX epoch)
+    pub expires_at: u64,
+}
+
+impl CapabilityGrant {
+    pub fn new(world_id: impl Into<String>, rights: &[&str], expires_at: u64) -> Self {
+        CapabilityGrant {
+            world_id: world_id.into(),
+            rights: rights.iter().map(|v| v.to_string()).collect(),
+            expires_at,
+        }
+    }
+    /// True if `self` grants nothing beyond `other`
+    pub fn is_subset_of(&self, other: &CapabilityGrant) -> bool {
+        self.world_id == other.world_id
+            && self.expires_at <= other.expires_at
+            && self.rights.iter().all(|r| other.rights.contains(r))
+    }
+    fn intersect(&self, other: &CapabilityGrant) -> CapabilityGrant {
+        CapabilityGrant {
+            world_id: self.world_id.clone(),
+            rights: self
+                .rights
+                .iter()
+                .filter(|r| other.rights.contains(r))
+                .cloned()
+                .collect(),
+            expires_at: std::cmp::min(self.expires_at, other.expires_at),
+        }
+    }
+    fn encode(&self, buf: &mut Vec<u8>) -> Result<()> {
+        write_str(buf, &self.world_id)?;
+        let n: u8 = self
+            .rights
+            .len()
+            .try_into()
+            .map_err(|_| SessionIdError::InvalidArgument("too many rights"))?;
+        buf.push(n);
+        for r in &self.rights {
+            write_str(buf, r)?;
+        }
+        buf.extend_from_slice(&self.expires_at.to_le_bytes());
+        Ok(())
+    }
+    fn decode(r: &mut Reader) -> Result<Self> {
+        let world_id = r.read_str()?;
+        let n = r.read(1)?[0];
+        let rights = (0..n).map(|_| r.read_str()).collect::<Result<_>>()?;
+        let expires_at = u64::from_le_bytes(r.read_array()?);
+        Ok(CapabilityGrant {
+            world_id,
+            rights,
+            expires_at,
+        })
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct CapabilityBlock {
+    grant: CapabilityGrant,
+    next_key: SessionId,
+    signature: SignatureSet,
+}
+
+/// Delegable, offline-verifiable capability token.
+/// The token is a bearer credential: whoever holds it can use and attenuate it.
+/// Tokens are equal when their blocks are: the proof is bound to the last block's key.
+#[derive(Clone)]
+pub struct CapabilityToken {
+    blocks: Vec<CapabilityBlock>,
+    // secret key of the last block's `next_key`
+    proof: SecretSessionKey,
+}
+
+impl PartialEq for CapabilityToken {
+    fn eq(&self, other: &Self) -> bool {
+        self.blocks == other.blocks
+    }
+}
+
+impl Eq for CapabilityToken {}
+
+fn signed_content(
+    prev: Option<&SignatureSet>,
+    grant: &CapabilityGrant,
+    next_key: &SessionId,
+) -> Result<Vec<u8>> {
+    let mut buf = Vec::new();
+    if let Some(prev) = prev {
+        buf.extend_from_slice(&prev.to_bytes());
+    }
+    grant.encode(&mut buf)?;
+    buf.extend_from_slice(next_key.as_ref());
+    Ok(buf)
+}
+
+fn new_block(
+    signer: &SessionIdPair,
+    prev: Option<&SignatureSet>,
+    grant: CapabilityGrant,
+) -> Result<(CapabilityBlock, SecretSessionKey)> {
+    let next = new_session_id_pair()?;
+    let next_key = next.get_id();
+    let content = signed_content(prev, &grant, &next_key)?;
+    let signature = signer.sign([CAPABILITY_CONTEXT, &content])?;
+    Ok((
+        CapabilityBlock {
+            grant,
+            next_key,
+            signature,
+        },
+        next.secret_key(),
+    ))
+}
+
+impl CapabilityToken {
+    /// Mint a token granting `grant`, signed by `issuer`
+    pub fn mint(issuer: &SessionIdPair, grant: CapabilityGrant) -> Result<Self> {
+        let (block, proof) = new_block(issuer, None, grant)?;
+        Ok(CapabilityToken {
+            blocks: vec![block],
+            proof,
+        })
+    }
+    /// Append a block restricting the token to `grant`.
+    /// `grant` must not exceed the current effective grant.
+    pub fn attenuate(&self, grant: CapabilityGrant) -> Result<Self> {
+        if !grant.is_subset_of(&self.effective_grant()) {
+            return Err(SessionIdError::InvalidClaim("grant"));
+        }
+        let holder = self.holder_pair()?;
+        let prev = &self.blocks.last().unwrap().signature;
+        let (block, proof) = new_block(&holder, Some(prev), grant)?;
+        let mut blocks = self.blocks.clone();
+        blocks.push(block);
+        Ok(CapabilityToken { blocks, proof })
+    }
+    /// Verify the chain against the issuer and return the effective grant
+    pub fn verify(&self, issuer: &SessionId) -> Result<CapabilityGrant> {
+        self.verify_at(issuer, &SystemClock)
+    }
+    /// Verify the chain against the issuer at the time of `clock`
+    pub fn verify_at(&self, issuer: &SessionId, clock: &dyn Clock) -> Result<CapabilityGrant> {
+        let now = clock.now();
+        let mut key = *issuer;
+        let mut prev: Option<&SignatureSet> = None;
+        for block in &self.blocks {
+            let content = signed_content(prev, &block.grant, &block.next_key)?;
+            key.verify([CAPABILITY_CONTEXT, &content], &block.signature)?;
+            key = block.next_key;
+            prev = Some(&block.signature);
+        }
+        if self.holder_pair()?.get_id() != key {
+            return Err(SessionIdError::Signature(
+                SignatureErrorKind::MalformedPublicKey,
+            ));
+        }
+        let grant = self.effective_grant();
+        if self
+            .blocks
+            .iter()
+            .any(|b| b.grant.world_id != grant.world_id)
+        {
+            return Err(SessionIdError::InvalidClaim("world_id"));
+        }
+        if now >= grant.expires_at {
+            return Err(SessionIdError::Expired);
+        }
+        Ok(grant)
+    }
+    /// Verify the token and check that it grants `right` in `world_id`
+    pub fn authorize(&self, issuer: &SessionId, world_id: &str, right: &str) -> Result<()> {
+        let grant = self.verify(issuer)?;
+        if grant.world_id != world_id {
+            return Err(SessionIdError::InvalidClaim("world_id"));
+        }
+        if !grant.rights.iter().any(|r| r == right) {
+            return Err(SessionIdError::InvalidClaim("rights"));
+        }
+        Ok(())
+    }
+    /// Number of blocks (1 + number of attenuations)
+    pub fn depth(&self) -> usize {
+        self.blocks.len()
+    }
+
+    fn effective_grant(&self) -> CapabilityGrant {
+        let first = self.blocks[0].grant.clone();
+        self.blocks[1..]
+            .iter()
+            .fold(first, |acc, b| acc.intersect(&b.grant))
+    }
+    fn holder_pair(&self) -> Result<SessionIdPair> {
+        self.proof.to_session_id_pair()
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let mut buf = vec![CAPABILITY_VERSION];
+        buf.push(
+            self.blocks
+                .len()
+                .try_into()
+                .map_err(|_| SessionIdError::InvalidArgument("too many blocks"))?,
+        );
+        for block in &self.blocks {
+            block.grant.encode(&mut buf)?;
+            buf.extend_from_slice(block.next_key.as_ref());
+            buf.extend_from_slice(&block.signature.to_bytes());
+        }
+        buf.extend_from_slice(self.proof.expose_secret());
+        Ok(buf)
+    }
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let mut r = Reader(bytes);
+        if r.read(1)?[0] != CAPABILITY_VERSION {
+            return Err(SessionIdError::InvalidFormat(
+                "capability: unsupported version".to_string(),
+            ));
+        }
+        let n = r.read(1)?[0];
+        if n == 0 {
+            return Err(SessionIdError::InvalidFormat(
+                "capability: no blocks".to_string(),
+            ));
+        }
+        let mut blocks = Vec::with_capacity(n as usize);
+        for _ in 0..n {
+            let grant = CapabilityGrant::decode(&mut r)?;
+            let next_key = SessionId::from(r.read_array::<SESSION_ID_SIZE>()?);
+            let signature = SignatureSet::from_bytes(&r.read_array::<SIGNATURE_SET_SIZE>()?);
+            blocks.push(CapabilityBlock {
+                grant,
+                next_key,
+                signature,
+            });
+        }
+        let proof = SecretSessionKey::new(r.read_array()?);
+        if !r.0.is_empty() {
+            return Err(SessionIdError::InvalidFormat(
+                "capability: trailing bytes".to_string(),
+            ));
+        }
+        Ok(CapabilityToken { blocks, proof })
+    }
+}
+
+impl fmt::Display for CapabilityToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let bytes = self.to_bytes().map_err(|_| fmt::Error)?;
+        write!(f, "{}", base64url::encode(bytes))
+    }
+}
+impl std::str::FromStr for CapabilityToken {
+    type Err = SessionIdError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_bytes(&base64url::decode(s)?)
+    }
+}
+impl fmt::Debug for CapabilityToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CapabilityToken")
+            .field("grant", &self.effective_grant())
+            .field("depth", &self.depth())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ManualClock;
+
+    #[test]
+    fn test_capability() {
+        let issuer = new_session_id_pair().unwrap();
+        let issuer_id = issuer.get_id();
+        let expires_at = SystemClock.now() + 60;
+        let grant = CapabilityGrant::new("world1", &["moderate", "kick"], expires_at);
+        let token = CapabilityToken::mint(&issuer, grant.clone()).unwrap();
+        assert_eq!(token.verify(&issuer_id).unwrap(), grant);
+        assert!(token.authorize(&issuer_id, "world1", "kick").is_ok());
+        assert!(token.authorize(&issuer_id, "world2", "kick").is_err());
+        assert!(token.authorize(&issuer_id, "world1", "ban").is_err());
+
+        let other = new_session_id_pair().unwrap().get_id();
+        assert!(token.verify(&other).is_err());
+        assert!(matches!(
+            token.verify_at(&issuer_id, &ManualClock::new(expires_at)),
+            Err(SessionIdError::Expired)
+        ));
+
+        // attenuate offline
+        let narrower = CapabilityGrant::new("world1", &["kick"], expires_at - 30);
+        let token1 = token.attenuate(narrower.clone()).unwrap();
+        assert_eq!(token1.depth(), 2);
+        assert_eq!(token1.verify(&issuer_id).unwrap(), narrower);
+        assert!(token1.authorize(&issuer_id, "world1", "moderate").is_err());
+        assert!(token1.authorize(&issuer_id, "world1", "kick").is_ok());
+
+        // cannot widen
+        assert!(token1
+            .attenuate(CapabilityGrant::new("world1", &["moderate"], expires_at))
+            .is_err());
+        assert!(token
+            .attenuate(CapabilityGrant::new("world2", &["kick"], expires_at))
+            .is_err());
+    }
+
+    #[test]
+    fn test_capability_serialize() {
+        let issuer = new_session_id_pair().unwrap();
+        let issuer_id = issuer.get_id();
+        let grant = CapabilityGrant::new("world1", &["moderate", "kick"], SystemClock.now() + 60);
+        let token = CapabilityToken::mint(&issuer, grant.clone())
+            .unwrap()
+            .attenuate(CapabilityGrant::new("world1", &["kick"], grant.expires_at))
+            .unwrap();
+
+        let s = token.to_string();
+        let token1: CapabilityToken = s.parse().unwrap();
+        assert_eq!(token1, token);
+        assert_eq!(token1.proof, token.proof);
+        assert!(token1.authorize(&issuer_id, "world1", "kick").is_ok());
+
+        // a forged widening block is rejected
+        let mut forged = token.clone();
+        forged.blocks[1].grant.rights.push("moderate".to_string());
+        assert!(forged.verify(&issuer_id).is_err());
+
+        // dropping the attenuation block breaks the proof
+        let mut truncated = token.clone();
+        truncated.blocks.pop();
+        assert!(truncated.verify(&issuer_id).is_err());
+
+        let bytes = token.to_bytes().unwrap();
+        assert!(CapabilityToken::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        assert!(CapabilityToken::from_bytes(&[2]).is_err());
+    }
+}
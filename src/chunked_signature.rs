@@ -0,0 +1,300 @@
+use crate::errors::{self, Result, SessionIdError};
+use crate::session_id_pair::{as_base64, from_base64};
+use crate::{ISessionIdPair, SessionId, SessionIdPair, SessionIdPublic, SignatureSet};
+use ed25519_dalek::{Digest, Sha512};
+use serde::{Deserialize, Serialize};
+
+/// Bytes of Merkle tree node hash
+pub const MERKLE_HASH_SIZE: usize = 32;
+/// Default chunk size of ChunkedSignature
+pub const DEFAULT_CHUNK_SIZE: u32 = 64 * 1024;
+
+/// Merkle tree node hash
+pub type MerkleHash = [u8; MERKLE_HASH_SIZE];
+
+const CHUNKED_SIGNATURE_CONTEXT: &[u8] = b"verse-session-id/chunked/v1";
+const LEAF_PREFIX: u8 = 0;
+const NODE_PREFIX: u8 = 1;
+
+fn hash_leaf(chunk: &[u8]) -> MerkleHash {
+    let mut hasher = Sha512::new();
+    hasher.update([LEAF_PREFIX]);
+    hasher.update(chunk);
+    truncate_hash(hasher)
+}
+
+fn hash_node(left: &MerkleHash, right: &MerkleHash) -> MerkleHash {
+    let mut hasher = Sha512::new();
+    hasher.update([NODE_PREFIX]);
+    hasher.update(left);
+    hasher.update(right);
+    truncate_hash(hasher)
+}
+
+fn truncate_hash(hasher: Sha512) -> MerkleHash {
+    let mut h = [0u8; MERKLE_HASH_SIZE];
+    h.copy_from_slice(&hasher.finalize()[..MERKLE_HASH_SIZE]);
+    h
+}
+
+fn chunk_count(total_size: u64, chunk_size: u32) -> u64 {
+    if chunk_size == 0 {
+        return 0;
+    }
+    // An empty payload is treated as a single empty chunk.
+    std::cmp::max(1, total_size.div_ceil(chunk_size as u64))
+}
+
+/// Merkle tree over a payload split into fixed-size chunks.
+/// Used by the signer to build a ChunkedSignature and inclusion proofs.
+#[derive(Clone, Debug)]
+pub struct MerkleTree {
+    chunk_size: u32,
+    total_size: u64,
+    // levels[0] are the leaves, the last level is the root.
+    levels: Vec<Vec<MerkleHash>>,
+}
+
+impl MerkleTree {
+    /// Build a Merkle tree from the payload
+    pub fn new(payload: &[u8], chunk_size: u32) -> Result<Self> {
+        if chunk_size == 0 {
+            return Err(SessionIdError::InvalidArgument("chunk_size == 0"));
+        }
+        let mut leaves: Vec<MerkleHash> =
+            payload.chunks(chunk_size as usize).map(hash_leaf).collect();
+        if leaves.is_empty() {
+            leaves.push(hash_leaf(&[]));
+        }
+        let mut levels = vec![leaves];
+        while levels.last().unwrap().len() > 1 {
+            let next = levels
+                .last()
+                .unwrap()
+                .chunks(2)
+                .map(|pair| match pair {
+                    [l, r] => hash_node(l, r),
+                    // odd node is carried up unchanged
+                    [l] => *l,
+                    _ => unreachable!(),
+                })
+                .collect();
+            levels.push(next);
+        }
+        Ok(MerkleTree {
+            chunk_size,
+            total_size: payload.len() as u64,
+            levels,
+        })
+    }
+    /// Merkle root
+    pub fn root(&self) -> MerkleHash {
+        self.levels.last().unwrap()[0]
+    }
+    /// Number of chunks
+    pub fn chunk_count(&self) -> u64 {
+        self.levels[0].len() as u64
+    }
+    /// Create an inclusion proof for the chunk at `index`
+    pub fn proof(&self, index: u64) -> Result<ChunkProof> {
+        if index >= self.chunk_count() {
+            return Err(SessionIdError::OutOfRange {
+                index,
+                len: self.chunk_count(),
+            });
+        }
+        let mut siblings = Vec::new();
+        let mut i = index as usize;
+        for level in &self.levels[..self.levels.len() - 1] {
+            let sibling = i ^ 1;
+            if sibling < level.len() {
+                siblings.push(level[sibling]);
+            }
+            i /= 2;
+        }
+        Ok(ChunkProof { index, siblings })
+    }
+    /// Sign the Merkle root
+    pub fn sign(&self, pair: &SessionIdPair) -> Result<ChunkedSignature> {
+        let root = self.root();
+        let header = signed_header(self.chunk_size, self.total_size);
+        let signature = pair.sign([CHUNKED_SIGNATURE_CONTEXT, &header, &root])?;
+        Ok(ChunkedSignature {
+            chunk_size: self.chunk_size,
+            total_size: self.total_size,
+            root,
+            signature,
+        })
+    }
+}
+
+fn signed_header(chunk_size: u32, total_size: u64) -> [u8; 12] {
+    let mut buf = [0u8; 12];
+    buf[..4].copy_from_slice(&chunk_size.to_le_bytes());
+    buf[4..].copy_from_slice(&total_size.to_le_bytes());
+    buf
+}
+
+/// Inclusion proof of a single chunk
+#[derive(Deserialize, Serialize, Eq, PartialEq, Clone, Debug)]
+pub struct ChunkProof {
+    pub index: u64,
+    pub siblings: Vec<MerkleHash>,
+}
+
+/// Signature over a Merkle root of a chunked payload.
+/// Individual chunks can be verified with a ChunkProof without the full payload.
+#[derive(Deserialize, Serialize, Eq, PartialEq, Debug)]
+pub struct ChunkedSignature {
+    pub chunk_size: u32,
+    pub total_size: u64,
+    #[serde(serialize_with = "as_base64", deserialize_with = "from_base64")]
+    pub root: MerkleHash,
+    pub signature: SignatureSet,
+}
+
+impl ChunkedSignature {
+    /// Create a signature for the whole payload
+    pub fn sign(pair: &SessionIdPair, payload: &[u8], chunk_size: u32) -> Result<Self> {
+        MerkleTree::new(payload, chunk_size)?.sign(pair)
+    }
+    /// Number of chunks
+    pub fn chunk_count(&self) -> u64 {
+        chunk_count(self.total_size, self.chunk_size)
+    }
+    /// Verify the signature of the Merkle root
+    pub fn verify(&self, session_id: &SessionId) -> Result<()> {
+        if self.chunk_size == 0 {
+            return Err(SessionIdError::InvalidArgument("chunk_size == 0"));
+        }
+        let header = signed_header(self.chunk_size, self.total_size);
+        session_id.verify(
+            [CHUNKED_SIGNATURE_CONTEXT, &header, &self.root],
+            &self.signature,
+        )
+    }
+    /// Verify a single chunk against the signed Merkle root
+    pub fn verify_chunk(
+        &self,
+        session_id: &SessionId,
+        chunk: &[u8],
+        proof: &ChunkProof,
+    ) -> Result<()> {
+        self.verify(session_id)?;
+
+        let count = self.chunk_count();
+        if proof.index >= count {
+            return Err(SessionIdError::OutOfRange {
+                index: proof.index,
+                len: count,
+            });
+        }
+        let expected_len = if proof.index + 1 == count {
+            self.total_size - proof.index * self.chunk_size as u64
+        } else {
+            self.chunk_size as u64
+        };
+        if chunk.len() as u64 != expected_len {
+            return Err(errors::invalid_length(expected_len as usize, chunk.len()));
+        }
+
+        let mut hash = hash_leaf(chunk);
+        let mut siblings = proof.siblings.iter();
+        let mut i = proof.index;
+        let mut n = count;
+        while n > 1 {
+            let sibling = i ^ 1;
+            if sibling < n {
+                let s = siblings.next().ok_or(SessionIdError::MerkleProof)?;
+                hash = if i & 1 == 0 {
+                    hash_node(&hash, s)
+                } else {
+                    hash_node(s, &hash)
+                };
+            }
+            i /= 2;
+            n = n.div_ceil(2);
+        }
+        if siblings.next().is_some() || hash != self.root {
+            return Err(SessionIdError::MerkleProof);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::new_session_id_pair;
+
+    #[test]
+    fn test_chunked_sign_verify() {
+        let kp = new_session_id_pair().unwrap();
+        let sid = kp.get_id();
+        for size in [0usize, 1, 7, 8, 9, 16, 17, 100] {
+            let payload: Vec<u8> = (0..size).map(|v| v as u8).collect();
+            let tree = MerkleTree::new(&payload, 8).unwrap();
+            let cs = tree.sign(&kp).unwrap();
+            assert!(cs.verify(&sid).is_ok());
+            assert_eq!(cs.chunk_count(), tree.chunk_count());
+
+            let chunks: Vec<&[u8]> = if payload.is_empty() {
+                vec![&[]]
+            } else {
+                payload.chunks(8).collect()
+            };
+            for (i, chunk) in chunks.iter().enumerate() {
+                let proof = tree.proof(i as u64).unwrap();
+                assert!(cs.verify_chunk(&sid, chunk, &proof).is_ok());
+            }
+            assert!(tree.proof(chunks.len() as u64).is_err());
+        }
+    }
+
+    #[test]
+    fn test_chunked_tampered() {
+        let kp = new_session_id_pair().unwrap();
+        let sid = kp.get_id();
+        let payload: Vec<u8> = (0..40).collect();
+        let tree = MerkleTree::new(&payload, 8).unwrap();
+        let cs = ChunkedSignature::sign(&kp, &payload, 8).unwrap();
+        assert_eq!(cs.root, tree.root());
+
+        let proof = tree.proof(2).unwrap();
+        let mut chunk = payload[16..24].to_vec();
+        assert!(cs.verify_chunk(&sid, &chunk, &proof).is_ok());
+        chunk[0] ^= 1;
+        assert!(cs.verify_chunk(&sid, &chunk, &proof).is_err());
+
+        let wrong_index = ChunkProof {
+            index: 3,
+            siblings: proof.siblings.clone(),
+        };
+        assert!(cs
+            .verify_chunk(&sid, &payload[16..24], &wrong_index)
+            .is_err());
+
+        let other = new_session_id_pair().unwrap().get_id();
+        assert!(cs.verify(&other).is_err());
+
+        let cs1 = ChunkedSignature {
+            total_size: 41,
+            root: cs.root,
+            chunk_size: cs.chunk_size,
+            signature: cs.signature,
+        };
+        assert!(cs1.verify(&sid).is_err());
+
+        assert!(MerkleTree::new(&payload, 0).is_err());
+    }
+
+    #[test]
+    fn test_chunked_serialize() {
+        let kp = new_session_id_pair().unwrap();
+        let cs = ChunkedSignature::sign(&kp, &[1; 20], 8).unwrap();
+        let serialized = serde_json::to_string(&cs).unwrap();
+        let deserialized: ChunkedSignature = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(cs, deserialized);
+        assert!(deserialized.verify(&kp.get_id()).is_ok());
+    }
+}
@@ -0,0 +1,172 @@
+//! Per-connection identifiers signed by a session identity.
+//!
+//! A [`ConnectionId`] is 16 bytes: a 48-bit big-endian millisecond timestamp followed by 80
+//! random bits, written as 26 Crockford base32 characters like a ULID. Byte, string and
+//! `Ord` order all follow the mint time, so IDs sort chronologically in logs.
+//!
+//! The signature binding an ID to its [`SessionId`] is recorded once, e.g. when the
+//! connection is accepted. Logs and traces then carry only the short ID.
+use crate::errors::{Result, SessionIdError};
+use crate::time::{Clock, SystemClock};
+use crate::{ISessionIdPair, SessionId, SessionIdPair, SessionIdPublic, SignatureSet};
+use std::fmt;
+
+const CONNECTION_CONTEXT: &[u8] = b"verse-session-id/connection/v1";
+/// Bytes of a ConnectionId
+pub const CONNECTION_ID_SIZE: usize = 16;
+/// Characters of a ConnectionId string
+pub const CONNECTION_ID_STRING_SIZE: usize = 26;
+pub(crate) const CROCKFORD: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+
+fn connection_id_error(msg: &str) -> SessionIdError {
+    SessionIdError::InvalidFormat(format!("connection_id: {}", msg))
+}
+
+/// Short, sortable, timestamped connection identifier
+#[derive(Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct ConnectionId([u8; CONNECTION_ID_SIZE]);
+
+impl ConnectionId {
+    /// New ID at the time of `clock` (milliseconds, truncated to 48 bits)
+    pub fn new_at(clock: &dyn Clock) -> Result<Self> {
+        let mut bytes = [0u8; CONNECTION_ID_SIZE];
+        bytes[..6].copy_from_slice(&clock.now_ms().to_be_bytes()[2..]);
+        getrandom::getrandom(&mut bytes[6..])?;
+        Ok(ConnectionId(bytes))
+    }
+    pub fn from_bytes(bytes: [u8; CONNECTION_ID_SIZE]) -> Self {
+        ConnectionId(bytes)
+    }
+    pub fn to_bytes(&self) -> [u8; CONNECTION_ID_SIZE] {
+        self.0
+    }
+    /// Mint time in milliseconds since UNIX epoch
+    pub fn timestamp_ms(&self) -> u64 {
+        let mut v = [0u8; 8];
+        v[2..].copy_from_slice(&self.0[..6]);
+        u64::from_be_bytes(v)
+    }
+}
+
+impl fmt::Display for ConnectionId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let v = u128::from_be_bytes(self.0);
+        let mut buf = [0u8; CONNECTION_ID_STRING_SIZE];
+        for (i, c) in buf.iter_mut().enumerate() {
+            *c = CROCKFORD[((v >> (125 - 5 * i)) & 0x1f) as usize];
+        }
+        f.pad(std::str::from_utf8(&buf).map_err(|_| fmt::Error)?)
+    }
+}
+impl fmt::Debug for ConnectionId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ConnectionId({})", self)
+    }
+}
+impl std::str::FromStr for ConnectionId {
+    type Err = SessionIdError;
+    fn from_str(s: &str) -> Result<Self> {
+        if s.len() != CONNECTION_ID_STRING_SIZE {
+            return Err(connection_id_error("invalid length"));
+        }
+        let mut v = 0u128;
+        for c in s.bytes() {
+            let digit = CROCKFORD
+                .iter()
+                .position(|d| *d == c.to_ascii_uppercase())
+                .ok_or_else(|| connection_id_error("invalid character"))?;
+            v = (v << 5) | digit as u128;
+        }
+        // the first character only carries 3 bits
+        if s.as_bytes()[0] > b'7' {
+            return Err(connection_id_error("overflow"));
+        }
+        Ok(ConnectionId(v.to_be_bytes()))
+    }
+}
+
+/// A ConnectionId with the signature binding it to its SessionId
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SignedConnectionId {
+    pub id: ConnectionId,
+    pub session_id: SessionId,
+    pub signature: SignatureSet,
+}
+
+impl SignedConnectionId {
+    pub fn verify(&self) -> Result<()> {
+        verify_connection_id(&self.session_id, &self.id, &self.signature)
+    }
+}
+
+/// Minting of ConnectionIds with a SessionIdPair
+pub trait ConnectionIdMinter {
+    /// New ConnectionId at the current time, signed by this pair
+    fn mint_connection_id(&self) -> Result<SignedConnectionId> {
+        self.mint_connection_id_at(&SystemClock)
+    }
+    /// New ConnectionId at the time of `clock`, signed by this pair
+    fn mint_connection_id_at(&self, clock: &dyn Clock) -> Result<SignedConnectionId>;
+}
+
+impl ConnectionIdMinter for SessionIdPair {
+    fn mint_connection_id_at(&self, clock: &dyn Clock) -> Result<SignedConnectionId> {
+        let id = ConnectionId::new_at(clock)?;
+        Ok(SignedConnectionId {
+            id,
+            session_id: self.get_id(),
+            signature: self.sign([CONNECTION_CONTEXT, &id.0])?,
+        })
+    }
+}
+
+/// Verify that `id` was minted by `session_id`
+pub fn verify_connection_id(
+    session_id: &SessionId,
+    id: &ConnectionId,
+    signature: &SignatureSet,
+) -> Result<()> {
+    session_id.verify([CONNECTION_CONTEXT, &id.0], signature)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{new_session_id_pair, ManualClock};
+
+    #[test]
+    fn test_connection_id() {
+        let pair = new_session_id_pair().unwrap();
+        let minted = pair.mint_connection_id().unwrap();
+        assert!(minted.verify().is_ok());
+        assert_eq!(minted.session_id, pair.get_id());
+
+        let other = new_session_id_pair().unwrap();
+        assert!(verify_connection_id(&other.get_id(), &minted.id, &minted.signature).is_err());
+        let forged = ConnectionId::new_at(&ManualClock::from_ms(minted.id.timestamp_ms())).unwrap();
+        assert!(verify_connection_id(&pair.get_id(), &forged, &minted.signature).is_err());
+
+        let s = minted.id.to_string();
+        assert_eq!(s.len(), CONNECTION_ID_STRING_SIZE);
+        assert_eq!(s.parse::<ConnectionId>().unwrap(), minted.id);
+        assert_eq!(s.to_lowercase().parse::<ConnectionId>().unwrap(), minted.id);
+        assert!("8ZZZZZZZZZZZZZZZZZZZZZZZZZ"
+            .parse::<ConnectionId>()
+            .is_err());
+        assert!("0000000000000000000000000U"
+            .parse::<ConnectionId>()
+            .is_err());
+
+        let clock = ManualClock::from_ms(1_700_000_000_000);
+        let a = pair.mint_connection_id_at(&clock).unwrap().id;
+        clock.advance_ms(1);
+        let b = ConnectionId::new_at(&clock).unwrap();
+        assert_eq!(a.timestamp_ms(), 1_700_000_000_000);
+        assert!(a < b);
+        assert!(a.to_string() < b.to_string());
+        assert_eq!(
+            ConnectionId::from_bytes([0xff; 16]).to_string(),
+            "7ZZZZZZZZZZZZZZZZZZZZZZZZZ"
+        );
+    }
+}
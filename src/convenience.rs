@@ -0,0 +1,41 @@
+//! String-in, string-out signing for consumers that do not need [`SignatureSet`].
+//!
+//! Session IDs and signatures are the base64 strings produced by their `Display`.
+//!
+//! [`SignatureSet`]: crate::SignatureSet
+use crate::errors::Result;
+use crate::{ISessionIdPair, SessionId, SessionIdPair, SessionIdPublic, SignatureSet};
+
+/// Whether `signature` is a valid signature of `data` by `session_id`.
+/// Malformed inputs are reported as `false`.
+pub fn verify_string(session_id: &str, signature: &str, data: &str) -> bool {
+    let Ok(sid) = session_id.parse::<SessionId>() else {
+        return false;
+    };
+    let Ok(ss) = signature.parse::<SignatureSet>() else {
+        return false;
+    };
+    sid.verify([data.as_bytes()], &ss).is_ok()
+}
+
+/// Sign `data`. Returns the session ID and the signature.
+pub fn sign_string(pair: &SessionIdPair, data: &str) -> Result<(String, String)> {
+    let signature = pair.sign([data.as_bytes()])?;
+    Ok((pair.get_id().to_string(), signature.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::new_session_id_pair;
+
+    #[test]
+    fn test_convenience() {
+        let pair = new_session_id_pair().unwrap();
+        let (sid, sig) = sign_string(&pair, "hello").unwrap();
+        assert!(verify_string(&sid, &sig, "hello"));
+        assert!(!verify_string(&sid, &sig, "hello!"));
+        assert!(!verify_string("", &sig, "hello"));
+        assert!(!verify_string(&sid, "!!", "hello"));
+    }
+}
@@ -0,0 +1,192 @@
+//! COSE (RFC 9052) support. Enabled with the `cose` feature.
+//!
+//! COSE_Sign1 messages are signed with PureEdDSA over the COSE `Sig_structure`,
+//! and carry the signer's SessionId as `kid` in the unprotected header.
+//! SessionIds are exported as OKP/Ed25519 COSE_Keys.
+use crate::errors::{self, Result, SessionIdError, SignatureErrorKind};
+use crate::{ISessionIdPair, SessionId, SessionIdPair};
+use coset::cbor::Value;
+use coset::{
+    iana, CborSerializable, CoseKey, CoseKeyBuilder, CoseSign1, CoseSign1Builder, HeaderBuilder,
+    KeyType, Label, TaggedCborSerializable,
+};
+
+pub(crate) fn cose_error(e: coset::CoseError) -> SessionIdError {
+    SessionIdError::InvalidFormat(format!("cose: {}", e))
+}
+
+/// COSE_Sign1 signing with a SessionIdPair
+pub trait CoseSign1Signer {
+    /// Create a tagged COSE_Sign1 structure over `payload`
+    fn to_cose_sign1(&self, payload: &[u8]) -> Result<Vec<u8>>;
+}
+
+impl CoseSign1Signer for SessionIdPair {
+    fn to_cose_sign1(&self, payload: &[u8]) -> Result<Vec<u8>> {
+        let protected = HeaderBuilder::new()
+            .algorithm(iana::Algorithm::EdDSA)
+            .build();
+        let unprotected = HeaderBuilder::new().key_id(self.get_id().to_vec()).build();
+        CoseSign1Builder::new()
+            .protected(protected)
+            .unprotected(unprotected)
+            .payload(payload.to_vec())
+            .create_signature(&[], |tbs| {
+                ed25519_dalek::Signer::sign(self, tbs).to_bytes().to_vec()
+            })
+            .build()
+            .to_tagged_vec()
+            .map_err(cose_error)
+    }
+}
+
+/// Verify a COSE_Sign1 structure (tagged or untagged) signed with EdDSA.
+/// Returns the signer (`kid`) and the payload.
+pub fn verify_cose_sign1(bytes: &[u8]) -> Result<(SessionId, Vec<u8>)> {
+    let sign1 = CoseSign1::from_tagged_slice(bytes)
+        .or_else(|_| CoseSign1::from_slice(bytes))
+        .map_err(cose_error)?;
+    let kid = if sign1.protected.header.key_id.is_empty() {
+        &sign1.unprotected.key_id
+    } else {
+        &sign1.protected.header.key_id
+    };
+    let session_id = SessionId::try_from(kid)?;
+    let payload = session_id.verify_cose_sign1(bytes)?;
+    Ok((session_id, payload))
+}
+
+impl SessionId {
+    /// Verify a COSE_Sign1 structure (tagged or untagged) signed by this session ID.
+    /// Returns the payload.
+    pub fn verify_cose_sign1(&self, bytes: &[u8]) -> Result<Vec<u8>> {
+        let sign1 = CoseSign1::from_tagged_slice(bytes)
+            .or_else(|_| CoseSign1::from_slice(bytes))
+            .map_err(cose_error)?;
+        if sign1.protected.header.alg
+            != Some(coset::RegisteredLabelWithPrivate::Assigned(
+                iana::Algorithm::EdDSA,
+            ))
+        {
+            return Err(SessionIdError::InvalidFormat(
+                "cose: alg is not EdDSA".to_string(),
+            ));
+        }
+        let pk = ed25519_dalek::PublicKey::from_bytes(self.as_ref())
+            .map_err(errors::signature(SignatureErrorKind::MalformedPublicKey))?;
+        sign1.verify_signature(&[], |sig, data| {
+            let sig = ed25519_dalek::Signature::from_bytes(sig)
+                .map_err(errors::signature(SignatureErrorKind::MalformedSignature))?;
+            pk.verify_strict(data, &sig)
+                .map_err(errors::signature(SignatureErrorKind::VerificationFailed))
+        })?;
+        sign1.payload.ok_or(SessionIdError::Required)
+    }
+
+    /// Encode as a COSE_Key (OKP, Ed25519) with the session ID as `kid`
+    pub fn to_cose_key(&self) -> Result<Vec<u8>> {
+        CoseKeyBuilder::new_okp_key()
+            .param(
+                iana::OkpKeyParameter::Crv as i64,
+                Value::from(iana::EllipticCurve::Ed25519 as u64),
+            )
+            .param(iana::OkpKeyParameter::X as i64, Value::Bytes(self.to_vec()))
+            .algorithm(iana::Algorithm::EdDSA)
+            .key_id(self.to_vec())
+            .build()
+            .to_vec()
+            .map_err(cose_error)
+    }
+    /// Decode from a COSE_Key (OKP, Ed25519)
+    pub fn from_cose_key(bytes: &[u8]) -> Result<Self> {
+        let key = CoseKey::from_slice(bytes).map_err(cose_error)?;
+        if key.kty != KeyType::Assigned(iana::KeyType::OKP) {
+            return Err(SessionIdError::InvalidFormat(
+                "cose: kty is not OKP".to_string(),
+            ));
+        }
+        let param = |label: iana::OkpKeyParameter| {
+            key.params
+                .iter()
+                .find(|(l, _)| *l == Label::Int(label as i64))
+                .map(|(_, v)| v)
+        };
+        if param(iana::OkpKeyParameter::Crv)
+            != Some(&Value::from(iana::EllipticCurve::Ed25519 as u64))
+        {
+            return Err(SessionIdError::InvalidFormat(
+                "cose: crv is not Ed25519".to_string(),
+            ));
+        }
+        match param(iana::OkpKeyParameter::X) {
+            Some(Value::Bytes(x)) => SessionId::try_from(x),
+            _ => Err(SessionIdError::Required),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::new_session_id_pair;
+
+    #[test]
+    fn test_cose_sign1() {
+        let kp = new_session_id_pair().unwrap();
+        let sid = kp.get_id();
+        let bytes = kp.to_cose_sign1(b"testdata").unwrap();
+        // tag 18
+        assert_eq!(bytes[0], 0xd2);
+
+        assert_eq!(sid.verify_cose_sign1(&bytes).unwrap(), b"testdata");
+        let (signer, payload) = verify_cose_sign1(&bytes).unwrap();
+        assert_eq!(signer, sid);
+        assert_eq!(payload, b"testdata");
+
+        let other = new_session_id_pair().unwrap().get_id();
+        assert!(matches!(
+            other.verify_cose_sign1(&bytes),
+            Err(SessionIdError::Signature(
+                SignatureErrorKind::VerificationFailed
+            ))
+        ));
+
+        let mut sign1 = CoseSign1::from_tagged_slice(&bytes).unwrap();
+        sign1.payload = Some(b"testdatb".to_vec());
+        let tampered = sign1.to_vec().unwrap();
+        assert!(sid.verify_cose_sign1(&tampered).is_err());
+
+        assert!(matches!(
+            verify_cose_sign1(&[0xff]),
+            Err(SessionIdError::InvalidFormat(_))
+        ));
+    }
+
+    #[test]
+    fn test_cose_key() {
+        let sid = new_session_id_pair().unwrap().get_id();
+        let bytes = sid.to_cose_key().unwrap();
+        assert_eq!(SessionId::from_cose_key(&bytes).unwrap(), sid);
+
+        let key = CoseKey::from_slice(&bytes).unwrap();
+        assert_eq!(key.kty, KeyType::Assigned(iana::KeyType::OKP));
+        assert_eq!(key.key_id, sid.to_vec());
+
+        let ec2 = CoseKeyBuilder::new_ec2_pub_key(iana::EllipticCurve::P_256, vec![1], vec![2])
+            .build()
+            .to_vec()
+            .unwrap();
+        assert!(SessionId::from_cose_key(&ec2).is_err());
+
+        let x25519 = CoseKeyBuilder::new_okp_key()
+            .param(
+                iana::OkpKeyParameter::Crv as i64,
+                Value::from(iana::EllipticCurve::X25519 as u64),
+            )
+            .param(iana::OkpKeyParameter::X as i64, Value::Bytes(sid.to_vec()))
+            .build()
+            .to_vec()
+            .unwrap();
+        assert!(SessionId::from_cose_key(&x25519).is_err());
+    }
+}
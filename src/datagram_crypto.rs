@@ -0,0 +1,235 @@
+//! Encryption of datagrams on unreliable channels. Enabled with the `cipher` feature.
+//!
+//! Each packet carries a 4-byte big-endian counter as its explicit nonce, followed by the
+//! ChaCha20-Poly1305 ciphertext and tag, so 20 bytes of overhead in total. Each direction
+//! has its own key, derived from the X25519 shared secret of the session keys, both
+//! SessionIds and a random salt from each peer, exchanged in the signaling handshake.
+//! Fresh salts give each session fresh keys, so restarting the counter at 0 never reuses a
+//! nonce and packets of an earlier session do not open in a later one.
+//!
+//! Lost packets are skipped and late packets inside the [`ReplayWindow`] are accepted once.
+use crate::errors::{self, Result, SessionIdError};
+use crate::{ISessionIdPair, ReplayWindow, SessionId, SessionIdPair, X25519KeyAgreement};
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Nonce};
+use ed25519_dalek::{Digest, Sha512};
+use zeroize::Zeroize;
+
+const DATAGRAM_CONTEXT: &[u8] = b"verse-session-id/datagram/v1";
+const COUNTER_SIZE: usize = 4;
+/// Size of the per-session salt each peer contributes
+pub const DATAGRAM_SALT_SIZE: usize = 32;
+/// Bytes added to each plaintext (counter and tag)
+pub const DATAGRAM_OVERHEAD: usize = COUNTER_SIZE + 16;
+/// Largest packet that fits a WebRTC data channel message without fragmentation
+pub const DATAGRAM_MTU: usize = 1200;
+/// Largest plaintext accepted by [`DatagramCrypto::seal`]
+pub const DATAGRAM_MAX_PLAINTEXT: usize = DATAGRAM_MTU - DATAGRAM_OVERHEAD;
+
+fn nonce(counter: u32) -> Nonce {
+    let mut nonce = [0u8; 12];
+    nonce[12 - COUNTER_SIZE..].copy_from_slice(&counter.to_be_bytes());
+    Nonce::from(nonce)
+}
+
+/// Generate the salt to send to the peer before [`DatagramCrypto::establish`].
+/// Use a new one for every session.
+pub fn new_datagram_salt() -> Result<[u8; DATAGRAM_SALT_SIZE]> {
+    let mut salt = [0u8; DATAGRAM_SALT_SIZE];
+    getrandom::getrandom(&mut salt)?;
+    Ok(salt)
+}
+
+/// Datagram cipher shared by two peers, with a send counter and a receive window.
+/// Not `Clone`: two copies would send under the same counters.
+pub struct DatagramCrypto {
+    send: ChaCha20Poly1305,
+    recv: ChaCha20Poly1305,
+    next_counter: u64,
+    window: ReplayWindow,
+    their_id: SessionId,
+}
+
+impl DatagramCrypto {
+    /// Derive the ciphers shared with `their_id` for this session, with the default replay window.
+    /// `my_salt` comes from [`new_datagram_salt`] and `their_salt` from the peer.
+    pub fn establish(
+        my_pair: &SessionIdPair,
+        their_id: &SessionId,
+        my_salt: &[u8; DATAGRAM_SALT_SIZE],
+        their_salt: &[u8; DATAGRAM_SALT_SIZE],
+    ) -> Result<Self> {
+        Self::with_window(
+            my_pair,
+            their_id,
+            my_salt,
+            their_salt,
+            ReplayWindow::default(),
+        )
+    }
+    /// Like [`establish`](Self::establish), accepting late packets inside `window`
+    pub fn with_window(
+        my_pair: &SessionIdPair,
+        their_id: &SessionId,
+        my_salt: &[u8; DATAGRAM_SALT_SIZE],
+        their_salt: &[u8; DATAGRAM_SALT_SIZE],
+        window: ReplayWindow,
+    ) -> Result<Self> {
+        let shared = my_pair.diffie_hellman(their_id)?;
+        let my_id = my_pair.get_id();
+        let ((lo, lo_salt), (hi, hi_salt)) = if my_id <= *their_id {
+            ((my_id, my_salt), (*their_id, their_salt))
+        } else {
+            ((*their_id, their_salt), (my_id, my_salt))
+        };
+        let mut hash = Sha512::new()
+            .chain(DATAGRAM_CONTEXT)
+            .chain(shared)
+            .chain(lo)
+            .chain(lo_salt)
+            .chain(hi)
+            .chain(hi_salt)
+            .finalize();
+        // the first half keys packets from `lo` to `hi`
+        let (lo_to_hi, hi_to_lo) = hash.split_at(32);
+        let (send, recv) = if my_id == lo {
+            (lo_to_hi, hi_to_lo)
+        } else {
+            (hi_to_lo, lo_to_hi)
+        };
+        let crypto = DatagramCrypto {
+            send: ChaCha20Poly1305::new(send.into()),
+            recv: ChaCha20Poly1305::new(recv.into()),
+            next_counter: 0,
+            window,
+            their_id: *their_id,
+        };
+        hash[..].zeroize();
+        Ok(crypto)
+    }
+    /// The peer this cipher is shared with
+    pub fn their_id(&self) -> SessionId {
+        self.their_id
+    }
+    /// Encrypt `plaintext` (at most [`DATAGRAM_MAX_PLAINTEXT`] bytes), authenticating `ad` as well.
+    /// Fails once 2^32 packets have been sealed; establish a new session before then.
+    pub fn seal(&mut self, ad: &[u8], plaintext: &[u8]) -> Result<Vec<u8>> {
+        if plaintext.len() > DATAGRAM_MAX_PLAINTEXT {
+            return Err(SessionIdError::InvalidArgument(
+                "datagram larger than the mtu",
+            ));
+        }
+        let counter = u32::try_from(self.next_counter)
+            .map_err(|_| SessionIdError::InvalidArgument("datagram counter exhausted"))?;
+        let ciphertext = self
+            .send
+            .encrypt(
+                &nonce(counter),
+                Payload {
+                    msg: plaintext,
+                    aad: ad,
+                },
+            )
+            .map_err(|_| SessionIdError::InvalidArgument("datagram larger than the mtu"))?;
+        self.next_counter += 1;
+        let mut buf = Vec::with_capacity(COUNTER_SIZE + ciphertext.len());
+        buf.extend_from_slice(&counter.to_be_bytes());
+        buf.extend_from_slice(&ciphertext);
+        Ok(buf)
+    }
+    /// Decrypt a packet produced by the peer's [`seal`](Self::seal).
+    /// Duplicates and packets older than the window fail with `Replayed`.
+    pub fn open(&mut self, ad: &[u8], packet: &[u8]) -> Result<Vec<u8>> {
+        let Some((counter, ciphertext)) = packet
+            .split_first_chunk::<COUNTER_SIZE>()
+            .filter(|_| packet.len() >= DATAGRAM_OVERHEAD)
+        else {
+            return Err(errors::invalid_length(DATAGRAM_OVERHEAD, packet.len()));
+        };
+        let counter = u32::from_be_bytes(*counter);
+        self.window.check(counter as u64)?;
+        let plaintext = self
+            .recv
+            .decrypt(
+                &nonce(counter),
+                Payload {
+                    msg: ciphertext,
+                    aad: ad,
+                },
+            )
+            .map_err(|_| SessionIdError::Decryption)?;
+        self.window.check_and_update(counter as u64)?;
+        Ok(plaintext)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::new_session_id_pair;
+
+    #[test]
+    fn test_datagram_crypto() {
+        let a = new_session_id_pair().unwrap();
+        let b = new_session_id_pair().unwrap();
+        let (sa, sb) = (new_datagram_salt().unwrap(), new_datagram_salt().unwrap());
+        let mut ca = DatagramCrypto::establish(&a, &b.get_id(), &sa, &sb).unwrap();
+        let mut cb = DatagramCrypto::establish(&b, &a.get_id(), &sb, &sa).unwrap();
+        assert_eq!(ca.their_id(), b.get_id());
+
+        let p0 = ca.seal(b"move", b"pos0").unwrap();
+        let p1 = ca.seal(b"move", b"pos1").unwrap();
+        let p2 = ca.seal(b"move", b"pos2").unwrap();
+        assert_eq!(p0.len(), 4 + DATAGRAM_OVERHEAD);
+        assert_eq!(&p2[..4], &[0, 0, 0, 2]);
+
+        // reordered and lost packets, then a duplicate
+        assert_eq!(cb.open(b"move", &p2).unwrap(), b"pos2");
+        assert_eq!(cb.open(b"move", &p0).unwrap(), b"pos0");
+        assert!(matches!(
+            cb.open(b"move", &p2),
+            Err(SessionIdError::Replayed(2))
+        ));
+        assert!(cb.open(b"voice", &p1).is_err());
+        let mut tampered = p1.clone();
+        tampered[6] ^= 1;
+        assert!(matches!(
+            cb.open(b"move", &tampered),
+            Err(SessionIdError::Decryption)
+        ));
+        assert_eq!(cb.open(b"move", &p1).unwrap(), b"pos1");
+
+        // each direction has its own key
+        let reply = cb.seal(b"move", b"ack").unwrap();
+        assert!(cb.open(b"move", &reply).is_err());
+        assert_eq!(ca.open(b"move", &reply).unwrap(), b"ack");
+
+        assert!(ca.open(b"move", &p0[..10]).is_err());
+        let full = ca.seal(b"", &[0; DATAGRAM_MAX_PLAINTEXT]).unwrap();
+        assert_eq!(full.len(), DATAGRAM_MTU);
+        assert!(ca.seal(b"", &[0; DATAGRAM_MAX_PLAINTEXT + 1]).is_err());
+        ca.next_counter = u32::MAX as u64 + 1;
+        assert!(ca.seal(b"", b"x").is_err());
+    }
+
+    #[test]
+    fn test_datagram_sessions_have_fresh_keys() {
+        let a = new_session_id_pair().unwrap();
+        let b = new_session_id_pair().unwrap();
+        let mut sessions = Vec::new();
+        for _ in 0..2 {
+            let (sa, sb) = (new_datagram_salt().unwrap(), new_datagram_salt().unwrap());
+            let ca = DatagramCrypto::establish(&a, &b.get_id(), &sa, &sb).unwrap();
+            let cb = DatagramCrypto::establish(&b, &a.get_id(), &sb, &sa).unwrap();
+            sessions.push((ca, cb));
+        }
+        let first = sessions[0].0.seal(b"", b"same").unwrap();
+        let second = sessions[1].0.seal(b"", b"same").unwrap();
+        // both use counter 0
+        assert_eq!(first[..4], second[..4]);
+        assert_ne!(first, second);
+        // a packet of the first session does not open in the second
+        assert!(sessions[1].1.open(b"", &first).is_err());
+        assert_eq!(sessions[0].1.open(b"", &first).unwrap(), b"same");
+    }
+}
@@ -0,0 +1,86 @@
+//! Minimal DER writer for the X.509 structures built by this crate.
+#![cfg_attr(not(feature = "x509"), allow(dead_code))]
+
+pub(crate) const TAG_INTEGER: u8 = 0x02;
+pub(crate) const TAG_BIT_STRING: u8 = 0x03;
+pub(crate) const TAG_OCTET_STRING: u8 = 0x04;
+pub(crate) const TAG_OID: u8 = 0x06;
+pub(crate) const TAG_UTF8_STRING: u8 = 0x0c;
+pub(crate) const TAG_UTC_TIME: u8 = 0x17;
+pub(crate) const TAG_GENERALIZED_TIME: u8 = 0x18;
+pub(crate) const TAG_SEQUENCE: u8 = 0x30;
+pub(crate) const TAG_SET: u8 = 0x31;
+
+/// id-Ed25519 (1.3.101.112)
+pub(crate) const OID_ED25519: &[u8] = &[0x2b, 0x65, 0x70];
+
+pub(crate) fn tlv(tag: u8, content: &[u8]) -> Vec<u8> {
+    let mut buf = vec![tag];
+    let len = content.len();
+    if len < 0x80 {
+        buf.push(len as u8);
+    } else {
+        let bytes = len.to_be_bytes();
+        let skip = bytes.iter().take_while(|b| **b == 0).count();
+        buf.push(0x80 | (bytes.len() - skip) as u8);
+        buf.extend_from_slice(&bytes[skip..]);
+    }
+    buf.extend_from_slice(content);
+    buf
+}
+
+pub(crate) fn sequence(items: &[&[u8]]) -> Vec<u8> {
+    tlv(TAG_SEQUENCE, &items.concat())
+}
+
+pub(crate) fn bit_string(bytes: &[u8]) -> Vec<u8> {
+    // no unused bits
+    tlv(TAG_BIT_STRING, &[&[0u8][..], bytes].concat())
+}
+
+/// AlgorithmIdentifier of Ed25519 (no parameters)
+pub(crate) fn ed25519_algorithm() -> Vec<u8> {
+    sequence(&[&tlv(TAG_OID, OID_ED25519)])
+}
+
+/// Reads one TLV, returning `(tag, content, rest)`
+pub(crate) fn read_tlv(bytes: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+    let (&tag, rest) = bytes.split_first()?;
+    let (&first, rest) = rest.split_first()?;
+    let (len, rest) = if first < 0x80 {
+        (first as usize, rest)
+    } else {
+        let n = (first & 0x7f) as usize;
+        if n == 0 || n > std::mem::size_of::<usize>() || rest.len() < n {
+            return None;
+        }
+        let len = rest[..n]
+            .iter()
+            .fold(0usize, |acc, b| (acc << 8) | *b as usize);
+        // DER requires the shortest form
+        if len < 0x80 || rest[0] == 0 {
+            return None;
+        }
+        (len, &rest[n..])
+    };
+    if rest.len() < len {
+        return None;
+    }
+    Some((tag, &rest[..len], &rest[len..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tlv() {
+        assert_eq!(tlv(TAG_OCTET_STRING, &[1, 2]), vec![4, 2, 1, 2]);
+        let long = tlv(TAG_OCTET_STRING, &[0; 300]);
+        assert_eq!(&long[..4], &[4, 0x82, 1, 44]);
+        let (tag, content, rest) = read_tlv(&long).unwrap();
+        assert_eq!((tag, content.len(), rest.len()), (4, 300, 0));
+        assert!(read_tlv(&[4, 0x81, 0x10]).is_none());
+        assert!(read_tlv(&[4, 3, 1]).is_none());
+    }
+}
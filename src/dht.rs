@@ -0,0 +1,223 @@
+//! Kademlia-style XOR metric over session IDs, and a routing table of k-buckets.
+use crate::errors::{Result, SessionIdError};
+use crate::{SessionId, SESSION_ID_SIZE};
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+/// XOR distance between two session IDs (big-endian)
+pub type XorDistance = [u8; SESSION_ID_SIZE];
+
+impl SessionId {
+    pub fn xor_distance(&self, other: &SessionId) -> XorDistance {
+        let mut d = [0u8; SESSION_ID_SIZE];
+        for (d, (a, b)) in d.iter_mut().zip(self.as_raw().iter().zip(other.as_raw())) {
+            *d = a ^ b;
+        }
+        d
+    }
+    /// Compare `a` and `b` by their distance to this ID
+    pub fn cmp_distance(&self, a: &SessionId, b: &SessionId) -> Ordering {
+        self.xor_distance(a).cmp(&self.xor_distance(b))
+    }
+    /// Number of leading bits shared with `other` (256 if equal).
+    /// The Kademlia bucket of `other` is `255 - common_prefix_len` for other IDs.
+    pub fn common_prefix_len(&self, other: &SessionId) -> usize {
+        let d = self.xor_distance(other);
+        match d.iter().position(|v| *v != 0) {
+            Some(i) => i * 8 + d[i].leading_zeros() as usize,
+            None => SESSION_ID_SIZE * 8,
+        }
+    }
+}
+
+/// The `n` IDs closest to `target`, nearest first
+pub fn closest_n<'a>(
+    ids: impl IntoIterator<Item = &'a SessionId>,
+    target: &SessionId,
+    n: usize,
+) -> Vec<SessionId> {
+    if n == 0 {
+        return Vec::new();
+    }
+    // max-heap of the n closest seen so far
+    let mut heap = BinaryHeap::with_capacity(n + 1);
+    for id in ids {
+        heap.push((target.xor_distance(id), *id));
+        if heap.len() > n {
+            heap.pop();
+        }
+    }
+    heap.into_sorted_vec()
+        .into_iter()
+        .map(|(_, id)| id)
+        .collect()
+}
+
+/// Number of k-buckets in a [`RoutingTable`], one per common prefix length
+pub const ROUTING_TABLE_BUCKETS: usize = SESSION_ID_SIZE * 8;
+
+/// Outcome of [`RoutingTable::insert`]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Insertion {
+    /// Added to a bucket with free space
+    Inserted,
+    /// Already known; moved to the most recently seen position
+    Refreshed,
+    /// The bucket is full. The least recently seen entry is returned so that the caller can
+    /// ping it and either [`RoutingTable::remove`] it and insert again, or
+    /// [`RoutingTable::insert`] it to keep it.
+    BucketFull(SessionId),
+}
+
+/// Kademlia routing table around a local ID.
+/// Bucket `i` holds the IDs sharing exactly `i` leading bits with the local ID, at most `k`
+/// of them, least recently seen first.
+#[derive(Clone, Debug)]
+pub struct RoutingTable {
+    local: SessionId,
+    k: usize,
+    buckets: Vec<Vec<SessionId>>,
+}
+
+impl RoutingTable {
+    /// Empty table with buckets of at most `k` IDs
+    pub fn new(local: SessionId, k: usize) -> Self {
+        RoutingTable {
+            local,
+            k,
+            buckets: vec![Vec::new(); ROUTING_TABLE_BUCKETS],
+        }
+    }
+    pub fn local_id(&self) -> &SessionId {
+        &self.local
+    }
+    pub fn k(&self) -> usize {
+        self.k
+    }
+    /// Bucket of `id`, `None` for the local ID
+    pub fn bucket_index(&self, id: &SessionId) -> Option<usize> {
+        let n = self.local.common_prefix_len(id);
+        (n < ROUTING_TABLE_BUCKETS).then_some(n)
+    }
+    /// IDs of bucket `index`, least recently seen first
+    pub fn bucket(&self, index: usize) -> &[SessionId] {
+        self.buckets.get(index).map_or(&[], |v| v.as_slice())
+    }
+    /// Record that `id` was seen
+    pub fn insert(&mut self, id: SessionId) -> Result<Insertion> {
+        let index = self
+            .bucket_index(&id)
+            .ok_or(SessionIdError::InvalidArgument("local id"))?;
+        let k = self.k;
+        let bucket = &mut self.buckets[index];
+        if let Some(i) = bucket.iter().position(|v| *v == id) {
+            bucket.remove(i);
+            bucket.push(id);
+            return Ok(Insertion::Refreshed);
+        }
+        if bucket.len() >= k {
+            return Ok(match bucket.first() {
+                Some(oldest) => Insertion::BucketFull(*oldest),
+                // k == 0
+                None => Insertion::BucketFull(id),
+            });
+        }
+        bucket.push(id);
+        Ok(Insertion::Inserted)
+    }
+    /// Evict `id`. Returns whether it was present.
+    pub fn remove(&mut self, id: &SessionId) -> bool {
+        let Some(bucket) = self.bucket_index(id).map(|i| &mut self.buckets[i]) else {
+            return false;
+        };
+        match bucket.iter().position(|v| v == id) {
+            Some(i) => {
+                bucket.remove(i);
+                true
+            }
+            None => false,
+        }
+    }
+    pub fn contains(&self, id: &SessionId) -> bool {
+        self.bucket_index(id)
+            .is_some_and(|i| self.buckets[i].contains(id))
+    }
+    pub fn len(&self) -> usize {
+        self.buckets.iter().map(|v| v.len()).sum()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.buckets.iter().all(|v| v.is_empty())
+    }
+    pub fn iter(&self) -> impl Iterator<Item = &SessionId> {
+        self.buckets.iter().flatten()
+    }
+    /// The `n` known IDs closest to `target`, nearest first
+    pub fn closest(&self, target: &SessionId, n: usize) -> Vec<SessionId> {
+        closest_n(self.iter(), target, n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn id(first: u8) -> SessionId {
+        let mut v = [0u8; 32];
+        v[0] = first;
+        SessionId::from(v)
+    }
+
+    #[test]
+    fn test_xor_distance() {
+        let a = id(0b1010_0000);
+        let b = id(0b1000_0000);
+        let mut expected = [0u8; 32];
+        expected[0] = 0b0010_0000;
+        assert_eq!(a.xor_distance(&b), expected);
+        assert_eq!(b.xor_distance(&a), expected);
+        assert_eq!(a.xor_distance(&a), [0u8; 32]);
+        assert_eq!(a.common_prefix_len(&b), 2);
+        assert_eq!(a.common_prefix_len(&a), 256);
+
+        let target = id(0);
+        assert_eq!(target.cmp_distance(&id(1), &id(2)), Ordering::Less);
+    }
+
+    #[test]
+    fn test_closest_n() {
+        let ids: Vec<SessionId> = [9u8, 1, 200, 3, 64, 2].iter().map(|v| id(*v)).collect();
+        let target = id(0);
+        assert_eq!(closest_n(&ids, &target, 3), vec![id(1), id(2), id(3)]);
+        assert_eq!(closest_n(&ids, &id(0xff), 1), vec![id(200)]);
+        assert_eq!(closest_n(&ids, &target, 10).len(), ids.len());
+        assert!(closest_n(&ids, &target, 0).is_empty());
+    }
+
+    #[test]
+    fn test_routing_table() {
+        let mut table = RoutingTable::new(id(0), 2);
+        assert!(table.insert(id(0)).is_err());
+        assert_eq!(table.bucket_index(&id(0x80)), Some(0));
+        assert_eq!(table.bucket_index(&id(0x01)), Some(7));
+
+        assert_eq!(table.insert(id(0x80)).unwrap(), Insertion::Inserted);
+        assert_eq!(table.insert(id(0x81)).unwrap(), Insertion::Inserted);
+        assert_eq!(table.insert(id(0x80)).unwrap(), Insertion::Refreshed);
+        assert_eq!(table.bucket(0), &[id(0x81), id(0x80)]);
+        assert_eq!(
+            table.insert(id(0x82)).unwrap(),
+            Insertion::BucketFull(id(0x81))
+        );
+        assert!(!table.contains(&id(0x82)));
+        assert!(table.remove(&id(0x81)));
+        assert!(!table.remove(&id(0x81)));
+        assert_eq!(table.insert(id(0x82)).unwrap(), Insertion::Inserted);
+
+        table.insert(id(0x01)).unwrap();
+        table.insert(id(0x02)).unwrap();
+        assert_eq!(table.len(), 4);
+        assert_eq!(table.closest(&id(0x03), 2), vec![id(0x02), id(0x01)]);
+        assert_eq!(table.closest(&id(0x83), 1), vec![id(0x82)]);
+        assert!(table.bucket(ROUTING_TABLE_BUCKETS).is_empty());
+    }
+}
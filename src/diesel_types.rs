@@ -0,0 +1,118 @@
+//! Diesel column types for [`SessionId`]. Enabled with the `diesel` feature.
+//!
+//! `SessionId` maps to `Binary` columns as its 32 raw bytes and to `Text` columns as
+//! its base64 string, like the sqlx support. Writing `Text` is implemented for Postgres and
+//! MySQL, and for SQLite with the `diesel-sqlite` feature.
+//!
+//! ```rust,ignore
+//! table! {
+//!     presence (session_id) {
+//!         session_id -> Binary,
+//!         session_text -> Text,
+//!     }
+//! }
+//! let sid: SessionId = presence::table
+//!     .select(presence::session_text)
+//!     .filter(presence::session_id.eq(sid))
+//!     .first(&mut conn)?;
+//! ```
+use crate::{RawSessionId, SessionId};
+use diesel::backend::Backend;
+use diesel::deserialize::{self, FromSql};
+use diesel::serialize::{self, IsNull, Output, ToSql};
+use diesel::sql_types::{Binary, Text};
+use std::io::Write;
+
+impl<DB: Backend> ToSql<Binary, DB> for SessionId
+where
+    RawSessionId: ToSql<Binary, DB>,
+{
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, DB>) -> serialize::Result {
+        self.as_raw().to_sql(out)
+    }
+}
+
+impl<DB: Backend> FromSql<Binary, DB> for SessionId
+where
+    Vec<u8>: FromSql<Binary, DB>,
+{
+    fn from_sql(bytes: DB::RawValue<'_>) -> deserialize::Result<Self> {
+        Ok(SessionId::try_from(Vec::<u8>::from_sql(bytes)?)?)
+    }
+}
+
+macro_rules! impl_to_sql_text {
+    ($($db:ty),*) => {
+        $(impl ToSql<Text, $db> for SessionId {
+            fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, $db>) -> serialize::Result {
+                out.write_all(self.to_array_string().as_bytes())?;
+                Ok(IsNull::No)
+            }
+        })*
+    };
+}
+impl_to_sql_text!(diesel::pg::Pg, diesel::mysql::Mysql);
+
+#[cfg(feature = "diesel-sqlite")]
+impl ToSql<Text, diesel::sqlite::Sqlite> for SessionId {
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, diesel::sqlite::Sqlite>) -> serialize::Result {
+        out.set_value(self.to_string());
+        Ok(IsNull::No)
+    }
+}
+
+impl<DB: Backend> FromSql<Text, DB> for SessionId
+where
+    String: FromSql<Text, DB>,
+{
+    fn from_sql(bytes: DB::RawValue<'_>) -> deserialize::Result<Self> {
+        Ok(String::from_sql(bytes)?.parse()?)
+    }
+}
+
+#[cfg(all(test, feature = "diesel-sqlite"))]
+mod tests {
+    use super::*;
+    use diesel::prelude::*;
+    use diesel::sql_query;
+
+    diesel::table! {
+        presence (b) {
+            b -> Binary,
+            t -> Text,
+        }
+    }
+
+    #[test]
+    fn test_diesel_sqlite() {
+        let sid = SessionId::from([7; 32]);
+        let mut conn = SqliteConnection::establish(":memory:").unwrap();
+        sql_query("CREATE TABLE presence (b BLOB PRIMARY KEY NOT NULL, t TEXT NOT NULL)")
+            .execute(&mut conn)
+            .unwrap();
+        diesel::insert_into(presence::table)
+            .values((presence::b.eq(sid), presence::t.eq(sid)))
+            .execute(&mut conn)
+            .unwrap();
+
+        let row: (SessionId, SessionId) = presence::table
+            .filter(presence::b.eq(sid))
+            .first(&mut conn)
+            .unwrap();
+        assert_eq!(row, (sid, sid));
+        let text: String = presence::table
+            .select(presence::t)
+            .first(&mut conn)
+            .unwrap();
+        assert_eq!(text, sid.to_string());
+
+        diesel::update(presence::table)
+            .set(presence::t.eq("AAAA"))
+            .execute(&mut conn)
+            .unwrap();
+        assert!(presence::table
+            .select(presence::t)
+            .first::<SessionId>(&mut conn)
+            .is_err());
+    }
+}
@@ -0,0 +1,35 @@
+//! Length-prefixed binary encoding shared by the signed document formats.
+use crate::errors::{self, Result, SessionIdError};
+
+pub(crate) fn write_str(buf: &mut Vec<u8>, s: &str) -> Result<()> {
+    let n: u16 = s
+        .len()
+        .try_into()
+        .map_err(|_| SessionIdError::InvalidArgument("string too long"))?;
+    buf.extend_from_slice(&n.to_le_bytes());
+    buf.extend_from_slice(s.as_bytes());
+    Ok(())
+}
+
+pub(crate) struct Reader<'a>(pub(crate) &'a [u8]);
+
+impl<'a> Reader<'a> {
+    pub(crate) fn read(&mut self, n: usize) -> Result<&'a [u8]> {
+        if self.0.len() < n {
+            return Err(errors::invalid_length(n, self.0.len()));
+        }
+        let (head, tail) = self.0.split_at(n);
+        self.0 = tail;
+        Ok(head)
+    }
+    pub(crate) fn read_array<const N: usize>(&mut self) -> Result<[u8; N]> {
+        let mut v = [0u8; N];
+        v.copy_from_slice(self.read(N)?);
+        Ok(v)
+    }
+    pub(crate) fn read_str(&mut self) -> Result<String> {
+        let n = u16::from_le_bytes(self.read_array()?) as usize;
+        String::from_utf8(self.read(n)?.to_vec())
+            .map_err(|_| SessionIdError::InvalidFormat("not utf-8".to_string()))
+    }
+}
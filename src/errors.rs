@@ -1,45 +1,215 @@
-use ed25519_dalek::SignatureError;
 use thiserror::Error;
 
+/// Reason of a signature error
+///
+/// There is no salt mismatch kind: the salt is hashed into the signed prehash, so a
+/// signature made with another salt is indistinguishable from any other invalid signature
+/// and fails with `VerificationFailed`. Code 103 is reserved for it and never reused.
+#[derive(Error, Debug, Clone, Copy, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub enum SignatureErrorKind {
+    /// Session ID is not a valid ED25519 public key
+    #[error("malformed public key")]
+    MalformedPublicKey,
+    /// Signature bytes are not a valid ED25519 signature
+    #[error("malformed signature bytes")]
+    MalformedSignature,
+    /// Signature does not verify against the session ID and payload
+    #[error("signature does not verify")]
+    VerificationFailed,
+    /// Key could not be created or payload could not be signed
+    #[error("signing failed")]
+    SigningFailed,
+}
+
+/// Error type of this crate.
+/// Converts into `anyhow::Error` via `?` for callers that use anyhow.
 #[derive(Error, Debug)]
+#[non_exhaustive]
 pub enum SessionIdError {
-    #[error("signature error: {0:?} {1}:{2}")]
-    Signature(SignatureError, String, u32),
-    #[error("convert error: {0}:{1}")]
-    Convert(String, u32),
-    #[error("required error: {0}:{1}")]
-    Required(String, u32),
+    /// ED25519 key or signature error
+    #[error("signature error: {0}")]
+    Signature(SignatureErrorKind),
+    /// Input has an unexpected number of bytes
+    #[error("invalid length: expected {expected}, got {actual}")]
+    InvalidLength { expected: usize, actual: usize },
+    /// Input is not valid base64
+    #[error("base64 error: {0}")]
+    Base64(#[from] base64::DecodeError),
+    /// Input is not in the expected encoding (CBOR, JSON, ...)
+    #[error("invalid format: {0}")]
+    InvalidFormat(String),
+    /// The random number generator failed
+    #[error("random error: {0}")]
+    Random(#[from] getrandom::Error),
+    /// A required value is missing
+    #[error("required value is missing")]
+    Required,
+    /// Index is out of range
+    #[error("index out of range: {index} >= {len}")]
+    OutOfRange { index: u64, len: u64 },
+    /// Argument is not acceptable
+    #[error("invalid argument: {0}")]
+    InvalidArgument(&'static str),
+    /// Merkle inclusion proof does not lead to the signed root
+    #[error("merkle proof mismatch")]
+    MerkleProof,
+    /// Token or signed message has expired
+    #[error("expired")]
+    Expired,
+    /// Token or signed message is not valid yet
+    #[error("not yet valid")]
+    NotYetValid,
+    /// Claim does not match the expected value
+    #[error("invalid claim: {0}")]
+    InvalidClaim(&'static str),
+    /// Sequence number was already seen or arrived out of order
+    #[error("replayed sequence number: {0}")]
+    Replayed(u64),
+    /// Platform key storage failed
+    #[error("keystore error: {0}")]
+    Keystore(String),
+    /// Ciphertext was tampered with, truncated or sealed under another key
+    #[error("decryption failed")]
+    Decryption,
 }
 
-#[macro_export]
-macro_rules! signature {
-    () => {
-        |v| errors::SessionIdError::Signature(v, file!().to_string(), line!())
-    };
+impl SignatureErrorKind {
+    /// Stable numeric code. See [`SessionIdError::code`].
+    pub fn code(&self) -> u32 {
+        match self {
+            SignatureErrorKind::MalformedPublicKey => 101,
+            SignatureErrorKind::MalformedSignature => 102,
+            SignatureErrorKind::VerificationFailed => 104,
+            SignatureErrorKind::SigningFailed => 105,
+        }
+    }
 }
 
-#[macro_export]
-macro_rules! required {
-    () => {
-        || errors::SessionIdError::Required(file!().to_string(), line!())
-    };
+impl SessionIdError {
+    /// Stable numeric code for FFI and structured logs.
+    /// Codes are never reused or renumbered.
+    ///
+    /// | code | error |
+    /// |------|-------|
+    /// | 101 | `Signature(MalformedPublicKey)` |
+    /// | 102 | `Signature(MalformedSignature)` |
+    /// | 103 | reserved (salt mismatch, reported as `VerificationFailed`) |
+    /// | 104 | `Signature(VerificationFailed)` |
+    /// | 105 | `Signature(SigningFailed)` |
+    /// | 201 | `InvalidLength` |
+    /// | 202 | `Base64` |
+    /// | 203 | `InvalidFormat` |
+    /// | 301 | `Random` |
+    /// | 401 | `Required` |
+    /// | 402 | `OutOfRange` |
+    /// | 403 | `InvalidArgument` |
+    /// | 501 | `MerkleProof` |
+    /// | 601 | `Expired` |
+    /// | 602 | `NotYetValid` |
+    /// | 603 | `InvalidClaim` |
+    /// | 604 | `Replayed` |
+    /// | 701 | `Keystore` |
+    /// | 801 | `Decryption` |
+    pub fn code(&self) -> u32 {
+        match self {
+            SessionIdError::Signature(kind) => kind.code(),
+            SessionIdError::InvalidLength { .. } => 201,
+            SessionIdError::Base64(_) => 202,
+            SessionIdError::InvalidFormat(_) => 203,
+            SessionIdError::Random(_) => 301,
+            SessionIdError::Required => 401,
+            SessionIdError::OutOfRange { .. } => 402,
+            SessionIdError::InvalidArgument(_) => 403,
+            SessionIdError::MerkleProof => 501,
+            SessionIdError::Expired => 601,
+            SessionIdError::NotYetValid => 602,
+            SessionIdError::InvalidClaim(_) => 603,
+            SessionIdError::Replayed(_) => 604,
+            SessionIdError::Keystore(_) => 701,
+            SessionIdError::Decryption => 801,
+        }
+    }
+}
+
+pub type Result<T, E = SessionIdError> = std::result::Result<T, E>;
+
+pub(crate) fn signature(
+    kind: SignatureErrorKind,
+) -> impl FnOnce(ed25519_dalek::SignatureError) -> SessionIdError {
+    move |_| SessionIdError::Signature(kind)
 }
 
-#[macro_export]
-macro_rules! convert {
-    () => {
-        anyhow::Error::from(errors::SessionIdError::Convert(
-            file!().to_string(),
-            line!(),
-        ))
-    };
-    ( $v:expr ) => {
-        anyhow::anyhow!(errors::SessionIdError::Convert(
-            format!("{},   {}", $v, file!().to_string()),
-            line!()
-        ))
-    };
+pub(crate) fn invalid_length(expected: usize, actual: usize) -> SessionIdError {
+    SessionIdError::InvalidLength { expected, actual }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_code() {
+        assert_eq!(
+            SessionIdError::Signature(SignatureErrorKind::MalformedPublicKey).code(),
+            101
+        );
+        assert_eq!(
+            SessionIdError::Signature(SignatureErrorKind::VerificationFailed).code(),
+            104
+        );
+        assert_eq!(invalid_length(1, 2).code(), 201);
+        assert_eq!(
+            SessionIdError::from(base64::decode("!!").unwrap_err()).code(),
+            202
+        );
+        assert_eq!(SessionIdError::InvalidFormat("".to_string()).code(), 203);
+        assert_eq!(SessionIdError::Required.code(), 401);
+        assert_eq!(SessionIdError::OutOfRange { index: 1, len: 1 }.code(), 402);
+        assert_eq!(SessionIdError::InvalidArgument("").code(), 403);
+        assert_eq!(SessionIdError::MerkleProof.code(), 501);
+        assert_eq!(SessionIdError::Expired.code(), 601);
+        assert_eq!(SessionIdError::NotYetValid.code(), 602);
+        assert_eq!(SessionIdError::InvalidClaim("").code(), 603);
+        assert_eq!(SessionIdError::Replayed(0).code(), 604);
+        assert_eq!(SessionIdError::Keystore("".to_string()).code(), 701);
+        assert_eq!(SessionIdError::Decryption.code(), 801);
+    }
+
+    #[test]
+    fn test_error_codes_in_c_header() {
+        let header = include_str!("../include/verse_session_id.h");
+        let errors = [
+            SessionIdError::Signature(SignatureErrorKind::MalformedPublicKey),
+            SessionIdError::Signature(SignatureErrorKind::MalformedSignature),
+            SessionIdError::Signature(SignatureErrorKind::VerificationFailed),
+            SessionIdError::Signature(SignatureErrorKind::SigningFailed),
+            invalid_length(1, 2),
+            SessionIdError::from(base64::decode("!!").unwrap_err()),
+            SessionIdError::InvalidFormat("".to_string()),
+            SessionIdError::Random(getrandom::Error::UNSUPPORTED),
+            SessionIdError::Required,
+            SessionIdError::OutOfRange { index: 1, len: 1 },
+            SessionIdError::InvalidArgument(""),
+            SessionIdError::MerkleProof,
+            SessionIdError::Expired,
+            SessionIdError::NotYetValid,
+            SessionIdError::InvalidClaim(""),
+            SessionIdError::Replayed(0),
+            SessionIdError::Keystore("".to_string()),
+            SessionIdError::Decryption,
+        ];
+        for e in errors {
+            let code = e.code();
+            assert!(
+                header
+                    .lines()
+                    .any(|v| v.starts_with("#define VERSE_ERR_")
+                        && v.ends_with(&format!(" {}", code))),
+                "{} ({:?}) is missing in verse_session_id.h",
+                code,
+                e
+            );
+        }
+    }
 }
-pub(crate) use convert;
-pub(crate) use required;
-pub(crate) use signature;
@@ -0,0 +1,324 @@
+//! C FFI layer. Enabled with the `ffi` feature.
+//!
+//! Every function returns `0` on success or a [`SessionIdError::code`] on failure.
+//! See `include/verse_session_id.h` for the C declarations.
+//!
+//! Build the shared or static library with
+//! `cargo rustc --release --features ffi --crate-type cdylib` (or `staticlib`).
+use crate::errors::{Result, SessionIdError};
+use crate::{
+    new_session_id_pair, session_id_pair_from_bytes, ISessionIdPair, RawSessionId, SessionId,
+    SessionIdPair, SessionIdPublic, SignatureSet, SIGNATURE_SALT_SIZE, SIGNATURE_SIZE,
+};
+use std::ffi::{c_char, CStr};
+
+/// Success
+pub const VERSE_OK: u32 = 0;
+/// Buffer size (including NUL) for a base64 session ID string
+pub const VERSE_SESSION_ID_STR_SIZE: usize = 45;
+/// Buffer size (including NUL) for a base64 signature string
+pub const VERSE_SIGNATURE_SET_STR_SIZE: usize = 97;
+/// Bytes of serialized keypair (secret key followed by public key)
+pub const VERSE_SESSION_ID_PAIR_SIZE: usize = crate::SESSION_ID_PAIR_SIZE;
+
+/// Session ID
+#[repr(C)]
+pub struct VerseSessionId {
+    pub bytes: RawSessionId,
+}
+
+/// Session ID and private key pair
+#[repr(C)]
+pub struct VerseSessionIdPair {
+    pub bytes: [u8; VERSE_SESSION_ID_PAIR_SIZE],
+}
+
+/// Signature
+#[repr(C)]
+pub struct VerseSignatureSet {
+    pub signature: [u8; SIGNATURE_SIZE],
+    pub salt: [u8; SIGNATURE_SALT_SIZE],
+}
+
+const NULL_POINTER: SessionIdError = SessionIdError::InvalidArgument("null pointer");
+
+fn to_code(res: Result<()>) -> u32 {
+    match res {
+        Ok(()) => VERSE_OK,
+        Err(e) => e.code(),
+    }
+}
+
+unsafe fn as_ref<'a, T>(p: *const T) -> Result<&'a T> {
+    p.as_ref().ok_or(NULL_POINTER)
+}
+
+unsafe fn as_mut<'a, T>(p: *mut T) -> Result<&'a mut T> {
+    p.as_mut().ok_or(NULL_POINTER)
+}
+
+unsafe fn as_slice<'a>(data: *const u8, len: usize) -> Result<&'a [u8]> {
+    if len == 0 {
+        return Ok(&[]);
+    }
+    if data.is_null() {
+        return Err(NULL_POINTER);
+    }
+    Ok(std::slice::from_raw_parts(data, len))
+}
+
+unsafe fn as_str<'a>(s: *const c_char) -> Result<&'a str> {
+    if s.is_null() {
+        return Err(NULL_POINTER);
+    }
+    CStr::from_ptr(s)
+        .to_str()
+        .map_err(|_| SessionIdError::InvalidArgument("not utf-8"))
+}
+
+unsafe fn write_str(s: &str, buf: *mut c_char, buf_len: usize) -> Result<()> {
+    if buf.is_null() {
+        return Err(NULL_POINTER);
+    }
+    if buf_len < s.len() + 1 {
+        return Err(crate::errors::invalid_length(s.len() + 1, buf_len));
+    }
+    std::ptr::copy_nonoverlapping(s.as_ptr() as *const c_char, buf, s.len());
+    *buf.add(s.len()) = 0;
+    Ok(())
+}
+
+fn to_pair(pair: &VerseSessionIdPair) -> Result<SessionIdPair> {
+    session_id_pair_from_bytes(&pair.bytes)
+}
+
+/// Generate a keypair
+///
+/// # Safety
+/// `out` must be null or point to a writable `VerseSessionIdPair`.
+#[no_mangle]
+pub unsafe extern "C" fn verse_session_id_pair_generate(out: *mut VerseSessionIdPair) -> u32 {
+    to_code((|| {
+        let out = as_mut(out)?;
+        out.bytes = new_session_id_pair()?.to_bytes();
+        Ok(())
+    })())
+}
+
+/// Get the session ID of a keypair
+///
+/// # Safety
+/// `pair` and `out` must be null or valid pointers.
+#[no_mangle]
+pub unsafe extern "C" fn verse_session_id_pair_get_id(
+    pair: *const VerseSessionIdPair,
+    out: *mut VerseSessionId,
+) -> u32 {
+    to_code((|| {
+        let pair = to_pair(as_ref(pair)?)?;
+        as_mut(out)?.bytes.copy_from_slice(pair.get_id().as_ref());
+        Ok(())
+    })())
+}
+
+/// Create a signature for `data`
+///
+/// # Safety
+/// `pair` and `out` must be null or valid pointers.
+/// `data` must be null or point to `data_len` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn verse_session_id_pair_sign(
+    pair: *const VerseSessionIdPair,
+    data: *const u8,
+    data_len: usize,
+    out: *mut VerseSignatureSet,
+) -> u32 {
+    to_code((|| {
+        let pair = to_pair(as_ref(pair)?)?;
+        let ss = pair.sign([as_slice(data, data_len)?])?;
+        let out = as_mut(out)?;
+        out.signature = ss.signature;
+        out.salt = ss.salt;
+        Ok(())
+    })())
+}
+
+/// Verify a signature of `data`
+///
+/// # Safety
+/// `session_id` and `sigset` must be null or valid pointers.
+/// `data` must be null or point to `data_len` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn verse_session_id_verify(
+    session_id: *const VerseSessionId,
+    data: *const u8,
+    data_len: usize,
+    sigset: *const VerseSignatureSet,
+) -> u32 {
+    to_code((|| {
+        let session_id = SessionId::from(as_ref(session_id)?.bytes);
+        let sigset = as_ref(sigset)?;
+        let ss = SignatureSet {
+            signature: sigset.signature,
+            salt: sigset.salt,
+        };
+        session_id.verify([as_slice(data, data_len)?], &ss)
+    })())
+}
+
+/// Parse a session ID from a NUL-terminated base64 string
+///
+/// # Safety
+/// `s` must be null or a NUL-terminated string. `out` must be null or a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn verse_session_id_from_str(
+    s: *const c_char,
+    out: *mut VerseSessionId,
+) -> u32 {
+    to_code((|| {
+        let sid: SessionId = as_str(s)?.parse()?;
+        as_mut(out)?.bytes.copy_from_slice(sid.as_ref());
+        Ok(())
+    })())
+}
+
+/// Write a session ID as a NUL-terminated base64 string.
+/// `buf_len` must be at least `VERSE_SESSION_ID_STR_SIZE`.
+///
+/// # Safety
+/// `session_id` must be null or a valid pointer.
+/// `buf` must be null or point to `buf_len` writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn verse_session_id_to_str(
+    session_id: *const VerseSessionId,
+    buf: *mut c_char,
+    buf_len: usize,
+) -> u32 {
+    to_code((|| {
+        let sid = SessionId::from(as_ref(session_id)?.bytes);
+        write_str(&sid.to_string(), buf, buf_len)
+    })())
+}
+
+/// Parse a signature from a NUL-terminated base64 string
+///
+/// # Safety
+/// `s` must be null or a NUL-terminated string. `out` must be null or a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn verse_signature_set_from_str(
+    s: *const c_char,
+    out: *mut VerseSignatureSet,
+) -> u32 {
+    to_code((|| {
+        let ss: SignatureSet = as_str(s)?.parse()?;
+        let out = as_mut(out)?;
+        out.signature = ss.signature;
+        out.salt = ss.salt;
+        Ok(())
+    })())
+}
+
+/// Write a signature as a NUL-terminated base64 string.
+/// `buf_len` must be at least `VERSE_SIGNATURE_SET_STR_SIZE`.
+///
+/// # Safety
+/// `sigset` must be null or a valid pointer.
+/// `buf` must be null or point to `buf_len` writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn verse_signature_set_to_str(
+    sigset: *const VerseSignatureSet,
+    buf: *mut c_char,
+    buf_len: usize,
+) -> u32 {
+    to_code((|| {
+        let sigset = as_ref(sigset)?;
+        let ss = SignatureSet {
+            signature: sigset.signature,
+            salt: sigset.salt,
+        };
+        write_str(&ss.to_string(), buf, buf_len)
+    })())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CString;
+
+    #[test]
+    fn test_ffi_sign_verify() {
+        unsafe {
+            let mut pair = VerseSessionIdPair { bytes: [0; 64] };
+            assert_eq!(verse_session_id_pair_generate(&mut pair), VERSE_OK);
+            let mut sid = VerseSessionId { bytes: [0; 32] };
+            assert_eq!(verse_session_id_pair_get_id(&pair, &mut sid), VERSE_OK);
+
+            let data = b"testdata";
+            let mut ss = VerseSignatureSet {
+                signature: [0; SIGNATURE_SIZE],
+                salt: [0; SIGNATURE_SALT_SIZE],
+            };
+            assert_eq!(
+                verse_session_id_pair_sign(&pair, data.as_ptr(), data.len(), &mut ss),
+                VERSE_OK
+            );
+            assert_eq!(
+                verse_session_id_verify(&sid, data.as_ptr(), data.len(), &ss),
+                VERSE_OK
+            );
+            assert_eq!(
+                verse_session_id_verify(&sid, data.as_ptr(), data.len() - 1, &ss),
+                104
+            );
+            assert_eq!(
+                verse_session_id_verify(std::ptr::null(), data.as_ptr(), data.len(), &ss),
+                403
+            );
+        }
+    }
+
+    #[test]
+    fn test_ffi_string() {
+        unsafe {
+            let sid = VerseSessionId { bytes: [1; 32] };
+            let mut buf = [0 as c_char; VERSE_SESSION_ID_STR_SIZE];
+            assert_eq!(
+                verse_session_id_to_str(&sid, buf.as_mut_ptr(), buf.len()),
+                VERSE_OK
+            );
+            let s = CStr::from_ptr(buf.as_ptr()).to_str().unwrap();
+            assert_eq!(s, SessionId::from([1; 32]).to_string());
+            assert_eq!(
+                verse_session_id_to_str(&sid, buf.as_mut_ptr(), buf.len() - 1),
+                201
+            );
+
+            let mut sid1 = VerseSessionId { bytes: [0; 32] };
+            let cs = CString::new(s).unwrap();
+            assert_eq!(verse_session_id_from_str(cs.as_ptr(), &mut sid1), VERSE_OK);
+            assert_eq!(sid1.bytes, sid.bytes);
+            let cs = CString::new("!!").unwrap();
+            assert_eq!(verse_session_id_from_str(cs.as_ptr(), &mut sid1), 202);
+
+            let ss = VerseSignatureSet {
+                signature: [1; SIGNATURE_SIZE],
+                salt: [2; SIGNATURE_SALT_SIZE],
+            };
+            let mut buf = [0 as c_char; VERSE_SIGNATURE_SET_STR_SIZE];
+            assert_eq!(
+                verse_signature_set_to_str(&ss, buf.as_mut_ptr(), buf.len()),
+                VERSE_OK
+            );
+            let mut ss1 = VerseSignatureSet {
+                signature: [0; SIGNATURE_SIZE],
+                salt: [0; SIGNATURE_SALT_SIZE],
+            };
+            assert_eq!(
+                verse_signature_set_from_str(buf.as_ptr(), &mut ss1),
+                VERSE_OK
+            );
+            assert_eq!(ss1.signature, ss.signature);
+            assert_eq!(ss1.salt, ss.salt);
+        }
+    }
+}
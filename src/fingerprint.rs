@@ -0,0 +1,209 @@
+//! Human-comparable fingerprints of session IDs.
+//!
+//! [`SessionId::fingerprint`] is the SHA-256 of the raw 32-byte key, the same value
+//! `sha256sum` prints for the key bytes, for logs and UIs.
+//!
+//! Uses the 64 symbol table of the Matrix SAS emoji verification, so that each symbol
+//! also has an unambiguous name that can be read aloud. A fingerprint is 7 symbols
+//! (42 bits) derived from a hash of the ID, which a forger cannot target without
+//! grinding on the order of 2^42 keys per victim.
+use crate::SessionId;
+use ed25519_dalek::{Digest, Sha512};
+use sha2::Sha256;
+use std::fmt;
+
+const FINGERPRINT_CONTEXT: &[u8] = b"verse-session-id/fingerprint/v1";
+/// Number of symbols in an emoji/word fingerprint
+pub const FINGERPRINT_SYMBOLS: usize = 7;
+
+const SYMBOLS: [(&str, &str); 64] = [
+    ("🐶", "Dog"),
+    ("🐱", "Cat"),
+    ("🦁", "Lion"),
+    ("🐎", "Horse"),
+    ("🦄", "Unicorn"),
+    ("🐷", "Pig"),
+    ("🐘", "Elephant"),
+    ("🐰", "Rabbit"),
+    ("🐼", "Panda"),
+    ("🐓", "Rooster"),
+    ("🐧", "Penguin"),
+    ("🐢", "Turtle"),
+    ("🐟", "Fish"),
+    ("🐙", "Octopus"),
+    ("🦋", "Butterfly"),
+    ("🌷", "Flower"),
+    ("🌳", "Tree"),
+    ("🌵", "Cactus"),
+    ("🍄", "Mushroom"),
+    ("🌏", "Globe"),
+    ("🌙", "Moon"),
+    ("☁️", "Cloud"),
+    ("🔥", "Fire"),
+    ("🍌", "Banana"),
+    ("🍎", "Apple"),
+    ("🍓", "Strawberry"),
+    ("🌽", "Corn"),
+    ("🍕", "Pizza"),
+    ("🎂", "Cake"),
+    ("❤️", "Heart"),
+    ("😀", "Smiley"),
+    ("🤖", "Robot"),
+    ("🎩", "Hat"),
+    ("👓", "Glasses"),
+    ("🔧", "Spanner"),
+    ("🎅", "Santa"),
+    ("👍", "Thumbs Up"),
+    ("☂️", "Umbrella"),
+    ("⌛", "Hourglass"),
+    ("⏰", "Clock"),
+    ("🎁", "Gift"),
+    ("💡", "Light Bulb"),
+    ("📕", "Book"),
+    ("✏️", "Pencil"),
+    ("📎", "Paperclip"),
+    ("✂️", "Scissors"),
+    ("🔒", "Lock"),
+    ("🔑", "Key"),
+    ("🔨", "Hammer"),
+    ("☎️", "Telephone"),
+    ("🏁", "Flag"),
+    ("🚂", "Train"),
+    ("🚲", "Bicycle"),
+    ("✈️", "Aeroplane"),
+    ("🚀", "Rocket"),
+    ("🏆", "Trophy"),
+    ("⚽", "Ball"),
+    ("🎸", "Guitar"),
+    ("🎺", "Trumpet"),
+    ("🔔", "Bell"),
+    ("⚓", "Anchor"),
+    ("🎧", "Headphones"),
+    ("📁", "Folder"),
+    ("📌", "Pin"),
+];
+
+/// SHA-256 of a session ID
+#[derive(Clone, Copy, Eq, PartialEq, Hash, Debug)]
+pub struct Fingerprint([u8; 32]);
+
+impl Fingerprint {
+    /// Lowercase hex, 64 characters
+    pub fn to_hex(&self) -> String {
+        self.truncated(64)
+    }
+    /// First `n` lowercase hex characters (at most 64)
+    pub fn truncated(&self, n: usize) -> String {
+        let mut s = String::with_capacity(64);
+        for b in self.0 {
+            s.push_str(&format!("{:02x}", b));
+        }
+        s.truncate(n);
+        s
+    }
+    /// Uppercase hex in groups of 4, e.g. `1F3A 09C2 ...`
+    pub fn hex_grouped(&self) -> String {
+        self.hex_grouped_truncated(64)
+    }
+    /// The first `n` hex characters of [`Fingerprint::hex_grouped`]
+    pub fn hex_grouped_truncated(&self, n: usize) -> String {
+        self.truncated(n)
+            .to_uppercase()
+            .as_bytes()
+            .chunks(4)
+            .map(|v| std::str::from_utf8(v).unwrap())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+impl AsRef<[u8]> for Fingerprint {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+/// Lowercase hex. The precision truncates, e.g. `{:.16}`.
+impl fmt::Display for Fingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.truncated(f.precision().unwrap_or(64)))
+    }
+}
+
+impl SessionId {
+    pub fn fingerprint(&self) -> Fingerprint {
+        let mut v = [0u8; 32];
+        v.copy_from_slice(&Sha256::digest(self.as_ref()));
+        Fingerprint(v)
+    }
+    /// Indexes into the symbol table (0..64)
+    fn fingerprint_indexes(&self) -> [usize; FINGERPRINT_SYMBOLS] {
+        let hash = Sha512::new()
+            .chain(FINGERPRINT_CONTEXT)
+            .chain(self)
+            .finalize();
+        let mut v = [0u8; 8];
+        v.copy_from_slice(&hash[..8]);
+        let bits = u64::from_be_bytes(v);
+        let mut res = [0usize; FINGERPRINT_SYMBOLS];
+        for (i, r) in res.iter_mut().enumerate() {
+            *r = ((bits >> (58 - i * 6)) & 0x3f) as usize;
+        }
+        res
+    }
+    /// e.g. `🐶 🔑 🎸 🌙 🍕 ⚓ 🚲`
+    pub fn to_emoji_fingerprint(&self) -> String {
+        self.fingerprint_indexes()
+            .iter()
+            .map(|i| SYMBOLS[*i].0)
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+    /// Names of the emoji fingerprint symbols, e.g. `Dog Key Guitar Moon Pizza Anchor Bicycle`
+    pub fn to_word_fingerprint(&self) -> String {
+        self.fingerprint_indexes()
+            .iter()
+            .map(|i| SYMBOLS[*i].1)
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sha256_fingerprint() {
+        let fp = SessionId::from([0; 32]).fingerprint();
+        assert_eq!(
+            fp.to_hex(),
+            "66687aadf862bd776c8fc18b8e9f8e20089714856ee233b3902a591d0d5f2925"
+        );
+        assert_eq!(fp.truncated(8), "66687aad");
+        assert_eq!(format!("{:.8}", fp), "66687aad");
+        assert_eq!(format!("{}", fp), fp.to_hex());
+        assert_eq!(fp.hex_grouped_truncated(10), "6668 7AAD F8");
+        assert_eq!(fp.hex_grouped().len(), 64 + 15);
+        assert_eq!(fp.as_ref().len(), 32);
+    }
+
+    #[test]
+    fn test_fingerprint() {
+        let a = SessionId::from([1; 32]);
+        let b = SessionId::from([2; 32]);
+        assert_eq!(
+            a.to_emoji_fingerprint().split(' ').count(),
+            FINGERPRINT_SYMBOLS
+        );
+        assert_eq!(a.to_emoji_fingerprint(), a.to_emoji_fingerprint());
+        assert_ne!(a.to_emoji_fingerprint(), b.to_emoji_fingerprint());
+        assert_ne!(a.to_word_fingerprint(), b.to_word_fingerprint());
+
+        // words and emoji name the same symbols
+        let idx = a.fingerprint_indexes();
+        let emoji: Vec<&str> = idx.iter().map(|i| SYMBOLS[*i].0).collect();
+        let words: Vec<&str> = idx.iter().map(|i| SYMBOLS[*i].1).collect();
+        assert_eq!(a.to_emoji_fingerprint(), emoji.join(" "));
+        assert_eq!(a.to_word_fingerprint(), words.join(" "));
+        assert!(idx.iter().all(|i| *i < SYMBOLS.len()));
+    }
+}
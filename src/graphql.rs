@@ -0,0 +1,88 @@
+//! async-graphql scalars for [`SessionId`] and [`SignatureSet`]. Enabled with the `graphql` feature.
+//!
+//! Both are exposed as standard base64 strings (44 and 96 characters) and validated when
+//! parsed, so a malformed argument is rejected before the resolver runs.
+//!
+//! ```graphql
+//! type Query {
+//!   presence(sessionId: SessionId!): Presence
+//! }
+//! ```
+use crate::{SessionId, SignatureSet};
+use async_graphql::{InputType, InputValueError, InputValueResult, Scalar, ScalarType, Value};
+use std::str::FromStr;
+
+fn parse_string<T>(value: Value) -> InputValueResult<T>
+where
+    T: FromStr + InputType,
+    T::Err: std::fmt::Display,
+{
+    match value {
+        Value::String(s) => s.parse().map_err(InputValueError::custom),
+        v => Err(InputValueError::expected_type(v)),
+    }
+}
+
+/// Ed25519 public key of a Verse session, standard base64
+#[Scalar(name = "SessionId")]
+impl ScalarType for SessionId {
+    fn parse(value: Value) -> InputValueResult<Self> {
+        parse_string(value)
+    }
+    fn to_value(&self) -> Value {
+        Value::String(self.to_string())
+    }
+}
+
+/// Ed25519 signature followed by its salt, standard base64
+#[Scalar(name = "SignatureSet")]
+impl ScalarType for SignatureSet {
+    fn parse(value: Value) -> InputValueResult<Self> {
+        parse_string(value)
+    }
+    fn to_value(&self) -> Value {
+        Value::String(self.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{new_session_id_pair, ISessionIdPair, SessionIdPublic};
+    use async_graphql::{EmptyMutation, EmptySubscription, Object, Schema};
+
+    struct Query;
+
+    #[Object]
+    impl Query {
+        async fn verify(&self, session_id: SessionId, signature: SignatureSet) -> bool {
+            session_id.verify([b"hello"], &signature).is_ok()
+        }
+        async fn echo(&self, session_id: SessionId) -> SessionId {
+            session_id
+        }
+    }
+
+    #[tokio::test]
+    async fn test_graphql_scalars() {
+        let schema = Schema::new(Query, EmptyMutation, EmptySubscription);
+        assert!(schema.sdl().contains("scalar SessionId"));
+
+        let pair = new_session_id_pair().unwrap();
+        let sid = pair.get_id();
+        let sig = pair.sign([b"hello"]).unwrap();
+        let res = schema
+            .execute(format!(
+                r#"{{ verify(sessionId: "{}", signature: "{}") echo(sessionId: "{}") }}"#,
+                sid, sig, sid
+            ))
+            .await;
+        assert!(res.errors.is_empty(), "{:?}", res.errors);
+        let data = res.data.into_json().unwrap();
+        assert_eq!(data["verify"], true);
+        assert_eq!(data["echo"], sid.to_string());
+
+        let res = schema.execute(r#"{ echo(sessionId: "AAAA") }"#).await;
+        assert_eq!(res.errors.len(), 1);
+    }
+}
@@ -0,0 +1,348 @@
+//! End-to-end encrypted group broadcasts with sender keys. Enabled with the `group` feature.
+//!
+//! The room owner generates a sender key for every member and distributes the set of
+//! all members' keys, sealed to each member's SessionId and signed by the owner.
+//! Members encrypt broadcasts with their own sender key and sign them with their session
+//! key, so that other members, who know the sender key, cannot forge them.
+//! Any membership change starts a new epoch with fresh keys, so removed members cannot
+//! read later messages and new members cannot read earlier ones.
+use crate::encoding::Reader;
+use crate::errors::{self, Result, SessionIdError, SignatureErrorKind};
+use crate::{
+    ISessionIdPair, PeerCipher, SessionId, SessionIdPair, SessionIdPublic, SignatureSet,
+    X25519KeyAgreement, SESSION_ID_SIZE, SIGNATURE_SET_SIZE, X25519_KEY_SIZE,
+};
+use curve25519_dalek::constants::X25519_BASEPOINT;
+use curve25519_dalek::montgomery::MontgomeryPoint;
+use curve25519_dalek::scalar::Scalar;
+use ed25519_dalek::{Digest, Sha512};
+use std::collections::BTreeMap;
+use zeroize::{Zeroize, Zeroizing};
+
+const SEAL_CONTEXT: &[u8] = b"verse-session-id/sealed-box/v1";
+const DISTRIBUTION_CONTEXT: &[u8] = b"verse-session-id/group-keys/v1";
+const BROADCAST_CONTEXT: &[u8] = b"verse-session-id/group-broadcast/v1";
+
+/// Symmetric key of one member for one epoch
+pub type SenderKey = [u8; 32];
+
+fn seal_key(shared: &[u8], ephemeral: &[u8], recipient: &[u8]) -> Zeroizing<[u8; 32]> {
+    let mut hash = Sha512::new()
+        .chain(SEAL_CONTEXT)
+        .chain(shared)
+        .chain(ephemeral)
+        .chain(recipient)
+        .finalize();
+    let mut key = Zeroizing::new([0u8; 32]);
+    key.copy_from_slice(&hash[..32]);
+    hash[..].zeroize();
+    key
+}
+
+/// Encrypt `plaintext` so that only the owner of `recipient` can read it.
+/// The sender stays anonymous; sign the result to authenticate it.
+pub fn seal(recipient: &SessionId, plaintext: &[u8]) -> Result<Vec<u8>> {
+    let mut secret = [0u8; X25519_KEY_SIZE];
+    getrandom::getrandom(&mut secret)?;
+    secret[0] &= 248;
+    secret[31] &= 127;
+    secret[31] |= 64;
+    let secret = Scalar::from_bits(secret);
+    let ephemeral = (X25519_BASEPOINT * secret).to_bytes();
+    let their_public = recipient.to_x25519_public()?;
+    let shared = (MontgomeryPoint(their_public) * secret).to_bytes();
+    if shared == [0u8; X25519_KEY_SIZE] {
+        return Err(SessionIdError::Signature(
+            SignatureErrorKind::MalformedPublicKey,
+        ));
+    }
+    let key = seal_key(&shared, &ephemeral, &their_public);
+    let mut buf = ephemeral.to_vec();
+    buf.extend_from_slice(&PeerCipher::with_key(&key, recipient).encrypt(&[], plaintext)?);
+    Ok(buf)
+}
+
+/// Decrypt a message produced by [`seal`] for this pair
+pub fn open_sealed(pair: &SessionIdPair, sealed: &[u8]) -> Result<Vec<u8>> {
+    let Some((ephemeral, ciphertext)) = sealed.split_first_chunk::<X25519_KEY_SIZE>() else {
+        return Err(errors::invalid_length(X25519_KEY_SIZE, sealed.len()));
+    };
+    let my_id = pair.get_id();
+    let my_public = my_id.to_x25519_public()?;
+    let shared =
+        (MontgomeryPoint(*ephemeral) * Scalar::from_bits(pair.to_x25519_secret())).to_bytes();
+    let key = seal_key(&shared, ephemeral, &my_public);
+    PeerCipher::with_key(&key, &my_id).decrypt(&[], ciphertext)
+}
+
+fn random_key() -> Result<Zeroizing<SenderKey>> {
+    let mut key = Zeroizing::new([0u8; 32]);
+    getrandom::getrandom(&mut key[..])?;
+    Ok(key)
+}
+
+fn group_error(msg: &str) -> SessionIdError {
+    SessionIdError::InvalidFormat(format!("group: {}", msg))
+}
+
+/// Key management on the room owner's side
+pub struct GroupOwner {
+    room_id: String,
+    epoch: u32,
+    keys: BTreeMap<SessionId, Zeroizing<SenderKey>>,
+}
+
+impl GroupOwner {
+    /// Room without members, at epoch 0
+    pub fn new(room_id: impl Into<String>) -> Self {
+        GroupOwner {
+            room_id: room_id.into(),
+            epoch: 0,
+            keys: BTreeMap::new(),
+        }
+    }
+    pub fn room_id(&self) -> &str {
+        &self.room_id
+    }
+    pub fn epoch(&self) -> u32 {
+        self.epoch
+    }
+    pub fn members(&self) -> impl Iterator<Item = &SessionId> {
+        self.keys.keys()
+    }
+    /// Add a member and rotate all keys. Distribute the keys again afterwards.
+    pub fn add_member(&mut self, id: SessionId) -> Result<()> {
+        self.keys.insert(id, Zeroizing::new([0; 32]));
+        self.rotate()
+    }
+    /// Remove a member and rotate all keys. Distribute the keys again afterwards.
+    pub fn remove_member(&mut self, id: &SessionId) -> Result<()> {
+        self.keys.remove(id);
+        self.rotate()
+    }
+    /// Start a new epoch with fresh sender keys
+    pub fn rotate(&mut self) -> Result<()> {
+        for key in self.keys.values_mut() {
+            *key = random_key()?;
+        }
+        self.epoch = self
+            .epoch
+            .checked_add(1)
+            .ok_or(SessionIdError::InvalidArgument("epoch overflow"))?;
+        Ok(())
+    }
+    /// Key distribution message for every member of the current epoch
+    pub fn distribute(&self, owner: &SessionIdPair) -> Result<Vec<(SessionId, Vec<u8>)>> {
+        let n: u16 = self
+            .keys
+            .len()
+            .try_into()
+            .map_err(|_| SessionIdError::InvalidArgument("too many members"))?;
+        // sized up front so that no copy of the keys is left behind by a reallocation
+        let mut plaintext = Zeroizing::new(Vec::with_capacity(2 + self.keys.len() * 64));
+        plaintext.extend_from_slice(&n.to_le_bytes());
+        for (id, key) in &self.keys {
+            plaintext.extend_from_slice(id.as_ref());
+            plaintext.extend_from_slice(&key[..]);
+        }
+        self.keys
+            .keys()
+            .map(|member| {
+                let mut msg = self.epoch.to_le_bytes().to_vec();
+                msg.extend_from_slice(&seal(member, &plaintext)?);
+                let signature = owner.sign([
+                    DISTRIBUTION_CONTEXT,
+                    self.room_id.as_bytes(),
+                    member.as_ref(),
+                    &msg,
+                ])?;
+                msg.extend_from_slice(&signature.to_bytes());
+                Ok((*member, msg))
+            })
+            .collect()
+    }
+}
+
+/// Sender keys of the current epoch on a member's side
+pub struct GroupMember {
+    room_id: String,
+    owner: SessionId,
+    epoch: u32,
+    keys: BTreeMap<SessionId, Zeroizing<SenderKey>>,
+}
+
+impl GroupMember {
+    /// Member of the room owned by `owner`. Call [`apply_distribution`](Self::apply_distribution) before use.
+    pub fn new(room_id: impl Into<String>, owner: SessionId) -> Self {
+        GroupMember {
+            room_id: room_id.into(),
+            owner,
+            epoch: 0,
+            keys: BTreeMap::new(),
+        }
+    }
+    pub fn epoch(&self) -> u32 {
+        self.epoch
+    }
+    /// Accept keys distributed by the owner. Messages of older epochs are rejected.
+    pub fn apply_distribution(&mut self, my_pair: &SessionIdPair, msg: &[u8]) -> Result<()> {
+        let Some(body_len) = msg.len().checked_sub(SIGNATURE_SET_SIZE) else {
+            return Err(errors::invalid_length(SIGNATURE_SET_SIZE, msg.len()));
+        };
+        let (body, signature) = msg.split_at(body_len);
+        let signature = SignatureSet::from_bytes(signature.try_into().unwrap());
+        let my_id = my_pair.get_id();
+        self.owner.verify(
+            [
+                DISTRIBUTION_CONTEXT,
+                self.room_id.as_bytes(),
+                my_id.as_ref(),
+                body,
+            ],
+            &signature,
+        )?;
+        let mut r = Reader(body);
+        let epoch = u32::from_le_bytes(r.read_array()?);
+        if epoch <= self.epoch {
+            return Err(group_error("stale epoch"));
+        }
+        let plaintext = Zeroizing::new(open_sealed(my_pair, r.0)?);
+        let mut r = Reader(&plaintext);
+        let n = u16::from_le_bytes(r.read_array()?);
+        let mut keys = BTreeMap::new();
+        for _ in 0..n {
+            let id = SessionId::from(r.read_array::<SESSION_ID_SIZE>()?);
+            let mut key = Zeroizing::new([0u8; 32]);
+            key.copy_from_slice(r.read(32)?);
+            keys.insert(id, key);
+        }
+        if !keys.contains_key(&my_id) {
+            return Err(group_error("not a member"));
+        }
+        self.epoch = epoch;
+        self.keys = keys;
+        Ok(())
+    }
+    /// Encrypt and sign a broadcast to the room
+    pub fn encrypt(&self, my_pair: &SessionIdPair, plaintext: &[u8]) -> Result<Vec<u8>> {
+        let my_id = my_pair.get_id();
+        let key = self
+            .keys
+            .get(&my_id)
+            .ok_or_else(|| group_error("no keys"))?;
+        let mut msg = self.epoch.to_le_bytes().to_vec();
+        msg.extend_from_slice(my_id.as_ref());
+        let ad = [BROADCAST_CONTEXT, self.room_id.as_bytes(), &msg].concat();
+        msg.extend_from_slice(&PeerCipher::with_key(key, &my_id).encrypt(&ad, plaintext)?);
+        let signature = my_pair.sign([BROADCAST_CONTEXT, self.room_id.as_bytes(), &msg])?;
+        msg.extend_from_slice(&signature.to_bytes());
+        Ok(msg)
+    }
+    /// Decrypt a broadcast. Returns the sender and the plaintext.
+    pub fn decrypt(&self, msg: &[u8]) -> Result<(SessionId, Vec<u8>)> {
+        let Some(body_len) = msg.len().checked_sub(SIGNATURE_SET_SIZE) else {
+            return Err(errors::invalid_length(SIGNATURE_SET_SIZE, msg.len()));
+        };
+        let (body, signature) = msg.split_at(body_len);
+        let signature = SignatureSet::from_bytes(signature.try_into().unwrap());
+        let mut r = Reader(body);
+        let epoch = u32::from_le_bytes(r.read_array()?);
+        if epoch != self.epoch {
+            return Err(group_error("epoch mismatch"));
+        }
+        let sender = SessionId::from(r.read_array::<SESSION_ID_SIZE>()?);
+        let key = self
+            .keys
+            .get(&sender)
+            .ok_or_else(|| group_error("unknown sender"))?;
+        sender.verify(
+            [BROADCAST_CONTEXT, self.room_id.as_bytes(), body],
+            &signature,
+        )?;
+        let header_len = body.len() - r.0.len();
+        let ad = [
+            BROADCAST_CONTEXT,
+            self.room_id.as_bytes(),
+            &body[..header_len],
+        ]
+        .concat();
+        let plaintext = PeerCipher::with_key(key, &sender).decrypt(&ad, r.0)?;
+        Ok((sender, plaintext))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::new_session_id_pair;
+
+    #[test]
+    fn test_sealed_box() {
+        let pair = new_session_id_pair().unwrap();
+        let sealed = seal(&pair.get_id(), b"secret").unwrap();
+        assert_eq!(open_sealed(&pair, &sealed).unwrap(), b"secret");
+        let other = new_session_id_pair().unwrap();
+        assert!(open_sealed(&other, &sealed).is_err());
+        assert!(open_sealed(&pair, &sealed[..10]).is_err());
+    }
+
+    #[test]
+    fn test_group() {
+        let owner = new_session_id_pair().unwrap();
+        let alice = new_session_id_pair().unwrap();
+        let bob = new_session_id_pair().unwrap();
+        let carol = new_session_id_pair().unwrap();
+
+        let mut room = GroupOwner::new("room1");
+        room.add_member(alice.get_id()).unwrap();
+        room.add_member(bob.get_id()).unwrap();
+        room.add_member(carol.get_id()).unwrap();
+        assert_eq!(room.epoch(), 3);
+
+        let mut members: Vec<GroupMember> = (0..3)
+            .map(|_| GroupMember::new("room1", owner.get_id()))
+            .collect();
+        let pairs = [&alice, &bob, &carol];
+        let apply = |members: &mut Vec<GroupMember>, room: &GroupOwner| {
+            for (id, msg) in room.distribute(&owner).unwrap() {
+                let i = pairs.iter().position(|p| p.get_id() == id).unwrap();
+                members[i].apply_distribution(pairs[i], &msg).unwrap();
+            }
+        };
+        apply(&mut members, &room);
+
+        let msg = members[0].encrypt(&alice, b"hello").unwrap();
+        assert_eq!(
+            members[1].decrypt(&msg).unwrap(),
+            (alice.get_id(), b"hello".to_vec())
+        );
+        assert_eq!(members[2].decrypt(&msg).unwrap().1, b"hello");
+        // forged sender is rejected
+        let mut forged = msg.clone();
+        forged[4..4 + SESSION_ID_SIZE].copy_from_slice(bob.get_id().as_ref());
+        assert!(members[2].decrypt(&forged).is_err());
+
+        // distributions are bound to the recipient and the owner
+        let dist = room.distribute(&owner).unwrap();
+        let (_, for_alice) = dist.iter().find(|(id, _)| *id == alice.get_id()).unwrap();
+        let mut m = GroupMember::new("room1", owner.get_id());
+        assert!(m.apply_distribution(&bob, for_alice).is_err());
+        let fake = room.distribute(&new_session_id_pair().unwrap()).unwrap();
+        let (_, fake_for_alice) = fake.iter().find(|(id, _)| *id == alice.get_id()).unwrap();
+        assert!(m.apply_distribution(&alice, fake_for_alice).is_err());
+        // replayed distributions are rejected
+        assert!(members[0].apply_distribution(&alice, for_alice).is_err());
+
+        // carol is removed and can no longer read
+        room.remove_member(&carol.get_id()).unwrap();
+        let dist = room.distribute(&owner).unwrap();
+        assert_eq!(dist.len(), 2);
+        for (id, msg) in dist {
+            let i = pairs.iter().position(|p| p.get_id() == id).unwrap();
+            members[i].apply_distribution(pairs[i], &msg).unwrap();
+        }
+        let msg = members[1].encrypt(&bob, b"after").unwrap();
+        assert_eq!(members[0].decrypt(&msg).unwrap().1, b"after");
+        assert!(members[2].decrypt(&msg).is_err());
+    }
+}
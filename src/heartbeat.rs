@@ -0,0 +1,156 @@
+//! Signed presence heartbeats.
+//!
+//! A [`Heartbeat`] proves that the owner of a session ID was alive at a given time.
+//! Relays accept it only within a [`FreshnessWindow`] and should drop heartbeats whose
+//! `seq` is not above the last one seen from the same session.
+use crate::errors::{self, Result, SessionIdError};
+use crate::time::{Clock, SystemClock};
+use crate::{
+    ISessionIdPair, SessionId, SessionIdPair, SessionIdPublic, SignatureSet, SESSION_ID_SIZE,
+    SIGNATURE_SET_SIZE,
+};
+
+const HEARTBEAT_CONTEXT: &[u8] = b"verse-session-id/heartbeat/v1";
+/// Size of an encoded heartbeat
+pub const HEARTBEAT_SIZE: usize = SESSION_ID_SIZE + 8 + 8 + SIGNATURE_SET_SIZE;
+
+/// How old, or how far in the future, a heartbeat may be
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct FreshnessWindow {
+    /// Maximum age in milliseconds
+    pub max_age_ms: u64,
+    /// Allowed clock skew into the future in milliseconds
+    pub max_skew_ms: u64,
+}
+
+impl Default for FreshnessWindow {
+    /// 30 seconds of age, 5 seconds of skew
+    fn default() -> Self {
+        FreshnessWindow {
+            max_age_ms: 30_000,
+            max_skew_ms: 5_000,
+        }
+    }
+}
+
+/// Keepalive signed by a session
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Heartbeat {
+    pub session_id: SessionId,
+    /// Increases with every heartbeat of the session
+    pub seq: u64,
+    /// Milliseconds since UNIX epoch
+    pub timestamp_ms: u64,
+    pub signature: SignatureSet,
+}
+
+fn signed_bytes(session_id: &SessionId, seq: u64, timestamp_ms: u64) -> [u8; SESSION_ID_SIZE + 16] {
+    let mut buf = [0u8; SESSION_ID_SIZE + 16];
+    buf[..SESSION_ID_SIZE].copy_from_slice(session_id.as_raw());
+    buf[SESSION_ID_SIZE..SESSION_ID_SIZE + 8].copy_from_slice(&seq.to_le_bytes());
+    buf[SESSION_ID_SIZE + 8..].copy_from_slice(&timestamp_ms.to_le_bytes());
+    buf
+}
+
+impl Heartbeat {
+    /// Heartbeat at the current time
+    pub fn sign(pair: &SessionIdPair, seq: u64) -> Result<Self> {
+        Self::sign_at(pair, seq, &SystemClock)
+    }
+    /// Heartbeat at the time of `clock`
+    pub fn sign_at(pair: &SessionIdPair, seq: u64, clock: &dyn Clock) -> Result<Self> {
+        let timestamp_ms = clock.now_ms();
+        let session_id = pair.get_id();
+        let signature = pair.sign([
+            HEARTBEAT_CONTEXT,
+            &signed_bytes(&session_id, seq, timestamp_ms),
+        ])?;
+        Ok(Heartbeat {
+            session_id,
+            seq,
+            timestamp_ms,
+            signature,
+        })
+    }
+    /// Verify the signature and freshness at the current time
+    pub fn verify(&self, window: &FreshnessWindow) -> Result<()> {
+        self.verify_at(window, &SystemClock)
+    }
+    /// Verify the signature and freshness at the time of `clock`
+    pub fn verify_at(&self, window: &FreshnessWindow, clock: &dyn Clock) -> Result<()> {
+        let now_ms = clock.now_ms();
+        if now_ms > self.timestamp_ms.saturating_add(window.max_age_ms) {
+            return Err(SessionIdError::Expired);
+        }
+        if self.timestamp_ms > now_ms.saturating_add(window.max_skew_ms) {
+            return Err(SessionIdError::NotYetValid);
+        }
+        self.session_id.verify(
+            [
+                HEARTBEAT_CONTEXT,
+                &signed_bytes(&self.session_id, self.seq, self.timestamp_ms),
+            ],
+            &self.signature,
+        )
+    }
+    pub fn to_bytes(&self) -> [u8; HEARTBEAT_SIZE] {
+        let mut buf = [0u8; HEARTBEAT_SIZE];
+        buf[..SESSION_ID_SIZE + 16].copy_from_slice(&signed_bytes(
+            &self.session_id,
+            self.seq,
+            self.timestamp_ms,
+        ));
+        buf[SESSION_ID_SIZE + 16..].copy_from_slice(&self.signature.to_bytes());
+        buf
+    }
+    /// Parse without verifying
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() != HEARTBEAT_SIZE {
+            return Err(errors::invalid_length(HEARTBEAT_SIZE, bytes.len()));
+        }
+        let (session_id, rest) = bytes.split_at(SESSION_ID_SIZE);
+        let (seq, rest) = rest.split_at(8);
+        let (timestamp_ms, signature) = rest.split_at(8);
+        Ok(Heartbeat {
+            session_id: SessionId::try_from(session_id)?,
+            seq: u64::from_le_bytes(seq.try_into().unwrap()),
+            timestamp_ms: u64::from_le_bytes(timestamp_ms.try_into().unwrap()),
+            signature: SignatureSet::from_bytes(signature.try_into().unwrap()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{new_session_id_pair, ManualClock};
+
+    #[test]
+    fn test_heartbeat() {
+        let pair = new_session_id_pair().unwrap();
+        let window = FreshnessWindow::default();
+        let now = 1_700_000_000_000;
+        let hb = Heartbeat::sign_at(&pair, 7, &ManualClock::from_ms(now)).unwrap();
+        let parsed = Heartbeat::from_bytes(&hb.to_bytes()).unwrap();
+        assert_eq!(parsed, hb);
+        assert!(parsed
+            .verify_at(&window, &ManualClock::from_ms(now + 1_000))
+            .is_ok());
+        assert!(matches!(
+            parsed.verify_at(&window, &ManualClock::from_ms(now + 30_001)),
+            Err(SessionIdError::Expired)
+        ));
+        assert!(matches!(
+            parsed.verify_at(&window, &ManualClock::from_ms(now - 5_001)),
+            Err(SessionIdError::NotYetValid)
+        ));
+
+        let mut forged = hb.clone();
+        forged.seq = 8;
+        assert!(forged
+            .verify_at(&window, &ManualClock::from_ms(now))
+            .is_err());
+        assert!(Heartbeat::from_bytes(&hb.to_bytes()[1..]).is_err());
+        assert!(Heartbeat::sign(&pair, 1).unwrap().verify(&window).is_ok());
+    }
+}
@@ -0,0 +1,486 @@
+//! HTTP Message Signatures (RFC 9421). Enabled with the `http` feature.
+//!
+//! Requests are signed with `alg="ed25519"` and carry the signer's SessionId
+//! (base64, same as `Display`) in the `keyid` parameter. Verification requires the
+//! `created` parameter and rejects signatures older than the allowed age, or past
+//! `expires` when present.
+//!
+//! **The body is not verified by [`verify_http_request`].** Covering `content-digest`
+//! only binds the header value; check it against the received body with
+//! [`verify_content_digest`], or use `SessionAuthLayer` (`tower` feature) which does so.
+use crate::errors::{self, Result, SessionIdError, SignatureErrorKind};
+use crate::time::{Clock, SystemClock};
+use crate::{ISessionIdPair, SessionId, SessionIdPair};
+use http::header::{HeaderMap, HeaderValue, HOST};
+use http::Request;
+use sha2::{Digest, Sha256, Sha512};
+
+const SIGNATURE_LABEL: &str = "sig1";
+const SIGNATURE_ALG: &str = "ed25519";
+const SIGNATURE_INPUT: &str = "signature-input";
+const SIGNATURE: &str = "signature";
+const CONTENT_DIGEST: &str = "content-digest";
+
+/// Components signed by default: method, path, query and authority
+pub const DEFAULT_HTTP_COMPONENTS: &[&str] = &["@method", "@path", "@query", "@authority"];
+/// Default maximum age of a signature in seconds
+pub const DEFAULT_HTTP_MAX_AGE: u64 = 300;
+/// Allowed clock skew of `created` into the future in seconds
+pub const HTTP_MAX_SKEW: u64 = 60;
+
+fn http_error(msg: &str) -> SessionIdError {
+    SessionIdError::InvalidFormat(format!("http: {}", msg))
+}
+
+/// `Content-Digest` header value (RFC 9530) of `body`, with sha-256
+pub fn content_digest(body: &[u8]) -> String {
+    format!("sha-256=:{}:", base64::encode(Sha256::digest(body)))
+}
+
+/// Check the `Content-Digest` header of `request` against `body`.
+/// Every sha-256 and sha-512 member must match, and at least one is required.
+pub fn verify_content_digest<B>(request: &Request<B>, body: &[u8]) -> Result<()> {
+    let mut verifier = ContentDigestVerifier::new(request.headers())?
+        .ok_or_else(|| http_error("missing content-digest"))?;
+    verifier.update(body);
+    verifier.finish()
+}
+
+/// Incremental check of a `Content-Digest` header
+pub(crate) struct ContentDigestVerifier {
+    sha256: Option<(Sha256, Vec<u8>)>,
+    sha512: Option<(Sha512, Vec<u8>)>,
+}
+
+impl ContentDigestVerifier {
+    /// Verifier of the `Content-Digest` header in `headers`, `None` without the header
+    pub(crate) fn new(headers: &HeaderMap) -> Result<Option<Self>> {
+        let mut values = headers.get_all(CONTENT_DIGEST).iter().peekable();
+        if values.peek().is_none() {
+            return Ok(None);
+        }
+        let mut verifier = ContentDigestVerifier {
+            sha256: None,
+            sha512: None,
+        };
+        for member in values.flat_map(|v| v.to_str().unwrap_or("").split(',')) {
+            let (alg, value) = member
+                .trim()
+                .split_once('=')
+                .ok_or_else(|| http_error("invalid content-digest"))?;
+            let value = || -> Result<Vec<u8>> {
+                let value = value
+                    .strip_prefix(':')
+                    .and_then(|v| v.strip_suffix(':'))
+                    .ok_or_else(|| http_error("invalid content-digest"))?;
+                Ok(base64::decode(value)?)
+            };
+            match alg {
+                "sha-256" => verifier.sha256 = Some((Sha256::new(), value()?)),
+                "sha-512" => verifier.sha512 = Some((Sha512::new(), value()?)),
+                _ => {}
+            }
+        }
+        if verifier.sha256.is_none() && verifier.sha512.is_none() {
+            return Err(http_error("unsupported content-digest"));
+        }
+        Ok(Some(verifier))
+    }
+    pub(crate) fn update(&mut self, data: &[u8]) {
+        if let Some((hasher, _)) = &mut self.sha256 {
+            hasher.update(data);
+        }
+        if let Some((hasher, _)) = &mut self.sha512 {
+            hasher.update(data);
+        }
+    }
+    pub(crate) fn finish(self) -> Result<()> {
+        let sha256 = self.sha256.map(|(h, v)| h.finalize()[..] == v[..]);
+        let sha512 = self.sha512.map(|(h, v)| h.finalize()[..] == v[..]);
+        if sha256 == Some(false) || sha512 == Some(false) {
+            return Err(SessionIdError::Signature(
+                SignatureErrorKind::VerificationFailed,
+            ));
+        }
+        Ok(())
+    }
+}
+
+fn component_value<B>(request: &Request<B>, name: &str) -> Result<String> {
+    let uri = request.uri();
+    Ok(match name {
+        "@method" => request.method().as_str().to_string(),
+        "@path" => uri.path().to_string(),
+        "@query" => format!("?{}", uri.query().unwrap_or("")),
+        "@target-uri" => uri.to_string(),
+        "@scheme" => uri
+            .scheme_str()
+            .ok_or_else(|| http_error("missing @scheme"))?
+            .to_string(),
+        "@authority" => match uri.authority() {
+            Some(v) => v.as_str().to_ascii_lowercase(),
+            None => request
+                .headers()
+                .get(HOST)
+                .and_then(|v| v.to_str().ok())
+                .ok_or_else(|| http_error("missing @authority"))?
+                .to_ascii_lowercase(),
+        },
+        _ if name.starts_with('@') => {
+            return Err(http_error(&format!("unsupported component {}", name)))
+        }
+        _ => {
+            let values = request
+                .headers()
+                .get_all(name)
+                .iter()
+                .map(|v| v.to_str().map(str::trim))
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|_| http_error(&format!("invalid header {}", name)))?;
+            if values.is_empty() {
+                return Err(http_error(&format!("missing header {}", name)));
+            }
+            values.join(", ")
+        }
+    })
+}
+
+fn signature_base<B>(request: &Request<B>, components: &[&str], params: &str) -> Result<String> {
+    let mut base = String::new();
+    for name in components {
+        base.push_str(&format!(
+            "\"{}\": {}\n",
+            name,
+            component_value(request, name)?
+        ));
+    }
+    base.push_str(&format!("\"@signature-params\": {}", params));
+    Ok(base)
+}
+
+/// Sign `request` over `components` (derived components such as `@method`, or lowercase
+/// header names), adding the `Signature-Input` and `Signature` headers.
+/// To cover the body, add a `content-digest` header with [`content_digest`] and include it.
+pub fn sign_http_request<B>(
+    pair: &SessionIdPair,
+    request: &mut Request<B>,
+    components: &[&str],
+) -> Result<()> {
+    sign_http_request_at(pair, request, components, &SystemClock)
+}
+
+/// Like [`sign_http_request`], with `created` at the time of `clock`
+pub fn sign_http_request_at<B>(
+    pair: &SessionIdPair,
+    request: &mut Request<B>,
+    components: &[&str],
+    clock: &dyn Clock,
+) -> Result<()> {
+    let components: Vec<String> = components.iter().map(|v| v.to_ascii_lowercase()).collect();
+    let components: Vec<&str> = components.iter().map(String::as_str).collect();
+    let params = format!(
+        "({});created={};keyid=\"{}\";alg=\"{}\"",
+        components
+            .iter()
+            .map(|v| format!("\"{}\"", v))
+            .collect::<Vec<_>>()
+            .join(" "),
+        clock.now(),
+        pair.get_id(),
+        SIGNATURE_ALG
+    );
+    let base = signature_base(request, &components, &params)?;
+    let signature = ed25519_dalek::Signer::sign(pair, base.as_bytes());
+
+    let headers = request.headers_mut();
+    let input = HeaderValue::from_str(&format!("{}={}", SIGNATURE_LABEL, params))
+        .map_err(|_| http_error("invalid signature-input"))?;
+    headers.insert(SIGNATURE_INPUT, input);
+    let signature = HeaderValue::from_str(&format!(
+        "{}=:{}:",
+        SIGNATURE_LABEL,
+        base64::encode(signature.to_bytes())
+    ))
+    .map_err(|_| http_error("invalid signature"))?;
+    headers.insert(SIGNATURE, signature);
+    Ok(())
+}
+
+/// Find the `sig1` member of a structured field dictionary header
+fn dictionary_member<'a, B>(request: &'a Request<B>, header: &str) -> Result<&'a str> {
+    request
+        .headers()
+        .get_all(header)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .find_map(|v| v.trim().strip_prefix(SIGNATURE_LABEL)?.strip_prefix('='))
+        .ok_or_else(|| http_error(&format!("missing {}", header)))
+}
+
+/// Verify the signature added by [`sign_http_request`], requiring it to cover
+/// [`DEFAULT_HTTP_COMPONENTS`] and to be at most [`DEFAULT_HTTP_MAX_AGE`] old.
+/// Returns the signer (`keyid`).
+///
+/// The body is not read: when `content-digest` is covered, call [`verify_content_digest`]
+/// on the received body as well.
+pub fn verify_http_request<B>(request: &Request<B>) -> Result<SessionId> {
+    verify_http_request_covering(request, DEFAULT_HTTP_COMPONENTS)
+}
+
+/// Verify the signature added by [`sign_http_request`] and check that it covers
+/// all of `required`. Returns the signer (`keyid`).
+pub fn verify_http_request_covering<B>(
+    request: &Request<B>,
+    required: &[&str],
+) -> Result<SessionId> {
+    verify_http_request_at(request, required, DEFAULT_HTTP_MAX_AGE, &SystemClock)
+}
+
+/// Verify the signature added by [`sign_http_request`] at the time of `clock`,
+/// checking that it covers all of `required` and is at most `max_age` seconds old.
+/// Returns the signer (`keyid`).
+pub fn verify_http_request_at<B>(
+    request: &Request<B>,
+    required: &[&str],
+    max_age: u64,
+    clock: &dyn Clock,
+) -> Result<SessionId> {
+    let now = clock.now();
+    let params = dictionary_member(request, SIGNATURE_INPUT)?;
+    let (list, rest) = params
+        .strip_prefix('(')
+        .and_then(|v| v.split_once(')'))
+        .ok_or_else(|| http_error("invalid signature-input"))?;
+    let components: Vec<&str> = list
+        .split_whitespace()
+        .map(|v| {
+            v.strip_prefix('"')
+                .and_then(|v| v.strip_suffix('"'))
+                .ok_or_else(|| http_error("invalid signature-input"))
+        })
+        .collect::<Result<_>>()?;
+    if let Some(name) = required
+        .iter()
+        .find(|v| !components.contains(&v.to_ascii_lowercase().as_str()))
+    {
+        return Err(http_error(&format!("{} is not signed", name)));
+    }
+
+    let mut keyid = None;
+    let mut created = None;
+    let mut expires = None;
+    let timestamp = |value: &str| {
+        value
+            .parse::<u64>()
+            .map_err(|_| http_error("invalid timestamp"))
+    };
+    for param in rest.split(';').filter(|v| !v.is_empty()) {
+        let (key, value) = param.split_once('=').unwrap_or((param, ""));
+        let value = value.trim_matches('"');
+        match key {
+            "keyid" => keyid = Some(value),
+            "created" => created = Some(timestamp(value)?),
+            "expires" => expires = Some(timestamp(value)?),
+            "alg" if value != SIGNATURE_ALG => {
+                return Err(http_error(&format!("unsupported alg {}", value)))
+            }
+            _ => {}
+        }
+    }
+    let session_id: SessionId = keyid.ok_or(SessionIdError::Required)?.parse()?;
+    let created = created.ok_or(SessionIdError::Required)?;
+    if created > now.saturating_add(HTTP_MAX_SKEW) {
+        return Err(SessionIdError::NotYetValid);
+    }
+    if now > created.saturating_add(max_age) || expires.is_some_and(|v| now >= v) {
+        return Err(SessionIdError::Expired);
+    }
+
+    let signature = dictionary_member(request, SIGNATURE)?
+        .strip_prefix(':')
+        .and_then(|v| v.strip_suffix(':'))
+        .ok_or_else(|| http_error("invalid signature"))?;
+    let signature = base64::decode(signature)?;
+
+    let base = signature_base(request, &components, params)?;
+    let pk = ed25519_dalek::PublicKey::from_bytes(session_id.as_ref())
+        .map_err(errors::signature(SignatureErrorKind::MalformedPublicKey))?;
+    let signature = ed25519_dalek::Signature::from_bytes(&signature)
+        .map_err(errors::signature(SignatureErrorKind::MalformedSignature))?;
+    pk.verify_strict(base.as_bytes(), &signature)
+        .map_err(errors::signature(SignatureErrorKind::VerificationFailed))?;
+    Ok(session_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{new_session_id_pair, ManualClock};
+
+    fn test_request() -> Request<()> {
+        Request::post("/foo?param=Value&Pet=dog")
+            .header("host", "example.com")
+            .header("date", "Tue, 20 Apr 2021 02:07:55 GMT")
+            .header("content-type", "application/json")
+            .header("content-length", "18")
+            .body(())
+            .unwrap()
+    }
+
+    #[test]
+    fn test_http_signature_vector() {
+        // RFC 9421 B.2.6 (test-key-ed25519)
+        let der =
+            base64::decode("MC4CAQAwBQYDK2VwBCIEIJ+DYvh6SEqVTm50DFtMDoQikTmiCqirVv9mWG9qfSnF")
+                .unwrap();
+        let sk = ed25519_dalek::SecretKey::from_bytes(&der[16..]).unwrap();
+        let kp = SessionIdPair {
+            public: (&sk).into(),
+            secret: sk,
+        };
+        let components = [
+            "date",
+            "@method",
+            "@path",
+            "@authority",
+            "content-type",
+            "content-length",
+        ];
+        let params = r#"("date" "@method" "@path" "@authority" "content-type" "content-length");created=1618884473;keyid="test-key-ed25519""#;
+        let base = signature_base(&test_request(), &components, params).unwrap();
+        let signature = ed25519_dalek::Signer::sign(&kp, base.as_bytes());
+        assert_eq!(
+            base64::encode(signature.to_bytes()),
+            "wqcAqbmYJ2ji2glfAMaRy4gruYYnx2nEFN2HN6jrnDnQCK1u02Gb04v9EDgwUPiu4A0w6vuQv5lIp5WPpBKRCw=="
+        );
+    }
+
+    #[test]
+    fn test_http_signature() {
+        let kp = new_session_id_pair().unwrap();
+        let mut request = test_request();
+        let mut components = DEFAULT_HTTP_COMPONENTS.to_vec();
+        components.push("Content-Type");
+        sign_http_request(&kp, &mut request, &components).unwrap();
+        assert_eq!(verify_http_request(&request).unwrap(), kp.get_id());
+
+        let mut tampered = request.clone();
+        *tampered.uri_mut() = "/foo?param=Value&Pet=cat".parse().unwrap();
+        assert!(matches!(
+            verify_http_request(&tampered),
+            Err(SessionIdError::Signature(
+                SignatureErrorKind::VerificationFailed
+            ))
+        ));
+        let mut tampered = request.clone();
+        tampered
+            .headers_mut()
+            .insert("content-type", HeaderValue::from_static("text/plain"));
+        assert!(verify_http_request(&tampered).is_err());
+        let mut missing = request.clone();
+        missing.headers_mut().remove("content-type");
+        assert!(matches!(
+            verify_http_request(&missing),
+            Err(SessionIdError::InvalidFormat(_))
+        ));
+
+        assert!(verify_http_request_covering(&request, &["Content-Type", "@method"]).is_ok());
+        assert!(verify_http_request_covering(&request, &["date"]).is_err());
+        assert!(verify_http_request(&test_request()).is_err());
+        assert!(sign_http_request(&kp, &mut test_request(), &["x-missing"]).is_err());
+
+        // the default components are required
+        let mut partial = test_request();
+        sign_http_request(&kp, &mut partial, &["@method", "@path"]).unwrap();
+        assert!(matches!(
+            verify_http_request(&partial),
+            Err(SessionIdError::InvalidFormat(_))
+        ));
+        assert!(verify_http_request_covering(&partial, &["@method"]).is_ok());
+    }
+
+    #[test]
+    fn test_content_digest() {
+        // RFC 9530 B.1
+        let body = br#"{"hello": "world"}"#;
+        let digest = content_digest(body);
+        assert_eq!(
+            digest,
+            "sha-256=:X48E9qOokqqrvdts8nOJRJN3OWDUoyWxBf7kbu9DBPE=:"
+        );
+        let with_digest = |value: &str| {
+            Request::post("/")
+                .header(CONTENT_DIGEST, value)
+                .body(())
+                .unwrap()
+        };
+        assert!(verify_content_digest(&with_digest(&digest), body).is_ok());
+        assert!(matches!(
+            verify_content_digest(&with_digest(&digest), b"{}"),
+            Err(SessionIdError::Signature(
+                SignatureErrorKind::VerificationFailed
+            ))
+        ));
+        let both = format!(
+            "{}, sha-512=:WZDPaVn/7XgHaAy8pmojAkGWoRx2UFChF41A2svX+TaPm+AbwAgBWnrIiYllu7BNNyealdVLvRwEmTHWXvJwew==:",
+            digest
+        );
+        assert!(verify_content_digest(&with_digest(&both), body).is_ok());
+        let wrong_512 = format!("{}, sha-512=:{}:", digest, base64::encode([0; 64]));
+        assert!(verify_content_digest(&with_digest(&wrong_512), body).is_err());
+        assert!(verify_content_digest(&with_digest("md5=:AAAA:"), body).is_err());
+        assert!(verify_content_digest(&test_request(), body).is_err());
+
+        // covering content-digest binds the body through verify_content_digest
+        let kp = new_session_id_pair().unwrap();
+        let mut request = with_digest(&digest);
+        let mut components = DEFAULT_HTTP_COMPONENTS.to_vec();
+        components.push(CONTENT_DIGEST);
+        *request.uri_mut() = "https://example.com/".parse().unwrap();
+        sign_http_request(&kp, &mut request, &components).unwrap();
+        assert!(verify_http_request_covering(&request, &components).is_ok());
+        assert!(verify_content_digest(&request, b"{}").is_err());
+    }
+
+    #[test]
+    fn test_http_signature_freshness() {
+        let kp = new_session_id_pair().unwrap();
+        let mut request = test_request();
+        sign_http_request(&kp, &mut request, DEFAULT_HTTP_COMPONENTS).unwrap();
+        let params = dictionary_member(&request, SIGNATURE_INPUT).unwrap();
+        let created: u64 = params
+            .split(';')
+            .find_map(|v| v.strip_prefix("created="))
+            .unwrap()
+            .parse()
+            .unwrap();
+        let verify = |max_age, now| {
+            verify_http_request_at(
+                &request,
+                DEFAULT_HTTP_COMPONENTS,
+                max_age,
+                &ManualClock::new(now),
+            )
+        };
+        assert!(verify(60, created + 60).is_ok());
+        assert!(matches!(
+            verify(60, created + 61),
+            Err(SessionIdError::Expired)
+        ));
+        assert!(verify(60, created - HTTP_MAX_SKEW).is_ok());
+        assert!(matches!(
+            verify(60, created - HTTP_MAX_SKEW - 1),
+            Err(SessionIdError::NotYetValid)
+        ));
+        assert!(matches!(
+            verify_http_request_at(
+                &request,
+                DEFAULT_HTTP_COMPONENTS,
+                DEFAULT_HTTP_MAX_AGE,
+                &ManualClock::new(created + DEFAULT_HTTP_MAX_AGE + 1)
+            ),
+            Err(SessionIdError::Expired)
+        ));
+    }
+}
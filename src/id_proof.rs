@@ -0,0 +1,87 @@
+//! Proof of work bound to a session ID.
+//!
+//! Relays can require new identities to present an [`IdProof`] so that creating
+//! many identities costs CPU time. Each extra bit of difficulty doubles the work.
+use crate::errors::{self, Result, SessionIdError, SignatureErrorKind};
+use crate::SessionId;
+use ed25519_dalek::{Digest, Sha512};
+use serde::{Deserialize, Serialize};
+
+const ID_PROOF_CONTEXT: &[u8] = b"verse-session-id/id-proof/v1";
+
+/// Nonce solving the proof of work for one session ID
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct IdProof {
+    pub nonce: u64,
+}
+
+fn leading_zero_bits(hash: &[u8]) -> u32 {
+    let mut n = 0;
+    for b in hash {
+        n += b.leading_zeros();
+        if *b != 0 {
+            break;
+        }
+    }
+    n
+}
+
+impl IdProof {
+    /// Search a nonce with at least `difficulty` leading zero bits for `id`
+    pub fn mine(id: &SessionId, difficulty: u32) -> Self {
+        (0..=u64::MAX)
+            .map(|nonce| IdProof { nonce })
+            .find(|p| p.work(id) >= difficulty)
+            .expect("nonce space exhausted")
+    }
+    /// Leading zero bits achieved for `id`
+    pub fn work(&self, id: &SessionId) -> u32 {
+        let hash = Sha512::new()
+            .chain(ID_PROOF_CONTEXT)
+            .chain(id)
+            .chain(self.nonce.to_le_bytes())
+            .finalize();
+        leading_zero_bits(&hash)
+    }
+    /// Check that the proof reaches `difficulty` for `id`
+    pub fn verify(&self, id: &SessionId, difficulty: u32) -> Result<()> {
+        if self.work(id) < difficulty {
+            return Err(SessionIdError::Signature(
+                SignatureErrorKind::VerificationFailed,
+            ));
+        }
+        Ok(())
+    }
+    pub fn to_bytes(&self) -> [u8; 8] {
+        self.nonce.to_le_bytes()
+    }
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let nonce: [u8; 8] = bytes
+            .try_into()
+            .map_err(|_| errors::invalid_length(8, bytes.len()))?;
+        Ok(IdProof {
+            nonce: u64::from_le_bytes(nonce),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_id_proof() {
+        assert_eq!(leading_zero_bits(&[0, 0b0001_0000, 0]), 11);
+        assert_eq!(leading_zero_bits(&[0, 0]), 16);
+
+        let id = SessionId::from([3; 32]);
+        let proof = IdProof::mine(&id, 12);
+        assert!(proof.work(&id) >= 12);
+        assert!(proof.verify(&id, 12).is_ok());
+        assert!(proof.verify(&id, 0).is_ok());
+        assert!(proof.verify(&SessionId::from([4; 32]), 12).is_err());
+        assert!(proof.verify(&id, 64).is_err());
+        assert_eq!(IdProof::from_bytes(&proof.to_bytes()).unwrap(), proof);
+        assert!(IdProof::from_bytes(&[0; 7]).is_err());
+    }
+}
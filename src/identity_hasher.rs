@@ -0,0 +1,71 @@
+//! Hasher for maps keyed by session IDs.
+//!
+//! Session IDs are Ed25519 public keys and already uniformly random, so their first
+//! 8 bytes make a good hash. This skips SipHashing all 32 bytes on every lookup.
+//! Keypairs with colliding prefixes can be ground offline, so keep the default
+//! hasher for maps an attacker can fill with many self-generated IDs.
+use crate::SessionId;
+use std::collections::{HashMap, HashSet};
+use std::hash::{BuildHasherDefault, Hasher};
+
+/// Uses the first 8 bytes of the key as the hash. Only meant for [`SessionId`] keys.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct IdentityHasher(u64);
+
+impl Hasher for IdentityHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+    fn write(&mut self, bytes: &[u8]) {
+        if let Some(v) = bytes.first_chunk::<8>() {
+            self.0 = u64::from_le_bytes(*v);
+        } else {
+            // not a session ID; fold so that the hasher still works
+            for b in bytes {
+                self.0 = self.0.rotate_left(8) ^ u64::from(*b);
+            }
+        }
+    }
+    fn write_usize(&mut self, _: usize) {
+        // length prefix of the key bytes
+    }
+}
+
+/// `BuildHasher` for [`IdentityHasher`]
+pub type IdentityBuildHasher = BuildHasherDefault<IdentityHasher>;
+/// `HashMap` keyed by [`SessionId`] using [`IdentityHasher`]
+pub type SessionIdHashMap<V> = HashMap<SessionId, V, IdentityBuildHasher>;
+/// `HashSet` of [`SessionId`] using [`IdentityHasher`]
+pub type SessionIdHashSet = HashSet<SessionId, IdentityBuildHasher>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::hash::{BuildHasher, Hash};
+
+    #[test]
+    fn test_identity_hasher() {
+        let mut raw = [0u8; 32];
+        raw[..8].copy_from_slice(&0x0123_4567_89ab_cdefu64.to_le_bytes());
+        let id = SessionId::from(raw);
+        assert_eq!(
+            IdentityBuildHasher::default().hash_one(id),
+            0x0123_4567_89ab_cdef
+        );
+
+        let mut map = SessionIdHashMap::default();
+        let ids: Vec<SessionId> = (0..100u8).map(|v| SessionId::from([v; 32])).collect();
+        for (i, id) in ids.iter().enumerate() {
+            map.insert(*id, i);
+        }
+        assert!(ids.iter().enumerate().all(|(i, id)| map[id] == i));
+        let set: SessionIdHashSet = ids.iter().copied().collect();
+        assert!(set.contains(&ids[42]));
+        assert!(!set.contains(&SessionId::from([200; 32])));
+
+        // other keys still hash
+        let mut h = IdentityHasher::default();
+        "abc".hash(&mut h);
+        assert_ne!(h.finish(), 0);
+    }
+}
@@ -0,0 +1,200 @@
+//! IPFS (kubo / go-ipfs) key interop and IPNS names.
+//!
+//! IPFS stores Ed25519 keys as libp2p protobuf messages: the keystore files and, in base64,
+//! `Identity.PrivKey` of the config. The IPNS name of a key is a CIDv1 (`libp2p-key` codec)
+//! of the identity multihash of the public key, written in base36 as `k51...`. Records
+//! published under that name are signed with the same key, so a world's assets resolve
+//! through a name only the host's SessionIdPair can update.
+use crate::errors::{self, Result, SessionIdError};
+use crate::{base32, session_id_pair_from_bytes, SessionId, SessionIdPair, SESSION_ID_SIZE};
+use zeroize::Zeroizing;
+
+// protobuf fields: Type (1) = Ed25519 (1), Data (2) with its length
+const PUBLIC_KEY_PREFIX: [u8; 4] = [0x08, 0x01, 0x12, 0x20];
+const PRIVATE_KEY_PREFIX: [u8; 4] = [0x08, 0x01, 0x12, 0x40];
+/// Size of a protobuf-encoded Ed25519 public key
+pub const IPFS_PUBLIC_KEY_SIZE: usize = PUBLIC_KEY_PREFIX.len() + SESSION_ID_SIZE;
+/// Size of a protobuf-encoded Ed25519 private key
+pub const IPFS_PRIVATE_KEY_SIZE: usize = PRIVATE_KEY_PREFIX.len() + 64;
+// CIDv1, libp2p-key codec, identity multihash of the public key
+const CID_PREFIX: [u8; 4] = [0x01, 0x72, 0x00, IPFS_PUBLIC_KEY_SIZE as u8];
+const IPNS_PATH: &str = "/ipns/";
+const BASE36: &[u8; 36] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+
+fn ipfs_error(msg: &str) -> SessionIdError {
+    SessionIdError::InvalidFormat(format!("ipfs: {}", msg))
+}
+
+// big-endian base conversion; leading zero bytes become leading '0's
+fn encode_base36(bytes: &[u8]) -> String {
+    let zeros = bytes.iter().take_while(|b| **b == 0).count();
+    let mut digits: Vec<u8> = vec![];
+    for b in &bytes[zeros..] {
+        let mut carry = *b as u32;
+        for d in digits.iter_mut() {
+            carry += (*d as u32) << 8;
+            *d = (carry % 36) as u8;
+            carry /= 36;
+        }
+        while carry > 0 {
+            digits.push((carry % 36) as u8);
+            carry /= 36;
+        }
+    }
+    let mut s = "0".repeat(zeros);
+    s.extend(digits.iter().rev().map(|d| BASE36[*d as usize] as char));
+    s
+}
+
+fn decode_base36(s: &str) -> Result<Vec<u8>> {
+    let zeros = s.bytes().take_while(|c| *c == b'0').count();
+    let mut bytes: Vec<u8> = vec![];
+    for c in s[zeros..].bytes() {
+        let mut carry = BASE36
+            .iter()
+            .position(|d| *d == c.to_ascii_lowercase())
+            .ok_or_else(|| ipfs_error("invalid base36 character"))? as u32;
+        for b in bytes.iter_mut() {
+            carry += *b as u32 * 36;
+            *b = carry as u8;
+            carry >>= 8;
+        }
+        while carry > 0 {
+            bytes.push(carry as u8);
+            carry >>= 8;
+        }
+    }
+    let mut v = vec![0; zeros];
+    v.extend(bytes.iter().rev());
+    Ok(v)
+}
+
+impl SessionId {
+    /// Protobuf-encoded public key, as in libp2p and IPFS
+    pub fn to_ipfs_public_key(&self) -> Vec<u8> {
+        let mut buf = PUBLIC_KEY_PREFIX.to_vec();
+        buf.extend_from_slice(self.as_raw());
+        buf
+    }
+    pub fn from_ipfs_public_key(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() != IPFS_PUBLIC_KEY_SIZE {
+            return Err(errors::invalid_length(IPFS_PUBLIC_KEY_SIZE, bytes.len()));
+        }
+        let (prefix, key) = bytes.split_at(PUBLIC_KEY_PREFIX.len());
+        if prefix != PUBLIC_KEY_PREFIX {
+            return Err(ipfs_error("not an ed25519 public key"));
+        }
+        SessionId::try_from(key)
+    }
+    /// IPNS name (`k51...`), resolvable at `/ipns/<name>`
+    pub fn to_ipns_name(&self) -> String {
+        let mut cid = CID_PREFIX.to_vec();
+        cid.extend_from_slice(&self.to_ipfs_public_key());
+        format!("k{}", encode_base36(&cid))
+    }
+    /// Parse an IPNS name in base36 (`k...`) or base32 (`b...`), optionally prefixed by `/ipns/`
+    pub fn from_ipns_name(s: &str) -> Result<Self> {
+        let s = s.strip_prefix(IPNS_PATH).unwrap_or(s);
+        let cid = match s.split_at_checked(1) {
+            Some(("k" | "K", v)) => decode_base36(v)?,
+            Some(("b" | "B", v)) => base32::decode(&v.to_ascii_uppercase())?,
+            _ => return Err(ipfs_error("unsupported multibase")),
+        };
+        match cid.strip_prefix(&CID_PREFIX[..]) {
+            Some(key) => Self::from_ipfs_public_key(key),
+            None => Err(ipfs_error("not an ed25519 libp2p-key cid")),
+        }
+    }
+}
+
+/// Conversion between SessionIdPair and IPFS private keys
+pub trait IpfsKey: Sized {
+    /// Protobuf-encoded private key, as in the IPFS keystore. Its base64 is the config's
+    /// `Identity.PrivKey`. Anyone holding it can publish under this IPNS name and sign as
+    /// this session ID.
+    fn to_ipfs_private_key(&self) -> Zeroizing<Vec<u8>>;
+    /// Parse a protobuf-encoded Ed25519 private key. The public key must match the seed.
+    fn from_ipfs_private_key(bytes: &[u8]) -> Result<Self>;
+}
+
+impl IpfsKey for SessionIdPair {
+    fn to_ipfs_private_key(&self) -> Zeroizing<Vec<u8>> {
+        let mut buf = Zeroizing::new(PRIVATE_KEY_PREFIX.to_vec());
+        buf.extend_from_slice(&Zeroizing::new(self.to_bytes())[..]);
+        buf
+    }
+    fn from_ipfs_private_key(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() != IPFS_PRIVATE_KEY_SIZE {
+            return Err(errors::invalid_length(IPFS_PRIVATE_KEY_SIZE, bytes.len()));
+        }
+        let (prefix, key) = bytes.split_at(PRIVATE_KEY_PREFIX.len());
+        if prefix != PRIVATE_KEY_PREFIX {
+            return Err(ipfs_error("not an ed25519 private key"));
+        }
+        session_id_pair_from_bytes(key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{new_session_id_pair, ISessionIdPair};
+
+    fn hex(s: &str) -> Vec<u8> {
+        (0..s.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_ipfs_keys() {
+        // from the libp2p-identity test suite
+        let private = hex(concat!(
+            "080112407e0830617c4a7de83925dfb2694556b12936c477a0e1feb2e148ec9da60fee7d",
+            "1ed1e8fae2c4a144b8be8fd4b47bf3d3b34b871c3cacf6010f0e42d474fce27e"
+        ));
+        let public =
+            hex("080112201ed1e8fae2c4a144b8be8fd4b47bf3d3b34b871c3cacf6010f0e42d474fce27e");
+        let pair = SessionIdPair::from_ipfs_private_key(&private).unwrap();
+        assert_eq!(pair.get_id().to_ipfs_public_key(), public);
+        assert_eq!(&pair.to_ipfs_private_key()[..], &private[..]);
+        assert_eq!(
+            SessionId::from_ipfs_public_key(&public).unwrap(),
+            pair.get_id()
+        );
+        let mut mismatched = private.clone();
+        mismatched[40] ^= 1;
+        assert!(SessionIdPair::from_ipfs_private_key(&mismatched).is_err());
+        assert!(SessionId::from_ipfs_public_key(&private[..36]).is_err());
+
+        let id = new_session_id_pair().unwrap().get_id();
+        let name = id.to_ipns_name();
+        // every Ed25519 IPNS name shares this prefix
+        assert!(name.starts_with("k51qzi5uqu5d"), "{}", name);
+        assert_eq!(SessionId::from_ipns_name(&name).unwrap(), id);
+        assert_eq!(
+            SessionId::from_ipns_name(&format!("/ipns/{}", name)).unwrap(),
+            id
+        );
+        let mut cid = CID_PREFIX.to_vec();
+        cid.extend_from_slice(&id.to_ipfs_public_key());
+        let base32_name = format!("b{}", base32::encode(&cid).to_lowercase());
+        assert_eq!(SessionId::from_ipns_name(&base32_name).unwrap(), id);
+        assert!(SessionId::from_ipns_name(&name[1..]).is_err());
+        assert!(SessionId::from_ipns_name(&name[..name.len() - 1]).is_err());
+        assert_eq!(
+            decode_base36(&encode_base36(&[0, 0, 1, 255])).unwrap(),
+            [0, 0, 1, 255]
+        );
+
+        #[cfg(feature = "libp2p")]
+        {
+            use crate::Libp2pKeypair;
+            let peer_id = id.to_libp2p_peer_id().unwrap();
+            assert_eq!(&cid[2..], &peer_id.to_bytes()[..]);
+            let keypair = pair.to_libp2p_keypair().unwrap();
+            assert_eq!(keypair.to_protobuf_encoding().unwrap(), private);
+        }
+    }
+}
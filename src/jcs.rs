@@ -0,0 +1,176 @@
+//! JSON signing over the JSON Canonicalization Scheme (RFC 8785). Enabled with the `jcs` feature.
+//!
+//! The signed payload is `["verse-session-id/jcs/v1", <canonical JSON>]`, so a JS client
+//! canonicalizing the same document with a JCS library verifies with the plain `verify`.
+use crate::errors::Result;
+use crate::{ISessionIdPair, SessionId, SessionIdPair, SessionIdPublic, SignatureSet};
+use serde_json::Value;
+use std::fmt::Write;
+
+const JCS_CONTEXT: &[u8] = b"verse-session-id/jcs/v1";
+
+/// Canonical JSON of `value` per RFC 8785
+pub fn to_canonical_json(value: &Value) -> String {
+    let mut out = String::new();
+    write_value(&mut out, value);
+    out
+}
+
+fn write_value(out: &mut String, value: &Value) {
+    match value {
+        Value::Null => out.push_str("null"),
+        Value::Bool(v) => out.push_str(if *v { "true" } else { "false" }),
+        // JSON numbers are IEEE 754 doubles in JCS
+        Value::Number(v) => write_number(out, v.as_f64().unwrap_or(0.0)),
+        Value::String(v) => write_string(out, v),
+        Value::Array(v) => {
+            out.push('[');
+            for (i, item) in v.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_value(out, item);
+            }
+            out.push(']');
+        }
+        Value::Object(v) => {
+            // members are sorted by the UTF-16 code units of their names
+            let mut members: Vec<(&String, &Value)> = v.iter().collect();
+            members.sort_by(|a, b| a.0.encode_utf16().cmp(b.0.encode_utf16()));
+            out.push('{');
+            for (i, (k, v)) in members.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_string(out, k);
+                out.push(':');
+                write_value(out, v);
+            }
+            out.push('}');
+        }
+    }
+}
+
+fn write_string(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\u{8}' => out.push_str("\\b"),
+            '\t' => out.push_str("\\t"),
+            '\n' => out.push_str("\\n"),
+            '\u{c}' => out.push_str("\\f"),
+            '\r' => out.push_str("\\r"),
+            c if c < ' ' => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+// ECMAScript Number.prototype.toString
+fn write_number(out: &mut String, v: f64) {
+    if v == 0.0 {
+        out.push('0');
+        return;
+    }
+    if v < 0.0 {
+        out.push('-');
+    }
+    // shortest round-trip digits, e.g. "1.2345e-7"
+    let sci = format!("{:e}", v.abs());
+    let (mantissa, exp) = sci.split_once('e').unwrap_or((&sci, "0"));
+    let digits: String = mantissa.chars().filter(|c| *c != '.').collect();
+    let k = digits.len() as i32;
+    // position of the decimal point relative to the digits
+    let n = exp.parse::<i32>().unwrap_or(0) + 1;
+    if k <= n && n <= 21 {
+        out.push_str(&digits);
+        out.extend(std::iter::repeat_n('0', (n - k) as usize));
+    } else if 0 < n && n <= 21 {
+        out.push_str(&digits[..n as usize]);
+        out.push('.');
+        out.push_str(&digits[n as usize..]);
+    } else if -6 < n && n <= 0 {
+        out.push_str("0.");
+        out.extend(std::iter::repeat_n('0', -n as usize));
+        out.push_str(&digits);
+    } else {
+        out.push_str(&digits[..1]);
+        if k > 1 {
+            out.push('.');
+            out.push_str(&digits[1..]);
+        }
+        let _ = write!(out, "e{}{}", if n > 0 { "+" } else { "-" }, (n - 1).abs());
+    }
+}
+
+/// Signing of canonical JSON with a SessionIdPair
+pub trait JsonSigner {
+    fn sign_json(&self, value: &Value) -> Result<SignatureSet>;
+}
+
+impl JsonSigner for SessionIdPair {
+    fn sign_json(&self, value: &Value) -> Result<SignatureSet> {
+        self.sign([JCS_CONTEXT, to_canonical_json(value).as_bytes()])
+    }
+}
+
+impl SessionId {
+    /// Verify a signature created by [`JsonSigner::sign_json`] over the same logical document
+    pub fn verify_json(&self, value: &Value, signature: &SignatureSet) -> Result<()> {
+        self.verify(
+            [JCS_CONTEXT, to_canonical_json(value).as_bytes()],
+            signature,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::new_session_id_pair;
+
+    #[test]
+    fn test_jcs() {
+        // RFC 8785 section 3.2.2
+        let input = r#"{
+            "numbers": [333333333.33333329, 1E30, 4.50, 2e-3, 0.000000000000000000000000001],
+            "string": "\u20ac$\u000F\u000aA'\u0042\u0022\u005c\\\"\/",
+            "literals": [null, true, false]
+        }"#;
+        let value: Value = serde_json::from_str(input).unwrap();
+        assert_eq!(
+            to_canonical_json(&value),
+            r#"{"literals":[null,true,false],"numbers":[333333333.3333333,1e+30,4.5,0.002,1e-27],"string":"€$\u000f\nA'B\"\\\\\"/"}"#
+        );
+        let numbers: Value =
+            serde_json::from_str("[0, -0.0, 100, 1e21, 1e20, 0.000001, 1e-7, -1.5, 123456789012]")
+                .unwrap();
+        assert_eq!(
+            to_canonical_json(&numbers),
+            "[0,0,100,1e+21,100000000000000000000,0.000001,1e-7,-1.5,123456789012]"
+        );
+        // UTF-16 ordering puts U+FB33 after U+1F600
+        let keys: Value = serde_json::from_str(r#"{"\ufb33":1,"\ud83d\ude00":2,"a":3}"#).unwrap();
+        assert_eq!(
+            to_canonical_json(&keys),
+            "{\"a\":3,\"\u{1f600}\":2,\"\u{fb33}\":1}"
+        );
+
+        let pair = new_session_id_pair().unwrap();
+        let signature = pair.sign_json(&value).unwrap();
+        let reordered: Value = serde_json::from_str(
+            r#"{"literals":[null,true,false],"string":"€$\u000f\nA'B\"\\\\\"/","numbers":[333333333.3333333,1e30,4.5,0.002,1e-27]}"#,
+        )
+        .unwrap();
+        assert!(pair.get_id().verify_json(&reordered, &signature).is_ok());
+        assert!(pair
+            .get_id()
+            .verify_json(&serde_json::json!({"a": 1}), &signature)
+            .is_err());
+    }
+}
@@ -0,0 +1,199 @@
+//! JOSE support. Enabled with the `jose` feature.
+//!
+//! JWS compact serialization (RFC 7515) with `alg: EdDSA` (RFC 8037).
+//! The `kid` header is the base64url (no padding) encoding of the SessionId.
+use crate::base64url;
+use crate::errors::{self, Result, SessionIdError, SignatureErrorKind};
+use crate::{ISessionIdPair, SessionId, SessionIdPair};
+use serde::{Deserialize, Serialize};
+
+const JWS_ALG: &str = "EdDSA";
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct JwsHeader {
+    pub alg: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub typ: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kid: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub crit: Option<Vec<String>>,
+}
+
+pub(crate) fn json_error(e: serde_json::Error) -> SessionIdError {
+    SessionIdError::InvalidFormat(format!("json: {}", e))
+}
+
+/// JWS signing with a SessionIdPair
+pub trait JwsSigner {
+    /// Create a JWS in compact serialization over `payload`
+    fn sign_jws(&self, payload: &[u8]) -> Result<String>;
+}
+
+impl JwsSigner for SessionIdPair {
+    fn sign_jws(&self, payload: &[u8]) -> Result<String> {
+        sign_jws_with_typ(self, None, payload)
+    }
+}
+
+pub(crate) fn sign_jws_with_typ(
+    pair: &SessionIdPair,
+    typ: Option<&str>,
+    payload: &[u8],
+) -> Result<String> {
+    let header = JwsHeader {
+        alg: JWS_ALG.to_string(),
+        typ: typ.map(|v| v.to_string()),
+        kid: Some(pair.get_id().to_jws_kid()),
+        crit: None,
+    };
+    let header = serde_json::to_vec(&header).map_err(json_error)?;
+    let signing_input = format!(
+        "{}.{}",
+        base64url::encode(header),
+        base64url::encode(payload)
+    );
+    let signature = ed25519_dalek::Signer::sign(pair, signing_input.as_bytes());
+    Ok(format!(
+        "{}.{}",
+        signing_input,
+        base64url::encode(signature.to_bytes())
+    ))
+}
+
+struct DecodedJws<'a> {
+    header: JwsHeader,
+    payload: Vec<u8>,
+    signing_input: &'a str,
+    signature: Vec<u8>,
+}
+
+fn decode_jws(jws: &str) -> Result<DecodedJws<'_>> {
+    let invalid = || SessionIdError::InvalidFormat("jws: not compact serialization".to_string());
+    let (signing_input, signature) = jws.rsplit_once('.').ok_or_else(invalid)?;
+    let (header, payload) = signing_input.split_once('.').ok_or_else(invalid)?;
+    let header: JwsHeader =
+        serde_json::from_slice(&base64url::decode(header)?).map_err(json_error)?;
+    if header.alg != JWS_ALG {
+        return Err(SessionIdError::InvalidFormat(format!(
+            "jws: unsupported alg {}",
+            header.alg
+        )));
+    }
+    if header.crit.is_some() {
+        return Err(SessionIdError::InvalidFormat(
+            "jws: unsupported crit".to_string(),
+        ));
+    }
+    Ok(DecodedJws {
+        header,
+        payload: base64url::decode(payload)?,
+        signing_input,
+        signature: base64url::decode(signature)?,
+    })
+}
+
+fn verify_decoded(session_id: &SessionId, jws: DecodedJws) -> Result<(JwsHeader, Vec<u8>)> {
+    let pk = ed25519_dalek::PublicKey::from_bytes(session_id.as_ref())
+        .map_err(errors::signature(SignatureErrorKind::MalformedPublicKey))?;
+    let signature = ed25519_dalek::Signature::from_bytes(&jws.signature)
+        .map_err(errors::signature(SignatureErrorKind::MalformedSignature))?;
+    pk.verify_strict(jws.signing_input.as_bytes(), &signature)
+        .map_err(errors::signature(SignatureErrorKind::VerificationFailed))?;
+    Ok((jws.header, jws.payload))
+}
+
+/// Verify a JWS signed by the SessionId in its `kid` header.
+/// Returns the signer and the payload.
+pub fn verify_jws(jws: &str) -> Result<(SessionId, Vec<u8>)> {
+    let (session_id, _, payload) = verify_jws_with_header(jws)?;
+    Ok((session_id, payload))
+}
+
+pub(crate) fn verify_jws_with_header(jws: &str) -> Result<(SessionId, JwsHeader, Vec<u8>)> {
+    let decoded = decode_jws(jws)?;
+    let session_id = SessionId::from_jws_kid(
+        decoded
+            .header
+            .kid
+            .as_deref()
+            .ok_or(SessionIdError::Required)?,
+    )?;
+    let (header, payload) = verify_decoded(&session_id, decoded)?;
+    Ok((session_id, header, payload))
+}
+
+impl SessionId {
+    /// JWS `kid`: base64url (no padding) of the session ID
+    pub fn to_jws_kid(&self) -> String {
+        base64url::encode(self)
+    }
+    pub fn from_jws_kid(kid: &str) -> Result<Self> {
+        base64url::decode(kid)?.try_into()
+    }
+    /// Verify a JWS signed by this session ID. Returns the payload.
+    pub fn verify_jws(&self, jws: &str) -> Result<Vec<u8>> {
+        Ok(verify_decoded(self, decode_jws(jws)?)?.1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::new_session_id_pair;
+
+    #[test]
+    fn test_jws() {
+        let kp = new_session_id_pair().unwrap();
+        let sid = kp.get_id();
+        let jws = kp.sign_jws(b"testdata").unwrap();
+        assert_eq!(jws.split('.').count(), 3);
+
+        let header: serde_json::Value =
+            serde_json::from_slice(&base64url::decode(jws.split('.').next().unwrap()).unwrap())
+                .unwrap();
+        assert_eq!(header["alg"], "EdDSA");
+        assert_eq!(header["kid"], sid.to_jws_kid());
+
+        assert_eq!(sid.verify_jws(&jws).unwrap(), b"testdata");
+        let (signer, payload) = verify_jws(&jws).unwrap();
+        assert_eq!(signer, sid);
+        assert_eq!(payload, b"testdata");
+
+        let other = new_session_id_pair().unwrap().get_id();
+        assert!(matches!(
+            other.verify_jws(&jws),
+            Err(SessionIdError::Signature(
+                SignatureErrorKind::VerificationFailed
+            ))
+        ));
+
+        let parts: Vec<&str> = jws.split('.').collect();
+        let tampered = format!(
+            "{}.{}.{}",
+            parts[0],
+            base64url::encode("testdatb"),
+            parts[2]
+        );
+        assert!(verify_jws(&tampered).is_err());
+
+        let none = format!(
+            "{}.{}.",
+            base64url::encode(r#"{"alg":"none"}"#),
+            base64url::encode("testdata")
+        );
+        assert!(matches!(
+            verify_jws(&none),
+            Err(SessionIdError::InvalidFormat(_))
+        ));
+        assert!(verify_jws("abc").is_err());
+    }
+
+    #[test]
+    fn test_jws_kid() {
+        let sid = SessionId::from([0xfb; 32]);
+        let kid = sid.to_jws_kid();
+        assert!(!kid.contains('+') && !kid.contains('/') && !kid.contains('='));
+        assert_eq!(SessionId::from_jws_kid(&kid).unwrap(), sid);
+    }
+}
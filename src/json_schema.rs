@@ -0,0 +1,110 @@
+//! `JsonSchema` implementations for OpenAPI generation. Enabled with the `schemars` feature.
+//!
+//! The schemas describe the JSON produced by this crate: a session ID as its base64
+//! string, a [`SignatureSet`] as an object of base64 fields.
+use crate::{SessionId, SignatureSet, VersionedSignatureSet};
+use schemars::{json_schema, JsonSchema, Schema, SchemaGenerator};
+use std::borrow::Cow;
+
+// standard base64 of 32, 64 and 8 bytes
+const SESSION_ID_PATTERN: &str = "^[A-Za-z0-9+/]{43}=$";
+const SIGNATURE_PATTERN: &str = "^[A-Za-z0-9+/]{86}==$";
+const SALT_PATTERN: &str = "^[A-Za-z0-9+/]{11}=$";
+
+impl JsonSchema for SessionId {
+    fn schema_name() -> Cow<'static, str> {
+        "SessionId".into()
+    }
+    fn schema_id() -> Cow<'static, str> {
+        "verse_session_id::SessionId".into()
+    }
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        json_schema!({
+            "type": "string",
+            "description": "Ed25519 public key, standard base64",
+            "minLength": 44,
+            "maxLength": 44,
+            "pattern": SESSION_ID_PATTERN,
+        })
+    }
+}
+
+impl JsonSchema for SignatureSet {
+    fn schema_name() -> Cow<'static, str> {
+        "SignatureSet".into()
+    }
+    fn schema_id() -> Cow<'static, str> {
+        "verse_session_id::SignatureSet".into()
+    }
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        json_schema!({
+            "type": "object",
+            "properties": {
+                "signature": {
+                    "type": "string",
+                    "description": "Ed25519 signature, standard base64",
+                    "minLength": 88,
+                    "maxLength": 88,
+                    "pattern": SIGNATURE_PATTERN,
+                },
+                "salt": {
+                    "type": "string",
+                    "description": "Signature salt, standard base64",
+                    "minLength": 12,
+                    "maxLength": 12,
+                    "pattern": SALT_PATTERN,
+                },
+            },
+            "required": ["signature", "salt"],
+            "additionalProperties": false,
+        })
+    }
+}
+
+impl JsonSchema for VersionedSignatureSet {
+    fn schema_name() -> Cow<'static, str> {
+        "VersionedSignatureSet".into()
+    }
+    fn schema_id() -> Cow<'static, str> {
+        "verse_session_id::VersionedSignatureSet".into()
+    }
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        json_schema!({
+            "type": "string",
+            "description": "Versioned signature, standard base64",
+            "contentEncoding": "base64",
+            "minLength": 96,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{new_session_id_pair, ISessionIdPair};
+
+    #[test]
+    fn test_json_schema() {
+        let pair = new_session_id_pair().unwrap();
+        let schema = schemars::schema_for!(SessionId);
+        assert_eq!(schema.get("type").unwrap(), "string");
+        assert_eq!(schema.get("pattern").unwrap(), SESSION_ID_PATTERN);
+        assert_eq!(
+            schema.get("maxLength").unwrap(),
+            pair.get_id().to_string().len()
+        );
+
+        let sig = serde_json::to_value(pair.sign([b"x"]).unwrap()).unwrap();
+        let schema = schemars::schema_for!(SignatureSet);
+        for field in ["signature", "salt"] {
+            let value = sig[field].as_str().unwrap();
+            let property = &schema.get("properties").unwrap()[field];
+            assert_eq!(property["maxLength"], value.len());
+            assert!(value.ends_with('='));
+        }
+        assert_eq!(
+            schema.get("required").unwrap(),
+            &serde_json::json!(["signature", "salt"])
+        );
+    }
+}
@@ -0,0 +1,310 @@
+//! Persistent storage of the keypair outside the application's own files.
+//!
+//! With the `keyring` feature the secret key is kept in the platform credential store
+//! (macOS Keychain, Windows Credential Manager, Secret Service / libsecret), which
+//! encrypts it at rest, so desktop clients do not write key files into the profile directory.
+//!
+//! With the `hardware-keystore` feature mobile apps keep the seed wrapped by a hardware-backed
+//! key (Android Keystore, iOS Secure Enclave) that requires biometric authentication. The app
+//! implements [`PlatformKeyWrapper`] (e.g. as a uniffi callback interface) and signs through a
+//! [`HardwareSigner`], which unwraps the seed for each signature only, so a stolen device does
+//! not leak the identity.
+use crate::errors::{Result, SessionIdError};
+#[cfg(feature = "hardware-keystore")]
+use crate::{ISessionIdPair, SessionId, SignatureSet, SESSION_ID_SIZE};
+use crate::{SecretSessionKey, SessionIdPair, SessionIdPairSecret, SECRET_KEY_SIZE};
+#[cfg(feature = "hardware-keystore")]
+use std::sync::Arc;
+use zeroize::Zeroizing;
+
+#[cfg(feature = "keyring")]
+fn keyring_error(e: keyring::Error) -> SessionIdError {
+    SessionIdError::Keystore(e.to_string())
+}
+
+/// Hardware-backed wrapping key and storage of the wrapped seed, implemented by the app.
+/// Errors are reported as [`SessionIdError::Keystore`].
+#[cfg(feature = "hardware-keystore")]
+pub trait PlatformKeyWrapper: Send + Sync {
+    /// Encrypt `secret` under a non-exportable key that requires user authentication
+    /// (e.g. `setUserAuthenticationRequired` or `kSecAccessControlBiometryCurrentSet`)
+    fn wrap(&self, secret: Vec<u8>) -> Result<Vec<u8>>;
+    /// Decrypt the output of `wrap`. Shows the biometric prompt.
+    fn unwrap(&self, wrapped: Vec<u8>) -> Result<Vec<u8>>;
+    /// The stored blob, `None` if there is none
+    fn load_wrapped(&self) -> Result<Option<Vec<u8>>>;
+    fn store_wrapped(&self, blob: Vec<u8>) -> Result<()>;
+    /// Remove the stored blob. Succeeds if there is none.
+    fn delete_wrapped(&self) -> Result<()>;
+}
+
+// blob of the hardware backend: the session ID, readable without authentication,
+// followed by the wrapped seed
+#[cfg(feature = "hardware-keystore")]
+fn parse_blob(blob: &[u8]) -> Result<(SessionId, &[u8])> {
+    if blob.len() <= SESSION_ID_SIZE {
+        return Err(SessionIdError::Keystore(
+            "stored blob has an invalid length".to_string(),
+        ));
+    }
+    let (id, wrapped) = blob.split_at(SESSION_ID_SIZE);
+    Ok((SessionId::try_from(id)?, wrapped))
+}
+
+// asks for user authentication
+#[cfg(feature = "hardware-keystore")]
+fn unwrap_pair(
+    wrapper: &dyn PlatformKeyWrapper,
+    id: &SessionId,
+    wrapped: &[u8],
+) -> Result<SessionIdPair> {
+    let secret = Zeroizing::new(wrapper.unwrap(wrapped.to_vec())?);
+    let pair = pair_from_secret(&secret)?;
+    if pair.get_id() != *id {
+        return Err(SessionIdError::Keystore(
+            "stored session id does not match the secret".to_string(),
+        ));
+    }
+    Ok(pair)
+}
+
+fn pair_from_secret(secret: &[u8]) -> Result<SessionIdPair> {
+    if secret.len() != SECRET_KEY_SIZE {
+        return Err(SessionIdError::Keystore(
+            "stored secret has an invalid length".to_string(),
+        ));
+    }
+    let mut key = SecretSessionKey::new([0; SECRET_KEY_SIZE]);
+    key.expose_secret_mut().copy_from_slice(secret);
+    key.to_session_id_pair()
+}
+
+enum Backend {
+    #[cfg(feature = "keyring")]
+    OsKeychain(keyring::Entry),
+    #[cfg(feature = "hardware-keystore")]
+    Hardware(Arc<dyn PlatformKeyWrapper>),
+}
+
+/// Storage location of one keypair
+pub struct Keystore {
+    backend: Backend,
+}
+
+impl Keystore {
+    /// Entry `account` of `service` (e.g. `"verse"`, `"default"`) in the platform credential store
+    #[cfg(feature = "keyring")]
+    pub fn os_keychain(service: &str, account: &str) -> Result<Self> {
+        Ok(Self::from_keyring_entry(
+            keyring::Entry::new(service, account).map_err(keyring_error)?,
+        ))
+    }
+    /// Custom keyring entry, e.g. with a non-default credential store
+    #[cfg(feature = "keyring")]
+    pub fn from_keyring_entry(entry: keyring::Entry) -> Self {
+        Keystore {
+            backend: Backend::OsKeychain(entry),
+        }
+    }
+    /// Seed wrapped by a hardware-backed key of the platform
+    #[cfg(feature = "hardware-keystore")]
+    pub fn hardware(wrapper: Arc<dyn PlatformKeyWrapper>) -> Self {
+        Keystore {
+            backend: Backend::Hardware(wrapper),
+        }
+    }
+
+    /// `None` if no keypair is stored.
+    /// With a hardware backend this asks for user authentication; prefer [`Keystore::signer`].
+    pub fn load(&self) -> Result<Option<SessionIdPair>> {
+        match &self.backend {
+            #[cfg(feature = "keyring")]
+            Backend::OsKeychain(entry) => {
+                let secret = match entry.get_secret() {
+                    Ok(v) => Zeroizing::new(v),
+                    Err(keyring::Error::NoEntry) => return Ok(None),
+                    Err(e) => return Err(keyring_error(e)),
+                };
+                pair_from_secret(&secret).map(Some)
+            }
+            #[cfg(feature = "hardware-keystore")]
+            Backend::Hardware(wrapper) => {
+                let Some(blob) = wrapper.load_wrapped()? else {
+                    return Ok(None);
+                };
+                let (id, wrapped) = parse_blob(&blob)?;
+                unwrap_pair(wrapper.as_ref(), &id, wrapped).map(Some)
+            }
+        }
+    }
+    /// Store `pair`, replacing any stored keypair
+    pub fn store(&self, pair: &SessionIdPair) -> Result<()> {
+        match &self.backend {
+            #[cfg(feature = "keyring")]
+            Backend::OsKeychain(entry) => entry
+                .set_secret(pair.secret_key().expose_secret())
+                .map_err(keyring_error),
+            #[cfg(feature = "hardware-keystore")]
+            Backend::Hardware(wrapper) => {
+                let secret = pair.secret_key().expose_secret().to_vec();
+                let mut blob = pair.get_id().as_raw().to_vec();
+                blob.extend_from_slice(&wrapper.wrap(secret)?);
+                wrapper.store_wrapped(blob)
+            }
+        }
+    }
+    /// Remove the stored keypair. Succeeds if there is none.
+    pub fn delete(&self) -> Result<()> {
+        match &self.backend {
+            #[cfg(feature = "keyring")]
+            Backend::OsKeychain(entry) => match entry.delete_credential() {
+                Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+                Err(e) => Err(keyring_error(e)),
+            },
+            #[cfg(feature = "hardware-keystore")]
+            Backend::Hardware(wrapper) => wrapper.delete_wrapped(),
+        }
+    }
+    /// The stored keypair, generating and storing one on first use
+    pub fn load_or_generate(&self) -> Result<SessionIdPair> {
+        if let Some(pair) = self.load()? {
+            return Ok(pair);
+        }
+        let pair = crate::new_session_id_pair()?;
+        self.store(&pair)?;
+        Ok(pair)
+    }
+    /// Signer over the stored keypair that authenticates the user for every signature.
+    /// `None` if no keypair is stored or the backend is not hardware-backed.
+    #[cfg(feature = "hardware-keystore")]
+    pub fn signer(&self) -> Result<Option<HardwareSigner>> {
+        #[allow(irrefutable_let_patterns)]
+        let Backend::Hardware(wrapper) = &self.backend
+        else {
+            return Ok(None);
+        };
+        let Some(blob) = wrapper.load_wrapped()? else {
+            return Ok(None);
+        };
+        let (id, wrapped) = parse_blob(&blob)?;
+        Ok(Some(HardwareSigner {
+            wrapper: wrapper.clone(),
+            id,
+            wrapped: wrapped.to_vec(),
+        }))
+    }
+}
+
+/// Signer whose seed stays wrapped by the platform except while signing
+#[cfg(feature = "hardware-keystore")]
+pub struct HardwareSigner {
+    wrapper: Arc<dyn PlatformKeyWrapper>,
+    id: SessionId,
+    wrapped: Vec<u8>,
+}
+
+#[cfg(feature = "hardware-keystore")]
+impl ISessionIdPair for HardwareSigner {
+    fn get_id(&self) -> SessionId {
+        self.id
+    }
+    /// Unwrap the seed (showing the biometric prompt), sign and drop it
+    fn sign<P>(&self, payload: P) -> Result<SignatureSet>
+    where
+        P: IntoIterator,
+        P::Item: AsRef<[u8]>,
+    {
+        unwrap_pair(self.wrapper.as_ref(), &self.id, &self.wrapped)?.sign(payload)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ISessionIdPair;
+
+    #[cfg(feature = "keyring")]
+    #[test]
+    fn test_keystore() {
+        let credential = keyring::mock::default_credential_builder()
+            .build(None, "verse", "default")
+            .unwrap();
+        let store = Keystore::from_keyring_entry(keyring::Entry::new_with_credential(credential));
+        assert!(store.load().unwrap().is_none());
+
+        let pair = store.load_or_generate().unwrap();
+        assert_eq!(store.load().unwrap().unwrap().get_id(), pair.get_id());
+        assert_eq!(store.load_or_generate().unwrap().get_id(), pair.get_id());
+
+        store.delete().unwrap();
+        assert!(store.load().unwrap().is_none());
+        store.delete().unwrap();
+    }
+
+    #[cfg(feature = "hardware-keystore")]
+    #[test]
+    fn test_hardware_keystore() {
+        use crate::SessionIdPublic;
+        use std::sync::Mutex;
+
+        // XOR stands in for the hardware key; `prompts` counts biometric prompts
+        #[derive(Default)]
+        struct MockWrapper {
+            blob: Mutex<Option<Vec<u8>>>,
+            prompts: Mutex<usize>,
+            deny: Mutex<bool>,
+        }
+        impl PlatformKeyWrapper for MockWrapper {
+            fn wrap(&self, secret: Vec<u8>) -> Result<Vec<u8>> {
+                Ok(secret.iter().map(|b| b ^ 0x5a).collect())
+            }
+            fn unwrap(&self, wrapped: Vec<u8>) -> Result<Vec<u8>> {
+                *self.prompts.lock().unwrap() += 1;
+                if *self.deny.lock().unwrap() {
+                    return Err(SessionIdError::Keystore(
+                        "authentication failed".to_string(),
+                    ));
+                }
+                Ok(wrapped.iter().map(|b| b ^ 0x5a).collect())
+            }
+            fn load_wrapped(&self) -> Result<Option<Vec<u8>>> {
+                Ok(self.blob.lock().unwrap().clone())
+            }
+            fn store_wrapped(&self, blob: Vec<u8>) -> Result<()> {
+                *self.blob.lock().unwrap() = Some(blob);
+                Ok(())
+            }
+            fn delete_wrapped(&self) -> Result<()> {
+                *self.blob.lock().unwrap() = None;
+                Ok(())
+            }
+        }
+
+        let wrapper = Arc::new(MockWrapper::default());
+        let store = Keystore::hardware(wrapper.clone());
+        assert!(store.signer().unwrap().is_none());
+        let pair = store.load_or_generate().unwrap();
+        let stored = wrapper.blob.lock().unwrap().clone().unwrap();
+        assert!(!stored
+            .windows(SECRET_KEY_SIZE)
+            .any(|w| w == pair.secret.as_bytes()));
+
+        // the id is readable without a prompt, every signature needs one
+        *wrapper.prompts.lock().unwrap() = 0;
+        let signer = store.signer().unwrap().unwrap();
+        assert_eq!(signer.get_id(), pair.get_id());
+        assert_eq!(*wrapper.prompts.lock().unwrap(), 0);
+        let sig = signer.sign([b"hello"]).unwrap();
+        assert!(pair.get_id().verify([b"hello"], &sig).is_ok());
+        signer.sign([b"hello"]).unwrap();
+        assert_eq!(*wrapper.prompts.lock().unwrap(), 2);
+        *wrapper.deny.lock().unwrap() = true;
+        assert!(signer.sign([b"hello"]).is_err());
+        assert!(store.load().is_err());
+        *wrapper.deny.lock().unwrap() = false;
+        assert_eq!(store.load().unwrap().unwrap().get_id(), pair.get_id());
+
+        store.delete().unwrap();
+        assert!(store.load().unwrap().is_none());
+    }
+}
@@ -0,0 +1,217 @@
+//! Signed TXT records for local-network peer discovery (mDNS / DNS-SD).
+//!
+//! A peer advertises its SessionId, addresses and a timestamp, signed by its session key, as
+//! TXT strings (RFC 6763 §6):
+//!
+//! ```text
+//! txtvers=1
+//! sid=<base64url session id>
+//! ts=<milliseconds since UNIX epoch>
+//! addrs=192.168.1.20:7000,[fe80::1]:7000
+//! sig=<base64url signature set>
+//! ```
+//!
+//! Anyone on the LAN can publish a record, so only the signature ties it to the SessionId, and
+//! the [`FreshnessWindow`] keeps old records from being replayed.
+use crate::errors::{Result, SessionIdError};
+use crate::time::{Clock, SystemClock};
+use crate::{
+    base64url, FreshnessWindow, ISessionIdPair, SessionId, SessionIdPair, SessionIdPublic,
+    SignatureSet, SIGNATURE_SET_SIZE,
+};
+use std::net::SocketAddr;
+
+const DISCOVERY_CONTEXT: &[u8] = b"verse-session-id/lan-discovery/v1";
+const TXT_VERSION: &str = "1";
+// limit of a single TXT string
+const MAX_TXT_ENTRY: usize = 255;
+
+fn discovery_error(msg: &str) -> SessionIdError {
+    SessionIdError::InvalidFormat(format!("lan_discovery: {}", msg))
+}
+
+/// Peer advertised on the local network
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DiscoveryRecord {
+    pub session_id: SessionId,
+    pub addrs: Vec<SocketAddr>,
+    /// Milliseconds since UNIX epoch
+    pub timestamp_ms: u64,
+}
+
+impl DiscoveryRecord {
+    fn addrs_value(&self) -> String {
+        self.addrs
+            .iter()
+            .map(|v| v.to_string())
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+    fn signed_bytes(&self) -> Vec<u8> {
+        let mut buf = self.session_id.as_raw().to_vec();
+        buf.extend_from_slice(&self.timestamp_ms.to_le_bytes());
+        buf.extend_from_slice(self.addrs_value().as_bytes());
+        buf
+    }
+    /// Signed TXT strings advertising `addrs` at the current time
+    pub fn sign(pair: &SessionIdPair, addrs: &[SocketAddr]) -> Result<Vec<String>> {
+        Self::sign_at(pair, addrs, &SystemClock)
+    }
+    /// Signed TXT strings advertising `addrs` at the time of `clock`
+    pub fn sign_at(
+        pair: &SessionIdPair,
+        addrs: &[SocketAddr],
+        clock: &dyn Clock,
+    ) -> Result<Vec<String>> {
+        let timestamp_ms = clock.now_ms();
+        let record = DiscoveryRecord {
+            session_id: pair.get_id(),
+            addrs: addrs.to_vec(),
+            timestamp_ms,
+        };
+        let signature = pair.sign([DISCOVERY_CONTEXT, &record.signed_bytes()])?;
+        let addrs = format!("addrs={}", record.addrs_value());
+        if addrs.len() > MAX_TXT_ENTRY {
+            return Err(SessionIdError::InvalidArgument(
+                "too many addresses for a txt record",
+            ));
+        }
+        Ok(vec![
+            format!("txtvers={}", TXT_VERSION),
+            format!("sid={}", base64url::encode(record.session_id)),
+            format!("ts={}", timestamp_ms),
+            addrs,
+            format!("sig={}", base64url::encode(signature.to_bytes())),
+        ])
+    }
+    /// Parse and verify TXT strings at the current time
+    pub fn verify<S: AsRef<str>>(
+        txt: impl IntoIterator<Item = S>,
+        window: &FreshnessWindow,
+    ) -> Result<Self> {
+        Self::verify_at(txt, window, &SystemClock)
+    }
+    /// Parse and verify TXT strings at the time of `clock`. Unknown keys are ignored.
+    pub fn verify_at<S: AsRef<str>>(
+        txt: impl IntoIterator<Item = S>,
+        window: &FreshnessWindow,
+        clock: &dyn Clock,
+    ) -> Result<Self> {
+        let now_ms = clock.now_ms();
+        let [version, sid, ts, addrs, sig] =
+            txt_values(txt, ["txtvers", "sid", "ts", "addrs", "sig"]);
+        if version.as_deref() != Some(TXT_VERSION) {
+            return Err(discovery_error("unsupported txtvers"));
+        }
+        let missing = || discovery_error("missing key");
+        let session_id = SessionId::try_from(&base64url::decode(&sid.ok_or_else(missing)?)?[..])?;
+        let timestamp_ms = ts
+            .ok_or_else(missing)?
+            .parse()
+            .map_err(|_| discovery_error("invalid ts"))?;
+        let addrs = match addrs.ok_or_else(missing)?.as_str() {
+            "" => vec![],
+            v => v
+                .split(',')
+                .map(|v| v.parse().map_err(|_| discovery_error("invalid address")))
+                .collect::<Result<_>>()?,
+        };
+        let sig = base64url::decode(&sig.ok_or_else(missing)?)?;
+        let sig: &[u8; SIGNATURE_SET_SIZE] = sig[..]
+            .try_into()
+            .map_err(|_| crate::errors::invalid_length(SIGNATURE_SET_SIZE, sig.len()))?;
+        let record = DiscoveryRecord {
+            session_id,
+            addrs,
+            timestamp_ms,
+        };
+        if now_ms > timestamp_ms.saturating_add(window.max_age_ms) {
+            return Err(SessionIdError::Expired);
+        }
+        if timestamp_ms > now_ms.saturating_add(window.max_skew_ms) {
+            return Err(SessionIdError::NotYetValid);
+        }
+        session_id.verify(
+            [DISCOVERY_CONTEXT, &record.signed_bytes()],
+            &SignatureSet::from_bytes(sig),
+        )?;
+        Ok(record)
+    }
+}
+
+// values of `keys`; keys are case-insensitive and the first occurrence wins (RFC 6763 §6.4)
+fn txt_values<S: AsRef<str>, const N: usize>(
+    txt: impl IntoIterator<Item = S>,
+    keys: [&str; N],
+) -> [Option<String>; N] {
+    let mut values = [(); N].map(|_| None);
+    for entry in txt {
+        let (key, value) = entry
+            .as_ref()
+            .split_once('=')
+            .unwrap_or((entry.as_ref(), ""));
+        if let Some(i) = keys.iter().position(|k| k.eq_ignore_ascii_case(key)) {
+            values[i].get_or_insert_with(|| value.to_string());
+        }
+    }
+    values
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{new_session_id_pair, ManualClock};
+
+    #[test]
+    fn test_discovery_record() {
+        let pair = new_session_id_pair().unwrap();
+        let addrs: Vec<SocketAddr> = vec![
+            "192.168.1.20:7000".parse().unwrap(),
+            "[fe80::1]:7000".parse().unwrap(),
+        ];
+        let window = FreshnessWindow::default();
+        let now = 1_700_000_000_000;
+        let txt = DiscoveryRecord::sign_at(&pair, &addrs, &ManualClock::from_ms(now)).unwrap();
+        assert!(txt.iter().all(|v| v.len() <= MAX_TXT_ENTRY));
+
+        let record =
+            DiscoveryRecord::verify_at(&txt, &window, &ManualClock::from_ms(now + 1_000)).unwrap();
+        assert_eq!(record.session_id, pair.get_id());
+        assert_eq!(record.addrs, addrs);
+        assert!(matches!(
+            DiscoveryRecord::verify_at(&txt, &window, &ManualClock::from_ms(now + 60_000)),
+            Err(SessionIdError::Expired)
+        ));
+
+        // a spoofer cannot claim another peer's id or redirect its addresses
+        let mallory = new_session_id_pair().unwrap();
+        let mut spoofed =
+            DiscoveryRecord::sign_at(&mallory, &addrs, &ManualClock::from_ms(now)).unwrap();
+        spoofed[1] = txt[1].clone();
+        assert!(DiscoveryRecord::verify_at(&spoofed, &window, &ManualClock::from_ms(now)).is_err());
+        let mut redirected = txt.clone();
+        redirected[3] = "addrs=10.0.0.66:7000".to_string();
+        assert!(
+            DiscoveryRecord::verify_at(&redirected, &window, &ManualClock::from_ms(now)).is_err()
+        );
+
+        // the first occurrence of a key wins, extra keys are ignored
+        let mut extended = txt.clone();
+        extended.push("ADDRS=10.0.0.66:7000".to_string());
+        extended.push("name=alice".to_string());
+        assert_eq!(
+            DiscoveryRecord::verify_at(&extended, &window, &ManualClock::from_ms(now)).unwrap(),
+            record
+        );
+        assert!(
+            DiscoveryRecord::verify_at(&txt[1..], &window, &ManualClock::from_ms(now)).is_err()
+        );
+
+        let empty = DiscoveryRecord::sign(&pair, &[]).unwrap();
+        assert!(DiscoveryRecord::verify(&empty, &window)
+            .unwrap()
+            .addrs
+            .is_empty());
+        assert!(DiscoveryRecord::sign(&pair, &[addrs[1]; 20]).is_err());
+    }
+}
@@ -0,0 +1,268 @@
+//! Signing on a Ledger hardware wallet. Enabled with the `ledger` feature.
+//!
+//! **Experimental.** A [`SignatureSet`] is an Ed25519ph signature over the salted payload,
+//! which no app in the Ledger catalog produces (the SSH/PGP agent and the coin apps sign
+//! pure Ed25519 or their own transaction formats). [`LedgerSigner`] therefore requires a
+//! device app named [`LEDGER_APP_NAME`] implementing the protocol below. No such app is
+//! published yet, so it has to be built and sideloaded, and the protocol may change
+//! before one is. The running app is checked with the standard BOLOS "get app and version"
+//! command before any other command is sent.
+//!
+//! | INS | P1 | data | response |
+//! |-----|----|------|----------|
+//! | `0x02` get public key | `0` | path | 32-byte public key |
+//! | `0x04` sign | `0` start | path | |
+//! | `0x04` sign | `1` message chunk | up to 255 bytes | |
+//! | `0x04` sign | `2` finish | | 64-byte Ed25519ph signature of the chunks |
+//!
+//! A path is a length byte and big-endian hardened indices (SLIP-0010). The secret never
+//! leaves the device, and the crate does not link a USB stack: [`LedgerTransport`] is
+//! implemented over e.g. `ledger-transport-hid`.
+use crate::errors::{Result, SessionIdError, SignatureErrorKind};
+use crate::session_id_pair::{prehash, verify_prehashed};
+use crate::{
+    ISessionIdPair, SessionId, SignatureSet, SESSION_ID_SIZE, SIGNATURE_SALT_SIZE, SIGNATURE_SIZE,
+};
+use ed25519_dalek::Digest;
+
+/// Name the device app must report
+pub const LEDGER_APP_NAME: &str = "Verse Session";
+const CLA: u8 = 0xe0;
+// BOLOS "get app and version", answered by the OS for whichever app is running
+const APP_AND_VERSION_APDU: [u8; 5] = [0xb0, 0x01, 0x00, 0x00, 0x00];
+const INS_GET_PUBLIC_KEY: u8 = 0x02;
+const INS_SIGN: u8 = 0x04;
+const P1_START: u8 = 0;
+const P1_CHUNK: u8 = 1;
+const P1_FINISH: u8 = 2;
+const SW_OK: u16 = 0x9000;
+const SW_DENIED: u16 = 0x6985;
+const MAX_CHUNK: usize = 255;
+const MAX_PATH_LEN: usize = 10;
+/// Flag of a hardened derivation index
+pub const LEDGER_HARDENED: u32 = 0x8000_0000;
+
+fn ledger_error(msg: impl std::fmt::Display) -> SessionIdError {
+    SessionIdError::Keystore(format!("ledger: {}", msg))
+}
+
+/// Channel to the device
+pub trait LedgerTransport {
+    /// Send a command APDU and return the response, including the status word
+    fn exchange(&self, apdu: &[u8]) -> Result<Vec<u8>>;
+}
+
+/// Signer whose secret key stays on a Ledger device
+pub struct LedgerSigner<T: LedgerTransport> {
+    transport: T,
+    path: Vec<u8>,
+    id: SessionId,
+}
+
+impl<T: LedgerTransport> LedgerSigner<T> {
+    /// Signer for the key at `path` (hardened indices, e.g. `[44 | LEDGER_HARDENED, ...]`).
+    /// Reads the public key from the device.
+    pub fn new(transport: T, path: &[u32]) -> Result<Self> {
+        if path.is_empty() || path.len() > MAX_PATH_LEN {
+            return Err(SessionIdError::InvalidArgument(
+                "invalid derivation path length",
+            ));
+        }
+        if path.iter().any(|v| v & LEDGER_HARDENED == 0) {
+            return Err(SessionIdError::InvalidArgument(
+                "ed25519 derivation paths must be hardened",
+            ));
+        }
+        let mut encoded = vec![path.len() as u8];
+        for v in path {
+            encoded.extend_from_slice(&v.to_be_bytes());
+        }
+        let mut signer = LedgerSigner {
+            transport,
+            path: encoded,
+            id: SessionId::default(),
+        };
+        let app = signer.app_name()?;
+        if app != LEDGER_APP_NAME {
+            return Err(ledger_error(format_args!(
+                "open the {} app on the device, {} is running",
+                LEDGER_APP_NAME, app
+            )));
+        }
+        let public_key = signer.exchange(INS_GET_PUBLIC_KEY, P1_START, &signer.path)?;
+        if public_key.len() != SESSION_ID_SIZE {
+            return Err(ledger_error("unexpected public key length"));
+        }
+        signer.id = SessionId::try_from(&public_key[..])?;
+        Ok(signer)
+    }
+    /// Name of the app running on the device
+    fn app_name(&self) -> Result<String> {
+        let response = self.transmit(&APP_AND_VERSION_APDU)?;
+        // format 1, then the length-prefixed name and version
+        let name = match response.split_first() {
+            Some((1, rest)) => rest
+                .split_first()
+                .and_then(|(n, rest)| rest.get(..*n as usize)),
+            _ => None,
+        };
+        name.and_then(|v| std::str::from_utf8(v).ok())
+            .map(str::to_string)
+            .ok_or_else(|| ledger_error("invalid app and version response"))
+    }
+    fn exchange(&self, ins: u8, p1: u8, data: &[u8]) -> Result<Vec<u8>> {
+        let mut apdu = vec![CLA, ins, p1, 0, data.len() as u8];
+        apdu.extend_from_slice(data);
+        self.transmit(&apdu)
+    }
+    fn transmit(&self, apdu: &[u8]) -> Result<Vec<u8>> {
+        let mut response = self.transport.exchange(apdu)?;
+        if response.len() < 2 {
+            return Err(ledger_error("truncated response"));
+        }
+        let sw = response.split_off(response.len() - 2);
+        match u16::from_be_bytes([sw[0], sw[1]]) {
+            SW_OK => Ok(response),
+            SW_DENIED => Err(ledger_error("rejected on the device")),
+            sw => Err(ledger_error(format_args!("status {:04x}", sw))),
+        }
+    }
+    pub fn into_transport(self) -> T {
+        self.transport
+    }
+}
+
+impl<T: LedgerTransport> ISessionIdPair for LedgerSigner<T> {
+    fn get_id(&self) -> SessionId {
+        self.id
+    }
+    /// Stream the salted payload to the device and sign it there. The user may have to
+    /// confirm on the device. The signature is verified before it is returned.
+    fn sign<P>(&self, payload: P) -> Result<SignatureSet>
+    where
+        P: IntoIterator,
+        P::Item: AsRef<[u8]>,
+    {
+        let mut salt = [0u8; SIGNATURE_SALT_SIZE];
+        getrandom::getrandom(&mut salt)?;
+        self.exchange(INS_SIGN, P1_START, &self.path)?;
+
+        // the same bytes are hashed here to check the device's signature
+        let mut hasher = prehash(&salt, std::iter::empty::<&[u8]>());
+        let mut chunk = Vec::with_capacity(MAX_CHUNK);
+        chunk.extend_from_slice(&salt);
+        for part in payload {
+            let mut part = part.as_ref();
+            hasher.update(part);
+            while !part.is_empty() {
+                let n = part.len().min(MAX_CHUNK - chunk.len());
+                chunk.extend_from_slice(&part[..n]);
+                part = &part[n..];
+                if chunk.len() == MAX_CHUNK {
+                    self.exchange(INS_SIGN, P1_CHUNK, &chunk)?;
+                    chunk.clear();
+                }
+            }
+        }
+        if !chunk.is_empty() {
+            self.exchange(INS_SIGN, P1_CHUNK, &chunk)?;
+        }
+
+        let signature: [u8; SIGNATURE_SIZE] =
+            self.exchange(INS_SIGN, P1_FINISH, &[])?
+                .try_into()
+                .map_err(|_| SessionIdError::Signature(SignatureErrorKind::SigningFailed))?;
+        let sigset = SignatureSet { signature, salt };
+        verify_prehashed(&self.id, hasher, &sigset)
+            .map_err(|_| SessionIdError::Signature(SignatureErrorKind::SigningFailed))?;
+        Ok(sigset)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{new_session_id_pair, SessionIdPair, SessionIdPublic};
+    use ed25519_dalek::Sha512;
+    use std::cell::RefCell;
+
+    // the dedicated app, with a software key
+    struct MockLedger {
+        app: &'static str,
+        pair: SessionIdPair,
+        hasher: RefCell<Option<Sha512>>,
+        chunks: RefCell<usize>,
+    }
+
+    impl MockLedger {
+        fn new(app: &'static str) -> Self {
+            MockLedger {
+                app,
+                pair: new_session_id_pair().unwrap(),
+                hasher: RefCell::new(None),
+                chunks: RefCell::new(0),
+            }
+        }
+    }
+
+    impl LedgerTransport for MockLedger {
+        fn exchange(&self, apdu: &[u8]) -> Result<Vec<u8>> {
+            if apdu == APP_AND_VERSION_APDU {
+                let mut response = vec![1, self.app.len() as u8];
+                response.extend_from_slice(self.app.as_bytes());
+                response.extend_from_slice(&[5, b'0', b'.', b'1', b'.', b'0', 1, 0, 0x90, 0]);
+                return Ok(response);
+            }
+            assert_eq!(apdu[0], CLA);
+            assert_eq!(apdu[4] as usize, apdu.len() - 5);
+            let data = &apdu[5..];
+            let mut response = match (apdu[1], apdu[2]) {
+                (INS_GET_PUBLIC_KEY, _) => self.pair.public.to_bytes().to_vec(),
+                (INS_SIGN, P1_START) => {
+                    *self.hasher.borrow_mut() = Some(Sha512::new());
+                    vec![]
+                }
+                (INS_SIGN, P1_CHUNK) => {
+                    self.hasher.borrow_mut().as_mut().unwrap().update(data);
+                    *self.chunks.borrow_mut() += 1;
+                    vec![]
+                }
+                (INS_SIGN, P1_FINISH) => {
+                    let hasher = self.hasher.borrow_mut().take().unwrap();
+                    self.pair
+                        .sign_prehashed(hasher, None)
+                        .unwrap()
+                        .to_bytes()
+                        .to_vec()
+                }
+                _ => return Ok(vec![0x6d, 0x00]),
+            };
+            response.extend_from_slice(&SW_OK.to_be_bytes());
+            Ok(response)
+        }
+    }
+
+    #[test]
+    fn test_ledger_signer() {
+        let device = MockLedger::new(LEDGER_APP_NAME);
+        let path = [44 | LEDGER_HARDENED, 1 | LEDGER_HARDENED];
+        let signer = LedgerSigner::new(device, &path).unwrap();
+        let id = signer.get_id();
+
+        let sig = signer.sign([b"world".as_slice(), b"owner"]).unwrap();
+        assert!(id.verify([b"worldowner"], &sig).is_ok());
+        let long = vec![7u8; 1000];
+        let sig = signer.sign([&long]).unwrap();
+        assert!(id.verify([&long], &sig).is_ok());
+        let device = signer.into_transport();
+        // 1008 bytes with the salt
+        assert_eq!(*device.chunks.borrow(), 1 + 4);
+
+        assert!(LedgerSigner::new(device, &[44]).is_err());
+        // no custom command reaches another app
+        assert!(matches!(
+            LedgerSigner::new(MockLedger::new("Ethereum"), &path),
+            Err(SessionIdError::Keystore(_))
+        ));
+    }
+}
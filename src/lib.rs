@@ -12,39 +12,40 @@
 //! ## Usage
 //! ### Signature Verification
 //! ```rust
-//! use verse_session_id::*;
-//!
-//! ...
-//! pub fn verify_string(session_id: &str, signature: &str, data: &str) -> bool {
-//!   let Ok(sid) = session_id.parse::<SessionId>() else {
-//!      return false;
-//!   };
-//!   let Ok(ss) = signature.parse::<SignatureSet>() else {
-//!      return false;
-//!   };
-//!
-//!   sid.verify(vec![data.as_bytes()], &ss).is_ok()
-//! }
+//! use verse_session_id::convenience::verify_string;
+//! # use verse_session_id::{convenience::sign_string, new_session_id_pair};
+//! # let (session_id, signature) = sign_string(&new_session_id_pair().unwrap(), "data").unwrap();
+//! # let (session_id, signature, data) = (session_id.as_str(), signature.as_str(), "data");
+//!
+//! let ok = verify_string(session_id, signature, data);
+//! # assert!(ok);
 //! ```
 //!
 //!
 //! ### Generate ID
 //! ```rust
+//! # use verse_session_id::*;
+//! # fn main() -> Result<(), SessionIdError> {
 //! let id_pair = new_session_id_pair()?;
 //! let session_id = id_pair.get_id();
 //! // to string
 //! let s = format!("{}", session_id);
+//! # Ok(())
+//! # }
 //! ```
 //!
 //!
 //! ### Create a signature
 //! ```rust
-//! pub fn sign_string(&self, data: &str) -> Result<String> {
-//!     let id_pair = ...;
-//!     Ok(id_pair
-//!         .sign(vec![data.as_bytes()])?
-//!         .to_string())
-//! }
+//! use verse_session_id::convenience::sign_string;
+//! # use verse_session_id::*;
+//! # fn main() -> Result<(), SessionIdError> {
+//! # let data = "data";
+//!
+//! let id_pair = new_session_id_pair()?;
+//! let (session_id, signature) = sign_string(&id_pair, data)?;
+//! # Ok(())
+//! # }
 //! ```
 mod session_id;
 pub use session_id::*;
@@ -52,4 +53,310 @@ pub use session_id::*;
 mod session_id_pair;
 pub use session_id_pair::*;
 
+mod secret_key;
+pub use secret_key::*;
+
+mod identity_hasher;
+pub use identity_hasher::*;
+
+mod session_id_set;
+pub use session_id_set::*;
+
+mod array_string;
+pub use array_string::*;
+
+mod base32;
+mod base64url;
+mod der;
+mod encoding;
 mod errors;
+mod time;
+pub use errors::{SessionIdError, SignatureErrorKind};
+pub use time::{Clock, ManualClock, SystemClock};
+
+pub mod convenience;
+pub mod serde_helpers;
+
+mod canonical;
+pub use canonical::*;
+
+mod chunked_signature;
+pub use chunked_signature::*;
+
+mod dht;
+pub use dht::*;
+
+mod fingerprint;
+pub use fingerprint::*;
+
+mod spki;
+
+mod id_proof;
+pub use id_proof::*;
+
+mod rendezvous;
+pub use rendezvous::*;
+
+mod signed_body;
+pub use signed_body::*;
+
+mod ws_auth;
+pub use ws_auth::*;
+
+mod webrtc;
+pub use webrtc::*;
+
+mod x25519;
+pub use x25519::*;
+
+#[cfg(feature = "ffi")]
+pub mod ffi;
+
+#[cfg(feature = "python")]
+mod python;
+
+#[cfg(feature = "actix")]
+mod actix_session;
+#[cfg(feature = "actix")]
+pub use actix_session::*;
+
+#[cfg(feature = "age")]
+mod age;
+#[cfg(feature = "age")]
+pub use age::*;
+
+#[cfg(feature = "axum")]
+mod axum_session;
+#[cfg(feature = "axum")]
+pub use axum_session::*;
+
+#[cfg(feature = "cipher")]
+mod peer_cipher;
+#[cfg(feature = "cipher")]
+pub use peer_cipher::*;
+
+#[cfg(feature = "cipher")]
+mod datagram_crypto;
+#[cfg(feature = "cipher")]
+pub use datagram_crypto::*;
+
+#[cfg(feature = "graphql")]
+mod graphql;
+
+#[cfg(feature = "group")]
+mod group;
+#[cfg(feature = "group")]
+pub use group::*;
+
+#[cfg(feature = "cose")]
+mod cose;
+#[cfg(feature = "cose")]
+pub use cose::*;
+
+#[cfg(feature = "jose")]
+mod jose;
+#[cfg(feature = "jose")]
+pub use jose::*;
+#[cfg(feature = "jose")]
+mod session_token;
+#[cfg(feature = "jose")]
+pub use session_token::*;
+
+#[cfg(feature = "diesel")]
+mod diesel_types;
+
+#[cfg(feature = "http")]
+mod http_signature;
+#[cfg(feature = "http")]
+pub use http_signature::*;
+
+#[cfg(feature = "tower")]
+mod tower_auth;
+#[cfg(feature = "tower")]
+pub use tower_auth::*;
+
+#[cfg(feature = "jcs")]
+mod jcs;
+#[cfg(feature = "jcs")]
+pub use jcs::*;
+
+#[cfg(any(feature = "keyring", feature = "hardware-keystore"))]
+mod keystore;
+#[cfg(any(feature = "keyring", feature = "hardware-keystore"))]
+pub use keystore::*;
+
+#[cfg(feature = "ledger")]
+mod ledger;
+#[cfg(feature = "ledger")]
+pub use ledger::*;
+
+#[cfg(feature = "libp2p")]
+mod libp2p;
+#[cfg(feature = "libp2p")]
+pub use libp2p::*;
+
+#[cfg(feature = "mac")]
+mod peer_mac;
+#[cfg(feature = "mac")]
+pub use peer_mac::*;
+
+#[cfg(feature = "minisign")]
+mod minisign;
+#[cfg(feature = "minisign")]
+pub use minisign::*;
+
+#[cfg(feature = "noise")]
+mod noise;
+#[cfg(feature = "noise")]
+pub use noise::*;
+
+#[cfg(feature = "turn")]
+mod turn;
+#[cfg(feature = "turn")]
+pub use turn::*;
+
+#[cfg(feature = "paseto")]
+mod paseto;
+#[cfg(feature = "paseto")]
+pub use paseto::*;
+
+#[cfg(feature = "passphrase")]
+mod passphrase;
+#[cfg(feature = "passphrase")]
+pub use passphrase::*;
+
+#[cfg(feature = "qr")]
+mod qr;
+#[cfg(feature = "qr")]
+pub use qr::*;
+
+#[cfg(feature = "redis")]
+mod redis_types;
+
+#[cfg(feature = "x509")]
+mod x509;
+#[cfg(feature = "x509")]
+pub use x509::*;
+
+#[cfg(feature = "rustls")]
+mod rustls_verifier;
+#[cfg(feature = "rustls")]
+pub use rustls_verifier::*;
+
+#[cfg(feature = "schemars")]
+mod json_schema;
+
+#[cfg(feature = "serde-secret")]
+pub mod serde_secret;
+
+#[cfg(feature = "sqlx")]
+mod sqlx_types;
+
+#[cfg(feature = "timestamping")]
+mod timestamping;
+#[cfg(feature = "timestamping")]
+pub use timestamping::*;
+
+#[cfg(feature = "tor")]
+mod tor;
+#[cfg(feature = "tor")]
+pub use tor::*;
+
+#[cfg(feature = "uuid")]
+mod session_uuid;
+
+#[cfg(feature = "verify-cache")]
+mod verify_cache;
+#[cfg(feature = "verify-cache")]
+pub use verify_cache::*;
+
+#[cfg(feature = "wasm")]
+mod wasm_storage;
+#[cfg(feature = "wasm")]
+pub use wasm_storage::*;
+
+#[cfg(feature = "wasm")]
+mod webcrypto;
+#[cfg(feature = "wasm")]
+pub use webcrypto::*;
+
+mod capability;
+pub use capability::*;
+
+mod signed_url;
+pub use signed_url::*;
+
+mod vanity;
+pub use vanity::*;
+
+mod pairwise;
+pub use pairwise::*;
+
+mod subkey;
+pub use subkey::*;
+
+mod profile;
+pub use profile::*;
+
+mod asset_signature;
+pub use asset_signature::*;
+
+mod blind_signature;
+pub use blind_signature::*;
+
+mod ring_signature;
+pub use ring_signature::*;
+
+mod signing_session;
+pub use signing_session::*;
+
+mod replay_window;
+pub use replay_window::*;
+
+mod versioned_signature;
+pub use versioned_signature::*;
+
+mod signed_string;
+pub use signed_string::*;
+
+mod payload;
+pub use payload::*;
+
+mod connection_id;
+pub use connection_id::*;
+
+mod redaction;
+pub use redaction::{redaction, set_redaction, Redaction};
+
+mod newtype;
+pub use newtype::*;
+
+mod prefix;
+pub use prefix::*;
+
+mod roster;
+pub use roster::*;
+
+mod heartbeat;
+pub use heartbeat::*;
+
+mod retry_token;
+pub use retry_token::*;
+
+mod lan_discovery;
+pub use lan_discovery::*;
+
+mod solana;
+pub use solana::*;
+
+mod stellar;
+pub use stellar::*;
+
+mod wireguard;
+pub use wireguard::*;
+
+mod ipfs;
+pub use ipfs::*;
+
+mod paper_backup;
+pub use paper_backup::*;
@@ -0,0 +1,102 @@
+//! libp2p interop. Enabled with the `libp2p` feature.
+//!
+//! A SessionId is an Ed25519 public key, so it maps to an Ed25519-keyed PeerId
+//! (identity multihash of the protobuf-encoded public key) and back.
+use crate::errors::{Result, SessionIdError};
+use crate::{SessionId, SessionIdPair};
+use libp2p_identity::{ed25519, Keypair, PeerId, PublicKey};
+
+// multihash code of the identity hash
+const IDENTITY_MULTIHASH: u64 = 0x00;
+
+fn libp2p_error(e: impl std::fmt::Display) -> SessionIdError {
+    SessionIdError::InvalidFormat(format!("libp2p: {}", e))
+}
+
+impl SessionId {
+    pub fn to_libp2p_public_key(&self) -> Result<PublicKey> {
+        let pk = ed25519::PublicKey::try_from_bytes(self.as_ref()).map_err(libp2p_error)?;
+        Ok(PublicKey::from(pk))
+    }
+    pub fn from_libp2p_public_key(key: &PublicKey) -> Result<Self> {
+        let pk = key.clone().try_into_ed25519().map_err(libp2p_error)?;
+        Ok(SessionId::from(pk.to_bytes()))
+    }
+    pub fn to_libp2p_peer_id(&self) -> Result<PeerId> {
+        Ok(self.to_libp2p_public_key()?.to_peer_id())
+    }
+    /// Only Ed25519-keyed PeerIds (which inline the public key) can be converted
+    pub fn from_libp2p_peer_id(peer_id: &PeerId) -> Result<Self> {
+        let multihash = peer_id.as_ref();
+        if multihash.code() != IDENTITY_MULTIHASH {
+            return Err(libp2p_error("peer id does not inline the public key"));
+        }
+        let key = PublicKey::try_decode_protobuf(multihash.digest()).map_err(libp2p_error)?;
+        Self::from_libp2p_public_key(&key)
+    }
+}
+
+/// Conversion between SessionIdPair and `libp2p_identity::Keypair`
+pub trait Libp2pKeypair: Sized {
+    fn to_libp2p_keypair(&self) -> Result<Keypair>;
+    fn from_libp2p_keypair(keypair: &Keypair) -> Result<Self>;
+}
+
+impl Libp2pKeypair for SessionIdPair {
+    fn to_libp2p_keypair(&self) -> Result<Keypair> {
+        let secret =
+            ed25519::SecretKey::try_from_bytes(self.secret.to_bytes()).map_err(libp2p_error)?;
+        Ok(Keypair::from(ed25519::Keypair::from(secret)))
+    }
+    fn from_libp2p_keypair(keypair: &Keypair) -> Result<Self> {
+        let keypair = keypair.clone().try_into_ed25519().map_err(libp2p_error)?;
+        let secret = ed25519_dalek::SecretKey::from_bytes(keypair.secret().as_ref())
+            .map_err(libp2p_error)?;
+        Ok(SessionIdPair {
+            public: (&secret).into(),
+            secret,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{new_session_id_pair, ISessionIdPair};
+
+    #[test]
+    fn test_libp2p_peer_id() {
+        let sid = new_session_id_pair().unwrap().get_id();
+        let peer_id = sid.to_libp2p_peer_id().unwrap();
+        // Ed25519 PeerIds start with "12D3KooW"
+        assert!(peer_id.to_base58().starts_with("12D3KooW"));
+        assert_eq!(SessionId::from_libp2p_peer_id(&peer_id).unwrap(), sid);
+
+        let parsed: PeerId = peer_id.to_base58().parse().unwrap();
+        assert_eq!(SessionId::from_libp2p_peer_id(&parsed).unwrap(), sid);
+        // sha2-256 multihash (RSA keys)
+        let hashed: PeerId = "QmYyQSo1c1Ym7orWxLYvCrM2EmxFTANf8wXmmE7DWjhx5N"
+            .parse()
+            .unwrap();
+        assert!(SessionId::from_libp2p_peer_id(&hashed).is_err());
+    }
+
+    #[test]
+    fn test_libp2p_keypair() {
+        let kp = new_session_id_pair().unwrap();
+        let keypair = kp.to_libp2p_keypair().unwrap();
+        assert_eq!(
+            keypair.public().to_peer_id(),
+            kp.get_id().to_libp2p_peer_id().unwrap()
+        );
+
+        // signatures are interchangeable (pure Ed25519)
+        let sig = keypair.sign(b"testdata").unwrap();
+        let pk = ed25519_dalek::PublicKey::from_bytes(kp.get_id().as_ref()).unwrap();
+        let sig = ed25519_dalek::Signature::from_bytes(&sig).unwrap();
+        assert!(pk.verify_strict(b"testdata", &sig).is_ok());
+
+        let kp1 = SessionIdPair::from_libp2p_keypair(&keypair).unwrap();
+        assert_eq!(kp1.get_id(), kp.get_id());
+    }
+}
@@ -0,0 +1,176 @@
+//! Minisign compatible signatures. Enabled with the `minisign` feature.
+//!
+//! Signatures of release artifacts made with a SessionIdPair can be checked with
+//! `minisign -V` or `rsign verify` using [`SessionId::to_minisign_public_key`].
+//! Signing uses the pre-hashed (BLAKE2b-512) algorithm; verification also accepts
+//! legacy signatures.
+use crate::errors::{self, Result, SessionIdError, SignatureErrorKind};
+use crate::{ISessionIdPair, SessionId, SessionIdPair};
+use blake2::{Blake2b512, Digest as _};
+use ed25519_dalek::{Digest, Sha512, Signer};
+
+const ALG_LEGACY: &[u8; 2] = b"Ed";
+const ALG_PREHASHED: &[u8; 2] = b"ED";
+const UNTRUSTED_PREFIX: &str = "untrusted comment: ";
+const TRUSTED_PREFIX: &str = "trusted comment: ";
+
+fn minisign_error(msg: &str) -> SessionIdError {
+    SessionIdError::InvalidFormat(format!("minisign: {}", msg))
+}
+
+impl SessionId {
+    /// Minisign key ID. Derived from the key, as session keys have no stored ID.
+    pub fn minisign_key_id(&self) -> [u8; 8] {
+        let hash = Sha512::digest(self.as_ref());
+        let mut id = [0u8; 8];
+        id.copy_from_slice(&hash[..8]);
+        id
+    }
+    /// Contents of a minisign public key file (`minisign.pub`)
+    pub fn to_minisign_public_key(&self) -> String {
+        let key_id = self.minisign_key_id();
+        let mut buf = ALG_LEGACY.to_vec();
+        buf.extend_from_slice(&key_id);
+        buf.extend_from_slice(self.as_ref());
+        format!(
+            "{}minisign public key {:X}\n{}\n",
+            UNTRUSTED_PREFIX,
+            u64::from_le_bytes(key_id),
+            base64::encode(buf)
+        )
+    }
+    /// Parse a minisign public key file, or its base64 line alone
+    pub fn from_minisign_public_key(s: &str) -> Result<Self> {
+        let line = s
+            .lines()
+            .find(|l| !l.is_empty() && !l.starts_with(UNTRUSTED_PREFIX))
+            .ok_or_else(|| minisign_error("no key"))?;
+        let buf = base64::decode(line.trim())?;
+        if buf.len() != 42 || &buf[..2] != ALG_LEGACY {
+            return Err(minisign_error("invalid public key"));
+        }
+        SessionId::try_from(&buf[10..])
+    }
+}
+
+/// Minisign signing with a SessionIdPair
+pub trait MinisignSigner {
+    /// Contents of a `.minisig` file for `data`. `trusted_comment` is signed too.
+    fn to_minisign(&self, data: &[u8], trusted_comment: &str) -> Result<String>;
+}
+
+impl MinisignSigner for SessionIdPair {
+    fn to_minisign(&self, data: &[u8], trusted_comment: &str) -> Result<String> {
+        if trusted_comment.contains(['\r', '\n']) {
+            return Err(SessionIdError::InvalidArgument("multi-line comment"));
+        }
+        let id = self.get_id();
+        let signature = Signer::sign(self, &Blake2b512::digest(data)).to_bytes();
+        let mut buf = ALG_PREHASHED.to_vec();
+        buf.extend_from_slice(&id.minisign_key_id());
+        buf.extend_from_slice(&signature);
+        let global =
+            Signer::sign(self, &[&signature[..], trusted_comment.as_bytes()].concat()).to_bytes();
+        Ok(format!(
+            "{}signature from verse-session-id key\n{}\n{}{}\n{}\n",
+            UNTRUSTED_PREFIX,
+            base64::encode(buf),
+            TRUSTED_PREFIX,
+            trusted_comment,
+            base64::encode(global)
+        ))
+    }
+}
+
+fn verify_strict(key: &ed25519_dalek::PublicKey, msg: &[u8], signature: &[u8]) -> Result<()> {
+    let signature = ed25519_dalek::Signature::from_bytes(signature)
+        .map_err(errors::signature(SignatureErrorKind::MalformedSignature))?;
+    key.verify_strict(msg, &signature)
+        .map_err(errors::signature(SignatureErrorKind::VerificationFailed))
+}
+
+/// Verify a `.minisig` file for `data`. Returns the trusted comment.
+pub fn verify_minisign(public_key: &SessionId, data: &[u8], minisig: &str) -> Result<String> {
+    let mut lines = minisig.lines();
+    let (Some(untrusted), Some(sig), Some(trusted), Some(global)) =
+        (lines.next(), lines.next(), lines.next(), lines.next())
+    else {
+        return Err(minisign_error("truncated signature"));
+    };
+    if !untrusted.starts_with(UNTRUSTED_PREFIX) {
+        return Err(minisign_error("missing untrusted comment"));
+    }
+    let trusted = trusted
+        .strip_prefix(TRUSTED_PREFIX)
+        .ok_or_else(|| minisign_error("missing trusted comment"))?;
+    let sig = base64::decode(sig.trim())?;
+    if sig.len() != 74 {
+        return Err(errors::invalid_length(74, sig.len()));
+    }
+    if sig[2..10] != public_key.minisign_key_id() {
+        return Err(minisign_error("key id mismatch"));
+    }
+    let key = ed25519_dalek::PublicKey::from_bytes(public_key.as_ref())
+        .map_err(errors::signature(SignatureErrorKind::MalformedPublicKey))?;
+    let signature = &sig[10..];
+    match &sig[..2] {
+        v if v == ALG_PREHASHED => verify_strict(&key, &Blake2b512::digest(data), signature)?,
+        v if v == ALG_LEGACY => verify_strict(&key, data, signature)?,
+        _ => return Err(minisign_error("unsupported algorithm")),
+    }
+    verify_strict(
+        &key,
+        &[signature, trusted.as_bytes()].concat(),
+        &base64::decode(global.trim())?,
+    )?;
+    Ok(trusted.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::new_session_id_pair;
+
+    #[test]
+    fn test_minisign() {
+        let pair = new_session_id_pair().unwrap();
+        let id = pair.get_id();
+        let pubkey = id.to_minisign_public_key();
+        assert!(pubkey.starts_with("untrusted comment: minisign public key "));
+        assert_eq!(SessionId::from_minisign_public_key(&pubkey).unwrap(), id);
+
+        let data = b"release artifact";
+        let minisig = pair
+            .to_minisign(data, "timestamp:1700000000\tfile:a.tar.gz")
+            .unwrap();
+        assert_eq!(minisig.lines().count(), 4);
+        assert_eq!(
+            verify_minisign(&id, data, &minisig).unwrap(),
+            "timestamp:1700000000\tfile:a.tar.gz"
+        );
+        assert!(verify_minisign(&id, b"tampered", &minisig).is_err());
+        let forged = minisig.replace("a.tar.gz", "b.tar.gz");
+        assert!(verify_minisign(&id, data, &forged).is_err());
+        let other = new_session_id_pair().unwrap().get_id();
+        assert!(verify_minisign(&other, data, &minisig).is_err());
+        assert!(pair.to_minisign(data, "a\nb").is_err());
+    }
+
+    #[test]
+    fn test_minisign_legacy() {
+        let pair = new_session_id_pair().unwrap();
+        let id = pair.get_id();
+        let data = b"legacy";
+        let mut buf = ALG_LEGACY.to_vec();
+        buf.extend_from_slice(&id.minisign_key_id());
+        let signature = Signer::sign(&pair, data).to_bytes();
+        buf.extend_from_slice(&signature);
+        let global = Signer::sign(&pair, &[&signature[..], b"tc"].concat()).to_bytes();
+        let minisig = format!(
+            "untrusted comment: x\n{}\ntrusted comment: tc\n{}\n",
+            base64::encode(buf),
+            base64::encode(global)
+        );
+        assert_eq!(verify_minisign(&id, data, &minisig).unwrap(), "tc");
+    }
+}
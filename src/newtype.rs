@@ -0,0 +1,165 @@
+//! Role-specific session ID types.
+//!
+//! [`define_session_id_newtype!`] declares a transparent wrapper over [`SessionId`](crate::SessionId),
+//! so a relay's ID cannot be passed where an avatar owner's ID is expected. Converting between
+//! roles is always explicit, through `SessionId`.
+#[doc(hidden)]
+pub use serde as __serde;
+
+/// Declare a newtype over [`SessionId`](crate::SessionId).
+///
+/// The type derefs to `SessionId` (so `verify` and the formatting helpers work unchanged),
+/// converts to and from it with `From`, parses and displays as base64 and serializes as a base64
+/// string like [`serde_helpers::base64`](crate::serde_helpers::base64).
+///
+/// ```
+/// use verse_session_id::*;
+///
+/// define_session_id_newtype!(
+///     /// Owner of an avatar
+///     pub AvatarOwnerId
+/// );
+/// define_session_id_newtype!(pub RelayId);
+///
+/// fn kick(owner: AvatarOwnerId) -> String {
+///     owner.to_string()
+/// }
+///
+/// let pair = new_session_id_pair().unwrap();
+/// let owner = AvatarOwnerId::from(pair.get_id());
+/// let relay = RelayId::from(pair.get_id());
+/// kick(owner);
+/// // kick(relay); // mismatched types
+/// kick(AvatarOwnerId::from(relay.into_inner()));
+/// ```
+#[macro_export]
+macro_rules! define_session_id_newtype {
+    ($(#[$meta:meta])* $vis:vis $name:ident) => {
+        $(#[$meta])*
+        #[derive(Clone, Copy, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
+        #[repr(transparent)]
+        $vis struct $name($crate::SessionId);
+
+        #[allow(dead_code)]
+        impl $name {
+            pub const fn new(id: $crate::SessionId) -> Self {
+                $name(id)
+            }
+            pub const fn as_session_id(&self) -> &$crate::SessionId {
+                &self.0
+            }
+            pub const fn into_inner(self) -> $crate::SessionId {
+                self.0
+            }
+        }
+
+        impl ::std::convert::From<$crate::SessionId> for $name {
+            fn from(id: $crate::SessionId) -> Self {
+                $name(id)
+            }
+        }
+        impl ::std::convert::From<$name> for $crate::SessionId {
+            fn from(id: $name) -> Self {
+                id.0
+            }
+        }
+        impl ::std::convert::From<$crate::RawSessionId> for $name {
+            fn from(raw: $crate::RawSessionId) -> Self {
+                $name($crate::SessionId::from(raw))
+            }
+        }
+        impl ::std::convert::TryFrom<&[u8]> for $name {
+            type Error = $crate::SessionIdError;
+            fn try_from(v: &[u8]) -> ::std::result::Result<Self, Self::Error> {
+                $crate::SessionId::try_from(v).map($name)
+            }
+        }
+        impl ::std::convert::TryFrom<&str> for $name {
+            type Error = $crate::SessionIdError;
+            fn try_from(v: &str) -> ::std::result::Result<Self, Self::Error> {
+                v.parse()
+            }
+        }
+        impl ::std::str::FromStr for $name {
+            type Err = $crate::SessionIdError;
+            fn from_str(s: &str) -> ::std::result::Result<Self, Self::Err> {
+                s.parse().map($name)
+            }
+        }
+        impl ::std::ops::Deref for $name {
+            type Target = $crate::SessionId;
+            fn deref(&self) -> &$crate::SessionId {
+                &self.0
+            }
+        }
+        impl ::std::convert::AsRef<$crate::SessionId> for $name {
+            fn as_ref(&self) -> &$crate::SessionId {
+                &self.0
+            }
+        }
+        impl ::std::convert::AsRef<[u8]> for $name {
+            fn as_ref(&self) -> &[u8] {
+                self.0.as_raw()
+            }
+        }
+        impl ::std::fmt::Display for $name {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                ::std::fmt::Display::fmt(&self.0, f)
+            }
+        }
+        impl ::std::fmt::Debug for $name {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                f.write_str(concat!(stringify!($name), "("))?;
+                ::std::fmt::Debug::fmt(&self.0, f)?;
+                f.write_str(")")
+            }
+        }
+        impl $crate::__serde::Serialize for $name {
+            fn serialize<S: $crate::__serde::Serializer>(
+                &self,
+                serializer: S,
+            ) -> ::std::result::Result<S::Ok, S::Error> {
+                $crate::serde_helpers::base64::serialize(&self.0, serializer)
+            }
+        }
+        impl<'de> $crate::__serde::Deserialize<'de> for $name {
+            fn deserialize<D: $crate::__serde::Deserializer<'de>>(
+                deserializer: D,
+            ) -> ::std::result::Result<Self, D::Error> {
+                $crate::serde_helpers::base64::deserialize(deserializer).map($name)
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{new_session_id_pair, ISessionIdPair, SessionId, SessionIdPublic};
+
+    define_session_id_newtype!(
+        /// Test role
+        pub(crate) PeerId
+    );
+
+    #[test]
+    fn test_session_id_newtype() {
+        let pair = new_session_id_pair().unwrap();
+        let peer = PeerId::from(pair.get_id());
+        assert_eq!(SessionId::from(peer), pair.get_id());
+        assert_eq!(peer.to_string(), pair.get_id().to_string());
+        assert_eq!(peer.to_string().parse::<PeerId>().unwrap(), peer);
+        assert_eq!(
+            format!("{:?}", peer),
+            format!("PeerId({:?})", pair.get_id())
+        );
+        assert_eq!(PeerId::try_from(&peer.as_raw()[..]).unwrap(), peer);
+
+        let sig = pair.sign([b"hello"]).unwrap();
+        assert!(peer.verify([b"hello"], &sig).is_ok());
+
+        let json = serde_json::to_string(&peer).unwrap();
+        assert_eq!(json, format!("\"{}\"", peer));
+        assert_eq!(serde_json::from_str::<PeerId>(&json).unwrap(), peer);
+        assert!(serde_json::from_str::<PeerId>("\"AAAA\"").is_err());
+    }
+}
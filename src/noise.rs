@@ -0,0 +1,268 @@
+//! Noise protocol handshakes keyed by session keys. Enabled with the `noise` feature.
+//!
+//! Static keys are the X25519 keys derived from the SessionIdPair. Each side sends its
+//! SessionId inside encrypted handshake payloads, and the peer checks it against the
+//! static key authenticated by the handshake.
+use crate::errors::{self, Result, SessionIdError, SignatureErrorKind};
+use crate::{ISessionIdPair, SessionId, SessionIdPair, X25519KeyAgreement, SESSION_ID_SIZE};
+use snow::{Builder, HandshakeState, TransportState};
+
+const NOISE_XX: &str = "Noise_XX_25519_ChaChaPoly_BLAKE2s";
+const NOISE_IK: &str = "Noise_IK_25519_ChaChaPoly_BLAKE2s";
+const NOISE_PROLOGUE: &[u8] = b"verse-session-id/noise/v1";
+/// Largest Noise message
+pub const NOISE_MAX_MESSAGE_SIZE: usize = 65535;
+const NOISE_TAG_SIZE: usize = 16;
+
+fn noise_error(e: snow::Error) -> SessionIdError {
+    SessionIdError::InvalidFormat(format!("noise: {}", e))
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Pattern {
+    XX,
+    IK,
+}
+
+/// Noise handshake in progress
+pub struct NoiseHandshake {
+    state: HandshakeState,
+    pattern: Pattern,
+    local_id: SessionId,
+    remote_id: Option<SessionId>,
+    messages: usize,
+}
+
+impl NoiseHandshake {
+    fn new(
+        pair: &SessionIdPair,
+        pattern: Pattern,
+        initiator: bool,
+        remote_id: Option<SessionId>,
+    ) -> Result<Self> {
+        let params = match pattern {
+            Pattern::XX => NOISE_XX,
+            Pattern::IK => NOISE_IK,
+        };
+        let secret = pair.to_x25519_secret();
+        let remote_public = remote_id.map(|v| v.to_x25519_public()).transpose()?;
+        let mut builder = Builder::new(params.parse().map_err(noise_error)?)
+            .local_private_key(&secret)
+            .prologue(NOISE_PROLOGUE);
+        if let Some(remote_public) = &remote_public {
+            builder = builder.remote_public_key(remote_public);
+        }
+        let state = if initiator {
+            builder.build_initiator()
+        } else {
+            builder.build_responder()
+        }
+        .map_err(noise_error)?;
+        Ok(NoiseHandshake {
+            state,
+            pattern,
+            local_id: pair.get_id(),
+            remote_id,
+            messages: 0,
+        })
+    }
+    /// XX initiator. Neither side needs to know the other in advance.
+    pub fn initiator_xx(pair: &SessionIdPair) -> Result<Self> {
+        Self::new(pair, Pattern::XX, true, None)
+    }
+    pub fn responder_xx(pair: &SessionIdPair) -> Result<Self> {
+        Self::new(pair, Pattern::XX, false, None)
+    }
+    /// IK initiator connecting to the known `remote_id` (one round trip)
+    pub fn initiator_ik(pair: &SessionIdPair, remote_id: &SessionId) -> Result<Self> {
+        Self::new(pair, Pattern::IK, true, Some(*remote_id))
+    }
+    pub fn responder_ik(pair: &SessionIdPair) -> Result<Self> {
+        Self::new(pair, Pattern::IK, false, None)
+    }
+
+    // The payload of every message but the first XX message is encrypted,
+    // and carries the sender's SessionId.
+    fn carries_id(&self) -> bool {
+        !(self.pattern == Pattern::XX && self.messages == 0)
+    }
+
+    /// Write the next handshake message carrying `payload`
+    pub fn write_message(&mut self, payload: &[u8]) -> Result<Vec<u8>> {
+        let mut buf = Vec::with_capacity(SESSION_ID_SIZE + payload.len());
+        if self.carries_id() {
+            buf.extend_from_slice(self.local_id.as_ref());
+        }
+        buf.extend_from_slice(payload);
+        let mut message = vec![0u8; NOISE_MAX_MESSAGE_SIZE];
+        let n = self
+            .state
+            .write_message(&buf, &mut message)
+            .map_err(noise_error)?;
+        message.truncate(n);
+        self.messages += 1;
+        Ok(message)
+    }
+    /// Read the next handshake message. Returns its payload.
+    pub fn read_message(&mut self, message: &[u8]) -> Result<Vec<u8>> {
+        let mut payload = vec![0u8; message.len()];
+        let n = self
+            .state
+            .read_message(message, &mut payload)
+            .map_err(noise_error)?;
+        payload.truncate(n);
+        if self.carries_id() {
+            if payload.len() < SESSION_ID_SIZE {
+                return Err(errors::invalid_length(SESSION_ID_SIZE, payload.len()));
+            }
+            let claimed = SessionId::try_from(&payload[..SESSION_ID_SIZE])?;
+            if self.remote_id.is_some_and(|v| v != claimed) {
+                return Err(SessionIdError::Signature(
+                    SignatureErrorKind::VerificationFailed,
+                ));
+            }
+            self.remote_id = Some(claimed);
+            payload.drain(..SESSION_ID_SIZE);
+        }
+        self.messages += 1;
+        Ok(payload)
+    }
+    pub fn is_finished(&self) -> bool {
+        self.state.is_handshake_finished()
+    }
+    /// True if the next step is [`write_message`](Self::write_message)
+    pub fn is_my_turn(&self) -> bool {
+        self.state.is_my_turn()
+    }
+    /// Finish the handshake. Fails unless the remote SessionId matches
+    /// the static key it authenticated with.
+    pub fn into_transport(self) -> Result<NoiseTransport> {
+        if !self.is_finished() {
+            return Err(SessionIdError::InvalidFormat(
+                "noise: handshake is not finished".to_string(),
+            ));
+        }
+        let remote_id = self.remote_id.ok_or(SessionIdError::Required)?;
+        if self.state.get_remote_static() != Some(&remote_id.to_x25519_public()?[..]) {
+            return Err(SessionIdError::Signature(
+                SignatureErrorKind::VerificationFailed,
+            ));
+        }
+        Ok(NoiseTransport {
+            state: self.state.into_transport_mode().map_err(noise_error)?,
+            remote_id,
+        })
+    }
+}
+
+/// Transport cipher states after a completed handshake
+pub struct NoiseTransport {
+    state: TransportState,
+    remote_id: SessionId,
+}
+
+impl NoiseTransport {
+    /// Authenticated SessionId of the peer
+    pub fn remote_id(&self) -> SessionId {
+        self.remote_id
+    }
+    pub fn encrypt(&mut self, plaintext: &[u8]) -> Result<Vec<u8>> {
+        let mut message = vec![0u8; plaintext.len() + NOISE_TAG_SIZE];
+        let n = self
+            .state
+            .write_message(plaintext, &mut message)
+            .map_err(noise_error)?;
+        message.truncate(n);
+        Ok(message)
+    }
+    pub fn decrypt(&mut self, message: &[u8]) -> Result<Vec<u8>> {
+        let mut payload = vec![0u8; message.len()];
+        let n = self
+            .state
+            .read_message(message, &mut payload)
+            .map_err(noise_error)?;
+        payload.truncate(n);
+        Ok(payload)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::new_session_id_pair;
+
+    fn run(
+        mut initiator: NoiseHandshake,
+        mut responder: NoiseHandshake,
+    ) -> Result<(NoiseTransport, NoiseTransport)> {
+        let (mut sender, mut receiver) = (&mut initiator, &mut responder);
+        while !(sender.is_finished() && receiver.is_finished()) {
+            let message = sender.write_message(b"")?;
+            receiver.read_message(&message)?;
+            std::mem::swap(&mut sender, &mut receiver);
+        }
+        Ok((initiator.into_transport()?, responder.into_transport()?))
+    }
+
+    #[test]
+    fn test_noise_xx() {
+        let a = new_session_id_pair().unwrap();
+        let b = new_session_id_pair().unwrap();
+        let (mut ta, mut tb) = run(
+            NoiseHandshake::initiator_xx(&a).unwrap(),
+            NoiseHandshake::responder_xx(&b).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(ta.remote_id(), b.get_id());
+        assert_eq!(tb.remote_id(), a.get_id());
+
+        let message = ta.encrypt(b"hello").unwrap();
+        assert_eq!(tb.decrypt(&message).unwrap(), b"hello");
+        let message = tb.encrypt(b"world").unwrap();
+        assert_eq!(ta.decrypt(&message).unwrap(), b"world");
+        assert!(ta.decrypt(&message).is_err());
+    }
+
+    #[test]
+    fn test_noise_ik() {
+        let a = new_session_id_pair().unwrap();
+        let b = new_session_id_pair().unwrap();
+        let (mut ta, mut tb) = run(
+            NoiseHandshake::initiator_ik(&a, &b.get_id()).unwrap(),
+            NoiseHandshake::responder_ik(&b).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(ta.remote_id(), b.get_id());
+        assert_eq!(tb.remote_id(), a.get_id());
+        let message = ta.encrypt(b"hello").unwrap();
+        assert_eq!(tb.decrypt(&message).unwrap(), b"hello");
+
+        // the responder is not who the initiator expected
+        let c = new_session_id_pair().unwrap();
+        assert!(run(
+            NoiseHandshake::initiator_ik(&a, &c.get_id()).unwrap(),
+            NoiseHandshake::responder_ik(&b).unwrap(),
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_noise_payload() {
+        let a = new_session_id_pair().unwrap();
+        let b = new_session_id_pair().unwrap();
+        let mut initiator = NoiseHandshake::initiator_xx(&a).unwrap();
+        let mut responder = NoiseHandshake::responder_xx(&b).unwrap();
+        let m = initiator.write_message(b"1").unwrap();
+        assert_eq!(responder.read_message(&m).unwrap(), b"1");
+        let m = responder.write_message(b"2").unwrap();
+        assert_eq!(initiator.read_message(&m).unwrap(), b"2");
+        assert!(initiator.is_my_turn());
+        assert!(NoiseHandshake::responder_xx(&b)
+            .unwrap()
+            .into_transport()
+            .is_err());
+        let m = initiator.write_message(b"3").unwrap();
+        assert_eq!(responder.read_message(&m).unwrap(), b"3");
+        assert_eq!(responder.into_transport().unwrap().remote_id(), a.get_id());
+    }
+}
@@ -0,0 +1,108 @@
+//! Pairwise pseudonymous identities.
+//!
+//! A user can present a different, deterministic sub-identity to every peer or world,
+//! so that peers cannot correlate them through a single stable ID. When the user wants
+//! to reveal the relation, a [`PairwiseLink`] proves that both keys belong to them.
+//! Sub-identities are derived with HKDF-SHA512 like [subkeys](crate::SubkeyDerivation),
+//! with the peer's SessionId as the info.
+use crate::errors::Result;
+use crate::{
+    session_id_pair_from_secret, ISessionIdPair, SecretSessionKey, SessionId, SessionIdPair,
+    SessionIdPublic, SignatureSet, SECRET_KEY_SIZE,
+};
+use hkdf::Hkdf;
+use sha2::Sha512;
+
+const PAIRWISE_SALT: &[u8] = b"verse-session-id/pairwise/v2";
+const PAIRWISE_LINK_CONTEXT: &[u8] = b"verse-session-id/pairwise-link/v1";
+
+/// Proof that `pairwise_id` was derived by the owner of a session ID for a peer
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct PairwiseLink {
+    pub pairwise_id: SessionId,
+    /// Signed by the owner's session key
+    pub owner_signature: SignatureSet,
+    /// Signed by the pairwise key
+    pub pairwise_signature: SignatureSet,
+}
+
+/// Derivation of per-peer sub-identities from a SessionIdPair
+pub trait PairwiseIdentity {
+    /// Sub-identity used with `peer`. Always the same for the same key and peer.
+    fn derive_pairwise(&self, peer: &SessionId) -> Result<SessionIdPair>;
+    /// Proof linking this identity to the one derived for `peer`
+    fn pairwise_link(&self, peer: &SessionId) -> Result<PairwiseLink>;
+}
+
+impl PairwiseIdentity for SessionIdPair {
+    fn derive_pairwise(&self, peer: &SessionId) -> Result<SessionIdPair> {
+        let mut secret = SecretSessionKey::new([0; SECRET_KEY_SIZE]);
+        Hkdf::<Sha512>::new(Some(PAIRWISE_SALT), self.secret.as_bytes())
+            .expand(peer.as_ref(), secret.expose_secret_mut())
+            .expect("valid HKDF-SHA512 output length");
+        session_id_pair_from_secret(&secret)
+    }
+    fn pairwise_link(&self, peer: &SessionId) -> Result<PairwiseLink> {
+        let pairwise = self.derive_pairwise(peer)?;
+        let owner_id = self.get_id();
+        let pairwise_id = pairwise.get_id();
+        let payload = link_payload(&owner_id, &pairwise_id, peer);
+        Ok(PairwiseLink {
+            pairwise_id,
+            owner_signature: self.sign(payload.clone())?,
+            pairwise_signature: pairwise.sign(payload)?,
+        })
+    }
+}
+
+fn link_payload<'a>(
+    owner: &'a SessionId,
+    pairwise_id: &'a SessionId,
+    peer: &'a SessionId,
+) -> Vec<&'a [u8]> {
+    vec![
+        PAIRWISE_LINK_CONTEXT,
+        owner.as_ref(),
+        pairwise_id.as_ref(),
+        peer.as_ref(),
+    ]
+}
+
+impl PairwiseLink {
+    /// Check that `owner` derived `pairwise_id` for `peer`
+    pub fn verify(&self, owner: &SessionId, peer: &SessionId) -> Result<()> {
+        let payload = link_payload(owner, &self.pairwise_id, peer);
+        owner.verify(payload.clone(), &self.owner_signature)?;
+        self.pairwise_id.verify(payload, &self.pairwise_signature)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::new_session_id_pair;
+
+    #[test]
+    fn test_pairwise() {
+        let me = new_session_id_pair().unwrap();
+        let a = new_session_id_pair().unwrap().get_id();
+        let b = new_session_id_pair().unwrap().get_id();
+
+        let pa = me.derive_pairwise(&a).unwrap();
+        assert_eq!(pa.get_id(), me.derive_pairwise(&a).unwrap().get_id());
+        assert_ne!(pa.get_id(), me.derive_pairwise(&b).unwrap().get_id());
+        assert_ne!(pa.get_id(), me.get_id());
+        let sig = pa.sign(vec![b"hello"]).unwrap();
+        assert!(pa.get_id().verify(vec![b"hello"], &sig).is_ok());
+
+        let link = me.pairwise_link(&a).unwrap();
+        assert_eq!(link.pairwise_id, pa.get_id());
+        assert!(link.verify(&me.get_id(), &a).is_ok());
+        assert!(link.verify(&me.get_id(), &b).is_err());
+        assert!(link.verify(&a, &a).is_err());
+        let other = new_session_id_pair().unwrap();
+        let mut forged = link;
+        forged.owner_signature = other.pairwise_link(&a).unwrap().owner_signature;
+        assert!(forged.verify(&other.get_id(), &a).is_err());
+    }
+}
@@ -0,0 +1,218 @@
+//! Printable paper backup of the secret key.
+//!
+//! ```text
+//! VERSE SESSION ID BACKUP v1
+//! 1 04AB-9KQ2 7
+//! 2 ...
+//! 8 M3ZP-0TXC *
+//! ```
+//!
+//! The 40 backed-up bytes (version, 32-byte seed, 7 bytes of SHA-256 checksum) are written in
+//! Crockford base32 as 8 numbered lines of 8 characters. The last symbol of each line is a
+//! Crockford mod-37 check over the line number and its characters, so a misread or mistyped
+//! line is reported by number. Import ignores case, spaces and hyphens, reads `O` as `0` and
+//! `I`/`L` as `1`, and accepts the lines in any order.
+use crate::connection_id::CROCKFORD;
+use crate::errors::{Result, SessionIdError};
+use crate::{SecretSessionKey, SessionIdPair, SessionIdPairSecret, SECRET_KEY_SIZE};
+use sha2::{Digest, Sha256};
+use zeroize::Zeroizing;
+
+const PAPER_BACKUP_VERSION: u8 = 1;
+const HEADER: &str = "VERSE SESSION ID BACKUP v1";
+const CHECKSUM_SIZE: usize = 7;
+const DATA_SIZE: usize = 1 + SECRET_KEY_SIZE + CHECKSUM_SIZE;
+const LINE_BYTES: usize = 5;
+const LINES: usize = DATA_SIZE / LINE_BYTES;
+const CHECK_SYMBOLS: &[u8; 37] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ*~$=U";
+
+fn paper_backup_error(msg: impl std::fmt::Display) -> SessionIdError {
+    SessionIdError::InvalidFormat(format!("paper_backup: {}", msg))
+}
+
+fn checksum(data: &[u8]) -> [u8; CHECKSUM_SIZE] {
+    let mut v = [0u8; CHECKSUM_SIZE];
+    v.copy_from_slice(&Sha256::digest(data)[..CHECKSUM_SIZE]);
+    v
+}
+
+// `line` is 1-based, so swapped lines fail their checks
+fn check_symbol(line: usize, value: u64) -> u8 {
+    CHECK_SYMBOLS[((((line as u64) << 40) | value) % 37) as usize]
+}
+
+// O, I and L are not Crockford digits and are read as the digits they resemble.
+// `None` for non-ASCII characters, which would otherwise truncate into a valid digit.
+fn normalize(c: char) -> Option<u8> {
+    match c.to_ascii_uppercase() {
+        'O' => Some(b'0'),
+        'I' | 'L' => Some(b'1'),
+        c if c.is_ascii() => Some(c as u8),
+        _ => None,
+    }
+}
+
+/// Export and import of the secret key as printable text
+pub trait PaperBackup: Sized {
+    /// Text block to print or write down. Anyone holding it can sign as this session ID.
+    fn export_paper_backup(&self) -> Zeroizing<String>;
+    /// Parse a paper backup, as typed or OCRed
+    fn import_paper_backup(text: &str) -> Result<Self>;
+}
+
+impl PaperBackup for SessionIdPair {
+    fn export_paper_backup(&self) -> Zeroizing<String> {
+        let mut data = Zeroizing::new([0u8; DATA_SIZE]);
+        data[0] = PAPER_BACKUP_VERSION;
+        data[1..=SECRET_KEY_SIZE].copy_from_slice(self.secret_key().expose_secret());
+        let sum = checksum(&data[..=SECRET_KEY_SIZE]);
+        data[SECRET_KEY_SIZE + 1..].copy_from_slice(&sum);
+
+        let mut s = Zeroizing::new(String::with_capacity(HEADER.len() + LINES * 14));
+        s.push_str(HEADER);
+        s.push('\n');
+        for (i, chunk) in data.chunks(LINE_BYTES).enumerate() {
+            let value = chunk.iter().fold(0u64, |v, b| (v << 8) | *b as u64);
+            s.push_str(&(i + 1).to_string());
+            s.push(' ');
+            for j in 0..8 {
+                if j == 4 {
+                    s.push('-');
+                }
+                s.push(CROCKFORD[((value >> (35 - 5 * j)) & 0x1f) as usize] as char);
+            }
+            s.push(' ');
+            s.push(check_symbol(i + 1, value) as char);
+            s.push('\n');
+        }
+        s
+    }
+    fn import_paper_backup(text: &str) -> Result<Self> {
+        let mut values = Zeroizing::new([None::<u64>; LINES]);
+        for line in text.lines().map(str::trim) {
+            if line.is_empty() || line.to_ascii_uppercase().starts_with("VERSE") {
+                continue;
+            }
+            let (n, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+            let n: usize = n
+                .trim_end_matches(['.', ':'])
+                .chars()
+                .map(|c| normalize(c).map(char::from))
+                .collect::<Option<String>>()
+                .and_then(|v| v.parse().ok())
+                .filter(|n| (1..=LINES).contains(n))
+                .ok_or_else(|| paper_backup_error(format_args!("invalid line number {:?}", n)))?;
+            let chars = Zeroizing::new(
+                rest.chars()
+                    .filter(|c| !c.is_whitespace() && *c != '-')
+                    .map(normalize)
+                    .collect::<Option<Vec<_>>>()
+                    .ok_or_else(|| {
+                        paper_backup_error(format_args!("line {}: invalid character", n))
+                    })?,
+            );
+            if chars.len() != 9 {
+                return Err(paper_backup_error(format_args!(
+                    "line {}: expected 8 characters and a check symbol",
+                    n
+                )));
+            }
+            let mut value = 0u64;
+            for c in &chars[..8] {
+                let digit = CROCKFORD.iter().position(|d| d == c).ok_or_else(|| {
+                    paper_backup_error(format_args!("line {}: invalid character", n))
+                })?;
+                value = (value << 5) | digit as u64;
+            }
+            if chars[8] != check_symbol(n, value) {
+                return Err(paper_backup_error(format_args!(
+                    "line {}: check symbol mismatch",
+                    n
+                )));
+            }
+            if values[n - 1].replace(value).is_some_and(|v| v != value) {
+                return Err(paper_backup_error(format_args!(
+                    "line {}: conflicting copies",
+                    n
+                )));
+            }
+        }
+
+        let mut data = Zeroizing::new([0u8; DATA_SIZE]);
+        for (i, value) in values.iter().enumerate() {
+            let value =
+                value.ok_or_else(|| paper_backup_error(format_args!("line {} missing", i + 1)))?;
+            data[i * LINE_BYTES..(i + 1) * LINE_BYTES].copy_from_slice(&value.to_be_bytes()[3..]);
+        }
+        if data[0] != PAPER_BACKUP_VERSION {
+            return Err(paper_backup_error("unsupported version"));
+        }
+        if checksum(&data[..=SECRET_KEY_SIZE]) != data[SECRET_KEY_SIZE + 1..] {
+            return Err(paper_backup_error("checksum mismatch"));
+        }
+        let mut secret = SecretSessionKey::new([0; SECRET_KEY_SIZE]);
+        secret
+            .expose_secret_mut()
+            .copy_from_slice(&data[1..=SECRET_KEY_SIZE]);
+        secret.to_session_id_pair()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{new_session_id_pair, ISessionIdPair};
+
+    #[test]
+    fn test_paper_backup() {
+        let pair = new_session_id_pair().unwrap();
+        let text = pair.export_paper_backup();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 1 + LINES);
+        assert_eq!(lines[0], HEADER);
+        assert!(lines[1..].iter().all(|v| v.len() == 13));
+        let import = |s: &str| SessionIdPair::import_paper_backup(s).map(|v| v.get_id());
+        assert_eq!(import(&text).unwrap(), pair.get_id());
+
+        // as typed: lower case, no hyphens, look-alike letters, shuffled, no header
+        let typed: Vec<String> = lines[1..]
+            .iter()
+            .rev()
+            .map(|v| {
+                v.to_lowercase()
+                    .replace('-', " ")
+                    .replace('0', "o")
+                    .replace('1', "l")
+            })
+            .collect();
+        assert_eq!(import(&typed.join("\n")).unwrap(), pair.get_id());
+
+        // a typo is reported with its line number
+        let mut typo: Vec<String> = lines.iter().map(|v| v.to_string()).collect();
+        let c = typo[3].as_bytes()[2];
+        typo[3].replace_range(2..3, if c == b'A' { "B" } else { "A" });
+        let err = import(&typo.join("\n")).unwrap_err().to_string();
+        assert!(err.contains("line 3"), "{}", err);
+
+        // swapped line contents, a missing line
+        let mut swapped: Vec<String> = lines.iter().map(|v| v.to_string()).collect();
+        swapped[3] = format!("3{}", &lines[4][1..]);
+        swapped[4] = format!("4{}", &lines[3][1..]);
+        assert!(import(&swapped.join("\n")).is_err());
+        assert!(import(&lines[..LINES].join("\n"))
+            .unwrap_err()
+            .to_string()
+            .contains("line 8 missing"));
+        assert!(import(&format!("{}\n9 0000-0000 0", text.as_str())).is_err());
+
+        // non-ASCII look-alikes are rejected, not truncated into a digit
+        let mut wide: Vec<String> = lines.iter().map(|v| v.to_string()).collect();
+        let c = char::from_u32(0x100 + wide[3].as_bytes()[2] as u32).unwrap();
+        wide[3].replace_range(2..3, &c.to_string());
+        let err = import(&wide.join("\n")).unwrap_err().to_string();
+        assert!(err.contains("line 3: invalid character"), "{}", err);
+        let mut wide: Vec<String> = lines.iter().map(|v| v.to_string()).collect();
+        wide[3].replace_range(0..1, "\u{133}");
+        assert!(import(&wide.join("\n")).is_err());
+    }
+}
@@ -0,0 +1,136 @@
+//! PASETO v4.public tokens. Enabled with the `paseto` feature.
+//!
+//! Tokens are signed with the SessionIdPair over the pre-authentication encoding (PAE)
+//! of the header, message, footer and implicit assertion.
+use crate::base64url;
+use crate::errors::{self, Result, SessionIdError, SignatureErrorKind};
+use crate::{SessionId, SessionIdPair, SIGNATURE_SIZE};
+
+const PASETO_V4_PUBLIC: &str = "v4.public.";
+
+fn le64(n: usize) -> [u8; 8] {
+    // MSB must be cleared
+    ((n as u64) & (u64::MAX >> 1)).to_le_bytes()
+}
+
+/// Pre-authentication encoding
+fn pae(pieces: &[&[u8]]) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(8 + pieces.iter().map(|p| 8 + p.len()).sum::<usize>());
+    buf.extend_from_slice(&le64(pieces.len()));
+    for p in pieces {
+        buf.extend_from_slice(&le64(p.len()));
+        buf.extend_from_slice(p);
+    }
+    buf
+}
+
+/// PASETO v4.public signing with a SessionIdPair
+pub trait PasetoSigner {
+    /// Issue a v4.public token over `message` with an optional `footer`
+    /// and implicit assertion
+    fn sign_paseto(&self, message: &[u8], footer: &[u8], implicit: &[u8]) -> Result<String>;
+}
+
+impl PasetoSigner for SessionIdPair {
+    fn sign_paseto(&self, message: &[u8], footer: &[u8], implicit: &[u8]) -> Result<String> {
+        let m2 = pae(&[PASETO_V4_PUBLIC.as_bytes(), message, footer, implicit]);
+        let signature = ed25519_dalek::Signer::sign(self, &m2);
+
+        let mut body = Vec::with_capacity(message.len() + SIGNATURE_SIZE);
+        body.extend_from_slice(message);
+        body.extend_from_slice(&signature.to_bytes());
+        let mut token = format!("{}{}", PASETO_V4_PUBLIC, base64url::encode(body));
+        if !footer.is_empty() {
+            token.push('.');
+            token.push_str(&base64url::encode(footer));
+        }
+        Ok(token)
+    }
+}
+
+impl SessionId {
+    /// Verify a v4.public token signed by this session ID.
+    /// Returns the message and the footer.
+    pub fn verify_paseto(&self, token: &str, implicit: &[u8]) -> Result<(Vec<u8>, Vec<u8>)> {
+        let rest = token.strip_prefix(PASETO_V4_PUBLIC).ok_or_else(|| {
+            SessionIdError::InvalidFormat("paseto: not a v4.public token".to_string())
+        })?;
+        let (body, footer) = match rest.split_once('.') {
+            Some((body, footer)) => (body, base64url::decode(footer)?),
+            None => (rest, Vec::new()),
+        };
+        let body = base64url::decode(body)?;
+        if body.len() < SIGNATURE_SIZE {
+            return Err(errors::invalid_length(SIGNATURE_SIZE, body.len()));
+        }
+        let (message, signature) = body.split_at(body.len() - SIGNATURE_SIZE);
+
+        let pk = ed25519_dalek::PublicKey::from_bytes(self.as_ref())
+            .map_err(errors::signature(SignatureErrorKind::MalformedPublicKey))?;
+        let signature = ed25519_dalek::Signature::from_bytes(signature)
+            .map_err(errors::signature(SignatureErrorKind::MalformedSignature))?;
+        let m2 = pae(&[PASETO_V4_PUBLIC.as_bytes(), message, &footer, implicit]);
+        pk.verify_strict(&m2, &signature)
+            .map_err(errors::signature(SignatureErrorKind::VerificationFailed))?;
+        Ok((message.to_vec(), footer))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{new_session_id_pair, ISessionIdPair};
+
+    fn hex(s: &str) -> Vec<u8> {
+        (0..s.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_paseto_vector() {
+        // PASETO test vector 4-S-1
+        let sk = ed25519_dalek::SecretKey::from_bytes(&hex(
+            "b4cbfb43df4ce210727d953e4a713307fa19bb7d9f85041438d9e11b942a3774",
+        ))
+        .unwrap();
+        let kp = SessionIdPair {
+            public: (&sk).into(),
+            secret: sk,
+        };
+        assert_eq!(
+            kp.get_id().to_vec(),
+            hex("1eb9dbbbbc047c03fd70604e0071f0987e16b28b757225c11f00415d0e20b1a2")
+        );
+        let message = br#"{"data":"this is a signed message","exp":"2022-01-01T00:00:00+00:00"}"#;
+        let token = kp.sign_paseto(message, b"", b"").unwrap();
+        assert_eq!(token, "v4.public.eyJkYXRhIjoidGhpcyBpcyBhIHNpZ25lZCBtZXNzYWdlIiwiZXhwIjoiMjAyMi0wMS0wMVQwMDowMDowMCswMDowMCJ9bg_XBBzds8lTZShVlwwKSgeKpLT3yukTw6JUz3W4h_ExsQV-P0V54zemZDcAxFaSeef1QlXEFtkqxT1ciiQEDA");
+        let (m, f) = kp.get_id().verify_paseto(&token, b"").unwrap();
+        assert_eq!(m, message);
+        assert!(f.is_empty());
+    }
+
+    #[test]
+    fn test_paseto() {
+        let kp = new_session_id_pair().unwrap();
+        let sid = kp.get_id();
+        let token = kp.sign_paseto(b"grant", b"kid", b"world").unwrap();
+        let (m, f) = sid.verify_paseto(&token, b"world").unwrap();
+        assert_eq!(m, b"grant");
+        assert_eq!(f, b"kid");
+
+        assert!(sid.verify_paseto(&token, b"other").is_err());
+        let other = new_session_id_pair().unwrap().get_id();
+        assert!(other.verify_paseto(&token, b"world").is_err());
+
+        let (body, _) = token.rsplit_once('.').unwrap();
+        let tampered = format!("{}.{}", body, base64url::encode("kie"));
+        assert!(sid.verify_paseto(&tampered, b"world").is_err());
+        assert!(matches!(
+            sid.verify_paseto("v3.public.abc", b""),
+            Err(SessionIdError::InvalidFormat(_))
+        ));
+        assert!(sid.verify_paseto("v4.public.", b"").is_err());
+    }
+}
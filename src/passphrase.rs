@@ -0,0 +1,167 @@
+//! Passphrase encryption of secret keys. Enabled with the `passphrase` feature.
+//!
+//! The key is derived with Argon2id and the seed is sealed with XChaCha20-Poly1305.
+//!
+//! | field | size |
+//! |-------|------|
+//! | version (`1`) | 1 |
+//! | Argon2 memory (KiB), iterations, parallelism (u32 LE each) | 12 |
+//! | salt | 16 |
+//! | nonce | 24 |
+//! | encrypted seed and tag | 48 |
+use crate::encoding::Reader;
+use crate::errors::{self, Result, SessionIdError};
+use crate::{SecretSessionKey, SECRET_KEY_SIZE};
+use argon2::{Algorithm, Argon2, Params, Version};
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use zeroize::Zeroizing;
+
+const PASSPHRASE_VERSION: u8 = 1;
+const HEADER_SIZE: usize = 1 + 12 + 16;
+/// Bytes of an encrypted secret key
+pub const ENCRYPTED_SECRET_KEY_SIZE: usize = HEADER_SIZE + 24 + SECRET_KEY_SIZE + 16;
+// upper bounds accepted when decrypting, so a crafted blob cannot exhaust memory or time
+const MAX_MEMORY_KIB: u32 = 1 << 20;
+const MAX_ITERATIONS: u32 = 16;
+const MAX_PARALLELISM: u32 = 16;
+
+fn argon2_error(e: argon2::Error) -> SessionIdError {
+    SessionIdError::InvalidFormat(format!("argon2: {}", e))
+}
+
+fn derive_key(passphrase: &str, header: &[u8; HEADER_SIZE]) -> Result<Zeroizing<[u8; 32]>> {
+    let mut r = Reader(&header[1..]);
+    let m_cost = u32::from_le_bytes(r.read_array()?);
+    let t_cost = u32::from_le_bytes(r.read_array()?);
+    let p_cost = u32::from_le_bytes(r.read_array()?);
+    if m_cost > MAX_MEMORY_KIB {
+        return Err(SessionIdError::InvalidArgument(
+            "argon2 memory cost too high",
+        ));
+    }
+    if t_cost > MAX_ITERATIONS {
+        return Err(SessionIdError::InvalidArgument("argon2 time cost too high"));
+    }
+    if p_cost > MAX_PARALLELISM {
+        return Err(SessionIdError::InvalidArgument(
+            "argon2 parallelism too high",
+        ));
+    }
+    let params = Params::new(m_cost, t_cost, p_cost, Some(32)).map_err(argon2_error)?;
+    let mut key = Zeroizing::new([0u8; 32]);
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(passphrase.as_bytes(), r.0, &mut key[..])
+        .map_err(argon2_error)?;
+    Ok(key)
+}
+
+impl SecretSessionKey {
+    /// Encrypt with `passphrase` using the default Argon2id parameters (19 MiB, 2 passes)
+    pub fn encrypt_with_passphrase(&self, passphrase: &str) -> Result<Vec<u8>> {
+        self.encrypt_with_params(
+            passphrase,
+            Params::DEFAULT_M_COST,
+            Params::DEFAULT_T_COST,
+            Params::DEFAULT_P_COST,
+        )
+    }
+    fn encrypt_with_params(
+        &self,
+        passphrase: &str,
+        m_cost: u32,
+        t_cost: u32,
+        p_cost: u32,
+    ) -> Result<Vec<u8>> {
+        let mut header = [0u8; HEADER_SIZE];
+        header[0] = PASSPHRASE_VERSION;
+        header[1..5].copy_from_slice(&m_cost.to_le_bytes());
+        header[5..9].copy_from_slice(&t_cost.to_le_bytes());
+        header[9..13].copy_from_slice(&p_cost.to_le_bytes());
+        getrandom::getrandom(&mut header[13..])?;
+        let mut nonce = [0u8; 24];
+        getrandom::getrandom(&mut nonce)?;
+
+        let key = derive_key(passphrase, &header)?;
+        let ciphertext = XChaCha20Poly1305::new((&*key).into())
+            .encrypt(
+                &XNonce::from(nonce),
+                Payload {
+                    msg: self.expose_secret(),
+                    aad: &header,
+                },
+            )
+            .map_err(|_| SessionIdError::InvalidArgument("seed encryption failed"))?;
+        let mut buf = Vec::with_capacity(ENCRYPTED_SECRET_KEY_SIZE);
+        buf.extend_from_slice(&header);
+        buf.extend_from_slice(&nonce);
+        buf.extend_from_slice(&ciphertext);
+        Ok(buf)
+    }
+    /// Decrypt the output of [`encrypt_with_passphrase`](Self::encrypt_with_passphrase).
+    /// A wrong passphrase fails with `Decryption`.
+    pub fn decrypt_with_passphrase(encrypted: &[u8], passphrase: &str) -> Result<Self> {
+        if encrypted.len() != ENCRYPTED_SECRET_KEY_SIZE {
+            return Err(errors::invalid_length(
+                ENCRYPTED_SECRET_KEY_SIZE,
+                encrypted.len(),
+            ));
+        }
+        let mut r = Reader(encrypted);
+        let header = r.read_array::<HEADER_SIZE>()?;
+        if header[0] != PASSPHRASE_VERSION {
+            return Err(SessionIdError::InvalidFormat(
+                "passphrase: unsupported version".to_string(),
+            ));
+        }
+        let nonce = r.read_array::<24>()?;
+        let key = derive_key(passphrase, &header)?;
+        let seed = Zeroizing::new(
+            XChaCha20Poly1305::new((&*key).into())
+                .decrypt(
+                    &XNonce::from(nonce),
+                    Payload {
+                        msg: r.0,
+                        aad: &header,
+                    },
+                )
+                .map_err(|_| SessionIdError::Decryption)?,
+        );
+        let mut secret = SecretSessionKey::new([0; SECRET_KEY_SIZE]);
+        secret.expose_secret_mut().copy_from_slice(&seed);
+        Ok(secret)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_passphrase() {
+        let key = SecretSessionKey::generate().unwrap();
+        // cheap parameters, the format is the same
+        let encrypted = key.encrypt_with_params("correct horse", 64, 1, 1).unwrap();
+        assert_eq!(encrypted.len(), ENCRYPTED_SECRET_KEY_SIZE);
+        assert_eq!(
+            SecretSessionKey::decrypt_with_passphrase(&encrypted, "correct horse").unwrap(),
+            key
+        );
+        assert!(matches!(
+            SecretSessionKey::decrypt_with_passphrase(&encrypted, "wrong"),
+            Err(SessionIdError::Decryption)
+        ));
+        // parameters are authenticated
+        let mut tampered = encrypted.clone();
+        tampered[5] = 2;
+        assert!(SecretSessionKey::decrypt_with_passphrase(&tampered, "correct horse").is_err());
+        for offset in [1, 5, 9] {
+            let mut huge = encrypted.clone();
+            huge[offset..offset + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+            assert!(matches!(
+                SecretSessionKey::decrypt_with_passphrase(&huge, "correct horse"),
+                Err(SessionIdError::InvalidArgument(_))
+            ));
+        }
+    }
+}
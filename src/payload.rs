@@ -0,0 +1,175 @@
+//! Signing payload assembled from typed parts.
+//!
+//! Parts are appended as their plain bytes (strings as UTF-8, integers as fixed-width
+//! little endian), so a `Payload` signs the same as the equivalent hand-built `Vec<&[u8]>`.
+use crate::SessionId;
+
+/// Value that can be appended to a [`Payload`]
+pub trait PayloadPart {
+    fn append_to(&self, buf: &mut Vec<u8>);
+}
+
+impl<T: PayloadPart + ?Sized> PayloadPart for &T {
+    fn append_to(&self, buf: &mut Vec<u8>) {
+        (**self).append_to(buf)
+    }
+}
+impl PayloadPart for [u8] {
+    fn append_to(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(self)
+    }
+}
+impl<const N: usize> PayloadPart for [u8; N] {
+    fn append_to(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(self)
+    }
+}
+impl PayloadPart for Vec<u8> {
+    fn append_to(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(self)
+    }
+}
+impl PayloadPart for str {
+    fn append_to(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(self.as_bytes())
+    }
+}
+impl PayloadPart for String {
+    fn append_to(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(self.as_bytes())
+    }
+}
+impl PayloadPart for SessionId {
+    fn append_to(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(self.as_ref())
+    }
+}
+#[cfg(feature = "uuid")]
+impl PayloadPart for uuid::Uuid {
+    fn append_to(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(self.as_bytes())
+    }
+}
+
+macro_rules! impl_payload_part_le {
+    ($($t:ty),*) => {
+        $(impl PayloadPart for $t {
+            fn append_to(&self, buf: &mut Vec<u8>) {
+                buf.extend_from_slice(&self.to_le_bytes())
+            }
+        })*
+    };
+}
+impl_payload_part_le!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128);
+
+/// Signing payload builder.
+/// Pass `&payload` to `sign` / `verify` like any other sequence of byte slices.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct Payload(Vec<u8>);
+
+impl Payload {
+    pub fn new() -> Self {
+        Payload(Vec::new())
+    }
+    /// Append any [`PayloadPart`]
+    pub fn append<T: PayloadPart + ?Sized>(mut self, part: &T) -> Self {
+        part.append_to(&mut self.0);
+        self
+    }
+    pub fn add_bytes(self, v: impl AsRef<[u8]>) -> Self {
+        self.append(v.as_ref())
+    }
+    /// Append the UTF-8 bytes of `v`
+    pub fn add_str(self, v: &str) -> Self {
+        self.append(v)
+    }
+    /// Append `v` as 8 bytes little endian
+    pub fn add_u64_le(self, v: u64) -> Self {
+        self.append(&v)
+    }
+    /// Append the 16 bytes of `v`
+    #[cfg(feature = "uuid")]
+    pub fn add_uuid(self, v: &uuid::Uuid) -> Self {
+        self.append(v)
+    }
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.0
+    }
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl AsRef<[u8]> for Payload {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl<'a> IntoIterator for &'a Payload {
+    type Item = &'a [u8];
+    type IntoIter = std::iter::Once<&'a [u8]>;
+    fn into_iter(self) -> Self::IntoIter {
+        std::iter::once(&self.0)
+    }
+}
+
+/// Build a [`Payload`] from heterogeneous [`PayloadPart`]s.
+///
+/// ```
+/// use verse_session_id::*;
+///
+/// let pair = new_session_id_pair().unwrap();
+/// let room = "lobby";
+/// let seq = 7u64;
+/// let signature = pair.sign(&payload![b"chat/v1", room, seq]).unwrap();
+/// assert!(pair
+///     .get_id()
+///     .verify(vec![&b"chat/v1"[..], room.as_bytes(), &seq.to_le_bytes()], &signature)
+///     .is_ok());
+/// ```
+#[macro_export]
+macro_rules! payload {
+    ($($part:expr),* $(,)?) => {
+        $crate::Payload::new()$(.append(&$part))*
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{new_session_id_pair, ISessionIdPair, SessionIdPublic};
+
+    #[test]
+    fn test_payload() {
+        let id = SessionId::from([3; 32]);
+        let p = Payload::new()
+            .add_bytes(b"ctx")
+            .add_str("name")
+            .add_u64_le(258)
+            .append(&id);
+        assert_eq!(p.len(), 3 + 4 + 8 + 32);
+        assert_eq!(&p.as_bytes()[7..15], &[2, 1, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(payload![b"ctx", "name", 258u64, id], p);
+        assert_eq!(
+            payload![1u8, -1i16, String::from("s"), vec![9u8]].into_bytes(),
+            vec![1, 0xff, 0xff, b's', 9]
+        );
+        assert!(payload![].is_empty());
+
+        let pair = new_session_id_pair().unwrap();
+        let sig = pair.sign(&p).unwrap();
+        let seq = 258u64.to_le_bytes();
+        assert!(pair
+            .get_id()
+            .verify(vec![&b"ctx"[..], b"name", &seq, id.as_ref()], &sig)
+            .is_ok());
+        assert!(pair.get_id().verify(&payload![b"ctx"], &sig).is_err());
+    }
+}
@@ -0,0 +1,131 @@
+//! Authenticated encryption between two identified peers. Enabled with the `cipher` feature.
+//!
+//! The key is derived from the X25519 shared secret of the session keys and both
+//! SessionIds. Messages are sealed with XChaCha20-Poly1305 under a random 24-byte nonce,
+//! which is prepended to the ciphertext.
+use crate::errors::{self, Result, SessionIdError};
+use crate::{ISessionIdPair, SessionId, SessionIdPair, X25519KeyAgreement};
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use ed25519_dalek::{Digest, Sha512};
+use zeroize::{Zeroize, Zeroizing};
+
+const PEER_CIPHER_CONTEXT: &[u8] = b"verse-session-id/peer-cipher/v1";
+const NONCE_SIZE: usize = 24;
+/// Bytes added to each plaintext (nonce and tag)
+pub const PEER_CIPHER_OVERHEAD: usize = NONCE_SIZE + 16;
+
+/// Symmetric cipher shared by two peers
+#[derive(Clone)]
+pub struct PeerCipher {
+    cipher: XChaCha20Poly1305,
+    their_id: SessionId,
+}
+
+impl PeerCipher {
+    /// Derive the cipher shared with `their_id`. Both peers derive the same key.
+    pub fn establish(my_pair: &SessionIdPair, their_id: &SessionId) -> Result<Self> {
+        let shared = my_pair.diffie_hellman(their_id)?;
+        let my_id = my_pair.get_id();
+        let (lo, hi) = if my_id <= *their_id {
+            (my_id, *their_id)
+        } else {
+            (*their_id, my_id)
+        };
+        let mut hash = Sha512::new()
+            .chain(PEER_CIPHER_CONTEXT)
+            .chain(shared)
+            .chain(lo)
+            .chain(hi)
+            .finalize();
+        let mut key = Zeroizing::new([0u8; 32]);
+        key.copy_from_slice(&hash[..32]);
+        hash[..].zeroize();
+        Ok(Self::with_key(&key, their_id))
+    }
+    /// Cipher under an already derived key
+    pub(crate) fn with_key(key: &[u8; 32], their_id: &SessionId) -> Self {
+        PeerCipher {
+            cipher: XChaCha20Poly1305::new(key.into()),
+            their_id: *their_id,
+        }
+    }
+    /// The peer this cipher is shared with
+    pub fn their_id(&self) -> SessionId {
+        self.their_id
+    }
+    /// Encrypt `plaintext`, authenticating `ad` as well
+    pub fn encrypt(&self, ad: &[u8], plaintext: &[u8]) -> Result<Vec<u8>> {
+        let mut nonce = [0u8; NONCE_SIZE];
+        getrandom::getrandom(&mut nonce)?;
+        let ciphertext = self
+            .cipher
+            .encrypt(
+                &XNonce::from(nonce),
+                Payload {
+                    msg: plaintext,
+                    aad: ad,
+                },
+            )
+            .map_err(|_| SessionIdError::InvalidArgument("plaintext too long"))?;
+        let mut buf = Vec::with_capacity(NONCE_SIZE + ciphertext.len());
+        buf.extend_from_slice(&nonce);
+        buf.extend_from_slice(&ciphertext);
+        Ok(buf)
+    }
+    /// Decrypt a message produced by the peer's [`encrypt`](Self::encrypt)
+    pub fn decrypt(&self, ad: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>> {
+        let Some((nonce, ciphertext)) = ciphertext
+            .split_first_chunk::<NONCE_SIZE>()
+            .filter(|_| ciphertext.len() >= PEER_CIPHER_OVERHEAD)
+        else {
+            return Err(errors::invalid_length(
+                PEER_CIPHER_OVERHEAD,
+                ciphertext.len(),
+            ));
+        };
+        self.cipher
+            .decrypt(
+                &XNonce::from(*nonce),
+                Payload {
+                    msg: ciphertext,
+                    aad: ad,
+                },
+            )
+            .map_err(|_| SessionIdError::Decryption)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::new_session_id_pair;
+
+    #[test]
+    fn test_peer_cipher() {
+        let a = new_session_id_pair().unwrap();
+        let b = new_session_id_pair().unwrap();
+        let ca = PeerCipher::establish(&a, &b.get_id()).unwrap();
+        let cb = PeerCipher::establish(&b, &a.get_id()).unwrap();
+        assert_eq!(ca.their_id(), b.get_id());
+
+        let m = ca.encrypt(b"room1", b"hello").unwrap();
+        assert_eq!(m.len(), 5 + PEER_CIPHER_OVERHEAD);
+        assert_eq!(cb.decrypt(b"room1", &m).unwrap(), b"hello");
+        assert_eq!(ca.decrypt(b"room1", &m).unwrap(), b"hello");
+        assert_ne!(ca.encrypt(b"room1", b"hello").unwrap(), m);
+
+        assert!(cb.decrypt(b"room2", &m).is_err());
+        let mut tampered = m.clone();
+        tampered[30] ^= 1;
+        assert!(matches!(
+            cb.decrypt(b"room1", &tampered),
+            Err(SessionIdError::Decryption)
+        ));
+        assert!(cb.decrypt(b"room1", &m[..10]).is_err());
+
+        let c = new_session_id_pair().unwrap();
+        let cc = PeerCipher::establish(&c, &a.get_id()).unwrap();
+        assert!(cc.decrypt(b"room1", &m).is_err());
+    }
+}
@@ -0,0 +1,244 @@
+//! Bit prefixes of session IDs, for routing buckets and shortened IDs typed by users.
+//!
+//! A [`SessionIdPrefix`] is written as truncated standard base64, e.g. `q3Fz`. A bit length
+//! that is not a multiple of 6 is appended CIDR-style, e.g. `q3Fz/20`. Hex prefixes are read
+//! with [`SessionIdPrefix::from_hex`].
+use crate::errors::{Result, SessionIdError};
+use crate::{RawSessionId, SessionId, SESSION_ID_SIZE};
+use std::fmt;
+use std::str::FromStr;
+
+const MAX_BITS: usize = SESSION_ID_SIZE * 8;
+const BASE64_CHARS: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+fn prefix_error(msg: &str) -> SessionIdError {
+    SessionIdError::InvalidFormat(format!("prefix: {}", msg))
+}
+
+/// The first `bits` bits of a session ID.
+/// Ordered by the bits, then shorter first, so a prefix sorts just before the IDs it matches.
+#[derive(Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct SessionIdPrefix {
+    // bits past `bits` are always zero
+    bytes: RawSessionId,
+    bits: u16,
+}
+
+impl SessionIdPrefix {
+    /// Prefix of `id` of `bits` bits (at most 256)
+    pub fn new(id: &SessionId, bits: usize) -> Result<Self> {
+        Self::from_bits(id.as_raw(), bits)
+    }
+    /// The first `bits` bits of `bytes`, which must cover them
+    pub fn from_bits(bytes: &[u8], bits: usize) -> Result<Self> {
+        if bits > MAX_BITS {
+            return Err(SessionIdError::InvalidArgument(
+                "prefix longer than a session id",
+            ));
+        }
+        let n = bits.div_ceil(8);
+        if bytes.len() < n {
+            return Err(crate::errors::invalid_length(n, bytes.len()));
+        }
+        let mut raw = [0; SESSION_ID_SIZE];
+        raw[..n].copy_from_slice(&bytes[..n]);
+        Ok(Self::masked(raw, bits))
+    }
+    // `bits` is at most 256
+    fn masked(mut bytes: RawSessionId, bits: usize) -> Self {
+        for (i, b) in bytes.iter_mut().enumerate() {
+            let keep = bits.saturating_sub(i * 8).min(8);
+            *b &= !(0xffu16 >> keep) as u8;
+        }
+        SessionIdPrefix {
+            bytes,
+            bits: bits as u16,
+        }
+    }
+    /// Parse hex digits, 4 bits each (e.g. `ab3f`)
+    pub fn from_hex(s: &str) -> Result<Self> {
+        Self::from_digits(s, 4, |c| (c as char).to_digit(16).map(|v| v as u8))
+    }
+    fn from_digits(s: &str, width: usize, digit: impl Fn(u8) -> Option<u8>) -> Result<Self> {
+        if s.len() * width > MAX_BITS + width - 1 {
+            return Err(prefix_error("too long"));
+        }
+        let mut bytes = [0u8; SESSION_ID_SIZE + 1];
+        for (i, c) in s.bytes().enumerate() {
+            let v = digit(c).ok_or_else(|| prefix_error("invalid character"))?;
+            for j in 0..width {
+                let bit = i * width + j;
+                if v & (1 << (width - 1 - j)) != 0 {
+                    bytes[bit / 8] |= 0x80 >> (bit % 8);
+                }
+            }
+        }
+        Self::from_bits(&bytes, (s.len() * width).min(MAX_BITS))
+    }
+    pub fn bits(&self) -> usize {
+        self.bits as usize
+    }
+    pub fn is_empty(&self) -> bool {
+        self.bits == 0
+    }
+    /// Whether `id` starts with this prefix
+    pub fn matches(&self, id: &SessionId) -> bool {
+        SessionIdPrefix::masked(*id.as_raw(), self.bits()) == *self
+    }
+    /// Whether every ID matched by `other` is also matched by this prefix
+    pub fn contains(&self, other: &SessionIdPrefix) -> bool {
+        other.bits >= self.bits && SessionIdPrefix::masked(other.bytes, self.bits()) == *self
+    }
+}
+
+impl SessionId {
+    /// Prefix of `bits` bits (at most 256)
+    pub fn prefix(&self, bits: usize) -> Result<SessionIdPrefix> {
+        SessionIdPrefix::new(self, bits)
+    }
+    /// The longest prefix shared with `other`
+    pub fn common_prefix(&self, other: &SessionId) -> SessionIdPrefix {
+        SessionIdPrefix::masked(*self.as_raw(), self.common_prefix_len(other))
+    }
+}
+
+/// Truncated base64, with `/bits` when the length is not a multiple of 6
+impl fmt::Display for SessionIdPrefix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let bits = self.bits();
+        for i in 0..bits.div_ceil(6) {
+            let mut v = 0;
+            for j in 0..6 {
+                let bit = i * 6 + j;
+                if bit < MAX_BITS && self.bytes[bit / 8] & (0x80 >> (bit % 8)) != 0 {
+                    v |= 0x20 >> j;
+                }
+            }
+            fmt::Write::write_char(f, BASE64_CHARS[v] as char)?;
+        }
+        if !bits.is_multiple_of(6) {
+            write!(f, "/{}", bits)?;
+        }
+        Ok(())
+    }
+}
+impl fmt::Debug for SessionIdPrefix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SessionIdPrefix({})", self)
+    }
+}
+
+/// Truncated standard or URL-safe base64 (e.g. `q3Fz`), optionally with `/bits`.
+/// A full 44 character session ID parses as a 256 bit prefix.
+impl FromStr for SessionIdPrefix {
+    type Err = SessionIdError;
+    fn from_str(s: &str) -> Result<Self> {
+        let (chars, bits) = match s.split_once('/') {
+            Some((chars, bits)) => {
+                let bits: usize = bits.parse().map_err(|_| prefix_error("invalid bits"))?;
+                (chars, Some(bits))
+            }
+            None => (s, None),
+        };
+        let chars = chars.trim_end_matches('=');
+        let prefix = Self::from_digits(chars, 6, |c| match c {
+            b'-' => Some(62),
+            b'_' => Some(63),
+            _ => BASE64_CHARS.iter().position(|v| *v == c).map(|v| v as u8),
+        })?;
+        match bits {
+            None => Ok(prefix),
+            Some(bits) if bits <= prefix.bits() && prefix.bits() - bits < 6 => {
+                Ok(Self::masked(prefix.bytes, bits))
+            }
+            Some(_) => Err(prefix_error("bits do not match the characters")),
+        }
+    }
+}
+
+/// The only ID in `ids` starting with `prefix`, e.g. to resolve a shortened ID typed by a user.
+/// Fails if none or several distinct IDs match.
+pub fn find_unique<'a>(
+    prefix: &SessionIdPrefix,
+    ids: impl IntoIterator<Item = &'a SessionId>,
+) -> Result<SessionId> {
+    let mut found: Option<SessionId> = None;
+    for id in ids.into_iter().filter(|id| prefix.matches(id)) {
+        match found {
+            Some(v) if v != *id => {
+                return Err(SessionIdError::InvalidArgument(
+                    "session id prefix is ambiguous",
+                ))
+            }
+            _ => found = Some(*id),
+        }
+    }
+    found.ok_or(SessionIdError::InvalidArgument(
+        "no session id matches the prefix",
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn id(first: u8, second: u8) -> SessionId {
+        let mut v = [0x55u8; 32];
+        v[0] = first;
+        v[1] = second;
+        SessionId::from(v)
+    }
+
+    #[test]
+    fn test_session_id_prefix() {
+        let a = id(0b1010_1100, 0x0f);
+        let p = a.prefix(12).unwrap();
+        assert!(p.matches(&a));
+        assert!(p.matches(&id(0b1010_1100, 0x00)));
+        assert!(!p.matches(&id(0b1010_1100, 0xf0)));
+        assert!(SessionIdPrefix::new(&a, 0).unwrap().matches(&id(0, 0)));
+        assert!(a.prefix(256).unwrap().matches(&a));
+        assert!(a.prefix(257).is_err());
+        assert_eq!(a.common_prefix(&id(0b1010_0000, 0)).bits(), 4);
+        assert!(a.prefix(4).unwrap().contains(&p));
+        assert!(!p.contains(&a.prefix(4).unwrap()));
+
+        // base64 round trip
+        let s = a.to_string();
+        let full: SessionIdPrefix = s.parse().unwrap();
+        assert_eq!(full.bits(), 256);
+        assert!(full.matches(&a));
+        let short: SessionIdPrefix = s[..4].parse().unwrap();
+        assert_eq!(short.bits(), 24);
+        assert!(short.matches(&a));
+        assert_eq!(short.to_string(), &s[..4]);
+        let odd = a.prefix(20).unwrap();
+        assert!(odd.to_string().starts_with(&s[..3]));
+        assert!(odd.to_string().ends_with("/20"));
+        assert_eq!(odd.to_string().parse::<SessionIdPrefix>().unwrap(), odd);
+        assert!(format!("{}/30", &s[..4])
+            .parse::<SessionIdPrefix>()
+            .is_err());
+        assert!("q3F!".parse::<SessionIdPrefix>().is_err());
+
+        let hex = SessionIdPrefix::from_hex("ac0").unwrap();
+        assert_eq!(hex, p);
+        assert!(SessionIdPrefix::from_hex(&"0".repeat(65)).is_err());
+        assert!(SessionIdPrefix::from_hex("xy").is_err());
+
+        // a prefix sorts before the longer prefixes it contains
+        assert!(a.prefix(4).unwrap() < p);
+        assert!(p < a.prefix(13).unwrap());
+    }
+
+    #[test]
+    fn test_find_unique() {
+        let ids = [id(0x10, 1), id(0x20, 1), id(0x21, 1)];
+        let p = |s: &str| SessionIdPrefix::from_hex(s).unwrap();
+        assert_eq!(find_unique(&p("1"), &ids).unwrap(), ids[0]);
+        assert_eq!(find_unique(&p("21"), &ids).unwrap(), ids[2]);
+        assert!(find_unique(&p("2"), &ids).is_err());
+        assert!(find_unique(&p("3"), &ids).is_err());
+        assert_eq!(find_unique(&p("1"), &[ids[0], ids[0]]).unwrap(), ids[0]);
+    }
+}
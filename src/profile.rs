@@ -0,0 +1,145 @@
+//! Self-asserted identity profiles.
+//!
+//! Clients exchange profiles with a signature by the SessionId they describe, so that
+//! relays cannot tamper with names or avatars. The signature covers a canonical binary
+//! encoding, independent of how the profile is transported (JSON, bincode, ...).
+use crate::encoding::{write_str, Reader};
+use crate::errors::{Result, SessionIdError};
+use crate::time::{Clock, SystemClock};
+use crate::{ISessionIdPair, SessionId, SessionIdPair, SessionIdPublic, SignatureSet};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+const PROFILE_CONTEXT: &[u8] = b"verse-session-id/profile/v1";
+const PROFILE_VERSION: u8 = 1;
+
+/// Public profile of a session ID
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct IdentityProfile {
+    pub display_name: String,
+    pub avatar_url: Option<String>,
+    /// Public key-value metadata. Sorted, so the encoding is canonical.
+    pub metadata: BTreeMap<String, String>,
+    /// Issue time (seconds since UNIX epoch). Newer profiles replace older ones.
+    pub issued_at: u64,
+}
+
+impl IdentityProfile {
+    /// Profile issued now
+    pub fn new(display_name: impl Into<String>) -> Self {
+        Self::new_at(display_name, &SystemClock)
+    }
+    /// Profile issued at the time of `clock`
+    pub fn new_at(display_name: impl Into<String>, clock: &dyn Clock) -> Self {
+        IdentityProfile {
+            display_name: display_name.into(),
+            issued_at: clock.now(),
+            ..Default::default()
+        }
+    }
+
+    pub fn to_canonical_bytes(&self) -> Result<Vec<u8>> {
+        let mut buf = vec![PROFILE_VERSION];
+        buf.extend_from_slice(&self.issued_at.to_le_bytes());
+        write_str(&mut buf, &self.display_name)?;
+        match &self.avatar_url {
+            Some(url) => {
+                buf.push(1);
+                write_str(&mut buf, url)?;
+            }
+            None => buf.push(0),
+        }
+        let n: u16 = self
+            .metadata
+            .len()
+            .try_into()
+            .map_err(|_| SessionIdError::InvalidArgument("too many metadata entries"))?;
+        buf.extend_from_slice(&n.to_le_bytes());
+        for (k, v) in &self.metadata {
+            write_str(&mut buf, k)?;
+            write_str(&mut buf, v)?;
+        }
+        Ok(buf)
+    }
+    pub fn from_canonical_bytes(bytes: &[u8]) -> Result<Self> {
+        let mut r = Reader(bytes);
+        if r.read(1)?[0] != PROFILE_VERSION {
+            return Err(profile_error("unsupported version"));
+        }
+        let issued_at = u64::from_le_bytes(r.read_array()?);
+        let display_name = r.read_str()?;
+        let avatar_url = match r.read(1)?[0] {
+            0 => None,
+            1 => Some(r.read_str()?),
+            _ => return Err(profile_error("invalid avatar_url")),
+        };
+        let n = u16::from_le_bytes(r.read_array()?);
+        let mut metadata = BTreeMap::new();
+        for _ in 0..n {
+            let k = r.read_str()?;
+            if metadata.keys().next_back().is_some_and(|last| *last >= k) {
+                return Err(profile_error("metadata not sorted"));
+            }
+            metadata.insert(k, r.read_str()?);
+        }
+        if !r.0.is_empty() {
+            return Err(profile_error("trailing bytes"));
+        }
+        Ok(IdentityProfile {
+            display_name,
+            avatar_url,
+            metadata,
+            issued_at,
+        })
+    }
+
+    /// Sign the profile with the key of the SessionId it describes
+    pub fn sign(&self, pair: &SessionIdPair) -> Result<SignatureSet> {
+        pair.sign([PROFILE_CONTEXT, &self.to_canonical_bytes()?])
+    }
+    /// Check that the profile was signed by `session_id`
+    pub fn verify(&self, session_id: &SessionId, signature: &SignatureSet) -> Result<()> {
+        session_id.verify([PROFILE_CONTEXT, &self.to_canonical_bytes()?], signature)
+    }
+}
+
+fn profile_error(msg: &str) -> SessionIdError {
+    SessionIdError::InvalidFormat(format!("profile: {}", msg))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::new_session_id_pair;
+
+    #[test]
+    fn test_identity_profile() {
+        let pair = new_session_id_pair().unwrap();
+        let mut profile = IdentityProfile::new("alice");
+        profile.avatar_url = Some("https://example.com/a.vrm".to_string());
+        profile
+            .metadata
+            .insert("lang".to_string(), "ja".to_string());
+        profile.metadata.insert("bio".to_string(), "hi".to_string());
+
+        let bytes = profile.to_canonical_bytes().unwrap();
+        assert_eq!(
+            IdentityProfile::from_canonical_bytes(&bytes).unwrap(),
+            profile
+        );
+        assert!(IdentityProfile::from_canonical_bytes(&bytes[1..]).is_err());
+
+        let sig = profile.sign(&pair).unwrap();
+        assert!(profile.verify(&pair.get_id(), &sig).is_ok());
+        // survives a JSON round trip
+        let json = serde_json::to_string(&profile).unwrap();
+        let decoded: IdentityProfile = serde_json::from_str(&json).unwrap();
+        assert!(decoded.verify(&pair.get_id(), &sig).is_ok());
+
+        let mut tampered = profile.clone();
+        tampered.display_name = "mallory".to_string();
+        assert!(tampered.verify(&pair.get_id(), &sig).is_err());
+        let other = new_session_id_pair().unwrap().get_id();
+        assert!(profile.verify(&other, &sig).is_err());
+    }
+}
@@ -0,0 +1,142 @@
+//! Python bindings. Enabled with the `python` feature.
+//!
+//! Build an extension module with maturin:
+//! `maturin build --features python,pyo3/extension-module`
+use crate::errors::SessionIdError;
+use crate::{
+    new_session_id_pair, ISessionIdPair, SessionId, SessionIdPair, SessionIdPublic, SignatureSet,
+};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+
+impl From<SessionIdError> for PyErr {
+    fn from(e: SessionIdError) -> Self {
+        PyValueError::new_err(e.to_string())
+    }
+}
+
+/// Session ID
+#[pyclass(name = "SessionId", frozen, eq, hash, module = "verse_session_id")]
+#[derive(PartialEq, Eq, Hash)]
+pub struct PySessionId(SessionId);
+
+#[pymethods]
+impl PySessionId {
+    /// Parse from base64 string
+    #[new]
+    fn new(s: &str) -> PyResult<Self> {
+        Ok(PySessionId(s.parse()?))
+    }
+    #[staticmethod]
+    fn from_bytes(b: &[u8]) -> PyResult<Self> {
+        Ok(PySessionId(b.try_into()?))
+    }
+    fn to_bytes<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        PyBytes::new(py, self.0.as_ref())
+    }
+    /// Verify signature of `data`
+    fn verify(&self, data: &[u8], signature: &PySignatureSet) -> bool {
+        self.0.verify([data], &signature.0).is_ok()
+    }
+    fn __str__(&self) -> String {
+        self.0.to_string()
+    }
+    fn __repr__(&self) -> String {
+        format!("SessionId('{}')", self.0)
+    }
+}
+
+/// Signature
+#[pyclass(name = "SignatureSet", frozen, eq, module = "verse_session_id")]
+#[derive(PartialEq, Eq)]
+pub struct PySignatureSet(SignatureSet);
+
+#[pymethods]
+impl PySignatureSet {
+    /// Parse from base64 string
+    #[new]
+    fn new(s: &str) -> PyResult<Self> {
+        Ok(PySignatureSet(s.parse()?))
+    }
+    #[getter]
+    fn signature<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        PyBytes::new(py, &self.0.signature)
+    }
+    #[getter]
+    fn salt<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        PyBytes::new(py, &self.0.salt)
+    }
+    fn __str__(&self) -> String {
+        self.0.to_string()
+    }
+    fn __repr__(&self) -> String {
+        format!("SignatureSet('{}')", self.0)
+    }
+}
+
+/// Session ID and private key pair
+#[pyclass(name = "SessionIdPair", frozen, module = "verse_session_id")]
+pub struct PySessionIdPair(SessionIdPair);
+
+#[pymethods]
+impl PySessionIdPair {
+    /// Generate a new keypair
+    #[staticmethod]
+    fn generate() -> PyResult<Self> {
+        Ok(PySessionIdPair(new_session_id_pair()?))
+    }
+    fn get_id(&self) -> PySessionId {
+        PySessionId(self.0.get_id())
+    }
+    /// Create a signature for `data`
+    fn sign(&self, data: &[u8]) -> PyResult<PySignatureSet> {
+        Ok(PySignatureSet(self.0.sign([data])?))
+    }
+    fn __repr__(&self) -> String {
+        format!("SessionIdPair({})", self.0.get_id())
+    }
+}
+
+/// Create a signature for `data`
+#[pyfunction]
+fn sign(pair: &PySessionIdPair, data: &[u8]) -> PyResult<PySignatureSet> {
+    pair.sign(data)
+}
+
+/// Verify signature of `data`
+#[pyfunction]
+fn verify(session_id: &PySessionId, data: &[u8], signature: &PySignatureSet) -> bool {
+    session_id.verify(data, signature)
+}
+
+#[pymodule]
+fn verse_session_id(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PySessionId>()?;
+    m.add_class::<PySignatureSet>()?;
+    m.add_class::<PySessionIdPair>()?;
+    m.add_function(wrap_pyfunction!(sign, m)?)?;
+    m.add_function(wrap_pyfunction!(verify, m)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_python_sign_verify() {
+        let pair = PySessionIdPair::generate().unwrap();
+        let sid = pair.get_id();
+        let ss = sign(&pair, b"testdata").unwrap();
+        assert!(verify(&sid, b"testdata", &ss));
+        assert!(!verify(&sid, b"testdat", &ss));
+
+        let sid1 = PySessionId::new(&sid.__str__()).unwrap();
+        assert!(sid1 == sid);
+        let ss1 = PySignatureSet::new(&ss.__str__()).unwrap();
+        assert!(ss1 == ss);
+        assert!(sid1.verify(b"testdata", &ss1));
+        assert!(PySessionId::from_bytes(&[1; 3]).is_err());
+    }
+}
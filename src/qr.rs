@@ -0,0 +1,118 @@
+//! QR code payloads for identities and invites. Enabled with the `qr` feature.
+//!
+//! Payloads are short URIs so that generic scanner apps show something meaningful:
+//! - `verse:id/<base64url SessionId>` to add a friend
+//! - `verse:invite/<base64url issuer>/<CapabilityToken>` to join a world
+use crate::base64url;
+use crate::errors::{Result, SessionIdError};
+use crate::{CapabilityToken, SessionId};
+use qrcode::render::{svg, unicode};
+use qrcode::{EcLevel, QrCode};
+use std::fmt;
+
+const ID_PREFIX: &str = "verse:id/";
+const INVITE_PREFIX: &str = "verse:invite/";
+
+/// Content of a scanned or generated QR code
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum QrPayload {
+    Identity(SessionId),
+    /// Invite token minted by `issuer`. Check it with [`CapabilityToken::authorize`].
+    Invite {
+        issuer: SessionId,
+        token: CapabilityToken,
+    },
+}
+
+fn qr_error(e: impl fmt::Display) -> SessionIdError {
+    SessionIdError::InvalidFormat(format!("qr: {}", e))
+}
+
+impl QrPayload {
+    /// QR code of the payload (error correction level M)
+    pub fn to_qr_code(&self) -> Result<QrCode> {
+        QrCode::with_error_correction_level(self.to_string(), EcLevel::M).map_err(qr_error)
+    }
+    /// Standalone SVG document
+    pub fn to_svg(&self) -> Result<String> {
+        Ok(self
+            .to_qr_code()?
+            .render::<svg::Color>()
+            .min_dimensions(200, 200)
+            .build())
+    }
+    /// Rendered with Unicode half blocks, for terminals
+    pub fn to_unicode(&self) -> Result<String> {
+        Ok(self.to_qr_code()?.render::<unicode::Dense1x2>().build())
+    }
+}
+
+impl fmt::Display for QrPayload {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QrPayload::Identity(id) => write!(f, "{}{}", ID_PREFIX, base64url::encode(id)),
+            QrPayload::Invite { issuer, token } => write!(
+                f,
+                "{}{}/{}",
+                INVITE_PREFIX,
+                base64url::encode(issuer),
+                token
+            ),
+        }
+    }
+}
+impl std::str::FromStr for QrPayload {
+    type Err = SessionIdError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(id) = s.strip_prefix(ID_PREFIX) {
+            return Ok(QrPayload::Identity(SessionId::try_from(
+                base64url::decode(id)?,
+            )?));
+        }
+        if let Some(rest) = s.strip_prefix(INVITE_PREFIX) {
+            let (issuer, token) = rest
+                .split_once('/')
+                .ok_or_else(|| qr_error("invalid invite"))?;
+            return Ok(QrPayload::Invite {
+                issuer: SessionId::try_from(base64url::decode(issuer)?)?,
+                token: token.parse()?,
+            });
+        }
+        Err(qr_error("unknown payload"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{new_session_id_pair, CapabilityGrant, ISessionIdPair};
+
+    #[test]
+    fn test_qr_payload() {
+        let pair = new_session_id_pair().unwrap();
+        let id = QrPayload::Identity(pair.get_id());
+        let s = id.to_string();
+        assert!(s.starts_with("verse:id/"));
+        assert_eq!(s.parse::<QrPayload>().unwrap(), id);
+        assert!(id.to_svg().unwrap().starts_with("<?xml"));
+        assert!(!id.to_unicode().unwrap().is_empty());
+
+        let token =
+            CapabilityToken::mint(&pair, CapabilityGrant::new("world1", &["join"], u64::MAX))
+                .unwrap();
+        let invite = QrPayload::Invite {
+            issuer: pair.get_id(),
+            token,
+        };
+        let parsed: QrPayload = invite.to_string().parse().unwrap();
+        assert_eq!(parsed, invite);
+        let QrPayload::Invite { issuer, token } = parsed else {
+            panic!()
+        };
+        assert!(token.authorize(&issuer, "world1", "join").is_ok());
+        assert!(invite.to_qr_code().is_ok());
+
+        assert!("verse:foo/abc".parse::<QrPayload>().is_err());
+        assert!("verse:invite/abc".parse::<QrPayload>().is_err());
+    }
+}
@@ -0,0 +1,88 @@
+//! Redaction of session IDs in `Debug` output and debug strings.
+//!
+//! The process-wide [`Redaction`] applies to `{:?}` of [`SessionId`](crate::SessionId),
+//! `to_debug_string()` and everything that embeds them, so a deployment with a strict log
+//! policy sets it once at startup with [`set_redaction`]. `Display` and serialization always
+//! produce the full ID.
+use sha2::{Digest, Sha256};
+use std::sync::RwLock;
+
+pub(crate) const DEBUG_STRING_LEN: usize = 7;
+
+/// How session IDs are rendered for debugging
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum Redaction {
+    /// The first `n` base64 characters. The default, with 7.
+    /// `{:#?}` and `{:.n?}` override the length.
+    Truncated(usize),
+    /// `#` and the first 16 hex characters of the SHA-256 fingerprint. Stable across
+    /// processes, so log lines can still be correlated.
+    Hashed,
+    /// The full base64 string
+    Full,
+}
+
+impl Default for Redaction {
+    fn default() -> Self {
+        Redaction::Truncated(DEBUG_STRING_LEN)
+    }
+}
+
+impl Redaction {
+    /// Render the bytes of a session ID
+    pub fn apply(&self, id: &[u8]) -> String {
+        match *self {
+            Redaction::Truncated(n) => {
+                let mut s = base64::encode(id);
+                s.truncate(n);
+                s
+            }
+            Redaction::Hashed => {
+                let mut s = String::with_capacity(17);
+                s.push('#');
+                for b in &Sha256::digest(id)[..8] {
+                    s.push_str(&format!("{:02x}", b));
+                }
+                s
+            }
+            Redaction::Full => base64::encode(id),
+        }
+    }
+}
+
+static REDACTION: RwLock<Redaction> = RwLock::new(Redaction::Truncated(DEBUG_STRING_LEN));
+
+/// Replace the process-wide redaction
+pub fn set_redaction(redaction: Redaction) {
+    *REDACTION.write().unwrap_or_else(|e| e.into_inner()) = redaction;
+}
+
+/// The process-wide redaction
+pub fn redaction() -> Redaction {
+    *REDACTION.read().unwrap_or_else(|e| e.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SessionId;
+
+    #[test]
+    fn test_redaction() {
+        let id = SessionId::from([0; 32]);
+        assert_eq!(
+            Redaction::default().apply(id.as_ref()),
+            id.to_debug_string()
+        );
+        assert_eq!(Redaction::Truncated(3).apply(id.as_ref()), "AAA");
+        assert_eq!(Redaction::Full.apply(id.as_ref()), id.to_string());
+        assert_eq!(
+            Redaction::Hashed.apply(id.as_ref()),
+            format!("#{}", id.fingerprint().truncated(16))
+        );
+
+        // other tests run concurrently, so only the default is installed here
+        set_redaction(Redaction::default());
+        assert_eq!(redaction(), Redaction::Truncated(7));
+    }
+}
@@ -0,0 +1,77 @@
+//! Redis argument and reply conversions for [`SessionId`]. Enabled with the `redis` feature.
+//!
+//! A `SessionId` is written as its 32 raw bytes, so it can be used directly as a key or
+//! value. Replies are read from the raw bytes or from a base64 string.
+//!
+//! ```rust,ignore
+//! let _: () = conn.set_ex(sid, peer_addr, 30).await?;
+//! let online: Vec<SessionId> = conn.smembers("room:lobby").await?;
+//! ```
+use crate::{SessionId, SESSION_ID_SIZE};
+use redis::{FromRedisValue, ParsingError, RedisWrite, ToRedisArgs, ToSingleRedisArg, Value};
+
+impl ToRedisArgs for SessionId {
+    fn write_redis_args<W>(&self, out: &mut W)
+    where
+        W: ?Sized + RedisWrite,
+    {
+        out.write_arg(self.as_raw())
+    }
+}
+
+impl ToSingleRedisArg for SessionId {}
+
+fn parse_reply(bytes: &[u8]) -> Result<SessionId, ParsingError> {
+    let res = if bytes.len() == SESSION_ID_SIZE {
+        SessionId::try_from(bytes)
+    } else {
+        std::str::from_utf8(bytes)?.parse()
+    };
+    res.map_err(|e| format!("redis: {}", e).into())
+}
+
+impl FromRedisValue for SessionId {
+    fn from_redis_value_ref(v: &Value) -> Result<Self, ParsingError> {
+        match v {
+            Value::BulkString(bytes) => parse_reply(bytes),
+            Value::SimpleString(s) => parse_reply(s.as_bytes()),
+            _ => Err(format!("redis: unexpected reply for SessionId: {:?}", v).into()),
+        }
+    }
+    fn from_redis_value(v: Value) -> Result<Self, ParsingError> {
+        Self::from_redis_value_ref(&v)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redis_types() {
+        let sid = SessionId::from([7; 32]);
+        assert_eq!(sid.to_redis_args(), vec![sid.to_vec()]);
+        let packed = redis::cmd("SADD")
+            .arg("room:lobby")
+            .arg(sid)
+            .get_packed_command();
+        assert!(packed.ends_with(&[b"$32\r\n".as_slice(), &[7; 32], b"\r\n"].concat()));
+
+        let raw = Value::BulkString(sid.to_vec());
+        assert_eq!(SessionId::from_redis_value(raw).unwrap(), sid);
+        let text = Value::BulkString(sid.to_string().into_bytes());
+        assert_eq!(SessionId::from_redis_value(text).unwrap(), sid);
+        let list = Value::Array(vec![Value::BulkString(sid.to_vec()); 2]);
+        assert_eq!(
+            Vec::<SessionId>::from_redis_value(list).unwrap(),
+            [sid, sid]
+        );
+        assert_eq!(
+            Option::<SessionId>::from_redis_value(Value::Nil).unwrap(),
+            None
+        );
+
+        assert!(SessionId::from_redis_value(Value::BulkString(vec![7; 31])).is_err());
+        assert!(SessionId::from_redis_value(Value::Int(7)).is_err());
+    }
+}
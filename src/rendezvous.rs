@@ -0,0 +1,85 @@
+//! Rendezvous (highest random weight) hashing over session IDs.
+//!
+//! Every node scores a resource key and the highest score owns it, so all servers
+//! agree on placement without coordination and only the keys of a removed node move.
+use crate::SessionId;
+use ed25519_dalek::{Digest, Sha512};
+
+const RENDEZVOUS_CONTEXT: &[u8] = b"verse-session-id/rendezvous/v1";
+
+impl SessionId {
+    /// Weight of this node for `resource_key`. Stable across platforms and versions.
+    pub fn rendezvous_score(&self, resource_key: &[u8]) -> u64 {
+        let hash = Sha512::new()
+            .chain(RENDEZVOUS_CONTEXT)
+            .chain(self)
+            .chain(resource_key)
+            .finalize();
+        let mut v = [0u8; 8];
+        v.copy_from_slice(&hash[..8]);
+        u64::from_be_bytes(v)
+    }
+}
+
+/// Node owning `resource_key`. `None` if `ids` is empty.
+pub fn select_owner<'a>(
+    ids: impl IntoIterator<Item = &'a SessionId>,
+    resource_key: &[u8],
+) -> Option<SessionId> {
+    ids.into_iter()
+        .max_by_key(|id| (id.rendezvous_score(resource_key), **id))
+        .copied()
+}
+
+/// The `n` nodes with the highest weight for `resource_key`, owner first (e.g. for replicas)
+pub fn select_owners<'a>(
+    ids: impl IntoIterator<Item = &'a SessionId>,
+    resource_key: &[u8],
+    n: usize,
+) -> Vec<SessionId> {
+    let mut scored: Vec<(u64, SessionId)> = ids
+        .into_iter()
+        .map(|id| (id.rendezvous_score(resource_key), *id))
+        .collect();
+    // ties are broken by the ID so that every node agrees
+    scored.sort_unstable_by(|a, b| b.cmp(a));
+    scored.into_iter().take(n).map(|(_, id)| id).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_rendezvous() {
+        let ids: Vec<SessionId> = (0..5u8).map(|v| SessionId::from([v; 32])).collect();
+        assert!(select_owner(&[], b"room").is_none());
+
+        let owner = select_owner(&ids, b"room1").unwrap();
+        assert_eq!(select_owners(&ids, b"room1", 2)[0], owner);
+        assert_eq!(select_owners(&ids, b"room1", 10).len(), 5);
+        // order independent
+        let reversed: Vec<SessionId> = ids.iter().rev().copied().collect();
+        assert_eq!(select_owner(&reversed, b"room1").unwrap(), owner);
+
+        // removing a node only moves its own keys
+        let keys: Vec<String> = (0..200).map(|i| format!("room{}", i)).collect();
+        let before: HashMap<&String, SessionId> = keys
+            .iter()
+            .map(|k| (k, select_owner(&ids, k.as_bytes()).unwrap()))
+            .collect();
+        let removed = ids[2];
+        let rest: Vec<SessionId> = ids.iter().filter(|v| **v != removed).copied().collect();
+        for k in &keys {
+            let after = select_owner(&rest, k.as_bytes()).unwrap();
+            if before[k] != removed {
+                assert_eq!(after, before[k]);
+            }
+        }
+        // roughly balanced
+        assert!(ids
+            .iter()
+            .all(|id| before.values().filter(|v| *v == id).count() > 10));
+    }
+}
@@ -0,0 +1,133 @@
+//! Sliding-window replay protection for unordered transports, as in IPsec (RFC 4303).
+//!
+//! The window remembers the highest accepted sequence number and a bitmap of the
+//! `size` numbers below it. Late messages inside the window are accepted once;
+//! duplicates and messages older than the window are rejected.
+use crate::errors::{Result, SessionIdError};
+
+/// Default window size in sequence numbers
+pub const DEFAULT_REPLAY_WINDOW_SIZE: usize = 1024;
+
+/// Bitmap over recently accepted sequence numbers
+#[derive(Clone, Debug)]
+pub struct ReplayWindow {
+    // ring bitmap indexed by `seq % size`
+    bits: Vec<u64>,
+    highest: Option<u64>,
+}
+
+impl Default for ReplayWindow {
+    fn default() -> Self {
+        Self::new(DEFAULT_REPLAY_WINDOW_SIZE)
+    }
+}
+
+impl ReplayWindow {
+    /// `size` is rounded up to a multiple of 64
+    pub fn new(size: usize) -> Self {
+        ReplayWindow {
+            bits: vec![0; size.max(1).div_ceil(64)],
+            highest: None,
+        }
+    }
+    /// Number of sequence numbers tracked below the highest one
+    pub fn size(&self) -> usize {
+        self.bits.len() * 64
+    }
+    /// Highest accepted sequence number
+    pub fn highest(&self) -> Option<u64> {
+        self.highest
+    }
+    fn slot(&self, seq: u64) -> (usize, u64) {
+        let i = (seq % self.size() as u64) as usize;
+        (i / 64, 1 << (i % 64))
+    }
+    /// Whether `seq` would be accepted. Does not change the window.
+    pub fn check(&self, seq: u64) -> Result<()> {
+        let Some(highest) = self.highest else {
+            return Ok(());
+        };
+        if seq > highest {
+            return Ok(());
+        }
+        if highest - seq >= self.size() as u64 {
+            return Err(SessionIdError::Replayed(seq));
+        }
+        let (word, mask) = self.slot(seq);
+        if self.bits[word] & mask != 0 {
+            return Err(SessionIdError::Replayed(seq));
+        }
+        Ok(())
+    }
+    /// Accept `seq` and mark it as seen.
+    /// Call only after the message is authenticated, so forged numbers cannot move the window.
+    pub fn check_and_update(&mut self, seq: u64) -> Result<()> {
+        self.check(seq)?;
+        match self.highest {
+            Some(highest) if seq <= highest => {}
+            Some(highest) if seq - highest < self.size() as u64 => {
+                // clear the slots that now belong to the new numbers
+                for s in highest + 1..seq {
+                    let (word, mask) = self.slot(s);
+                    self.bits[word] &= !mask;
+                }
+                self.highest = Some(seq);
+            }
+            _ => {
+                self.bits.fill(0);
+                self.highest = Some(seq);
+            }
+        }
+        let (word, mask) = self.slot(seq);
+        self.bits[word] |= mask;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{new_session_id_pair, ISessionIdPair, SigningSession, VerifyingSession};
+
+    #[test]
+    fn test_replay_window() {
+        let mut w = ReplayWindow::new(100);
+        assert_eq!(w.size(), 128);
+        assert!(w.check_and_update(5).is_ok());
+        assert!(w.check_and_update(3).is_ok());
+        assert!(w.check_and_update(3).is_err());
+        assert!(w.check_and_update(5).is_err());
+        assert!(w.check_and_update(4).is_ok());
+        assert_eq!(w.highest(), Some(5));
+
+        // slide by less than the window; old slots are reused
+        assert!(w.check_and_update(130).is_ok());
+        assert!(w.check(3).is_err());
+        assert!(w.check(5).is_err());
+        assert!(w.check(6).is_ok());
+        assert!(w.check(2).is_err());
+        assert!(w.check(3 + 128).is_ok());
+        assert!(w.check_and_update(129).is_ok());
+        assert!(w.check_and_update(129).is_err());
+
+        // jump past the whole window
+        assert!(w.check_and_update(10_000).is_ok());
+        assert!(w.check(10_000 - 128).is_err());
+        assert!(w.check(10_000 - 127).is_ok());
+        assert!(w.check_and_update(10_000).is_err());
+
+        // with signed sequence numbers delivered out of order
+        let pair = new_session_id_pair().unwrap();
+        let mut signer = SigningSession::new(&pair, b"udp");
+        let mut verifier = VerifyingSession::with_replay_window(pair.get_id(), b"udp", 64);
+        let sigs: Vec<_> = (0..4).map(|_| signer.sign(b"m").unwrap()).collect();
+        for i in [2, 0, 3, 1] {
+            assert_eq!(verifier.verify(b"m", &sigs[i]).unwrap(), i as u64);
+        }
+        assert!(matches!(
+            verifier.verify(b"m", &sigs[1]),
+            Err(SessionIdError::Replayed(1))
+        ));
+        assert_eq!(verifier.last_seq(), Some(3));
+    }
+}
@@ -0,0 +1,189 @@
+//! Stateless address validation tokens for QUIC-style transports.
+//!
+//! The server answers a first packet with a [`RetryToken`] bound to the peer's SessionId and
+//! its address, and keeps no state. The peer echoes the token, and the server validates it with
+//! its own SessionId before committing resources to the connection.
+use crate::errors::{self, Result, SessionIdError};
+use crate::time::{Clock, SystemClock};
+use crate::{
+    ISessionIdPair, SessionId, SessionIdPair, SessionIdPublic, SignatureSet, SESSION_ID_SIZE,
+    SIGNATURE_SET_SIZE,
+};
+use std::net::{IpAddr, SocketAddr};
+
+const RETRY_TOKEN_CONTEXT: &[u8] = b"verse-session-id/retry-token/v1";
+const RETRY_TOKEN_VERSION: u8 = 1;
+/// Size of an encoded token
+pub const RETRY_TOKEN_SIZE: usize = 1 + 8 + SESSION_ID_SIZE + SIGNATURE_SET_SIZE;
+
+fn retry_token_error(msg: &str) -> SessionIdError {
+    SessionIdError::InvalidFormat(format!("retry_token: {}", msg))
+}
+
+fn addr_bytes(addr: &SocketAddr) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(19);
+    match addr.ip() {
+        IpAddr::V4(ip) => {
+            buf.push(4);
+            buf.extend_from_slice(&ip.octets());
+        }
+        IpAddr::V6(ip) => {
+            buf.push(6);
+            buf.extend_from_slice(&ip.octets());
+        }
+    }
+    buf.extend_from_slice(&addr.port().to_be_bytes());
+    buf
+}
+
+/// Claims of a validated retry token. The address is only covered by the signature.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct RetryToken {
+    /// The peer the token was minted for
+    pub session_id: SessionId,
+    /// Seconds since UNIX epoch
+    pub expires_at: u64,
+}
+
+impl RetryToken {
+    fn signed_bytes(&self, remote_addr: &SocketAddr) -> Vec<u8> {
+        let mut buf = vec![RETRY_TOKEN_VERSION];
+        buf.extend_from_slice(&self.expires_at.to_le_bytes());
+        buf.extend_from_slice(self.session_id.as_raw());
+        buf.extend_from_slice(&addr_bytes(remote_addr));
+        buf
+    }
+    /// Token for `session_id` at `remote_addr`, valid for `ttl` seconds, signed by `server`
+    pub fn mint(
+        server: &SessionIdPair,
+        session_id: &SessionId,
+        remote_addr: &SocketAddr,
+        ttl: u64,
+    ) -> Result<Vec<u8>> {
+        Self::mint_at(server, session_id, remote_addr, ttl, &SystemClock)
+    }
+    /// [`mint`](Self::mint) at the time of `clock`
+    pub fn mint_at(
+        server: &SessionIdPair,
+        session_id: &SessionId,
+        remote_addr: &SocketAddr,
+        ttl: u64,
+        clock: &dyn Clock,
+    ) -> Result<Vec<u8>> {
+        let claims = RetryToken {
+            session_id: *session_id,
+            expires_at: clock.now().saturating_add(ttl),
+        };
+        let signature = server.sign([RETRY_TOKEN_CONTEXT, &claims.signed_bytes(remote_addr)])?;
+        let mut buf = Vec::with_capacity(RETRY_TOKEN_SIZE);
+        buf.push(RETRY_TOKEN_VERSION);
+        buf.extend_from_slice(&claims.expires_at.to_le_bytes());
+        buf.extend_from_slice(session_id.as_raw());
+        buf.extend_from_slice(&signature.to_bytes());
+        Ok(buf)
+    }
+    /// Check that `token` was minted by `server` for `remote_addr` and has not expired
+    pub fn validate(
+        server: &SessionId,
+        token: &[u8],
+        remote_addr: &SocketAddr,
+    ) -> Result<RetryToken> {
+        Self::validate_at(server, token, remote_addr, &SystemClock)
+    }
+    /// [`validate`](Self::validate) at the time of `clock`
+    pub fn validate_at(
+        server: &SessionId,
+        token: &[u8],
+        remote_addr: &SocketAddr,
+        clock: &dyn Clock,
+    ) -> Result<RetryToken> {
+        if token.len() != RETRY_TOKEN_SIZE {
+            return Err(errors::invalid_length(RETRY_TOKEN_SIZE, token.len()));
+        }
+        if token[0] != RETRY_TOKEN_VERSION {
+            return Err(retry_token_error("unsupported version"));
+        }
+        let (expires_at, rest) = token[1..].split_at(8);
+        let (session_id, signature) = rest.split_at(SESSION_ID_SIZE);
+        let claims = RetryToken {
+            session_id: SessionId::try_from(session_id)?,
+            expires_at: u64::from_le_bytes(expires_at.try_into().unwrap()),
+        };
+        server.verify(
+            [RETRY_TOKEN_CONTEXT, &claims.signed_bytes(remote_addr)],
+            &SignatureSet::from_bytes(signature.try_into().unwrap()),
+        )?;
+        if clock.now() >= claims.expires_at {
+            return Err(SessionIdError::Expired);
+        }
+        Ok(claims)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{new_session_id_pair, ManualClock};
+
+    #[test]
+    fn test_retry_token() {
+        let server = new_session_id_pair().unwrap();
+        let peer = new_session_id_pair().unwrap().get_id();
+        let addr: SocketAddr = "192.0.2.1:4433".parse().unwrap();
+        let now = 1_700_000_000;
+        let token = RetryToken::mint_at(&server, &peer, &addr, 10, &ManualClock::new(now)).unwrap();
+        assert_eq!(token.len(), RETRY_TOKEN_SIZE);
+
+        let claims =
+            RetryToken::validate_at(&server.get_id(), &token, &addr, &ManualClock::new(now + 9))
+                .unwrap();
+        assert_eq!(claims.session_id, peer);
+        assert_eq!(claims.expires_at, now + 10);
+        assert!(matches!(
+            RetryToken::validate_at(&server.get_id(), &token, &addr, &ManualClock::new(now + 10)),
+            Err(SessionIdError::Expired)
+        ));
+
+        // another address, port or server
+        let moved: SocketAddr = "192.0.2.2:4433".parse().unwrap();
+        assert!(
+            RetryToken::validate_at(&server.get_id(), &token, &moved, &ManualClock::new(now))
+                .is_err()
+        );
+        let port: SocketAddr = "192.0.2.1:4434".parse().unwrap();
+        assert!(
+            RetryToken::validate_at(&server.get_id(), &token, &port, &ManualClock::new(now))
+                .is_err()
+        );
+        let v6: SocketAddr = "[2001:db8::1]:4433".parse().unwrap();
+        assert!(
+            RetryToken::validate_at(&server.get_id(), &token, &v6, &ManualClock::new(now)).is_err()
+        );
+        let other = new_session_id_pair().unwrap();
+        assert!(
+            RetryToken::validate_at(&other.get_id(), &token, &addr, &ManualClock::new(now))
+                .is_err()
+        );
+
+        // a longer lifetime cannot be forged
+        let mut extended = token.clone();
+        extended[1..9].copy_from_slice(&(now + 1000).to_le_bytes());
+        assert!(RetryToken::validate_at(
+            &server.get_id(),
+            &extended,
+            &addr,
+            &ManualClock::new(now)
+        )
+        .is_err());
+        assert!(RetryToken::validate_at(
+            &server.get_id(),
+            &token[1..],
+            &addr,
+            &ManualClock::new(now)
+        )
+        .is_err());
+
+        let token = RetryToken::mint(&server, &peer, &v6, 60).unwrap();
+        assert!(RetryToken::validate(&server.get_id(), &token, &v6).is_ok());
+    }
+}
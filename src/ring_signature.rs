@@ -0,0 +1,221 @@
+//! Ring signatures over session IDs.
+//!
+//! Proves that one of the members of a ring signed a payload without revealing which one,
+//! e.g. for anonymous abuse reports from a room. Uses the Schnorr-based AOS construction
+//! on the Ed25519 group. Signatures grow by 32 bytes per member.
+use crate::errors::{self, Result, SessionIdError, SignatureErrorKind};
+use crate::{ISessionIdPair, SessionId, SessionIdPair, X25519KeyAgreement};
+use curve25519_dalek::constants::ED25519_BASEPOINT_POINT;
+use curve25519_dalek::edwards::{CompressedEdwardsY, EdwardsPoint};
+use curve25519_dalek::scalar::Scalar;
+use ed25519_dalek::{Digest, Sha512};
+
+const RING_CONTEXT: &[u8] = b"verse-session-id/ring/v1";
+
+/// Signature by one member of a ring
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RingSignature {
+    c0: Scalar,
+    s: Vec<Scalar>,
+}
+
+fn ring_prefix(members: &[SessionId], payload: &[u8]) -> Sha512 {
+    let mut hasher = Sha512::new();
+    hasher.update(RING_CONTEXT);
+    hasher.update((members.len() as u64).to_le_bytes());
+    for m in members {
+        hasher.update(m);
+    }
+    hasher.update((payload.len() as u64).to_le_bytes());
+    hasher.update(payload);
+    hasher
+}
+
+fn challenge(prefix: &Sha512, point: &EdwardsPoint) -> Scalar {
+    let hash = prefix.clone().chain(point.compress().as_bytes()).finalize();
+    let mut wide = [0u8; 64];
+    wide.copy_from_slice(&hash);
+    Scalar::from_bytes_mod_order_wide(&wide)
+}
+
+fn random_scalar() -> Result<Scalar> {
+    let mut v = [0u8; 64];
+    getrandom::getrandom(&mut v)?;
+    Ok(Scalar::from_bytes_mod_order_wide(&v))
+}
+
+// Small-order members are known to everyone (their secret is any multiple of the group
+// order) and duplicates hide a smaller ring, so both are rejected.
+fn points(members: &[SessionId]) -> Result<Vec<EdwardsPoint>> {
+    let mut seen = std::collections::HashSet::with_capacity(members.len());
+    members
+        .iter()
+        .map(|m| {
+            if !seen.insert(m) {
+                return Err(SessionIdError::InvalidArgument("duplicate ring member"));
+            }
+            let point = CompressedEdwardsY::from_slice(m.as_ref())
+                .decompress()
+                .ok_or(SessionIdError::Signature(
+                    SignatureErrorKind::MalformedPublicKey,
+                ))?;
+            if point.is_small_order() {
+                return Err(SessionIdError::InvalidArgument("small order ring member"));
+            }
+            Ok(point)
+        })
+        .collect()
+}
+
+/// Sign `payload` as an anonymous member of `members`, which must contain `pair`'s ID
+pub fn ring_sign(
+    pair: &SessionIdPair,
+    members: &[SessionId],
+    payload: &[u8],
+) -> Result<RingSignature> {
+    let my_id = pair.get_id();
+    let me = members
+        .iter()
+        .position(|m| *m == my_id)
+        .ok_or(SessionIdError::InvalidArgument("signer is not a member"))?;
+    let points = points(members)?;
+    let prefix = ring_prefix(members, payload);
+    let n = members.len();
+    let x = Scalar::from_bytes_mod_order(pair.to_x25519_secret());
+
+    let mut c = vec![Scalar::zero(); n];
+    let mut s = vec![Scalar::zero(); n];
+    let u = random_scalar()?;
+    c[(me + 1) % n] = challenge(&prefix, &(u * ED25519_BASEPOINT_POINT));
+    for j in 1..n {
+        let i = (me + j) % n;
+        s[i] = random_scalar()?;
+        c[(i + 1) % n] = challenge(
+            &prefix,
+            &(s[i] * ED25519_BASEPOINT_POINT + c[i] * points[i]),
+        );
+    }
+    s[me] = u - c[me] * x;
+    Ok(RingSignature { c0: c[0], s })
+}
+
+/// Check that one of `members` signed `payload`
+pub fn ring_verify(members: &[SessionId], payload: &[u8], signature: &RingSignature) -> Result<()> {
+    if members.len() != signature.s.len() || members.is_empty() {
+        return Err(errors::invalid_length(members.len(), signature.s.len()));
+    }
+    let points = points(members)?;
+    let prefix = ring_prefix(members, payload);
+    let mut c = signature.c0;
+    for (s, p) in signature.s.iter().zip(&points) {
+        c = challenge(&prefix, &(s * ED25519_BASEPOINT_POINT + c * p));
+    }
+    if c != signature.c0 {
+        return Err(SessionIdError::Signature(
+            SignatureErrorKind::VerificationFailed,
+        ));
+    }
+    Ok(())
+}
+
+impl RingSignature {
+    /// Number of ring members
+    pub fn len(&self) -> usize {
+        self.s.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.s.is_empty()
+    }
+    /// `c0` followed by one response per member (32 bytes each)
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(32 * (self.s.len() + 1));
+        buf.extend_from_slice(self.c0.as_bytes());
+        for s in &self.s {
+            buf.extend_from_slice(s.as_bytes());
+        }
+        buf
+    }
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() < 64 || !bytes.len().is_multiple_of(32) {
+            return Err(errors::invalid_length(
+                bytes.len().next_multiple_of(32).max(64),
+                bytes.len(),
+            ));
+        }
+        let mut scalars = bytes.chunks_exact(32).map(|v| {
+            let mut b = [0u8; 32];
+            b.copy_from_slice(v);
+            Scalar::from_canonical_bytes(b).ok_or(SessionIdError::Signature(
+                SignatureErrorKind::MalformedSignature,
+            ))
+        });
+        let c0 = scalars.next().unwrap()?;
+        Ok(RingSignature {
+            c0,
+            s: scalars.collect::<Result<_>>()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::new_session_id_pair;
+
+    #[test]
+    fn test_ring_signature() {
+        let pairs: Vec<SessionIdPair> = (0..4).map(|_| new_session_id_pair().unwrap()).collect();
+        let ring: Vec<SessionId> = pairs.iter().map(|p| p.get_id()).collect();
+
+        for pair in &pairs {
+            let sig = ring_sign(pair, &ring, b"report:user42").unwrap();
+            assert_eq!(sig.len(), 4);
+            assert!(ring_verify(&ring, b"report:user42", &sig).is_ok());
+            assert!(ring_verify(&ring, b"report:user43", &sig).is_err());
+            assert!(ring_verify(&ring[..3], b"report:user42", &sig).is_err());
+            let mut reordered = ring.clone();
+            reordered.swap(0, 1);
+            assert!(ring_verify(&reordered, b"report:user42", &sig).is_err());
+
+            let decoded = RingSignature::from_bytes(&sig.to_bytes()).unwrap();
+            assert_eq!(decoded, sig);
+        }
+        let outsider = new_session_id_pair().unwrap();
+        assert!(ring_sign(&outsider, &ring, b"x").is_err());
+
+        // single member ring
+        let sig = ring_sign(&pairs[0], &ring[..1], b"x").unwrap();
+        assert!(ring_verify(&ring[..1], b"x", &sig).is_ok());
+        assert!(RingSignature::from_bytes(&[0; 40]).is_err());
+    }
+
+    #[test]
+    fn test_ring_rejects_weak_members() {
+        let pairs: Vec<SessionIdPair> = (0..2).map(|_| new_session_id_pair().unwrap()).collect();
+        let ring: Vec<SessionId> = pairs.iter().map(|p| p.get_id()).collect();
+        let sig = ring_sign(&pairs[0], &ring, b"x").unwrap();
+
+        // the identity point has small order and anyone can sign for it
+        let mut identity = [0u8; 32];
+        identity[0] = 1;
+        let weak = vec![ring[0], SessionId::from(identity)];
+        assert!(matches!(
+            ring_sign(&pairs[0], &weak, b"x"),
+            Err(SessionIdError::InvalidArgument(_))
+        ));
+        assert!(matches!(
+            ring_verify(&weak, b"x", &sig),
+            Err(SessionIdError::InvalidArgument(_))
+        ));
+
+        let duplicate = vec![ring[0], ring[0]];
+        assert!(matches!(
+            ring_sign(&pairs[0], &duplicate, b"x"),
+            Err(SessionIdError::InvalidArgument(_))
+        ));
+        assert!(matches!(
+            ring_verify(&duplicate, b"x", &sig),
+            Err(SessionIdError::InvalidArgument(_))
+        ));
+    }
+}
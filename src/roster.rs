@@ -0,0 +1,421 @@
+//! Compact encoding of sets of session IDs, e.g. "who's in this world" snapshots, and
+//! host-signed rosters that clients can trust when relayed by untrusted peers.
+//!
+//! The IDs are sorted and deduplicated, then each one after the first stores only the
+//! number of leading bytes it shares with the previous ID and the remaining bytes:
+//!
+//! | field | size |
+//! |-------|------|
+//! | version (1) | 1 |
+//! | count | 4 (LE) |
+//! | first ID | 32 |
+//! | per further ID: shared prefix length (0..=31) | 1 |
+//! | per further ID: remaining bytes | 32 - shared |
+//!
+//! Random keys share about log256(n) leading bytes with their sorted neighbour, which roughly
+//! pays for the length byte, so a roster is about 32 bytes per ID against 47 for a JSON array
+//! of base64 strings. IDs clustered by prefix (e.g. vanity or bucketed IDs) compress further.
+//!
+//! The encoding is canonical: decoding rejects unsorted or duplicated IDs.
+//!
+//! A host publishes a [`SignedRoster`] snapshot, then a [`RosterDiff`] of joins and leaves
+//! for every change, each with the next sequence number. Clients keep a [`Roster`] and
+//! [`Roster::apply`] the diffs in order; after a gap they fetch a new snapshot.
+use crate::encoding::{write_str, Reader};
+use crate::errors::{Result, SessionIdError};
+use crate::{
+    ISessionIdPair, RawSessionId, SessionId, SessionIdPair, SessionIdPublic, SignatureSet,
+    SESSION_ID_SIZE, SIGNATURE_SET_SIZE,
+};
+
+const ROSTER_VERSION: u8 = 1;
+const ROSTER_CONTEXT: &[u8] = b"verse-session-id/roster/v1";
+const SNAPSHOT_TAG: u8 = 1;
+const DIFF_TAG: u8 = 2;
+
+fn roster_error(msg: &str) -> SessionIdError {
+    SessionIdError::InvalidFormat(format!("roster: {}", msg))
+}
+
+fn shared_prefix_len(a: &[u8], b: &[u8]) -> usize {
+    a.iter().zip(b).take_while(|(a, b)| a == b).count()
+}
+
+/// Encode `ids` as a sorted, prefix-compressed roster. Duplicates are dropped.
+pub fn encode_roster(ids: &[SessionId]) -> Vec<u8> {
+    let mut sorted = ids.to_vec();
+    sorted.sort_unstable();
+    sorted.dedup();
+
+    let mut buf = Vec::with_capacity(5 + sorted.len() * SESSION_ID_SIZE);
+    buf.push(ROSTER_VERSION);
+    buf.extend_from_slice(&(sorted.len() as u32).to_le_bytes());
+    let mut prev: Option<&SessionId> = None;
+    for id in &sorted {
+        let shared = prev.map_or(0, |p| shared_prefix_len(p.as_raw(), id.as_raw()));
+        if prev.is_some() {
+            buf.push(shared as u8);
+        }
+        buf.extend_from_slice(&id.as_raw()[shared..]);
+        prev = Some(id);
+    }
+    buf
+}
+
+/// Decode a roster produced by [`encode_roster`]. The IDs are returned sorted.
+pub fn decode_roster(bytes: &[u8]) -> Result<Vec<SessionId>> {
+    let mut r = Reader(bytes);
+    if r.read(1)?[0] != ROSTER_VERSION {
+        return Err(roster_error("unsupported version"));
+    }
+    let count = u32::from_le_bytes(r.read_array()?) as usize;
+    // every ID takes at least 2 bytes, so a count beyond that is garbage
+    if count > 0 && count - 1 > r.0.len().saturating_sub(SESSION_ID_SIZE) / 2 {
+        return Err(roster_error("count exceeds data"));
+    }
+    let mut ids: Vec<SessionId> = Vec::with_capacity(count);
+    let mut raw: RawSessionId = [0; SESSION_ID_SIZE];
+    for i in 0..count {
+        let shared = if i == 0 { 0 } else { r.read(1)?[0] as usize };
+        if shared >= SESSION_ID_SIZE {
+            return Err(roster_error("invalid prefix length"));
+        }
+        raw[shared..].copy_from_slice(r.read(SESSION_ID_SIZE - shared)?);
+        let id = SessionId::from(raw);
+        if ids
+            .last()
+            .is_some_and(|prev| *prev >= id || shared_prefix_len(prev.as_raw(), &raw) != shared)
+        {
+            return Err(roster_error("not canonical"));
+        }
+        ids.push(id);
+    }
+    if !r.0.is_empty() {
+        return Err(roster_error("trailing bytes"));
+    }
+    Ok(ids)
+}
+
+fn write_roster(buf: &mut Vec<u8>, ids: &[SessionId]) {
+    let roster = encode_roster(ids);
+    buf.extend_from_slice(&(roster.len() as u32).to_le_bytes());
+    buf.extend_from_slice(&roster);
+}
+
+fn read_roster(r: &mut Reader) -> Result<Vec<SessionId>> {
+    let n = u32::from_le_bytes(r.read_array()?) as usize;
+    decode_roster(r.read(n)?)
+}
+
+fn sorted(ids: &[SessionId]) -> Vec<SessionId> {
+    let mut v = ids.to_vec();
+    v.sort_unstable();
+    v.dedup();
+    v
+}
+
+/// Membership of a world at a sequence number. Members are kept sorted.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Roster {
+    world_id: String,
+    seq: u64,
+    members: Vec<SessionId>,
+}
+
+impl Roster {
+    pub fn new(world_id: impl Into<String>, seq: u64, members: &[SessionId]) -> Self {
+        Roster {
+            world_id: world_id.into(),
+            seq,
+            members: sorted(members),
+        }
+    }
+    pub fn world_id(&self) -> &str {
+        &self.world_id
+    }
+    pub fn seq(&self) -> u64 {
+        self.seq
+    }
+    /// Members, sorted
+    pub fn members(&self) -> &[SessionId] {
+        &self.members
+    }
+    pub fn contains(&self, id: &SessionId) -> bool {
+        self.members.binary_search(id).is_ok()
+    }
+    fn snapshot_body(&self) -> Result<Vec<u8>> {
+        let mut buf = vec![ROSTER_VERSION, SNAPSHOT_TAG];
+        write_str(&mut buf, &self.world_id)?;
+        buf.extend_from_slice(&self.seq.to_le_bytes());
+        write_roster(&mut buf, &self.members);
+        Ok(buf)
+    }
+    /// Snapshot signed by `host`
+    pub fn sign(&self, host: &SessionIdPair) -> Result<SignedRoster> {
+        Ok(SignedRoster {
+            signature: host.sign([ROSTER_CONTEXT, &self.snapshot_body()?])?,
+            roster: self.clone(),
+        })
+    }
+    /// Host side: apply `joined` and `left` as the next sequence number and return the signed diff
+    pub fn sign_update(
+        &mut self,
+        host: &SessionIdPair,
+        joined: &[SessionId],
+        left: &[SessionId],
+    ) -> Result<RosterDiff> {
+        let seq = self
+            .seq
+            .checked_add(1)
+            .ok_or(SessionIdError::InvalidClaim("seq"))?;
+        let diff = RosterDiff::sign(host, self.world_id.clone(), seq, joined, left)?;
+        self.apply(&diff, &host.get_id())?;
+        Ok(diff)
+    }
+    /// Client side: verify `diff` against `host` and apply it.
+    /// It must be the next sequence number, and only add non-members and remove members.
+    /// The roster is unchanged on error.
+    pub fn apply(&mut self, diff: &RosterDiff, host: &SessionId) -> Result<()> {
+        if diff.world_id != self.world_id {
+            return Err(SessionIdError::InvalidClaim("world_id"));
+        }
+        if diff.seq <= self.seq {
+            return Err(SessionIdError::Replayed(diff.seq));
+        }
+        if diff.seq != self.seq + 1 {
+            return Err(SessionIdError::InvalidClaim("seq"));
+        }
+        diff.verify(host)?;
+        if diff.joined.iter().any(|id| self.contains(id))
+            || diff.left.iter().any(|id| !self.contains(id))
+            || diff
+                .joined
+                .iter()
+                .any(|id| diff.left.binary_search(id).is_ok())
+        {
+            return Err(SessionIdError::InvalidClaim("members"));
+        }
+        self.members
+            .retain(|id| diff.left.binary_search(id).is_err());
+        self.members.extend_from_slice(&diff.joined);
+        self.members.sort_unstable();
+        self.seq = diff.seq;
+        Ok(())
+    }
+}
+
+/// [`Roster`] snapshot signed by the host of the world
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SignedRoster {
+    roster: Roster,
+    signature: SignatureSet,
+}
+
+impl SignedRoster {
+    /// Check the host signature and return the roster
+    pub fn verify(&self, host: &SessionId) -> Result<Roster> {
+        host.verify(
+            [ROSTER_CONTEXT, &self.roster.snapshot_body()?],
+            &self.signature,
+        )?;
+        Ok(self.roster.clone())
+    }
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let mut buf = self.roster.snapshot_body()?;
+        buf.extend_from_slice(&self.signature.to_bytes());
+        Ok(buf)
+    }
+    /// Parse without verifying
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let mut r = Reader(bytes);
+        if r.read(1)?[0] != ROSTER_VERSION {
+            return Err(roster_error("unsupported version"));
+        }
+        if r.read(1)?[0] != SNAPSHOT_TAG {
+            return Err(roster_error("not a snapshot"));
+        }
+        let world_id = r.read_str()?;
+        let seq = u64::from_le_bytes(r.read_array()?);
+        let members = read_roster(&mut r)?;
+        let signature = SignatureSet::from_bytes(&r.read_array::<SIGNATURE_SET_SIZE>()?);
+        if !r.0.is_empty() {
+            return Err(roster_error("trailing bytes"));
+        }
+        Ok(SignedRoster {
+            roster: Roster {
+                world_id,
+                seq,
+                members,
+            },
+            signature,
+        })
+    }
+}
+
+/// Joins and leaves signed by the host of the world, taking a roster to `seq`
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RosterDiff {
+    world_id: String,
+    seq: u64,
+    joined: Vec<SessionId>,
+    left: Vec<SessionId>,
+    signature: SignatureSet,
+}
+
+impl RosterDiff {
+    pub fn sign(
+        host: &SessionIdPair,
+        world_id: impl Into<String>,
+        seq: u64,
+        joined: &[SessionId],
+        left: &[SessionId],
+    ) -> Result<Self> {
+        let mut diff = RosterDiff {
+            world_id: world_id.into(),
+            seq,
+            joined: sorted(joined),
+            left: sorted(left),
+            signature: SignatureSet::from_bytes(&[0; SIGNATURE_SET_SIZE]),
+        };
+        diff.signature = host.sign([ROSTER_CONTEXT, &diff.body()?])?;
+        Ok(diff)
+    }
+    pub fn world_id(&self) -> &str {
+        &self.world_id
+    }
+    pub fn seq(&self) -> u64 {
+        self.seq
+    }
+    /// Joined IDs, sorted
+    pub fn joined(&self) -> &[SessionId] {
+        &self.joined
+    }
+    /// Departed IDs, sorted
+    pub fn left(&self) -> &[SessionId] {
+        &self.left
+    }
+    fn body(&self) -> Result<Vec<u8>> {
+        let mut buf = vec![ROSTER_VERSION, DIFF_TAG];
+        write_str(&mut buf, &self.world_id)?;
+        buf.extend_from_slice(&self.seq.to_le_bytes());
+        write_roster(&mut buf, &self.joined);
+        write_roster(&mut buf, &self.left);
+        Ok(buf)
+    }
+    pub fn verify(&self, host: &SessionId) -> Result<()> {
+        host.verify([ROSTER_CONTEXT, &self.body()?], &self.signature)
+    }
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let mut buf = self.body()?;
+        buf.extend_from_slice(&self.signature.to_bytes());
+        Ok(buf)
+    }
+    /// Parse without verifying
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let mut r = Reader(bytes);
+        if r.read(1)?[0] != ROSTER_VERSION {
+            return Err(roster_error("unsupported version"));
+        }
+        if r.read(1)?[0] != DIFF_TAG {
+            return Err(roster_error("not a diff"));
+        }
+        let world_id = r.read_str()?;
+        let seq = u64::from_le_bytes(r.read_array()?);
+        let joined = read_roster(&mut r)?;
+        let left = read_roster(&mut r)?;
+        let signature = SignatureSet::from_bytes(&r.read_array::<SIGNATURE_SET_SIZE>()?);
+        if !r.0.is_empty() {
+            return Err(roster_error("trailing bytes"));
+        }
+        Ok(RosterDiff {
+            world_id,
+            seq,
+            joined,
+            left,
+            signature,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{new_session_id_pair, ISessionIdPair};
+
+    #[test]
+    fn test_roster() {
+        let mut ids: Vec<SessionId> = (0..500)
+            .map(|_| new_session_id_pair().unwrap().get_id())
+            .collect();
+        ids.push(ids[0]);
+        let encoded = encode_roster(&ids);
+        let json: Vec<String> = ids.iter().map(|id| id.to_string()).collect();
+        let json_len = serde_json::to_vec(&json).unwrap().len();
+        assert!(encoded.len() < 5 + 500 * (SESSION_ID_SIZE + 1));
+        assert!(encoded.len() * 10 < json_len * 7);
+        let decoded = decode_roster(&encoded).unwrap();
+        ids.sort();
+        ids.dedup();
+        assert_eq!(decoded, ids);
+
+        let close = [
+            SessionId::from([1; 32]),
+            SessionId::from({
+                let mut v = [1; 32];
+                v[31] = 2;
+                v
+            }),
+        ];
+        let encoded = encode_roster(&close);
+        assert_eq!(encoded.len(), 5 + 32 + 2);
+        assert_eq!(decode_roster(&encoded).unwrap(), close);
+
+        assert!(decode_roster(&encode_roster(&[])).unwrap().is_empty());
+        let mut bad = encoded.clone();
+        bad[37] = 32;
+        assert!(decode_roster(&bad).is_err());
+        let mut bad = encoded.clone();
+        bad[38] = 0;
+        assert!(decode_roster(&bad).is_err());
+        assert!(decode_roster(&encoded[..encoded.len() - 1]).is_err());
+        assert!(decode_roster(&[ROSTER_VERSION, 0xff, 0xff, 0xff, 0xff]).is_err());
+    }
+
+    #[test]
+    fn test_signed_roster() {
+        let host = new_session_id_pair().unwrap();
+        let [a, b, c] = [1u8, 2, 3].map(|v| SessionId::from([v; 32]));
+
+        let mut hosted = Roster::new("lobby", 1, &[b, a]);
+        let snapshot = hosted.sign(&host).unwrap();
+        let relayed = SignedRoster::from_bytes(&snapshot.to_bytes().unwrap()).unwrap();
+        let mut client = relayed.verify(&host.get_id()).unwrap();
+        assert_eq!(client.members(), &[a, b]);
+        assert_eq!(client.seq(), 1);
+        let mallory = new_session_id_pair().unwrap();
+        assert!(relayed.verify(&mallory.get_id()).is_err());
+
+        let diff = hosted.sign_update(&host, &[c], &[a]).unwrap();
+        let diff = RosterDiff::from_bytes(&diff.to_bytes().unwrap()).unwrap();
+        client.apply(&diff, &host.get_id()).unwrap();
+        assert_eq!(client, hosted);
+        assert_eq!(client.members(), &[b, c]);
+        assert!(matches!(
+            client.apply(&diff, &host.get_id()),
+            Err(SessionIdError::Replayed(2))
+        ));
+
+        // gaps, forgeries and inconsistent diffs leave the roster unchanged
+        let gap = RosterDiff::sign(&host, "lobby", 4, &[a], &[]).unwrap();
+        assert!(client.apply(&gap, &host.get_id()).is_err());
+        let forged = RosterDiff::sign(&mallory, "lobby", 3, &[a], &[]).unwrap();
+        assert!(client.apply(&forged, &host.get_id()).is_err());
+        let other_world = RosterDiff::sign(&host, "hall", 3, &[a], &[]).unwrap();
+        assert!(client.apply(&other_world, &host.get_id()).is_err());
+        let rejoin = RosterDiff::sign(&host, "lobby", 3, &[b], &[]).unwrap();
+        assert!(client.apply(&rejoin, &host.get_id()).is_err());
+        assert!(hosted.sign_update(&host, &[], &[a]).is_err());
+        assert_eq!(client, hosted);
+        assert!(SignedRoster::from_bytes(&diff.to_bytes().unwrap()).is_err());
+    }
+}
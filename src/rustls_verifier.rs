@@ -0,0 +1,227 @@
+//! Identity-pinned TLS with rustls. Enabled with the `rustls` feature.
+//!
+//! [`SessionIdCertVerifier`] accepts a peer only if its certificate carries the Ed25519
+//! key of an expected SessionId, e.g. one made by
+//! [`X509Signer::to_self_signed_cert`](crate::X509Signer::to_self_signed_cert).
+//! Names, validity and issuers are not checked: the key is the identity, and the
+//! handshake signature proves possession of it.
+use crate::errors::{self, SignatureErrorKind};
+use crate::{SessionId, SessionIdSet};
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use rustls::server::danger::{ClientCertVerified, ClientCertVerifier};
+use rustls::{CertificateError, DigitallySignedStruct, DistinguishedName, Error, SignatureScheme};
+use std::sync::{Arc, RwLock};
+
+/// Set of SessionIds accepted by a [`SessionIdCertVerifier`]. Can be updated while in use.
+#[derive(Debug, Default)]
+pub struct TrustStore {
+    ids: RwLock<SessionIdSet>,
+}
+
+impl TrustStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Returns false if the ID was already trusted
+    pub fn insert(&self, id: SessionId) -> bool {
+        self.ids.write().unwrap().insert(id)
+    }
+    /// Returns false if the ID was not trusted
+    pub fn remove(&self, id: &SessionId) -> bool {
+        self.ids.write().unwrap().remove(id)
+    }
+    pub fn contains(&self, id: &SessionId) -> bool {
+        self.ids.read().unwrap().contains(id)
+    }
+}
+
+impl FromIterator<SessionId> for TrustStore {
+    fn from_iter<T: IntoIterator<Item = SessionId>>(iter: T) -> Self {
+        TrustStore {
+            ids: RwLock::new(iter.into_iter().collect()),
+        }
+    }
+}
+
+/// rustls server and client certificate verifier pinned to SessionIds
+#[derive(Debug)]
+pub struct SessionIdCertVerifier {
+    trusted: Arc<TrustStore>,
+}
+
+impl SessionIdCertVerifier {
+    /// Accept only `expected`
+    pub fn new(expected: SessionId) -> Self {
+        Self::with_trust_store(Arc::new([expected].into_iter().collect()))
+    }
+    /// Accept any ID in `trusted`
+    pub fn with_trust_store(trusted: Arc<TrustStore>) -> Self {
+        SessionIdCertVerifier { trusted }
+    }
+
+    fn check_cert(&self, cert: &CertificateDer<'_>) -> Result<SessionId, Error> {
+        let id = SessionId::from_x509_cert(cert)
+            .map_err(|_| Error::InvalidCertificate(CertificateError::BadEncoding))?;
+        if !self.trusted.contains(&id) {
+            return Err(Error::InvalidCertificate(
+                CertificateError::ApplicationVerificationFailure,
+            ));
+        }
+        Ok(id)
+    }
+    fn check_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, Error> {
+        if dss.scheme != SignatureScheme::ED25519 {
+            return Err(Error::PeerMisbehaved(
+                rustls::PeerMisbehaved::SignedHandshakeWithUnadvertisedSigScheme,
+            ));
+        }
+        let id = self.check_cert(cert)?;
+        let verify = || {
+            let pk = ed25519_dalek::PublicKey::from_bytes(id.as_ref())
+                .map_err(errors::signature(SignatureErrorKind::MalformedPublicKey))?;
+            let signature = ed25519_dalek::Signature::from_bytes(dss.signature())
+                .map_err(errors::signature(SignatureErrorKind::MalformedSignature))?;
+            pk.verify_strict(message, &signature)
+                .map_err(errors::signature(SignatureErrorKind::VerificationFailed))
+        };
+        verify()
+            .map(|_| HandshakeSignatureValid::assertion())
+            .map_err(|_| Error::InvalidCertificate(CertificateError::BadSignature))
+    }
+}
+
+impl ServerCertVerifier for SessionIdCertVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, Error> {
+        self.check_cert(end_entity)
+            .map(|_| ServerCertVerified::assertion())
+    }
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, Error> {
+        self.check_signature(message, cert, dss)
+    }
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, Error> {
+        self.check_signature(message, cert, dss)
+    }
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        vec![SignatureScheme::ED25519]
+    }
+}
+
+impl ClientCertVerifier for SessionIdCertVerifier {
+    fn root_hint_subjects(&self) -> &[DistinguishedName] {
+        &[]
+    }
+    fn verify_client_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _now: UnixTime,
+    ) -> Result<ClientCertVerified, Error> {
+        self.check_cert(end_entity)
+            .map(|_| ClientCertVerified::assertion())
+    }
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, Error> {
+        self.check_signature(message, cert, dss)
+    }
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, Error> {
+        self.check_signature(message, cert, dss)
+    }
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        vec![SignatureScheme::ED25519]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{new_session_id_pair, ISessionIdPair, X509Signer};
+    use rustls::internal::msgs::codec::Codec;
+
+    fn dss(scheme: u16, signature: &[u8]) -> DigitallySignedStruct {
+        let mut buf = scheme.to_be_bytes().to_vec();
+        buf.extend_from_slice(&(signature.len() as u16).to_be_bytes());
+        buf.extend_from_slice(signature);
+        DigitallySignedStruct::read_bytes(&buf).unwrap()
+    }
+
+    #[test]
+    fn test_session_id_cert_verifier() {
+        let pair = new_session_id_pair().unwrap();
+        let cert = pair.to_self_signed_cert(&["node1"]).unwrap();
+        let cert = CertificateDer::from(cert.cert_der);
+        let name = ServerName::try_from("node1").unwrap();
+        let now = UnixTime::now();
+
+        let verifier = SessionIdCertVerifier::new(pair.get_id());
+        assert!(verifier
+            .verify_server_cert(&cert, &[], &name, &[], now)
+            .is_ok());
+        assert!(verifier.verify_client_cert(&cert, &[], now).is_ok());
+
+        let message = b"TLS 1.3, server CertificateVerify";
+        let signature = ed25519_dalek::Signer::sign(&pair, message).to_bytes();
+        let good = dss(0x0807, &signature);
+        assert!(
+            ServerCertVerifier::verify_tls13_signature(&verifier, message, &cert, &good).is_ok()
+        );
+        assert!(
+            ServerCertVerifier::verify_tls13_signature(&verifier, b"other", &cert, &good).is_err()
+        );
+        // wrong scheme
+        let ecdsa = dss(0x0403, &signature);
+        assert!(
+            ClientCertVerifier::verify_tls13_signature(&verifier, message, &cert, &ecdsa).is_err()
+        );
+
+        // unknown identity
+        let other = new_session_id_pair().unwrap();
+        let verifier = SessionIdCertVerifier::new(other.get_id());
+        assert!(verifier
+            .verify_server_cert(&cert, &[], &name, &[], now)
+            .is_err());
+        assert!(verifier
+            .verify_server_cert(&CertificateDer::from(vec![0x30, 0]), &[], &name, &[], now)
+            .is_err());
+
+        // trust store updated at runtime
+        let store = Arc::new(TrustStore::new());
+        let verifier = SessionIdCertVerifier::with_trust_store(store.clone());
+        assert!(verifier.verify_client_cert(&cert, &[], now).is_err());
+        store.insert(pair.get_id());
+        assert!(verifier.verify_client_cert(&cert, &[], now).is_ok());
+        store.remove(&pair.get_id());
+        assert!(verifier.verify_client_cert(&cert, &[], now).is_err());
+    }
+}
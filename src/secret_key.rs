@@ -0,0 +1,78 @@
+//! Secret seed of a keypair that cannot be printed by accident.
+use crate::errors::Result;
+use crate::{session_id_pair_from_secret, SessionIdPair, SECRET_KEY_SIZE};
+use std::fmt;
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+/// 32-byte secret seed of a SessionIdPair.
+/// `Debug` prints `<REDACTED>` and the bytes are zeroized on drop.
+#[derive(Clone, Eq, PartialEq)]
+pub struct SecretSessionKey([u8; SECRET_KEY_SIZE]);
+
+impl SecretSessionKey {
+    pub fn new(seed: [u8; SECRET_KEY_SIZE]) -> Self {
+        SecretSessionKey(seed)
+    }
+    /// Random secret key
+    pub fn generate() -> Result<Self> {
+        let mut key = SecretSessionKey([0; SECRET_KEY_SIZE]);
+        getrandom::getrandom(&mut key.0)?;
+        Ok(key)
+    }
+    /// The raw seed. Make sure it does not end up in logs or unencrypted storage.
+    pub fn expose_secret(&self) -> &[u8; SECRET_KEY_SIZE] {
+        &self.0
+    }
+    pub(crate) fn expose_secret_mut(&mut self) -> &mut [u8; SECRET_KEY_SIZE] {
+        &mut self.0
+    }
+    pub fn to_session_id_pair(&self) -> Result<SessionIdPair> {
+        session_id_pair_from_secret(self)
+    }
+}
+
+impl From<[u8; SECRET_KEY_SIZE]> for SecretSessionKey {
+    fn from(seed: [u8; SECRET_KEY_SIZE]) -> Self {
+        SecretSessionKey(seed)
+    }
+}
+
+impl fmt::Debug for SecretSessionKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("<REDACTED>")
+    }
+}
+
+impl Drop for SecretSessionKey {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+impl ZeroizeOnDrop for SecretSessionKey {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ISessionIdPair, SessionIdPairSecret};
+
+    #[test]
+    fn test_secret_session_key() {
+        let key = SecretSessionKey::generate().unwrap();
+        assert_eq!(format!("{:?}", key), "<REDACTED>");
+        assert_eq!(format!("{:?}", Some(&key)), "Some(<REDACTED>)");
+
+        let pair = key.to_session_id_pair().unwrap();
+        assert_eq!(pair.secret_key(), key);
+        assert_eq!(pair.secret.as_bytes(), key.expose_secret());
+        assert_eq!(
+            SecretSessionKey::from([7; 32])
+                .to_session_id_pair()
+                .unwrap()
+                .get_id(),
+            SecretSessionKey::new([7; 32])
+                .to_session_id_pair()
+                .unwrap()
+                .get_id()
+        );
+    }
+}
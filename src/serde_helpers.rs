@@ -0,0 +1,248 @@
+//! `#[serde(with = ...)]` modules for [`SessionId`], [`RawSessionId`] and [`SignatureSet`]
+//! fields of downstream structs, one per encoding:
+//!
+//! | module | encoding |
+//! |--------|----------|
+//! | [`base64`] | standard base64 string |
+//! | [`hex`] | lowercase hex string (uppercase accepted) |
+//! | [`raw_bytes`] | bytes (`serialize_bytes`) |
+//! | [`option_base64`] | `Option` of a base64 string |
+//!
+//! ```rust,ignore
+//! #[derive(Serialize, Deserialize)]
+//! struct Presence {
+//!     #[serde(with = "verse_session_id::serde_helpers::hex")]
+//!     session_id: SessionId,
+//!     #[serde(with = "verse_session_id::serde_helpers::option_base64")]
+//!     signature: Option<SignatureSet>,
+//! }
+//! ```
+use crate::errors::{Result, SessionIdError};
+use crate::{RawSessionId, SessionId, SignatureSet, SIGNATURE_SET_SIZE};
+use serde::de;
+use std::fmt;
+
+/// Fixed-size value with a byte encoding, usable with the modules of [`serde_helpers`](self)
+pub trait SerdeBytes: Sized {
+    /// Call `f` with the byte encoding
+    fn with_bytes<R>(&self, f: impl FnOnce(&[u8]) -> R) -> R;
+    fn from_bytes_slice(bytes: &[u8]) -> Result<Self>;
+}
+
+impl SerdeBytes for SessionId {
+    fn with_bytes<R>(&self, f: impl FnOnce(&[u8]) -> R) -> R {
+        f(self.as_raw())
+    }
+    fn from_bytes_slice(bytes: &[u8]) -> Result<Self> {
+        SessionId::try_from(bytes)
+    }
+}
+impl SerdeBytes for RawSessionId {
+    fn with_bytes<R>(&self, f: impl FnOnce(&[u8]) -> R) -> R {
+        f(self)
+    }
+    fn from_bytes_slice(bytes: &[u8]) -> Result<Self> {
+        Ok(*SessionId::try_from(bytes)?.as_raw())
+    }
+}
+impl SerdeBytes for SignatureSet {
+    fn with_bytes<R>(&self, f: impl FnOnce(&[u8]) -> R) -> R {
+        f(&self.to_bytes())
+    }
+    fn from_bytes_slice(bytes: &[u8]) -> Result<Self> {
+        SignatureSet::try_from(bytes)
+    }
+}
+
+fn hex_encode(bytes: &[u8]) -> String {
+    const DIGITS: &[u8; 16] = b"0123456789abcdef";
+    bytes
+        .iter()
+        .flat_map(|b| [DIGITS[(b >> 4) as usize], DIGITS[(b & 0xf) as usize]])
+        .map(char::from)
+        .collect()
+}
+
+fn hex_decode(s: &str) -> Result<Vec<u8>> {
+    let nibble = |c: u8| {
+        (c as char)
+            .to_digit(16)
+            .map(|v| v as u8)
+            .ok_or_else(|| SessionIdError::InvalidFormat("hex: invalid digit".to_string()))
+    };
+    let s = s.as_bytes();
+    if !s.len().is_multiple_of(2) {
+        return Err(SessionIdError::InvalidFormat(
+            "hex: odd number of digits".to_string(),
+        ));
+    }
+    s.chunks(2)
+        .map(|pair| Ok((nibble(pair[0])? << 4) | nibble(pair[1])?))
+        .collect()
+}
+
+/// Standard base64 string in every format
+pub mod base64 {
+    use super::SerdeBytes;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<T: SerdeBytes, S: Serializer>(
+        v: &T,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        v.with_bytes(|b| serializer.serialize_str(&::base64::encode(b)))
+    }
+    pub fn deserialize<'de, T: SerdeBytes, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<T, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        let bytes = ::base64::decode(s).map_err(serde::de::Error::custom)?;
+        T::from_bytes_slice(&bytes).map_err(serde::de::Error::custom)
+    }
+}
+
+/// Lowercase hex string in every format
+pub mod hex {
+    use super::{hex_decode, hex_encode, SerdeBytes};
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<T: SerdeBytes, S: Serializer>(
+        v: &T,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        v.with_bytes(|b| serializer.serialize_str(&hex_encode(b)))
+    }
+    pub fn deserialize<'de, T: SerdeBytes, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<T, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        let bytes = hex_decode(&s).map_err(serde::de::Error::custom)?;
+        T::from_bytes_slice(&bytes).map_err(serde::de::Error::custom)
+    }
+}
+
+/// Raw bytes in every format. Sequences of integers are accepted when deserializing.
+pub mod raw_bytes {
+    use super::{BytesVisitor, SerdeBytes};
+    use serde::{Deserializer, Serializer};
+
+    pub fn serialize<T: SerdeBytes, S: Serializer>(
+        v: &T,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        v.with_bytes(|b| serializer.serialize_bytes(b))
+    }
+    pub fn deserialize<'de, T: SerdeBytes, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<T, D::Error> {
+        let bytes = deserializer.deserialize_bytes(BytesVisitor)?;
+        T::from_bytes_slice(&bytes).map_err(serde::de::Error::custom)
+    }
+}
+
+/// `Option` of a standard base64 string; `None` is serialized as none (`null` in JSON)
+pub mod option_base64 {
+    use super::SerdeBytes;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<T: SerdeBytes, S: Serializer>(
+        v: &Option<T>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match v {
+            Some(v) => v.with_bytes(|b| serializer.serialize_some(&::base64::encode(b))),
+            None => serializer.serialize_none(),
+        }
+    }
+    pub fn deserialize<'de, T: SerdeBytes, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<T>, D::Error> {
+        let Some(s) = Option::<String>::deserialize(deserializer)? else {
+            return Ok(None);
+        };
+        let bytes = ::base64::decode(s).map_err(serde::de::Error::custom)?;
+        T::from_bytes_slice(&bytes)
+            .map(Some)
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+struct BytesVisitor;
+
+impl<'de> de::Visitor<'de> for BytesVisitor {
+    type Value = Vec<u8>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "at most {} bytes", SIGNATURE_SET_SIZE)
+    }
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> std::result::Result<Self::Value, E> {
+        Ok(v.to_vec())
+    }
+    fn visit_byte_buf<E: de::Error>(self, v: Vec<u8>) -> std::result::Result<Self::Value, E> {
+        Ok(v)
+    }
+    fn visit_seq<A: de::SeqAccess<'de>>(
+        self,
+        mut seq: A,
+    ) -> std::result::Result<Self::Value, A::Error> {
+        let mut res = Vec::with_capacity(SIGNATURE_SET_SIZE);
+        while let Some(b) = seq.next_element()? {
+            if res.len() == SIGNATURE_SET_SIZE {
+                return Err(de::Error::invalid_length(res.len() + 1, &self));
+            }
+            res.push(b);
+        }
+        Ok(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Record {
+        #[serde(with = "crate::serde_helpers::base64")]
+        a: SessionId,
+        #[serde(with = "crate::serde_helpers::hex")]
+        b: RawSessionId,
+        #[serde(with = "crate::serde_helpers::raw_bytes")]
+        c: SignatureSet,
+        #[serde(with = "crate::serde_helpers::option_base64")]
+        d: Option<SessionId>,
+        #[serde(with = "crate::serde_helpers::option_base64")]
+        e: Option<SignatureSet>,
+    }
+
+    #[test]
+    fn test_serde_helpers() {
+        let record = Record {
+            a: SessionId::from([1; 32]),
+            b: [0xab; 32],
+            c: SignatureSet::from([3; SIGNATURE_SET_SIZE]),
+            d: Some(SessionId::from([4; 32])),
+            e: None,
+        };
+        let json = serde_json::to_value(&record).unwrap();
+        assert_eq!(json["a"], ::base64::encode([1; 32]));
+        assert_eq!(json["b"], "ab".repeat(32));
+        assert_eq!(json["c"].as_array().unwrap().len(), SIGNATURE_SET_SIZE);
+        assert_eq!(json["d"], ::base64::encode([4; 32]));
+        assert!(json["e"].is_null());
+        assert_eq!(
+            serde_json::from_value::<Record>(json.clone()).unwrap(),
+            record
+        );
+
+        let mut upper = json.clone();
+        upper["b"] = "AB".repeat(32).into();
+        assert_eq!(serde_json::from_value::<Record>(upper).unwrap(), record);
+        let mut bad = json;
+        bad["b"] = "ab".repeat(31).into();
+        assert!(serde_json::from_value::<Record>(bad).is_err());
+
+        let bin = bincode::serialize(&record).unwrap();
+        assert_eq!(bincode::deserialize::<Record>(&bin).unwrap(), record);
+    }
+}
@@ -0,0 +1,67 @@
+//! Serde support for secret keys. Enabled with the `serde-secret` feature.
+//!
+//! Serializing a secret writes the raw 32-byte seed (base64 in human readable formats).
+//! Only enable this where the serialized output is itself protected, such as an
+//! encrypted configuration store.
+//!
+//! [`SecretSessionKey`] implements `Serialize`/`Deserialize`. A `SessionIdPair` field
+//! uses this module:
+//!
+//! ```rust,ignore
+//! #[derive(Serialize, Deserialize)]
+//! struct Config {
+//!     #[serde(with = "verse_session_id::serde_secret")]
+//!     identity: SessionIdPair,
+//! }
+//! ```
+use crate::session_id_pair::{as_base64, from_base64};
+use crate::{SecretSessionKey, SessionIdPair, SessionIdPairSecret};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+impl Serialize for SecretSessionKey {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        as_base64(self.expose_secret(), serializer)
+    }
+}
+impl<'de> Deserialize<'de> for SecretSessionKey {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(SecretSessionKey::new(from_base64(deserializer)?))
+    }
+}
+
+pub fn serialize<S: Serializer>(pair: &SessionIdPair, serializer: S) -> Result<S::Ok, S::Error> {
+    pair.secret_key().serialize(serializer)
+}
+
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<SessionIdPair, D::Error> {
+    SecretSessionKey::deserialize(deserializer)?
+        .to_session_id_pair()
+        .map_err(serde::de::Error::custom)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{new_session_id_pair, ISessionIdPair, SessionIdPair};
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Serialize, Deserialize)]
+    struct Config {
+        #[serde(with = "crate::serde_secret")]
+        identity: SessionIdPair,
+    }
+
+    #[test]
+    fn test_serde_secret() {
+        let config = Config {
+            identity: new_session_id_pair().unwrap(),
+        };
+        let json = serde_json::to_string(&config).unwrap();
+        assert_eq!(json.len(), r#"{"identity":""}"#.len() + 44);
+        let restored: Config = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.identity.get_id(), config.identity.get_id());
+
+        let bin = bincode::serialize(&config).unwrap();
+        let restored: Config = bincode::deserialize(&bin).unwrap();
+        assert_eq!(restored.identity.to_bytes(), config.identity.to_bytes());
+    }
+}
@@ -1,5 +1,7 @@
-use crate::errors;
-use anyhow::Result;
+use crate::errors::{self, Result, SessionIdError};
+use crate::redaction::{redaction, Redaction};
+use crate::{ArrayString, SESSION_ID_BASE64_SIZE};
+use std::borrow::Borrow;
 use std::cmp::Ordering;
 use std::fmt;
 
@@ -11,73 +13,129 @@ pub type RawSessionId = [u8; SESSION_ID_SIZE];
 /// Session ID
 /// The session ID is the public key for ED25519.
 #[derive(Eq, PartialEq, PartialOrd, Ord, Hash, Clone, Copy)]
+#[cfg_attr(
+    feature = "borsh",
+    derive(borsh::BorshSerialize, borsh::BorshDeserialize)
+)]
+#[cfg_attr(
+    feature = "rkyv",
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize),
+    rkyv(derive(Eq, PartialEq, Hash), compare(PartialEq))
+)]
+#[cfg_attr(
+    feature = "diesel",
+    derive(diesel::expression::AsExpression, diesel::deserialize::FromSqlRow),
+    diesel(sql_type = diesel::sql_types::Binary, sql_type = diesel::sql_types::Text)
+)]
 pub struct SessionId(RawSessionId);
 
 fn compare_session_ids(a: &[u8], b: &[u8]) -> Ordering {
-    let n = a.len();
-    if n != b.len() {
-        // 想定外
-        return n.cmp(&b.len());
-        // return (n - b.len()) as i32;
-    }
-    for i in 0..n {
-        let diff = (a[i] as i32).cmp(&(b[i] as i32));
-        if diff.is_ne() {
-            return diff;
-        }
-    }
-    Ordering::Equal
+    // shorter (unexpected) input sorts first, then memcmp order
+    a.len().cmp(&b.len()).then_with(|| a.cmp(b))
 }
 
 impl SessionId {
+    /// All-zero placeholder ID. It is not a usable public key.
+    pub const ZERO: SessionId = SessionId([0; SESSION_ID_SIZE]);
+
+    /// Usable in `const` and `static` initializers
+    pub const fn from_raw(raw: RawSessionId) -> Self {
+        SessionId(raw)
+    }
+    pub const fn as_raw(&self) -> &RawSessionId {
+        &self.0
+    }
+    pub fn is_zero(&self) -> bool {
+        *self == Self::ZERO
+    }
     pub fn eq_slice(&self, other: &impl AsRef<[u8]>) -> bool {
-        self.cmp_slice(other).is_eq()
+        self.0[..] == *other.as_ref()
     }
     pub fn cmp_slice(&self, other: impl AsRef<[u8]>) -> Ordering {
         compare_session_ids(self.as_ref(), other.as_ref())
     }
+    /// Rendered with the process-wide [`Redaction`], the same as `{:?}`
     pub fn to_debug_string(&self) -> String {
-        let mut s = base64::encode(self);
-        s.truncate(7);
-        s
+        redaction().apply(self.as_ref())
+    }
+    /// The first `n` base64 characters, regardless of the process-wide [`Redaction`]
+    pub fn to_debug_string_len(&self, n: usize) -> String {
+        Redaction::Truncated(n).apply(self.as_ref())
     }
     pub fn to_vec(&self) -> Vec<u8> {
         self.0.to_vec()
     }
+    /// Base64 string without heap allocation
+    pub fn to_array_string(&self) -> ArrayString<SESSION_ID_BASE64_SIZE> {
+        ArrayString::base64(&self.0)
+    }
+    /// Write the base64 string to `w` without heap allocation
+    pub fn encode_to(&self, w: &mut impl fmt::Write) -> fmt::Result {
+        w.write_str(&self.to_array_string())
+    }
 }
 impl Default for SessionId {
     fn default() -> Self {
-        SessionId([0; SESSION_ID_SIZE])
+        Self::ZERO
     }
 }
+/// Standard base64. The precision truncates, e.g. `{:.8}`.
 impl fmt::Display for SessionId {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", base64::encode(self.0))
+        f.pad(&self.to_array_string())
+    }
+}
+/// Lowercase hex, 64 characters. The precision truncates.
+impl fmt::LowerHex for SessionId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_hex(self, f, b"0123456789abcdef")
+    }
+}
+/// Uppercase hex, 64 characters. The precision truncates.
+impl fmt::UpperHex for SessionId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_hex(self, f, b"0123456789ABCDEF")
+    }
+}
+fn fmt_hex(id: &SessionId, f: &mut fmt::Formatter<'_>, digits: &[u8; 16]) -> fmt::Result {
+    let mut buf = [0u8; SESSION_ID_SIZE * 2];
+    for (pair, b) in buf.chunks_exact_mut(2).zip(id.0) {
+        pair[0] = digits[(b >> 4) as usize];
+        pair[1] = digits[(b & 0xf) as usize];
     }
+    f.pad(std::str::from_utf8(&buf).map_err(|_| fmt::Error)?)
 }
 impl std::str::FromStr for SessionId {
-    type Err = anyhow::Error;
+    type Err = SessionIdError;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         base64::decode(s)?.try_into()
     }
 }
+impl TryFrom<&str> for SessionId {
+    type Error = SessionIdError;
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
 
 impl TryFrom<&[u8]> for SessionId {
-    type Error = anyhow::Error;
+    type Error = SessionIdError;
     fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
-        let v: RawSessionId = value.try_into()?;
+        let v: RawSessionId = value
+            .try_into()
+            .map_err(|_| errors::invalid_length(SESSION_ID_SIZE, value.len()))?;
         Ok(SessionId(v))
     }
 }
 
 impl TryFrom<&Vec<u8>> for SessionId {
-    type Error = anyhow::Error;
+    type Error = SessionIdError;
     fn try_from(value: &Vec<u8>) -> Result<Self, Self::Error> {
         Self::try_from(value.as_ref() as &[u8])
     }
 }
 impl TryFrom<Vec<u8>> for SessionId {
-    type Error = anyhow::Error;
+    type Error = SessionIdError;
     fn try_from(value: Vec<u8>) -> Result<Self, Self::Error> {
         Self::try_from(value.as_ref() as &[u8])
     }
@@ -88,46 +146,95 @@ impl From<RawSessionId> for SessionId {
         SessionId(v)
     }
 }
+impl From<SessionId> for RawSessionId {
+    fn from(v: SessionId) -> Self {
+        v.0
+    }
+}
 impl From<SessionId> for Vec<u8> {
     fn from(v: SessionId) -> Self {
         v.to_vec()
     }
 }
 
+#[cfg(feature = "rkyv")]
+impl From<&ArchivedSessionId> for SessionId {
+    fn from(v: &ArchivedSessionId) -> Self {
+        SessionId(v.0)
+    }
+}
+#[cfg(feature = "rkyv")]
+impl AsRef<[u8]> for ArchivedSessionId {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
 impl AsRef<[u8]> for SessionId {
     fn as_ref(&self) -> &[u8] {
         &self.0
     }
 }
+impl AsRef<RawSessionId> for SessionId {
+    fn as_ref(&self) -> &RawSessionId {
+        &self.0
+    }
+}
+// `Hash`, `Eq` and `Ord` agree with those of the bytes, so maps keyed by
+// SessionId can be queried with raw buffers
+impl Borrow<[u8]> for SessionId {
+    fn borrow(&self) -> &[u8] {
+        &self.0
+    }
+}
+impl Borrow<RawSessionId> for SessionId {
+    fn borrow(&self) -> &RawSessionId {
+        &self.0
+    }
+}
+impl PartialEq<RawSessionId> for SessionId {
+    fn eq(&self, other: &RawSessionId) -> bool {
+        self.0 == *other
+    }
+}
+impl PartialEq<[u8]> for SessionId {
+    fn eq(&self, other: &[u8]) -> bool {
+        self.0[..] == *other
+    }
+}
+impl PartialEq<&[u8]> for SessionId {
+    fn eq(&self, other: &&[u8]) -> bool {
+        self.0[..] == **other
+    }
+}
+/// Rendered with the process-wide [`Redaction`], like [`SessionId::to_debug_string`].
+/// When truncated, `{:#?}` prints the full ID and the precision sets the length, e.g. `{:.12?}`.
 impl fmt::Debug for SessionId {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.to_debug_string())
+        let Redaction::Truncated(len) = redaction() else {
+            return f.pad(&self.to_debug_string());
+        };
+        let s = self.to_array_string();
+        let n = match f.precision() {
+            Some(n) => n,
+            None if f.alternate() => s.len(),
+            None => len,
+        };
+        f.pad(&s[..n.min(s.len())])
     }
 }
 
 pub trait SessionIdCompatible {
     fn to_bytes(&self) -> Option<&[u8]>;
     fn to_session_id(&self) -> Result<SessionId> {
-        self.to_bytes().ok_or_else(errors::required!())?.try_into()
+        self.to_bytes().ok_or(SessionIdError::Required)?.try_into()
     }
     fn eq_slice(&self, other: &impl SessionIdCompatible) -> bool {
-        let a = self.to_bytes();
-        let b = other.to_bytes();
-        if a.is_none() && b.is_none() {
-            return true;
-        }
-        if a.is_none() != b.is_none() {
-            return false;
-        }
-        compare_session_ids(a.unwrap(), b.unwrap()).is_eq()
+        self.to_bytes() == other.to_bytes()
     }
     fn to_debug_string(&self) -> String {
         match self.to_bytes() {
-            Some(v) => {
-                let mut s = base64::encode(v);
-                s.truncate(7);
-                s
-            }
+            Some(v) => redaction().apply(v),
             None => "<NOID>".to_string(),
         }
     }
@@ -137,11 +244,13 @@ impl SessionIdCompatible for Option<Vec<u8>> {
         self.as_ref().map(|v| v as &[u8])
     }
 }
+#[allow(clippy::needless_lifetimes)]
 impl<'a> SessionIdCompatible for Option<&'a SessionId> {
     fn to_bytes(&self) -> Option<&[u8]> {
         self.map(|v| v.as_ref())
     }
 }
+#[allow(clippy::needless_lifetimes)]
 impl<'a> SessionIdCompatible for &'a [u8] {
     fn to_bytes(&self) -> Option<&[u8]> {
         Some(self)
@@ -176,6 +285,7 @@ mod tests {
     use std::str::FromStr;
 
     #[test]
+    #[allow(clippy::clone_on_copy, clippy::needless_borrows_for_generic_args)]
     fn test_session_id() {
         let sid0 = SessionId::from([1; SESSION_ID_SIZE]);
         let sid1 = SessionId::from([2; SESSION_ID_SIZE]);
@@ -201,12 +311,36 @@ mod tests {
         assert!(sid0.eq_slice(&sid0.to_vec()));
 
         assert!(sid0.cmp_slice(&[]).is_ne());
+        // same order as `Ord`, shorter slices first
+        let (lo, hi) = (SessionId::from([1; 32]), SessionId::from([2; 32]));
+        assert_eq!(lo.cmp_slice(hi), lo.cmp(&hi));
+        assert_eq!(hi.cmp_slice(lo), hi.cmp(&lo));
+        assert!(hi.cmp_slice([0xff; 31]).is_gt());
+
+        static WELL_KNOWN: [SessionId; 2] = [SessionId::ZERO, SessionId::from_raw([1; 32])];
+        assert!(WELL_KNOWN[0].is_zero());
+        assert!(SessionId::default().is_zero());
+        assert!(!WELL_KNOWN[1].is_zero());
+        assert_eq!(WELL_KNOWN[1].as_raw(), &[1; 32]);
 
         assert_ne!(sid0.to_debug_string(), sid1.to_debug_string());
         assert_eq!(sid0.to_debug_string(), format!("{:?}", sid0));
+        assert_eq!(format!("{:#?}", sid0), sid0.to_string());
+        assert_eq!(sid0.to_debug_string_len(10), &sid0.to_string()[..10]);
+        assert_eq!(format!("{:.3?}", sid0), &sid0.to_string()[..3]);
+        assert_eq!(format!("{:.8}", sid0), &sid0.to_string()[..8]);
+        assert_eq!(format!("{:>46}", sid0), format!("  {}", sid0));
+        let one = SessionId::from([0xab; 32]);
+        assert_eq!(format!("{:x}", one), "ab".repeat(32));
+        assert_eq!(format!("{:X}", one), "AB".repeat(32));
+        assert_eq!(format!("{:.8x}", one), "abababab");
 
         let str = format!("{}", sid0);
         assert_eq!(SessionId::from_str(&str).unwrap(), sid0);
+        assert_eq!(sid0.to_array_string(), str.as_str());
+        let mut buf = String::new();
+        sid0.encode_to(&mut buf).unwrap();
+        assert_eq!(buf, base64::encode(sid0));
 
         let str = format!("{:?}", sid0);
         assert!(SessionId::from_str(&str).is_err());
@@ -223,6 +357,74 @@ mod tests {
         assert_eq!(v, sid0.to_vec());
     }
     #[test]
+    fn test_session_id_error() {
+        assert!(matches!(
+            SessionId::try_from(vec![1u8; 3]),
+            Err(SessionIdError::InvalidLength {
+                expected: SESSION_ID_SIZE,
+                actual: 3
+            })
+        ));
+        assert!(matches!(
+            SessionId::from_str("!!"),
+            Err(SessionIdError::Base64(_))
+        ));
+        assert!(matches!(
+            (None as Option<Vec<u8>>).to_session_id(),
+            Err(SessionIdError::Required)
+        ));
+
+        // compatible with anyhow
+        let f = || -> anyhow::Result<SessionId> { Ok(SessionId::from_str("!!")?) };
+        let err = f().unwrap_err();
+        assert!(err.downcast_ref::<SessionIdError>().is_some());
+    }
+    #[test]
+    fn test_session_id_conversions() {
+        let sid = SessionId::from([5; SESSION_ID_SIZE]);
+        let raw: RawSessionId = sid.into();
+        assert_eq!(raw, [5; SESSION_ID_SIZE]);
+        assert_eq!(AsRef::<RawSessionId>::as_ref(&sid), &raw);
+        assert_eq!(SessionId::try_from(sid.to_string().as_str()).unwrap(), sid);
+        assert!(SessionId::try_from("!!").is_err());
+
+        assert_eq!(sid, raw);
+        assert_eq!(sid, &raw[..]);
+        assert!(sid != [6; SESSION_ID_SIZE]);
+        let short: &[u8] = &raw[1..];
+        assert!(sid != short);
+
+        let mut map = std::collections::HashMap::new();
+        map.insert(sid, 1);
+        assert_eq!(map.get(&raw), Some(&1));
+        assert_eq!(map.get(&raw[..]), Some(&1));
+        let set: std::collections::BTreeSet<SessionId> =
+            [SessionId::from([1; 32]), SessionId::from([9; 32])].into();
+        assert!(set.contains(&[9u8; 32][..]));
+    }
+    #[cfg(feature = "borsh")]
+    #[test]
+    fn test_session_id_borsh() {
+        let sid = SessionId::from([5; SESSION_ID_SIZE]);
+        let v = borsh::to_vec(&sid).unwrap();
+        assert_eq!(v, sid.to_vec());
+        assert_eq!(borsh::from_slice::<SessionId>(&v).unwrap(), sid);
+        assert!(borsh::from_slice::<SessionId>(&v[1..]).is_err());
+    }
+    #[cfg(feature = "rkyv")]
+    #[test]
+    fn test_session_id_rkyv() {
+        let sid = SessionId::from([5; SESSION_ID_SIZE]);
+        let bytes = rkyv::to_bytes::<rkyv::rancor::Error>(&sid).unwrap();
+        let archived = rkyv::access::<ArchivedSessionId, rkyv::rancor::Error>(&bytes).unwrap();
+        assert!(archived == &sid);
+        assert_eq!(archived.as_ref(), &sid.as_raw()[..]);
+        assert_eq!(SessionId::from(archived), sid);
+        let deserialized = rkyv::from_bytes::<SessionId, rkyv::rancor::Error>(&bytes).unwrap();
+        assert_eq!(deserialized, sid);
+    }
+    #[test]
+    #[allow(clippy::clone_on_copy, clippy::needless_borrow)]
     fn test_session_id_compatible() {
         let sid0raw = [3; SESSION_ID_SIZE];
         let sid0 = SessionId::from(sid0raw.clone());
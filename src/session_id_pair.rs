@@ -1,75 +1,158 @@
-use crate::errors;
-use crate::SessionId;
-use anyhow::Result;
+use crate::errors::{self, Result, SessionIdError, SignatureErrorKind};
+use crate::{ArrayString, SecretSessionKey, SessionId, SIGNATURE_SET_BASE64_SIZE};
 use ed25519_dalek::Digest;
-use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 use std::fmt;
+use zeroize::Zeroizing;
 
 /// Session ID and private key pair (ED25519).
 pub type SessionIdPair = ed25519_dalek::Keypair;
 
+/// Bytes of the secret key (seed)
+pub const SECRET_KEY_SIZE: usize = ed25519_dalek::SECRET_KEY_LENGTH;
+/// Bytes of a serialized keypair (secret key followed by the session ID)
+pub const SESSION_ID_PAIR_SIZE: usize = ed25519_dalek::KEYPAIR_LENGTH;
 /// Signature Salt Size
 pub const SIGNATURE_SALT_SIZE: usize = 8;
 /// Signature Size
 pub const SIGNATURE_SIZE: usize = ed25519_dalek::Signature::BYTE_SIZE;
+/// Bytes of SignatureSet (signature followed by salt)
+pub const SIGNATURE_SET_SIZE: usize = SIGNATURE_SIZE + SIGNATURE_SALT_SIZE;
 
 /// Session ID as public key
 pub trait SessionIdPublic {
-    /// Verify signature
-    fn verify(&self, payload: Vec<&[u8]>, sigset: &SignatureSet) -> Result<()>;
+    /// Verify signature.
+    /// `payload` is any sequence of byte slices (`Vec<&[u8]>`, `&[&[u8]]`, `[&str; N]`, ...);
+    /// verification does not allocate.
+    fn verify<P>(&self, payload: P, sigset: &SignatureSet) -> Result<()>
+    where
+        P: IntoIterator,
+        P::Item: AsRef<[u8]>;
 }
 
 impl SessionIdPublic for SessionId {
-    fn verify(&self, payload: Vec<&[u8]>, sigset: &SignatureSet) -> Result<()> {
-        let pk =
-            ed25519_dalek::PublicKey::from_bytes(self.as_ref()).map_err(errors::signature!())?;
-        let mut hasher = ed25519_dalek::Sha512::new();
-        hasher.update(sigset.salt);
-        for p in payload {
-            hasher.update(p);
-        }
-        let signature = ed25519_dalek::Signature::from_bytes(&sigset.signature)
-            .map_err(errors::signature!())?;
-        Ok(pk
-            .verify_prehashed(hasher, None, &signature)
-            .map_err(errors::signature!())?)
+    fn verify<P>(&self, payload: P, sigset: &SignatureSet) -> Result<()>
+    where
+        P: IntoIterator,
+        P::Item: AsRef<[u8]>,
+    {
+        verify_prehashed(self, prehash(&sigset.salt, payload), sigset)
+    }
+}
+
+pub(crate) fn prehash<P>(salt: &[u8], payload: P) -> ed25519_dalek::Sha512
+where
+    P: IntoIterator,
+    P::Item: AsRef<[u8]>,
+{
+    let mut hasher = ed25519_dalek::Sha512::new();
+    hasher.update(salt);
+    for p in payload {
+        hasher.update(p);
     }
+    hasher
+}
+
+pub(crate) fn verify_prehashed(
+    session_id: &SessionId,
+    hasher: ed25519_dalek::Sha512,
+    sigset: &SignatureSet,
+) -> Result<()> {
+    let pk = ed25519_dalek::PublicKey::from_bytes(session_id.as_ref())
+        .map_err(errors::signature(SignatureErrorKind::MalformedPublicKey))?;
+    let signature = ed25519_dalek::Signature::from_bytes(&sigset.signature)
+        .map_err(errors::signature(SignatureErrorKind::MalformedSignature))?;
+    pk.verify_prehashed(hasher, None, &signature)
+        .map_err(errors::signature(SignatureErrorKind::VerificationFailed))
 }
 
 pub trait ISessionIdPair {
     /// Get session ID
     fn get_id(&self) -> SessionId;
-    /// Create a signature for input data
-    fn sign(&self, payload: Vec<&[u8]>) -> Result<SignatureSet>;
+    /// Create a signature for input data.
+    /// `payload` is any sequence of byte slices (`Vec<&[u8]>`, `&[&[u8]]`, `[&str; N]`, ...),
+    /// all of which hash the same for the same bytes.
+    fn sign<P>(&self, payload: P) -> Result<SignatureSet>
+    where
+        P: IntoIterator,
+        P::Item: AsRef<[u8]>;
 }
 
 /// Generate SessionIdPair
 pub fn new_session_id_pair() -> Result<SessionIdPair> {
-    let sk = &mut [0u8; ed25519_dalek::SECRET_KEY_LENGTH];
-    getrandom::getrandom(sk)?;
-    let sk = ed25519_dalek::SecretKey::from_bytes(sk).map_err(errors::signature!())?;
+    session_id_pair_from_secret(&SecretSessionKey::generate()?)
+}
 
+/// Keypair from its secret seed
+pub fn session_id_pair_from_secret(secret: &SecretSessionKey) -> Result<SessionIdPair> {
+    let sk = ed25519_dalek::SecretKey::from_bytes(secret.expose_secret())
+        .map_err(errors::signature(SignatureErrorKind::SigningFailed))?;
     Ok(ed25519_dalek::Keypair {
         public: ed25519_dalek::PublicKey::from(&sk),
         secret: sk,
     })
 }
 
+/// Keypair from the output of `SessionIdPair::to_bytes` (secret key followed by the session ID).
+/// Unlike `SessionIdPair::from_bytes`, fails if the session ID does not belong to the secret key.
+pub fn session_id_pair_from_bytes(bytes: &[u8]) -> Result<SessionIdPair> {
+    if bytes.len() != SESSION_ID_PAIR_SIZE {
+        return Err(errors::invalid_length(SESSION_ID_PAIR_SIZE, bytes.len()));
+    }
+    let mut secret = SecretSessionKey::new([0; SECRET_KEY_SIZE]);
+    secret
+        .expose_secret_mut()
+        .copy_from_slice(&bytes[..SECRET_KEY_SIZE]);
+    let pair = session_id_pair_from_secret(&secret)?;
+    if pair.public.as_bytes()[..] != bytes[SECRET_KEY_SIZE..] {
+        return Err(SessionIdError::InvalidArgument(
+            "session ID does not match the secret key",
+        ));
+    }
+    Ok(pair)
+}
+
+/// Export of the secret key, for persisting a keypair
+pub trait SessionIdPairSecret: Sized {
+    fn secret_key(&self) -> SecretSessionKey;
+    /// Base64 of the 32-byte secret seed. Anyone holding it can sign as this session ID.
+    fn to_secret_string(&self) -> String;
+    /// Parse the output of `to_secret_string`. The 64-byte keypair form is also accepted.
+    fn from_secret_str(s: &str) -> Result<Self>;
+}
+
+impl SessionIdPairSecret for SessionIdPair {
+    fn secret_key(&self) -> SecretSessionKey {
+        SecretSessionKey::new(self.secret.to_bytes())
+    }
+    fn to_secret_string(&self) -> String {
+        base64::encode(self.secret_key().expose_secret())
+    }
+    fn from_secret_str(s: &str) -> Result<Self> {
+        let bytes = Zeroizing::new(base64::decode(s)?);
+        if bytes.len() != SECRET_KEY_SIZE {
+            return session_id_pair_from_bytes(&bytes);
+        }
+        let mut secret = SecretSessionKey::new([0; SECRET_KEY_SIZE]);
+        secret.expose_secret_mut().copy_from_slice(&bytes);
+        session_id_pair_from_secret(&secret)
+    }
+}
+
 impl ISessionIdPair for SessionIdPair {
     fn get_id(&self) -> SessionId {
         self.public.to_bytes().into()
     }
-    fn sign(&self, payload: Vec<&[u8]>) -> Result<SignatureSet> {
+    fn sign<P>(&self, payload: P) -> Result<SignatureSet>
+    where
+        P: IntoIterator,
+        P::Item: AsRef<[u8]>,
+    {
         let mut salt = [0u8; SIGNATURE_SALT_SIZE];
         getrandom::getrandom(&mut salt)?;
-        let mut hasher = ed25519_dalek::Sha512::new();
-        hasher.update(salt);
-        for p in payload {
-            hasher.update(p);
-        }
         let signature = self
-            .sign_prehashed(hasher, None)
-            .map_err(errors::signature!())?;
+            .sign_prehashed(prehash(&salt, payload), None)
+            .map_err(errors::signature(SignatureErrorKind::SigningFailed))?;
 
         Ok(SignatureSet {
             signature: signature.to_bytes(),
@@ -79,7 +162,16 @@ impl ISessionIdPair for SessionIdPair {
 }
 
 /// Signature
-#[derive(Deserialize, Serialize, Eq, PartialEq, Debug)]
+#[derive(Deserialize, Serialize, Eq, PartialEq, PartialOrd, Ord, Hash, Clone, Copy, Debug)]
+#[cfg_attr(
+    feature = "borsh",
+    derive(borsh::BorshSerialize, borsh::BorshDeserialize)
+)]
+#[cfg_attr(
+    feature = "rkyv",
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize),
+    rkyv(derive(Eq, PartialEq, Debug), compare(PartialEq))
+)]
 pub struct SignatureSet {
     #[serde(serialize_with = "as_base64", deserialize_with = "from_base64")]
     pub signature: [u8; SIGNATURE_SIZE],
@@ -87,60 +179,145 @@ pub struct SignatureSet {
     pub salt: [u8; SIGNATURE_SALT_SIZE],
 }
 
+#[cfg(feature = "rkyv")]
+impl From<&ArchivedSignatureSet> for SignatureSet {
+    fn from(v: &ArchivedSignatureSet) -> Self {
+        SignatureSet {
+            signature: v.signature,
+            salt: v.salt,
+        }
+    }
+}
+
+impl SignatureSet {
+    /// Canonical fixed layout: signature followed by salt
+    pub fn to_bytes(&self) -> [u8; SIGNATURE_SET_SIZE] {
+        let mut buf = [0u8; SIGNATURE_SET_SIZE];
+        buf[..SIGNATURE_SIZE].copy_from_slice(&self.signature);
+        buf[SIGNATURE_SIZE..].copy_from_slice(&self.salt);
+        buf
+    }
+    pub fn from_bytes(bytes: &[u8; SIGNATURE_SET_SIZE]) -> Self {
+        let mut ss = SignatureSet {
+            signature: [0; SIGNATURE_SIZE],
+            salt: [0; SIGNATURE_SALT_SIZE],
+        };
+        ss.signature.copy_from_slice(&bytes[..SIGNATURE_SIZE]);
+        ss.salt.copy_from_slice(&bytes[SIGNATURE_SIZE..]);
+        ss
+    }
+    /// Base64 string without heap allocation
+    pub fn to_array_string(&self) -> ArrayString<SIGNATURE_SET_BASE64_SIZE> {
+        ArrayString::base64(&self.to_bytes())
+    }
+    /// Write the base64 string to `w` without heap allocation
+    pub fn encode_to(&self, w: &mut impl fmt::Write) -> fmt::Result {
+        w.write_str(&self.to_array_string())
+    }
+}
+
 impl fmt::Display for SignatureSet {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let mut buf = Vec::<u8>::with_capacity(SIGNATURE_SIZE + SIGNATURE_SALT_SIZE);
-        buf.extend_from_slice(&self.signature);
-        buf.extend_from_slice(&self.salt);
-        write!(f, "{}", base64::encode(buf))
+        self.encode_to(f)
     }
 }
 impl std::str::FromStr for SignatureSet {
-    type Err = anyhow::Error;
+    type Err = SessionIdError;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         base64::decode(s)?.try_into()
     }
 }
+impl TryFrom<&[u8]> for SignatureSet {
+    type Error = SessionIdError;
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        let v: &[u8; SIGNATURE_SET_SIZE] = value
+            .try_into()
+            .map_err(|_| errors::invalid_length(SIGNATURE_SET_SIZE, value.len()))?;
+        Ok(SignatureSet::from_bytes(v))
+    }
+}
 impl TryFrom<Vec<u8>> for SignatureSet {
-    type Error = anyhow::Error;
+    type Error = SessionIdError;
     fn try_from(value: Vec<u8>) -> Result<Self, Self::Error> {
-        if value.len() != SIGNATURE_SIZE + SIGNATURE_SALT_SIZE {
-            return Err(errors::convert!(format!(
-                "{:?} != {:?}",
-                value.len(),
-                SIGNATURE_SIZE + SIGNATURE_SALT_SIZE
-            )));
-        }
-        let ss = SignatureSet {
-            signature: value[0..SIGNATURE_SIZE]
-                .try_into()
-                .map_err(|_v| errors::convert!())?,
-            salt: value[SIGNATURE_SIZE..]
-                .try_into()
-                .map_err(|_v| errors::convert!())?,
-        };
-        Ok(ss)
+        Self::try_from(value.as_ref() as &[u8])
+    }
+}
+impl From<[u8; SIGNATURE_SET_SIZE]> for SignatureSet {
+    fn from(v: [u8; SIGNATURE_SET_SIZE]) -> Self {
+        SignatureSet::from_bytes(&v)
+    }
+}
+impl From<SignatureSet> for [u8; SIGNATURE_SET_SIZE] {
+    fn from(v: SignatureSet) -> Self {
+        v.to_bytes()
+    }
+}
+impl From<SignatureSet> for Vec<u8> {
+    fn from(v: SignatureSet) -> Self {
+        v.to_bytes().to_vec()
     }
 }
 
-fn as_base64<const N: usize, S: Serializer>(
+/// Serialize as base64 string for human readable formats (JSON) and as raw bytes otherwise.
+pub(crate) fn as_base64<const N: usize, S: Serializer>(
     val: &[u8; N],
     serializer: S,
 ) -> Result<S::Ok, S::Error> {
-    serializer.serialize_str(&base64::encode(val))
+    if serializer.is_human_readable() {
+        serializer.serialize_str(&base64::encode(val))
+    } else {
+        serializer.serialize_bytes(val)
+    }
 }
 
-fn from_base64<'de, const N: usize, D: Deserializer<'de>>(
+/// Deserialize from base64 string or raw bytes regardless of the format,
+/// so data written before binary formats switched to raw bytes can still be read.
+pub(crate) fn from_base64<'de, const N: usize, D: Deserializer<'de>>(
     deserializer: D,
 ) -> Result<[u8; N], D::Error> {
-    use serde::de;
+    if deserializer.is_human_readable() {
+        deserializer.deserialize_str(BytesVisitor::<N>)
+    } else {
+        deserializer.deserialize_bytes(BytesVisitor::<N>)
+    }
+}
+
+struct BytesVisitor<const N: usize>;
+
+impl<'de, const N: usize> de::Visitor<'de> for BytesVisitor<N> {
+    type Value = [u8; N];
 
-    let res = <&str>::deserialize(deserializer).and_then(|s| {
-        base64::decode(s)
-            .map_err(|e| de::Error::custom(format!("invalid base64 string: {}, {}", s, e)))
-    })?;
-    res.try_into()
-        .map_err(|_| de::Error::custom(format!("invalid array size: {}", N)))
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} bytes or base64 string", N)
+    }
+    fn visit_str<E: de::Error>(self, s: &str) -> Result<Self::Value, E> {
+        let res = base64::decode(s)
+            .map_err(|e| E::custom(format!("invalid base64 string: {}, {}", s, e)))?;
+        res.try_into()
+            .map_err(|_| E::custom(format!("invalid array size: {}", N)))
+    }
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Self::Value, E> {
+        if let Ok(v) = v.try_into() {
+            return Ok(v);
+        }
+        // legacy base64 string stored by a binary format
+        match std::str::from_utf8(v) {
+            Ok(s) => self.visit_str(s),
+            Err(_) => Err(E::custom(format!("invalid array size: {}", N))),
+        }
+    }
+    fn visit_seq<A: de::SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut res = [0u8; N];
+        for (i, b) in res.iter_mut().enumerate() {
+            *b = seq
+                .next_element()?
+                .ok_or_else(|| de::Error::invalid_length(i, &self))?;
+        }
+        if seq.next_element::<u8>()?.is_some() {
+            return Err(de::Error::invalid_length(N + 1, &self));
+        }
+        Ok(res)
+    }
 }
 
 #[cfg(test)]
@@ -154,6 +331,31 @@ mod tests {
     }
 
     #[test]
+    fn test_keypair_bytes() {
+        let kp = new_session_id_pair().unwrap();
+        let bytes = kp.to_bytes();
+        let restored = session_id_pair_from_bytes(&bytes).unwrap();
+        assert_eq!(restored.get_id(), kp.get_id());
+        assert!(session_id_pair_from_bytes(&bytes[1..]).is_err());
+        let mut mismatched = bytes;
+        mismatched[SECRET_KEY_SIZE] ^= 1;
+        assert!(session_id_pair_from_bytes(&mismatched).is_err());
+
+        let s = kp.to_secret_string();
+        assert_eq!(base64::decode(&s).unwrap().len(), SECRET_KEY_SIZE);
+        let restored = SessionIdPair::from_secret_str(&s).unwrap();
+        assert_eq!(restored.to_bytes(), bytes);
+        let long = base64::encode(bytes);
+        assert_eq!(
+            SessionIdPair::from_secret_str(&long).unwrap().get_id(),
+            kp.get_id()
+        );
+        assert!(SessionIdPair::from_secret_str(&base64::encode(mismatched)).is_err());
+        assert!(SessionIdPair::from_secret_str("!!").is_err());
+    }
+
+    #[test]
+    #[allow(clippy::clone_on_copy)]
     fn test_sign_verify() {
         let kp = new_session_id_pair().unwrap();
         let ss = kp
@@ -167,6 +369,16 @@ mod tests {
 
         let res = session_id.verify(vec!["0234".as_bytes(), "testdata".as_bytes()], &ss);
         assert!(res.is_err());
+        // any sequence of byte slices hashes the same
+        assert!(session_id.verify(["1234", "testdata"], &ss).is_ok());
+        let parts: &[&[u8]] = &[b"1234", b"testdata"];
+        assert!(session_id.verify(parts, &ss).is_ok());
+        // composed and owned parts
+        let owned = vec![b"1234".to_vec(), String::from("testdata").into_bytes()];
+        let ss2 = kp.sign(&owned).unwrap();
+        assert!(session_id
+            .verify(vec!["1234".as_bytes(), "testdata".as_bytes()], &ss2)
+            .is_ok());
 
         let kp = new_session_id_pair().unwrap();
         let session_id = kp.get_id();
@@ -188,6 +400,37 @@ mod tests {
         assert!(res.is_err());
     }
     #[test]
+    fn test_verify_error_kind() {
+        let kind = |res: Result<()>| match res {
+            Err(SessionIdError::Signature(kind)) => Some(kind),
+            _ => None,
+        };
+        let kp = new_session_id_pair().unwrap();
+        let session_id = kp.get_id();
+        let ss = kp.sign(vec!["1234".as_bytes()]).unwrap();
+
+        let res = session_id.verify(vec!["0234".as_bytes()], &ss);
+        assert_eq!(kind(res), Some(SignatureErrorKind::VerificationFailed));
+
+        let ss1 = SignatureSet {
+            signature: ss.signature,
+            salt: [0; SIGNATURE_SALT_SIZE],
+        };
+        let res = session_id.verify(vec!["1234".as_bytes()], &ss1);
+        assert_eq!(kind(res), Some(SignatureErrorKind::VerificationFailed));
+
+        let mut ss1 = SignatureSet {
+            signature: ss.signature,
+            salt: ss.salt,
+        };
+        ss1.signature[SIGNATURE_SIZE - 1] = 0xff;
+        let res = session_id.verify(vec!["1234".as_bytes()], &ss1);
+        assert_eq!(kind(res), Some(SignatureErrorKind::MalformedSignature));
+
+        let res = SessionId::from([2; crate::SESSION_ID_SIZE]).verify(vec!["1234".as_bytes()], &ss);
+        assert_eq!(kind(res), Some(SignatureErrorKind::MalformedPublicKey));
+    }
+    #[test]
     fn test_ss_serialize() {
         let ss = SignatureSet {
             signature: [1; SIGNATURE_SIZE],
@@ -198,6 +441,96 @@ mod tests {
         assert_eq!(ss, deserialized);
     }
     #[test]
+    fn test_ss_serialize_binary() {
+        let ss = SignatureSet {
+            signature: [1; SIGNATURE_SIZE],
+            salt: [2; SIGNATURE_SALT_SIZE],
+        };
+        let serialized = bincode::serialize(&ss).unwrap();
+        // two length prefixes and raw bytes
+        assert_eq!(
+            serialized.len(),
+            8 + SIGNATURE_SIZE + 8 + SIGNATURE_SALT_SIZE
+        );
+        let deserialized: SignatureSet = bincode::deserialize(&serialized).unwrap();
+        assert_eq!(ss, deserialized);
+
+        // legacy base64 strings
+        #[derive(Serialize)]
+        struct Legacy {
+            signature: String,
+            salt: String,
+        }
+        let legacy = Legacy {
+            signature: base64::encode(ss.signature),
+            salt: base64::encode(ss.salt),
+        };
+        let serialized = bincode::serialize(&legacy).unwrap();
+        let deserialized: SignatureSet = bincode::deserialize(&serialized).unwrap();
+        assert_eq!(ss, deserialized);
+
+        let serialized = serde_json::to_string(&legacy).unwrap();
+        let deserialized: SignatureSet = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(ss, deserialized);
+    }
+    #[cfg(feature = "borsh")]
+    #[test]
+    fn test_ss_borsh() {
+        let ss = SignatureSet {
+            signature: [1; SIGNATURE_SIZE],
+            salt: [2; SIGNATURE_SALT_SIZE],
+        };
+        let v = borsh::to_vec(&ss).unwrap();
+        assert_eq!(v.len(), SIGNATURE_SIZE + SIGNATURE_SALT_SIZE);
+        assert_eq!(&v[..SIGNATURE_SIZE], &ss.signature);
+        assert_eq!(borsh::from_slice::<SignatureSet>(&v).unwrap(), ss);
+    }
+    #[cfg(feature = "rkyv")]
+    #[test]
+    fn test_ss_rkyv() {
+        let kp = new_session_id_pair().unwrap();
+        let ss = kp.sign(vec!["1234".as_bytes()]).unwrap();
+        let bytes = rkyv::to_bytes::<rkyv::rancor::Error>(&ss).unwrap();
+        let archived = rkyv::access::<ArchivedSignatureSet, rkyv::rancor::Error>(&bytes).unwrap();
+        assert_eq!(archived, &ss);
+        let ss1 = SignatureSet::from(archived);
+        assert!(kp.get_id().verify(vec!["1234".as_bytes()], &ss1).is_ok());
+    }
+    #[test]
+    fn test_ss_bytes() {
+        let ss = SignatureSet {
+            signature: [1; SIGNATURE_SIZE],
+            salt: [2; SIGNATURE_SALT_SIZE],
+        };
+        let bytes = ss.to_bytes();
+        assert_eq!(&bytes[..SIGNATURE_SIZE], &ss.signature);
+        assert_eq!(&bytes[SIGNATURE_SIZE..], &ss.salt);
+        assert_eq!(SignatureSet::from_bytes(&bytes), ss);
+        assert_eq!(SignatureSet::from(bytes), ss);
+
+        let v: Vec<u8> = ss.into();
+        assert_eq!(v, bytes.to_vec());
+        assert_eq!(SignatureSet::try_from(v.clone()).unwrap(), ss);
+        assert_eq!(SignatureSet::try_from(&v[..]).unwrap(), ss);
+        assert!(matches!(
+            SignatureSet::try_from(&v[1..]),
+            Err(SessionIdError::InvalidLength {
+                expected: SIGNATURE_SET_SIZE,
+                actual: 71
+            })
+        ));
+
+        let ss1 = SignatureSet {
+            signature: [1; SIGNATURE_SIZE],
+            salt: [3; SIGNATURE_SALT_SIZE],
+        };
+        assert!(ss < ss1);
+        let mut set = std::collections::HashSet::new();
+        set.insert(ss);
+        assert!(set.contains(&ss));
+        assert!(!set.contains(&ss1));
+    }
+    #[test]
     fn test_ss_serialize_str() {
         let ss = SignatureSet {
             signature: [1; SIGNATURE_SIZE],
@@ -206,5 +539,7 @@ mod tests {
         let serialized = ss.to_string();
         let deserialized: SignatureSet = serialized.parse().unwrap();
         assert_eq!(ss, deserialized);
+        assert_eq!(serialized, base64::encode(ss.to_bytes()));
+        assert_eq!(ss.to_array_string(), serialized.as_str());
     }
 }
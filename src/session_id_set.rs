@@ -0,0 +1,258 @@
+//! Set of session IDs tuned for membership checks over large rosters.
+//!
+//! Rosters are filled with IDs chosen by peers, who can grind keypairs with colliding
+//! prefixes, so the set keeps the randomly keyed SipHash of `std` rather than
+//! [`IdentityHasher`](crate::IdentityHasher). An optional bloom filter answers most
+//! negative lookups from a compact bit array.
+use crate::errors::{self, Result};
+use crate::{SessionId, SESSION_ID_SIZE};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::HashSet;
+
+type Inner = HashSet<SessionId>;
+
+const BLOOM_BITS_PER_ID: usize = 10;
+const BLOOM_HASHES: u64 = 7;
+
+#[derive(Clone, Debug)]
+struct Bloom {
+    bits: Vec<u64>,
+    capacity: usize,
+}
+
+impl Bloom {
+    fn new(capacity: usize) -> Self {
+        let words = (capacity.max(64) * BLOOM_BITS_PER_ID).div_ceil(64);
+        Bloom {
+            bits: vec![0; words],
+            capacity,
+        }
+    }
+    // double hashing over two independent 8-byte words of the key
+    fn positions(&self, id: &SessionId) -> impl Iterator<Item = usize> {
+        let b = id.as_raw();
+        let h1 = u64::from_le_bytes(b[8..16].try_into().unwrap());
+        let h2 = u64::from_le_bytes(b[16..24].try_into().unwrap()) | 1;
+        let m = (self.bits.len() * 64) as u64;
+        (0..BLOOM_HASHES).map(move |i| (h1.wrapping_add(i.wrapping_mul(h2)) % m) as usize)
+    }
+    fn insert(&mut self, id: &SessionId) {
+        for p in self.positions(id).collect::<Vec<_>>() {
+            self.bits[p / 64] |= 1 << (p % 64);
+        }
+    }
+    fn might_contain(&self, id: &SessionId) -> bool {
+        self.positions(id)
+            .all(|p| self.bits[p / 64] & (1 << (p % 64)) != 0)
+    }
+}
+
+/// Set of session IDs
+#[derive(Clone, Debug, Default)]
+pub struct SessionIdSet {
+    inner: Inner,
+    prefilter: Option<Bloom>,
+}
+
+impl SessionIdSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn with_capacity(capacity: usize) -> Self {
+        SessionIdSet {
+            inner: Inner::with_capacity_and_hasher(capacity, Default::default()),
+            prefilter: None,
+        }
+    }
+    /// Set with a bloom filter prefilter sized for `expected_len` IDs.
+    /// The filter grows with the set; removals leave stale bits until it is rebuilt.
+    pub fn with_prefilter(expected_len: usize) -> Self {
+        SessionIdSet {
+            inner: Inner::with_capacity_and_hasher(expected_len, Default::default()),
+            prefilter: Some(Bloom::new(expected_len)),
+        }
+    }
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+    pub fn has_prefilter(&self) -> bool {
+        self.prefilter.is_some()
+    }
+    pub fn contains(&self, id: &SessionId) -> bool {
+        if let Some(bloom) = &self.prefilter {
+            if !bloom.might_contain(id) {
+                return false;
+            }
+        }
+        self.inner.contains(id)
+    }
+    /// Returns false if the ID was already present
+    pub fn insert(&mut self, id: SessionId) -> bool {
+        if !self.inner.insert(id) {
+            return false;
+        }
+        match &mut self.prefilter {
+            Some(bloom) if self.inner.len() > bloom.capacity => self.rebuild_prefilter(),
+            Some(bloom) => bloom.insert(&id),
+            None => {}
+        }
+        true
+    }
+    /// Returns false if the ID was not present
+    pub fn remove(&mut self, id: &SessionId) -> bool {
+        self.inner.remove(id)
+    }
+    pub fn clear(&mut self) {
+        self.inner.clear();
+        if self.prefilter.is_some() {
+            self.rebuild_prefilter();
+        }
+    }
+    /// Rebuild the prefilter for the current contents, dropping bits of removed IDs
+    pub fn rebuild_prefilter(&mut self) {
+        let mut bloom = Bloom::new(self.inner.len() * 2);
+        for id in &self.inner {
+            bloom.insert(id);
+        }
+        self.prefilter = Some(bloom);
+    }
+    pub fn iter(&self) -> impl Iterator<Item = &SessionId> {
+        self.inner.iter()
+    }
+    /// IDs in `self` but not in `other`
+    pub fn difference<'a>(
+        &'a self,
+        other: &'a SessionIdSet,
+    ) -> impl Iterator<Item = &'a SessionId> {
+        self.inner.iter().filter(move |id| !other.contains(id))
+    }
+    /// Sorted IDs, concatenated
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut ids: Vec<&SessionId> = self.inner.iter().collect();
+        ids.sort_unstable();
+        let mut buf = Vec::with_capacity(ids.len() * SESSION_ID_SIZE);
+        for id in ids {
+            buf.extend_from_slice(id.as_ref());
+        }
+        buf
+    }
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        if !bytes.len().is_multiple_of(SESSION_ID_SIZE) {
+            return Err(errors::invalid_length(
+                bytes.len().next_multiple_of(SESSION_ID_SIZE),
+                bytes.len(),
+            ));
+        }
+        bytes
+            .chunks_exact(SESSION_ID_SIZE)
+            .map(SessionId::try_from)
+            .collect()
+    }
+}
+
+impl PartialEq for SessionIdSet {
+    fn eq(&self, other: &Self) -> bool {
+        self.inner == other.inner
+    }
+}
+impl Eq for SessionIdSet {}
+
+impl Extend<SessionId> for SessionIdSet {
+    fn extend<T: IntoIterator<Item = SessionId>>(&mut self, iter: T) {
+        let iter = iter.into_iter();
+        self.inner.reserve(iter.size_hint().0);
+        let had_prefilter = self.prefilter.take().is_some();
+        self.inner.extend(iter);
+        if had_prefilter {
+            self.rebuild_prefilter();
+        }
+    }
+}
+impl<'a> Extend<&'a SessionId> for SessionIdSet {
+    fn extend<T: IntoIterator<Item = &'a SessionId>>(&mut self, iter: T) {
+        self.extend(iter.into_iter().copied())
+    }
+}
+impl FromIterator<SessionId> for SessionIdSet {
+    fn from_iter<T: IntoIterator<Item = SessionId>>(iter: T) -> Self {
+        let mut set = SessionIdSet::new();
+        set.extend(iter);
+        set
+    }
+}
+impl<'a> IntoIterator for &'a SessionIdSet {
+    type Item = &'a SessionId;
+    type IntoIter = std::collections::hash_set::Iter<'a, SessionId>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.inner.iter()
+    }
+}
+
+/// Serialized as the concatenated sorted IDs (see [`SessionIdSet::to_bytes`])
+impl Serialize for SessionIdSet {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(&self.to_bytes())
+    }
+}
+impl<'de> Deserialize<'de> for SessionIdSet {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let bytes = Vec::<u8>::deserialize(deserializer)?;
+        SessionIdSet::from_bytes(&bytes).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{new_session_id_pair, ISessionIdPair};
+
+    #[test]
+    fn test_session_id_set() {
+        let ids: Vec<SessionId> = (0..1000)
+            .map(|_| new_session_id_pair().unwrap().get_id())
+            .collect();
+        let others: Vec<SessionId> = (0..1000)
+            .map(|_| new_session_id_pair().unwrap().get_id())
+            .collect();
+
+        for mut set in [SessionIdSet::new(), SessionIdSet::with_prefilter(10)] {
+            for id in &ids {
+                assert!(set.insert(*id));
+            }
+            assert!(!set.insert(ids[0]));
+            assert_eq!(set.len(), ids.len());
+            assert!(ids.iter().all(|id| set.contains(id)));
+            assert!(others.iter().all(|id| !set.contains(id)));
+
+            assert!(set.remove(&ids[0]));
+            assert!(!set.contains(&ids[0]));
+            assert!(!set.remove(&ids[0]));
+            set.rebuild_prefilter();
+            assert!(set.contains(&ids[1]));
+        }
+
+        let mut set = SessionIdSet::with_prefilter(0);
+        set.extend(&ids);
+        assert!(ids.iter().all(|id| set.contains(id)));
+        let half: SessionIdSet = ids[..500].iter().copied().collect();
+        assert_eq!(set.difference(&half).count(), 500);
+    }
+
+    #[test]
+    fn test_session_id_set_serialize() {
+        let set: SessionIdSet = (0..3u8).map(|v| SessionId::from([v; 32])).collect();
+        let bytes = set.to_bytes();
+        assert_eq!(bytes.len(), 96);
+        assert_eq!(bytes[32], 1);
+        assert_eq!(SessionIdSet::from_bytes(&bytes).unwrap(), set);
+        assert!(SessionIdSet::from_bytes(&bytes[1..]).is_err());
+
+        let json = serde_json::to_string(&set).unwrap();
+        assert_eq!(serde_json::from_str::<SessionIdSet>(&json).unwrap(), set);
+        let bin = bincode::serialize(&set).unwrap();
+        assert_eq!(bincode::deserialize::<SessionIdSet>(&bin).unwrap(), set);
+    }
+}
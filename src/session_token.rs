@@ -0,0 +1,250 @@
+//! JWT session tokens. Enabled with the `jose` feature.
+//!
+//! Tokens are EdDSA-signed JWTs whose `iss` and `sub` are SessionIds
+//! (base64url, same as the JWS `kid`).
+use crate::errors::{Result, SessionIdError};
+use crate::jose::{json_error, sign_jws_with_typ, verify_jws_with_header};
+use crate::time::{Clock, SystemClock};
+use crate::{ISessionIdPair, SessionId, SessionIdPair};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+const JWT_TYP: &str = "JWT";
+
+/// Claims of a session token
+#[derive(Deserialize, Serialize, Eq, PartialEq, Clone, Debug)]
+pub struct SessionTokenClaims {
+    /// Issuer
+    #[serde(serialize_with = "as_kid", deserialize_with = "from_kid")]
+    pub iss: SessionId,
+    /// Subject
+    #[serde(serialize_with = "as_kid", deserialize_with = "from_kid")]
+    pub sub: SessionId,
+    /// Audience
+    #[serde(
+        default,
+        skip_serializing_if = "Vec::is_empty",
+        serialize_with = "as_audience",
+        deserialize_with = "from_audience"
+    )]
+    pub aud: Vec<String>,
+    /// Expiration time (seconds since UNIX epoch)
+    pub exp: u64,
+    /// Not before (seconds since UNIX epoch)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nbf: Option<u64>,
+    /// Issued at (seconds since UNIX epoch)
+    pub iat: u64,
+}
+
+impl SessionTokenClaims {
+    /// Claims issued now and valid for `ttl` seconds
+    pub fn new(iss: SessionId, sub: SessionId, ttl: u64) -> Self {
+        Self::new_at(iss, sub, ttl, &SystemClock)
+    }
+    /// Claims issued at the time of `clock` and valid for `ttl` seconds
+    pub fn new_at(iss: SessionId, sub: SessionId, ttl: u64, clock: &dyn Clock) -> Self {
+        let now = clock.now();
+        SessionTokenClaims {
+            iss,
+            sub,
+            aud: Vec::new(),
+            exp: now.saturating_add(ttl),
+            nbf: None,
+            iat: now,
+        }
+    }
+    pub fn with_audience(mut self, aud: impl Into<String>) -> Self {
+        self.aud.push(aud.into());
+        self
+    }
+    pub fn with_not_before(mut self, nbf: u64) -> Self {
+        self.nbf = Some(nbf);
+        self
+    }
+}
+
+/// Validation rules of a session token
+#[derive(Clone, Debug)]
+pub struct SessionTokenValidation {
+    /// Required issuer
+    pub issuer: SessionId,
+    /// Required audience. `aud` must contain it if set.
+    pub audience: Option<String>,
+    /// Allowed clock skew in seconds
+    pub leeway: u64,
+}
+
+impl SessionTokenValidation {
+    /// Accept only tokens issued by `issuer`, with any audience and no leeway
+    pub fn new(issuer: SessionId) -> Self {
+        SessionTokenValidation {
+            issuer,
+            audience: None,
+            leeway: 0,
+        }
+    }
+    pub fn with_audience(mut self, audience: impl Into<String>) -> Self {
+        self.audience = Some(audience.into());
+        self
+    }
+    pub fn with_leeway(mut self, leeway: u64) -> Self {
+        self.leeway = leeway;
+        self
+    }
+}
+
+/// Mint and validate JWT session tokens
+pub struct SessionToken;
+
+impl SessionToken {
+    /// Mint a token signed by `issuer`. `claims.iss` must be the issuer's session ID.
+    pub fn mint(issuer: &SessionIdPair, claims: &SessionTokenClaims) -> Result<String> {
+        if claims.iss != issuer.get_id() {
+            return Err(SessionIdError::InvalidClaim("iss"));
+        }
+        let payload = serde_json::to_vec(claims).map_err(json_error)?;
+        sign_jws_with_typ(issuer, Some(JWT_TYP), &payload)
+    }
+    /// Verify the signature and validate the claims at the current time
+    pub fn validate(
+        token: &str,
+        validation: &SessionTokenValidation,
+    ) -> Result<SessionTokenClaims> {
+        Self::validate_at(token, validation, &SystemClock)
+    }
+    /// Verify the signature and validate the claims at the time of `clock`
+    pub fn validate_at(
+        token: &str,
+        validation: &SessionTokenValidation,
+        clock: &dyn Clock,
+    ) -> Result<SessionTokenClaims> {
+        let now = clock.now();
+        let (signer, header, payload) = verify_jws_with_header(token)?;
+        if header.typ.as_deref().is_some_and(|v| v != JWT_TYP) {
+            return Err(SessionIdError::InvalidFormat(
+                "jwt: typ is not JWT".to_string(),
+            ));
+        }
+        let claims: SessionTokenClaims = serde_json::from_slice(&payload).map_err(json_error)?;
+        if claims.iss != signer {
+            return Err(SessionIdError::InvalidClaim("iss"));
+        }
+        if claims.iss != validation.issuer {
+            return Err(SessionIdError::InvalidClaim("iss"));
+        }
+        if let Some(aud) = &validation.audience {
+            if !claims.aud.contains(aud) {
+                return Err(SessionIdError::InvalidClaim("aud"));
+            }
+        }
+        if now >= claims.exp.saturating_add(validation.leeway) {
+            return Err(SessionIdError::Expired);
+        }
+        if claims
+            .nbf
+            .is_some_and(|nbf| now.saturating_add(validation.leeway) < nbf)
+        {
+            return Err(SessionIdError::NotYetValid);
+        }
+        Ok(claims)
+    }
+}
+
+fn as_kid<S: Serializer>(v: &SessionId, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&v.to_jws_kid())
+}
+
+fn from_kid<'de, D: Deserializer<'de>>(deserializer: D) -> Result<SessionId, D::Error> {
+    let s = String::deserialize(deserializer)?;
+    SessionId::from_jws_kid(&s).map_err(de::Error::custom)
+}
+
+fn as_audience<S: Serializer>(v: &[String], serializer: S) -> Result<S::Ok, S::Error> {
+    if v.len() == 1 {
+        serializer.serialize_str(&v[0])
+    } else {
+        v.serialize(serializer)
+    }
+}
+
+fn from_audience<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(String),
+        Many(Vec<String>),
+    }
+    Ok(match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(v) => vec![v],
+        OneOrMany::Many(v) => v,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{new_session_id_pair, ManualClock};
+
+    #[test]
+    fn test_session_token() {
+        let issuer = new_session_id_pair().unwrap();
+        let subject = new_session_id_pair().unwrap().get_id();
+        let claims = SessionTokenClaims::new(issuer.get_id(), subject, 60).with_audience("world");
+        let token = SessionToken::mint(&issuer, &claims).unwrap();
+
+        let validation = SessionTokenValidation::new(issuer.get_id()).with_audience("world");
+        assert_eq!(SessionToken::validate(&token, &validation).unwrap(), claims);
+        assert_eq!(
+            SessionToken::validate(&token, &SessionTokenValidation::new(issuer.get_id())).unwrap(),
+            claims
+        );
+
+        let res = SessionToken::validate_at(&token, &validation, &ManualClock::new(claims.exp));
+        assert!(matches!(res, Err(SessionIdError::Expired)));
+        let lenient = validation.clone().with_leeway(10);
+        assert!(SessionToken::validate_at(&token, &lenient, &ManualClock::new(claims.exp)).is_ok());
+
+        let other_aud = SessionTokenValidation {
+            audience: Some("other".to_string()),
+            ..validation.clone()
+        };
+        let res = SessionToken::validate(&token, &other_aud);
+        assert!(matches!(res, Err(SessionIdError::InvalidClaim("aud"))));
+
+        let other_iss = SessionTokenValidation {
+            issuer: subject,
+            ..validation.clone()
+        };
+        let res = SessionToken::validate(&token, &other_iss);
+        assert!(matches!(res, Err(SessionIdError::InvalidClaim("iss"))));
+    }
+
+    #[test]
+    fn test_session_token_claims() {
+        let issuer = new_session_id_pair().unwrap();
+        let subject = new_session_id_pair().unwrap().get_id();
+
+        let claims = SessionTokenClaims::new(subject, subject, 60);
+        assert!(matches!(
+            SessionToken::mint(&issuer, &claims),
+            Err(SessionIdError::InvalidClaim("iss"))
+        ));
+
+        let claims = SessionTokenClaims::new(issuer.get_id(), subject, 60)
+            .with_not_before(SystemClock.now() + 30);
+        let token = SessionToken::mint(&issuer, &claims).unwrap();
+        let res = SessionToken::validate(&token, &SessionTokenValidation::new(issuer.get_id()));
+        assert!(matches!(res, Err(SessionIdError::NotYetValid)));
+
+        let json = serde_json::to_value(
+            SessionTokenClaims::new(issuer.get_id(), subject, 60)
+                .with_audience("a")
+                .with_audience("b"),
+        )
+        .unwrap();
+        assert_eq!(json["aud"], serde_json::json!(["a", "b"]));
+        assert_eq!(json["iss"], issuer.get_id().to_jws_kid());
+        let claims: SessionTokenClaims = serde_json::from_value(json).unwrap();
+        assert_eq!(claims.aud, vec!["a", "b"]);
+    }
+}
@@ -0,0 +1,41 @@
+//! Deterministic UUID view of session IDs. Enabled with the `uuid` feature.
+//!
+//! [`SessionId::to_uuid`] is an RFC 9562 UUIDv8 whose 122 free bits are the first bits of
+//! `SHA-256(context || id)`. It is stable across runs and platforms, so external systems that
+//! only accept UUIDs can key players by it.
+//!
+//! The mapping is one-way and not injective. Random collisions follow the birthday bound of
+//! 122 bits (about 2^61 IDs for a 50% chance), but a malicious peer can grind keys to hit a
+//! chosen UUID with about 2^122 work, and no faster. Treat the UUID as a lookup key, never as
+//! proof of identity; verify signatures against the [`SessionId`] itself.
+use crate::SessionId;
+use ed25519_dalek::Digest;
+use sha2::Sha256;
+use uuid::{Builder, Uuid};
+
+const UUID_CONTEXT: &[u8] = b"verse-session-id/uuid/v1";
+
+impl SessionId {
+    /// Stable UUIDv8 derived from the key
+    pub fn to_uuid(&self) -> Uuid {
+        let hash = Sha256::new().chain(UUID_CONTEXT).chain(self).finalize();
+        let mut bytes = [0u8; 16];
+        bytes.copy_from_slice(&hash[..16]);
+        Builder::from_custom_bytes(bytes).into_uuid()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_uuid() {
+        let a = SessionId::from([1; 32]).to_uuid();
+        assert_eq!(a, SessionId::from([1; 32]).to_uuid());
+        assert_ne!(a, SessionId::from([2; 32]).to_uuid());
+        assert_eq!(a.get_version_num(), 8);
+        assert_eq!(a.get_variant(), uuid::Variant::RFC4122);
+        assert_eq!(a.to_string(), "cf664ab7-86db-8cad-932b-0efc3b38423c");
+    }
+}
@@ -0,0 +1,201 @@
+//! Requests signed by a session identity, carried in the
+//! `X-Verse-Session` / `X-Verse-Signature` / `X-Verse-Created` headers.
+//! Shared by the web framework integrations.
+//!
+//! The signature covers the method, the path and query, the creation time and the body,
+//! so a captured request cannot be replayed against another endpoint, and is only
+//! accepted for [`DEFAULT_SIGNED_BODY_MAX_AGE`] seconds against the same one.
+use crate::encoding::write_str;
+use crate::errors::{Result, SessionIdError};
+use crate::time::{Clock, SystemClock};
+use crate::{ISessionIdPair, SessionId, SessionIdPair, SessionIdPublic, SignatureSet};
+
+const SIGNED_BODY_CONTEXT: &[u8] = b"verse-session-id/body/v2";
+
+/// Header carrying the signer's SessionId (base64)
+pub const VERSE_SESSION_HEADER: &str = "x-verse-session";
+/// Header carrying the SignatureSet over the request (base64)
+pub const VERSE_SIGNATURE_HEADER: &str = "x-verse-signature";
+/// Header carrying the signing time (seconds since UNIX epoch)
+pub const VERSE_CREATED_HEADER: &str = "x-verse-created";
+/// Default lifetime of a signed request in seconds
+pub const DEFAULT_SIGNED_BODY_MAX_AGE: u64 = 300;
+/// Tolerated clock skew for `created` in the future, in seconds
+const SIGNED_BODY_MAX_SKEW: u64 = 60;
+/// Largest body buffered for verification (same as axum's default body limit)
+#[cfg_attr(not(feature = "axum"), allow(dead_code))]
+pub(crate) const MAX_SIGNED_BODY_SIZE: usize = 2 * 1024 * 1024;
+
+/// The parts of a request covered by the signature
+#[derive(Clone, Copy, Debug)]
+pub struct SignedRequest<'a> {
+    /// HTTP method, compared case-insensitively
+    pub method: &'a str,
+    /// Path and query as sent, e.g. `/rooms?id=1`
+    pub path_and_query: &'a str,
+    pub body: &'a [u8],
+}
+
+/// Header values signing a [`SignedRequest`]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SignedBodyHeaders {
+    /// `X-Verse-Session`
+    pub session: String,
+    /// `X-Verse-Signature`
+    pub signature: String,
+    /// `X-Verse-Created`
+    pub created: String,
+}
+
+fn signed_head(request: &SignedRequest, created: u64) -> Result<Vec<u8>> {
+    let mut head = Vec::new();
+    write_str(&mut head, &request.method.to_ascii_uppercase())?;
+    write_str(&mut head, request.path_and_query)?;
+    head.extend_from_slice(&created.to_le_bytes());
+    Ok(head)
+}
+
+/// Header values signing `request` at the current time
+pub fn sign_body(pair: &SessionIdPair, request: &SignedRequest) -> Result<SignedBodyHeaders> {
+    sign_body_at(pair, request, &SystemClock)
+}
+
+/// Header values signing `request` at the time of `clock`
+pub fn sign_body_at(
+    pair: &SessionIdPair,
+    request: &SignedRequest,
+    clock: &dyn Clock,
+) -> Result<SignedBodyHeaders> {
+    let created = clock.now();
+    let head = signed_head(request, created)?;
+    let sig = pair.sign([SIGNED_BODY_CONTEXT, &head, request.body])?;
+    Ok(SignedBodyHeaders {
+        session: pair.get_id().to_string(),
+        signature: sig.to_string(),
+        created: created.to_string(),
+    })
+}
+
+/// Verify the `X-Verse-Session` / `X-Verse-Signature` / `X-Verse-Created` header values
+/// against `request`, rejecting signatures older than [`DEFAULT_SIGNED_BODY_MAX_AGE`].
+/// Returns the signer.
+pub fn verify_body(
+    session: &str,
+    signature: &str,
+    created: &str,
+    request: &SignedRequest,
+) -> Result<SessionId> {
+    verify_body_at(
+        session,
+        signature,
+        created,
+        request,
+        DEFAULT_SIGNED_BODY_MAX_AGE,
+        &SystemClock,
+    )
+}
+
+/// Like [`verify_body`], at the time of `clock` and rejecting signatures older than
+/// `max_age` seconds
+pub fn verify_body_at(
+    session: &str,
+    signature: &str,
+    created: &str,
+    request: &SignedRequest,
+    max_age: u64,
+    clock: &dyn Clock,
+) -> Result<SessionId> {
+    let session_id: SessionId = session.trim().parse()?;
+    let sig: SignatureSet = signature.trim().parse()?;
+    let created: u64 = created
+        .trim()
+        .parse()
+        .map_err(|_| SessionIdError::InvalidFormat("signed_body: invalid created".to_string()))?;
+    let head = signed_head(request, created)?;
+    session_id.verify([SIGNED_BODY_CONTEXT, &head, request.body], &sig)?;
+    let now = clock.now();
+    if now > created.saturating_add(max_age) {
+        return Err(SessionIdError::Expired);
+    }
+    if created > now.saturating_add(SIGNED_BODY_MAX_SKEW) {
+        return Err(SessionIdError::NotYetValid);
+    }
+    Ok(session_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{new_session_id_pair, ManualClock};
+
+    #[test]
+    fn test_signed_body() {
+        let kp = new_session_id_pair().unwrap();
+        let request = SignedRequest {
+            method: "POST",
+            path_and_query: "/rooms?id=1",
+            body: b"{}",
+        };
+        let h = sign_body(&kp, &request).unwrap();
+        let verify =
+            |request: &SignedRequest| verify_body(&h.session, &h.signature, &h.created, request);
+        assert_eq!(verify(&request).unwrap(), kp.get_id());
+        let lowercase = SignedRequest {
+            method: "post",
+            ..request
+        };
+        assert!(verify(&lowercase).is_ok());
+        assert!(verify(&SignedRequest {
+            body: b"{ }",
+            ..request
+        })
+        .is_err());
+        assert!(verify(&SignedRequest {
+            method: "PUT",
+            ..request
+        })
+        .is_err());
+        assert!(verify(&SignedRequest {
+            path_and_query: "/rooms?id=2",
+            ..request
+        })
+        .is_err());
+        assert!(verify_body(&h.signature, &h.session, &h.created, &request).is_err());
+        let created = (h.created.parse::<u64>().unwrap() + 1).to_string();
+        assert!(verify_body(&h.session, &h.signature, &created, &request).is_err());
+    }
+
+    #[test]
+    fn test_signed_body_freshness() {
+        let kp = new_session_id_pair().unwrap();
+        let request = SignedRequest {
+            method: "POST",
+            path_and_query: "/",
+            body: b"hello",
+        };
+        let clock = ManualClock::new(1_700_000_000);
+        let h = sign_body_at(&kp, &request, &clock).unwrap();
+        assert_eq!(h.created, "1700000000");
+        let verify = |max_age, now| {
+            verify_body_at(
+                &h.session,
+                &h.signature,
+                &h.created,
+                &request,
+                max_age,
+                &ManualClock::new(now),
+            )
+        };
+        assert!(verify(60, 1_700_000_060).is_ok());
+        assert!(matches!(
+            verify(60, 1_700_000_061),
+            Err(SessionIdError::Expired)
+        ));
+        assert!(verify(60, 1_700_000_000 - SIGNED_BODY_MAX_SKEW).is_ok());
+        assert!(matches!(
+            verify(60, 1_700_000_000 - SIGNED_BODY_MAX_SKEW - 1),
+            Err(SessionIdError::NotYetValid)
+        ));
+        assert!(verify_body(&h.session, &h.signature, &h.created, &request).is_err());
+    }
+}
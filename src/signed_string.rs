@@ -0,0 +1,97 @@
+//! Single-string signed message: `<session id>.<signature set>.<payload>`.
+//!
+//! All three parts are standard base64, the same encoding as the `Display` of
+//! [`SessionId`] and [`SignatureSet`]. The signature is the plain salted signature
+//! over the payload, so a JS client can verify it with the existing `verify` API.
+use crate::errors::{Result, SessionIdError};
+use crate::{ISessionIdPair, SessionId, SessionIdPair, SessionIdPublic, SignatureSet};
+use std::fmt;
+
+const SEPARATOR: char = '.';
+
+/// Message together with its signer and signature
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SignedString {
+    pub session_id: SessionId,
+    pub signature: SignatureSet,
+    pub payload: Vec<u8>,
+}
+
+impl SignedString {
+    /// Sign `msg` and encode it as a single string
+    pub fn encode(pair: &SessionIdPair, msg: &[u8]) -> Result<String> {
+        Ok(SignedString {
+            session_id: pair.get_id(),
+            signature: pair.sign([msg])?,
+            payload: msg.to_vec(),
+        }
+        .to_string())
+    }
+    /// Parse `s` and verify its signature
+    pub fn decode_verify(s: &str) -> Result<Self> {
+        let mut parts = s.split(SEPARATOR);
+        let (Some(session_id), Some(signature), Some(payload), None) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            return Err(SessionIdError::InvalidFormat(
+                "signed string: expected 3 parts".to_string(),
+            ));
+        };
+        let signed = SignedString {
+            session_id: session_id.parse()?,
+            signature: signature.parse()?,
+            payload: base64::decode(payload)?,
+        };
+        signed
+            .session_id
+            .verify([&signed.payload], &signed.signature)?;
+        Ok(signed)
+    }
+}
+
+impl fmt::Display for SignedString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}{SEPARATOR}{}{SEPARATOR}{}",
+            self.session_id,
+            self.signature,
+            base64::encode(&self.payload)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::new_session_id_pair;
+
+    #[test]
+    fn test_signed_string() {
+        let pair = new_session_id_pair().unwrap();
+        let s = SignedString::encode(&pair, b"hello").unwrap();
+        assert_eq!(s.split('.').count(), 3);
+        let signed = SignedString::decode_verify(&s).unwrap();
+        assert_eq!(signed.session_id, pair.get_id());
+        assert_eq!(signed.payload, b"hello");
+        assert_eq!(signed.to_string(), s);
+
+        // compatible with verifying the parts separately
+        let parts: Vec<&str> = s.split('.').collect();
+        let id: SessionId = parts[0].parse().unwrap();
+        assert!(id
+            .verify(vec![b"hello"], &parts[1].parse().unwrap())
+            .is_ok());
+
+        let other = SignedString::encode(&pair, b"other").unwrap();
+        let forged = format!(
+            "{}.{}",
+            &s[..s.rfind('.').unwrap()],
+            &other[other.rfind('.').unwrap() + 1..]
+        );
+        assert!(SignedString::decode_verify(&forged).is_err());
+        assert!(SignedString::decode_verify(&format!("{}.", s)).is_err());
+        assert!(SignedString::decode_verify(&parts[..2].join(".")).is_err());
+        assert!(SignedString::decode_verify(&SignedString::encode(&pair, b"").unwrap()).is_ok());
+    }
+}
@@ -0,0 +1,148 @@
+//! Expiring URLs signed by a session identity.
+//!
+//! `sid` (base64url SessionId), `exp` (seconds since UNIX epoch) and `sig` (base64url
+//! SignatureSet) are appended to the query. The signature covers the URL up to `sig`,
+//! excluding the fragment.
+use crate::base64url;
+use crate::errors::{Result, SessionIdError};
+use crate::time::{Clock, SystemClock};
+use crate::{ISessionIdPair, SessionId, SessionIdPair, SessionIdPublic, SignatureSet};
+
+const URL_CONTEXT: &[u8] = b"verse-session-id/url/v1";
+const SIG_PARAM: &str = "&sig=";
+
+fn split_fragment(url: &str) -> (&str, &str) {
+    match url.find('#') {
+        Some(i) => url.split_at(i),
+        None => (url, ""),
+    }
+}
+
+fn query_params(url: &str) -> impl Iterator<Item = (&str, &str)> {
+    url.split_once('?')
+        .map(|(_, q)| q)
+        .unwrap_or("")
+        .split('&')
+        .map(|v| v.split_once('=').unwrap_or((v, "")))
+}
+
+/// URL signing with a SessionIdPair
+pub trait UrlSigner {
+    /// Append `sid`, `exp` and `sig` to `url`. It expires at `expires_at` (seconds since UNIX epoch).
+    fn sign_url(&self, url: &str, expires_at: u64) -> Result<String>;
+}
+
+impl UrlSigner for SessionIdPair {
+    fn sign_url(&self, url: &str, expires_at: u64) -> Result<String> {
+        let (url, fragment) = split_fragment(url);
+        if query_params(url).any(|(k, _)| matches!(k, "sid" | "exp" | "sig")) {
+            return Err(SessionIdError::InvalidArgument(
+                "url already has sid, exp or sig",
+            ));
+        }
+        let sep = if !url.contains('?') {
+            "?"
+        } else if url.ends_with('?') || url.ends_with('&') {
+            ""
+        } else {
+            "&"
+        };
+        let signed = format!(
+            "{}{}sid={}&exp={}",
+            url,
+            sep,
+            base64url::encode(self.get_id()),
+            expires_at
+        );
+        let sig = self.sign([URL_CONTEXT, signed.as_bytes()])?;
+        Ok(format!(
+            "{}{}{}{}",
+            signed,
+            SIG_PARAM,
+            base64url::encode(sig.to_bytes()),
+            fragment
+        ))
+    }
+}
+
+/// Verify a URL signed by [`UrlSigner::sign_url`]. Returns the signer.
+pub fn verify_url(url: &str) -> Result<SessionId> {
+    verify_url_at(url, &SystemClock)
+}
+
+/// Verify a signed URL at the time of `clock`. Returns the signer.
+pub fn verify_url_at(url: &str, clock: &dyn Clock) -> Result<SessionId> {
+    let now = clock.now();
+    let (url, _) = split_fragment(url);
+    let (signed, sig) = url.rsplit_once(SIG_PARAM).ok_or(SessionIdError::Required)?;
+    let sig = SignatureSet::try_from(base64url::decode(sig)?)?;
+
+    let (mut sid, mut exp) = (None, None);
+    for (k, v) in query_params(signed) {
+        match k {
+            "sid" if sid.is_none() => sid = Some(v),
+            "exp" if exp.is_none() => exp = Some(v),
+            "sid" | "exp" | "sig" => {
+                return Err(SessionIdError::InvalidFormat(format!(
+                    "url: duplicate {}",
+                    k
+                )))
+            }
+            _ => {}
+        }
+    }
+    let sid = base64url::decode(sid.ok_or(SessionIdError::Required)?)?;
+    let session_id = SessionId::try_from(sid)?;
+    let exp: u64 = exp
+        .ok_or(SessionIdError::Required)?
+        .parse()
+        .map_err(|_| SessionIdError::InvalidClaim("exp"))?;
+
+    session_id.verify([URL_CONTEXT, signed.as_bytes()], &sig)?;
+    if now >= exp {
+        return Err(SessionIdError::Expired);
+    }
+    Ok(session_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{new_session_id_pair, ManualClock};
+
+    #[test]
+    fn test_signed_url() {
+        let kp = new_session_id_pair().unwrap();
+        let exp = SystemClock.now() + 60;
+        for url in [
+            "https://example.com/assets/a.glb",
+            "https://example.com/assets/a.glb?v=2",
+            "https://example.com/assets/a.glb?",
+            "/assets/a.glb?v=2#top",
+        ] {
+            let signed = kp.sign_url(url, exp).unwrap();
+            assert_eq!(verify_url(&signed).unwrap(), kp.get_id());
+        }
+
+        let signed = kp.sign_url("https://example.com/a.glb#top", exp).unwrap();
+        assert!(signed.starts_with("https://example.com/a.glb?sid="));
+        assert!(signed.ends_with("#top"));
+        assert!(matches!(
+            verify_url_at(&signed, &ManualClock::new(exp)),
+            Err(SessionIdError::Expired)
+        ));
+
+        let tampered = signed.replace("/a.glb", "/b.glb");
+        assert!(matches!(
+            verify_url(&tampered),
+            Err(SessionIdError::Signature(_))
+        ));
+        let tampered = signed.replace(&format!("exp={}", exp), &format!("exp={}", exp + 1));
+        assert!(verify_url(&tampered).is_err());
+        let appended = signed.replace("#top", "&x=1#top");
+        assert!(verify_url(&appended).is_err());
+
+        assert!(verify_url("https://example.com/a.glb").is_err());
+        assert!(kp.sign_url("https://example.com/a.glb?sig=1", exp).is_err());
+    }
+}
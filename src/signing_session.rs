@@ -0,0 +1,202 @@
+//! Signatures carrying a monotonic sequence number, for ordered replay-proof channels.
+//!
+//! The sender signs every message with a [`SigningSession`], which binds the channel
+//! name and the next sequence number into the signature. The receiver keeps a
+//! [`VerifyingSession`] per peer and channel and rejects any sequence number that is
+//! not greater than the last accepted one. Gaps are allowed so lost messages do not
+//! stall the channel; compare the returned sequence number to detect them.
+//! For unordered transports use [`VerifyingSession::with_replay_window`] instead.
+use crate::errors::{Result, SessionIdError};
+use crate::{
+    ISessionIdPair, ReplayWindow, SessionId, SessionIdPair, SessionIdPublic, SignatureSet,
+    SIGNATURE_SET_SIZE,
+};
+
+const SIGNING_SESSION_CONTEXT: &[u8] = b"verse-session-id/signing-session/v1";
+/// Bytes of SequencedSignature (sequence number followed by the signature set)
+pub const SEQUENCED_SIGNATURE_SIZE: usize = 8 + SIGNATURE_SET_SIZE;
+
+/// Signature of one message and its sequence number
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct SequencedSignature {
+    pub seq: u64,
+    pub signature: SignatureSet,
+}
+
+impl SequencedSignature {
+    /// Sequence number (little endian) followed by the signature set
+    pub fn to_bytes(&self) -> [u8; SEQUENCED_SIGNATURE_SIZE] {
+        let mut buf = [0u8; SEQUENCED_SIGNATURE_SIZE];
+        buf[..8].copy_from_slice(&self.seq.to_le_bytes());
+        buf[8..].copy_from_slice(&self.signature.to_bytes());
+        buf
+    }
+    pub fn from_bytes(bytes: &[u8; SEQUENCED_SIGNATURE_SIZE]) -> Self {
+        let mut seq = [0u8; 8];
+        seq.copy_from_slice(&bytes[..8]);
+        let mut signature = [0u8; SIGNATURE_SET_SIZE];
+        signature.copy_from_slice(&bytes[8..]);
+        SequencedSignature {
+            seq: u64::from_le_bytes(seq),
+            signature: SignatureSet::from_bytes(&signature),
+        }
+    }
+}
+
+fn signed_payload<'a>(
+    channel: &'a [u8],
+    len: &'a [u8; 8],
+    seq: &'a [u8; 8],
+    payload: &'a [u8],
+) -> [&'a [u8]; 5] {
+    [SIGNING_SESSION_CONTEXT, len, channel, seq, payload]
+}
+
+/// Sender side. Signs messages with increasing sequence numbers starting at 0.
+#[derive(Debug)]
+pub struct SigningSession<'a> {
+    pair: &'a SessionIdPair,
+    channel: Vec<u8>,
+    next_seq: u64,
+}
+
+impl<'a> SigningSession<'a> {
+    pub fn new(pair: &'a SessionIdPair, channel: &[u8]) -> Self {
+        Self::resume(pair, channel, 0)
+    }
+    /// Continue a session whose state was persisted, e.g. across a reconnect
+    pub fn resume(pair: &'a SessionIdPair, channel: &[u8], next_seq: u64) -> Self {
+        SigningSession {
+            pair,
+            channel: channel.to_vec(),
+            next_seq,
+        }
+    }
+    pub fn channel(&self) -> &[u8] {
+        &self.channel
+    }
+    /// Sequence number of the next signature
+    pub fn next_seq(&self) -> u64 {
+        self.next_seq
+    }
+    pub fn sign(&mut self, payload: &[u8]) -> Result<SequencedSignature> {
+        let seq = self.next_seq;
+        let next_seq = seq
+            .checked_add(1)
+            .ok_or(SessionIdError::InvalidArgument("sequence number overflow"))?;
+        let len = (self.channel.len() as u64).to_le_bytes();
+        let signature = self
+            .pair
+            .sign(signed_payload(&self.channel, &len, &seq.to_le_bytes(), payload).to_vec())?;
+        self.next_seq = next_seq;
+        Ok(SequencedSignature { seq, signature })
+    }
+}
+
+/// Receiver side. Accepts only strictly increasing sequence numbers from `peer`,
+/// or any unseen number inside the replay window.
+#[derive(Clone, Debug)]
+pub struct VerifyingSession {
+    peer: SessionId,
+    channel: Vec<u8>,
+    last_seq: Option<u64>,
+    window: Option<ReplayWindow>,
+}
+
+impl VerifyingSession {
+    pub fn new(peer: SessionId, channel: &[u8]) -> Self {
+        VerifyingSession {
+            peer,
+            channel: channel.to_vec(),
+            last_seq: None,
+            window: None,
+        }
+    }
+    /// Accept messages delivered out of order, rejecting duplicates with a
+    /// [`ReplayWindow`] of `window_size` sequence numbers
+    pub fn with_replay_window(peer: SessionId, channel: &[u8], window_size: usize) -> Self {
+        VerifyingSession {
+            window: Some(ReplayWindow::new(window_size)),
+            ..Self::new(peer, channel)
+        }
+    }
+    pub fn peer(&self) -> &SessionId {
+        &self.peer
+    }
+    /// Highest accepted sequence number
+    pub fn last_seq(&self) -> Option<u64> {
+        match &self.window {
+            Some(window) => window.highest(),
+            None => self.last_seq,
+        }
+    }
+    /// Verify the signature of the current message. Returns its sequence number.
+    /// The state only advances when the message is accepted.
+    pub fn verify(&mut self, payload: &[u8], sig: &SequencedSignature) -> Result<u64> {
+        self.verify_signature(payload, sig)?;
+        if let Some(window) = &mut self.window {
+            window.check_and_update(sig.seq)?;
+            return Ok(sig.seq);
+        }
+        if self.last_seq.is_some_and(|last| sig.seq <= last) {
+            return Err(SessionIdError::Replayed(sig.seq));
+        }
+        self.last_seq = Some(sig.seq);
+        Ok(sig.seq)
+    }
+    /// Verify the signature without checking or advancing the sequence number.
+    /// For use with a custom replay policy.
+    pub fn verify_signature(&self, payload: &[u8], sig: &SequencedSignature) -> Result<()> {
+        let len = (self.channel.len() as u64).to_le_bytes();
+        self.peer.verify(
+            signed_payload(&self.channel, &len, &sig.seq.to_le_bytes(), payload),
+            &sig.signature,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::new_session_id_pair;
+
+    #[test]
+    fn test_signing_session() {
+        let pair = new_session_id_pair().unwrap();
+        let mut signer = SigningSession::new(&pair, b"chat");
+        let mut verifier = VerifyingSession::new(pair.get_id(), b"chat");
+
+        let s0 = signer.sign(b"hello").unwrap();
+        let s1 = signer.sign(b"world").unwrap();
+        let s2 = signer.sign(b"!").unwrap();
+        assert_eq!((s0.seq, s1.seq, signer.next_seq()), (0, 1, 3));
+        assert_eq!(SequencedSignature::from_bytes(&s1.to_bytes()), s1);
+
+        assert_eq!(verifier.verify(b"hello", &s0).unwrap(), 0);
+        // duplicate
+        assert!(matches!(
+            verifier.verify(b"hello", &s0),
+            Err(SessionIdError::Replayed(0))
+        ));
+        // gap, then out of order
+        assert_eq!(verifier.verify(b"!", &s2).unwrap(), 2);
+        assert!(matches!(
+            verifier.verify(b"world", &s1),
+            Err(SessionIdError::Replayed(1))
+        ));
+        assert_eq!(verifier.last_seq(), Some(2));
+
+        // sequence number, payload and channel are bound to the signature
+        let s3 = signer.sign(b"next").unwrap();
+        let forged = SequencedSignature { seq: 4, ..s3 };
+        assert!(verifier.verify(b"next", &forged).is_err());
+        assert!(verifier.verify(b"other", &s3).is_err());
+        assert!(VerifyingSession::new(pair.get_id(), b"chat2")
+            .verify(b"next", &s3)
+            .is_err());
+        assert_eq!(verifier.verify(b"next", &s3).unwrap(), 3);
+
+        let mut resumed = SigningSession::resume(&pair, b"chat", u64::MAX);
+        assert!(resumed.sign(b"x").is_err());
+    }
+}
@@ -0,0 +1,91 @@
+//! Solana keypair file interop.
+//!
+//! `solana-keygen` stores a keypair as a JSON array of 64 numbers: the 32-byte Ed25519 seed
+//! followed by the public key. The same key can sign as a wallet and as a SessionId.
+use crate::errors::{Result, SessionIdError};
+use crate::{session_id_pair_from_bytes, SessionIdPair};
+use zeroize::Zeroizing;
+
+const SOLANA_KEYPAIR_SIZE: usize = 64;
+
+fn solana_error(msg: &str) -> SessionIdError {
+    SessionIdError::InvalidFormat(format!("solana: {}", msg))
+}
+
+/// Conversion between SessionIdPair and Solana keypair files
+pub trait SolanaKeypair: Sized {
+    /// JSON array of the 64 keypair bytes. Anyone holding it can sign as this session ID.
+    fn to_solana_json(&self) -> String;
+    /// Parse a Solana keypair file. The public key must match the seed.
+    fn from_solana_json(s: &str) -> Result<Self>;
+}
+
+impl SolanaKeypair for SessionIdPair {
+    fn to_solana_json(&self) -> String {
+        let bytes = Zeroizing::new(self.to_bytes());
+        let mut s = String::with_capacity(4 * SOLANA_KEYPAIR_SIZE + 2);
+        s.push('[');
+        for (i, b) in bytes.iter().enumerate() {
+            if i > 0 {
+                s.push(',');
+            }
+            s.push_str(&b.to_string());
+        }
+        s.push(']');
+        s
+    }
+    fn from_solana_json(s: &str) -> Result<Self> {
+        let body = s
+            .trim()
+            .strip_prefix('[')
+            .and_then(|v| v.strip_suffix(']'))
+            .ok_or_else(|| solana_error("not a json array"))?;
+        let mut bytes = Zeroizing::new([0u8; SOLANA_KEYPAIR_SIZE]);
+        let mut n = 0;
+        for v in body.split(',') {
+            if n == SOLANA_KEYPAIR_SIZE {
+                return Err(crate::errors::invalid_length(SOLANA_KEYPAIR_SIZE, n + 1));
+            }
+            bytes[n] = v.trim().parse().map_err(|_| solana_error("invalid byte"))?;
+            n += 1;
+        }
+        if n != SOLANA_KEYPAIR_SIZE {
+            return Err(crate::errors::invalid_length(SOLANA_KEYPAIR_SIZE, n));
+        }
+        session_id_pair_from_bytes(&bytes[..])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{new_session_id_pair, ISessionIdPair};
+
+    #[test]
+    fn test_solana_json() {
+        let pair = new_session_id_pair().unwrap();
+        let json = pair.to_solana_json();
+        let parsed: Vec<u8> = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, pair.to_bytes());
+        assert_eq!(
+            SessionIdPair::from_solana_json(&json).unwrap().get_id(),
+            pair.get_id()
+        );
+
+        // solana-keygen writes without spaces, other tools pretty-print
+        let pretty = serde_json::to_string_pretty(&parsed).unwrap();
+        assert_eq!(
+            SessionIdPair::from_solana_json(&pretty).unwrap().get_id(),
+            pair.get_id()
+        );
+
+        let mut mismatched = parsed.clone();
+        mismatched[40] ^= 1;
+        let mismatched = serde_json::to_string(&mismatched).unwrap();
+        assert!(SessionIdPair::from_solana_json(&mismatched).is_err());
+        assert!(SessionIdPair::from_solana_json(&json[1..]).is_err());
+        assert!(SessionIdPair::from_solana_json("[1,2,3]").is_err());
+        assert!(SessionIdPair::from_solana_json(&json.replace("]", ",1]")).is_err());
+        assert!(SessionIdPair::from_solana_json(&json.replacen(",", ",256,", 1)).is_err());
+    }
+}
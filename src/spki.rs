@@ -0,0 +1,48 @@
+//! DER `SubjectPublicKeyInfo` of session IDs (RFC 8410).
+use crate::der;
+use crate::errors::{Result, SessionIdError};
+use crate::SessionId;
+
+/// `SEQUENCE { SEQUENCE { OID 1.3.101.112 }, BIT STRING` header of an Ed25519 SPKI
+const SPKI_PREFIX: [u8; 12] = [
+    0x30, 0x2a, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70, 0x03, 0x21, 0x00,
+];
+
+impl SessionId {
+    /// DER `SubjectPublicKeyInfo`, for X.509 certificates and CSRs (44 bytes)
+    pub fn to_spki_der(&self) -> Vec<u8> {
+        der::sequence(&[&der::ed25519_algorithm(), &der::bit_string(self.as_ref())])
+    }
+    /// Parse an Ed25519 DER `SubjectPublicKeyInfo`
+    pub fn from_spki_der(spki: &[u8]) -> Result<SessionId> {
+        match spki.strip_prefix(&SPKI_PREFIX) {
+            Some(key) => SessionId::try_from(key),
+            None => Err(SessionIdError::InvalidFormat(
+                "spki: not an Ed25519 key".to_string(),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spki_der() {
+        let id = SessionId::from([7; 32]);
+        let spki = id.to_spki_der();
+        assert_eq!(spki.len(), 44);
+        assert_eq!(spki[..12], SPKI_PREFIX);
+        // openssl pkey -pubin -outform der
+        assert_eq!(
+            base64::encode(&spki),
+            "MCowBQYDK2VwAyEABwcHBwcHBwcHBwcHBwcHBwcHBwcHBwcHBwcHBwcHBwc="
+        );
+        assert_eq!(SessionId::from_spki_der(&spki).unwrap(), id);
+        assert!(SessionId::from_spki_der(&spki[..43]).is_err());
+        let mut p256 = spki.clone();
+        p256[8] = 0x71;
+        assert!(SessionId::from_spki_der(&p256).is_err());
+    }
+}
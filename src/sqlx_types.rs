@@ -0,0 +1,107 @@
+//! sqlx column types for [`SessionId`]. Enabled with the `sqlx` feature.
+//!
+//! A `SessionId` binds as its 32 raw bytes (`BYTEA` in Postgres, `BLOB` in SQLite).
+//! For `TEXT` columns, bind [`sqlx::types::Text<SessionId>`](sqlx::types::Text), which
+//! stores the base64 string. Decoding a `SessionId` accepts either column type.
+//!
+//! ```rust,ignore
+//! sqlx::query("INSERT INTO presence (session_id, session_text) VALUES ($1, $2)")
+//!     .bind(sid)
+//!     .bind(Text(sid))
+//!     .execute(&pool)
+//!     .await?;
+//! let sid: SessionId = sqlx::query_scalar("SELECT session_text FROM presence")
+//!     .fetch_one(&pool)
+//!     .await?;
+//! ```
+use crate::SessionId;
+use sqlx::encode::IsNull;
+use sqlx::error::BoxDynError;
+use sqlx::{Database, Decode, Encode, Type, ValueRef};
+
+impl<DB: Database> Type<DB> for SessionId
+where
+    Vec<u8>: Type<DB>,
+    String: Type<DB>,
+{
+    fn type_info() -> DB::TypeInfo {
+        <Vec<u8> as Type<DB>>::type_info()
+    }
+    fn compatible(ty: &DB::TypeInfo) -> bool {
+        <Vec<u8> as Type<DB>>::compatible(ty) || <String as Type<DB>>::compatible(ty)
+    }
+}
+
+impl<'q, DB: Database> Encode<'q, DB> for SessionId
+where
+    Vec<u8>: Encode<'q, DB>,
+{
+    fn encode_by_ref(&self, buf: &mut DB::ArgumentBuffer<'q>) -> Result<IsNull, BoxDynError> {
+        self.to_vec().encode(buf)
+    }
+    fn size_hint(&self) -> usize {
+        self.as_raw().len()
+    }
+}
+
+impl<'r, DB: Database> Decode<'r, DB> for SessionId
+where
+    Vec<u8>: Decode<'r, DB>,
+    String: Decode<'r, DB> + Type<DB>,
+{
+    fn decode(value: DB::ValueRef<'r>) -> Result<Self, BoxDynError> {
+        let is_text = <String as Type<DB>>::compatible(&value.type_info());
+        if is_text {
+            Ok(String::decode(value)?.parse()?)
+        } else {
+            Ok(SessionId::try_from(Vec::<u8>::decode(value)?)?)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::types::Text;
+    use sqlx::{Connection, SqliteConnection};
+
+    #[tokio::test]
+    async fn test_sqlx_sqlite() {
+        let sid = SessionId::from([7; 32]);
+        let mut conn = SqliteConnection::connect("sqlite::memory:").await.unwrap();
+        sqlx::query("CREATE TABLE presence (b BLOB NOT NULL, t TEXT NOT NULL)")
+            .execute(&mut conn)
+            .await
+            .unwrap();
+        sqlx::query("INSERT INTO presence (b, t) VALUES (?, ?)")
+            .bind(sid)
+            .bind(Text(sid))
+            .execute(&mut conn)
+            .await
+            .unwrap();
+
+        let (b, t): (SessionId, SessionId) = sqlx::query_as("SELECT b, t FROM presence")
+            .fetch_one(&mut conn)
+            .await
+            .unwrap();
+        assert_eq!(b, sid);
+        assert_eq!(t, sid);
+        let (raw, text): (Vec<u8>, String) = sqlx::query_as("SELECT b, t FROM presence")
+            .fetch_one(&mut conn)
+            .await
+            .unwrap();
+        assert_eq!(raw, sid.to_vec());
+        assert_eq!(text, sid.to_string());
+        let found: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM presence WHERE b = ?")
+            .bind(sid)
+            .fetch_one(&mut conn)
+            .await
+            .unwrap();
+        assert_eq!(found, 1);
+
+        let short = sqlx::query_scalar::<_, SessionId>("SELECT x'0102'")
+            .fetch_one(&mut conn)
+            .await;
+        assert!(short.is_err());
+    }
+}
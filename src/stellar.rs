@@ -0,0 +1,137 @@
+//! Stellar strkey interop.
+//!
+//! A SessionId is an Ed25519 public key, so it is also a Stellar account ID (`G...`), and the
+//! seed of a SessionIdPair is a Stellar secret seed (`S...`), as exported by SEP-0005 wallets.
+//! A strkey is the RFC 4648 base32 of a version byte, the 32-byte key and a CRC16-XModem
+//! checksum (little-endian).
+use crate::errors::{Result, SessionIdError};
+use crate::{
+    base32, session_id_pair_from_secret, SecretSessionKey, SessionId, SessionIdPair,
+    SessionIdPairSecret, SECRET_KEY_SIZE, SESSION_ID_SIZE,
+};
+use zeroize::Zeroizing;
+
+// version bytes of ed25519 account IDs ('G') and secret seeds ('S')
+const ACCOUNT_ID_VERSION: u8 = 6 << 3;
+const SECRET_SEED_VERSION: u8 = 18 << 3;
+const STRKEY_SIZE: usize = 1 + 32 + 2;
+/// Characters of a strkey
+pub const STRKEY_STRING_SIZE: usize = 56;
+
+fn stellar_error(msg: &str) -> SessionIdError {
+    SessionIdError::InvalidFormat(format!("stellar: {}", msg))
+}
+
+fn crc16_xmodem(data: &[u8]) -> u16 {
+    let mut crc = 0u16;
+    for b in data {
+        crc ^= (*b as u16) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x1021
+            } else {
+                crc << 1
+            };
+        }
+    }
+    crc
+}
+
+fn encode_strkey(version: u8, key: &[u8; 32]) -> String {
+    let mut data = Zeroizing::new([0u8; STRKEY_SIZE]);
+    data[0] = version;
+    data[1..33].copy_from_slice(key);
+    let crc = crc16_xmodem(&data[..33]);
+    data[33..].copy_from_slice(&crc.to_le_bytes());
+    // 35 bytes are exactly 56 characters, so there is no padding
+    base32::encode(&data[..])
+}
+
+fn decode_strkey(version: u8, s: &str) -> Result<Zeroizing<[u8; 32]>> {
+    if s.len() != STRKEY_STRING_SIZE {
+        return Err(stellar_error("invalid length"));
+    }
+    let data = Zeroizing::new(base32::decode(s)?);
+    if data[0] != version {
+        return Err(stellar_error("unexpected version byte"));
+    }
+    if crc16_xmodem(&data[..33]).to_le_bytes() != data[33..] {
+        return Err(stellar_error("checksum mismatch"));
+    }
+    let mut key = Zeroizing::new([0u8; 32]);
+    key.copy_from_slice(&data[1..33]);
+    Ok(key)
+}
+
+impl SessionId {
+    /// Stellar account ID (`G...`)
+    pub fn to_stellar_address(&self) -> String {
+        encode_strkey(ACCOUNT_ID_VERSION, self.as_raw())
+    }
+    pub fn from_stellar_address(s: &str) -> Result<Self> {
+        let key = decode_strkey(ACCOUNT_ID_VERSION, s)?;
+        let mut raw = [0u8; SESSION_ID_SIZE];
+        raw.copy_from_slice(&key[..]);
+        Ok(SessionId::from(raw))
+    }
+}
+
+/// Conversion between SessionIdPair and Stellar secret seeds
+pub trait StellarKeypair: Sized {
+    /// Stellar secret seed (`S...`). Anyone holding it can sign as this session ID.
+    fn to_stellar_seed(&self) -> String;
+    fn from_stellar_seed(s: &str) -> Result<Self>;
+}
+
+impl StellarKeypair for SessionIdPair {
+    fn to_stellar_seed(&self) -> String {
+        encode_strkey(SECRET_SEED_VERSION, self.secret_key().expose_secret())
+    }
+    fn from_stellar_seed(s: &str) -> Result<Self> {
+        let seed = decode_strkey(SECRET_SEED_VERSION, s)?;
+        let mut secret = SecretSessionKey::new([0; SECRET_KEY_SIZE]);
+        secret.expose_secret_mut().copy_from_slice(&seed[..]);
+        session_id_pair_from_secret(&secret)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{new_session_id_pair, ISessionIdPair};
+
+    #[test]
+    fn test_stellar_strkey() {
+        assert_eq!(crc16_xmodem(b"123456789"), 0x31c3);
+        // the all-zero account
+        let zero = SessionId::from([0; 32]);
+        assert_eq!(
+            zero.to_stellar_address(),
+            "GAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAWHF"
+        );
+
+        let pair = new_session_id_pair().unwrap();
+        let address = pair.get_id().to_stellar_address();
+        assert!(address.starts_with('G'));
+        assert_eq!(
+            SessionId::from_stellar_address(&address).unwrap(),
+            pair.get_id()
+        );
+        let seed = pair.to_stellar_seed();
+        assert!(seed.starts_with('S'));
+        assert_eq!(seed.len(), STRKEY_STRING_SIZE);
+        assert_eq!(
+            SessionIdPair::from_stellar_seed(&seed).unwrap().get_id(),
+            pair.get_id()
+        );
+
+        // a seed is not an address, and typos fail the checksum
+        assert!(SessionId::from_stellar_address(&seed).is_err());
+        assert!(SessionIdPair::from_stellar_seed(&address).is_err());
+        let mut typo = address.clone().into_bytes();
+        typo[10] = if typo[10] == b'A' { b'B' } else { b'A' };
+        assert!(SessionId::from_stellar_address(std::str::from_utf8(&typo).unwrap()).is_err());
+        assert!(SessionId::from_stellar_address(&address.to_lowercase()).is_err());
+        assert!(SessionId::from_stellar_address(&address[1..]).is_err());
+    }
+}
@@ -0,0 +1,108 @@
+//! Purpose-bound child keys.
+//!
+//! Separate keys for chat, asset signing, presence, ... are derived with HKDF-SHA512
+//! from the session key and a label. A [`SubkeyProof`] anchors a child key to its
+//! parent SessionId, so a leaked child key does not expose the parent.
+use crate::errors::Result;
+use crate::{
+    session_id_pair_from_secret, ISessionIdPair, SecretSessionKey, SessionId, SessionIdPair,
+    SessionIdPublic, SignatureSet, SECRET_KEY_SIZE,
+};
+use hkdf::Hkdf;
+use sha2::Sha512;
+
+const SUBKEY_SALT: &[u8] = b"verse-session-id/subkey/v1";
+const SUBKEY_PROOF_CONTEXT: &[u8] = b"verse-session-id/subkey-proof/v1";
+
+/// Proof that `subkey_id` is the child of a session ID for `label`
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SubkeyProof {
+    pub label: String,
+    pub subkey_id: SessionId,
+    /// Signed by the parent key
+    pub parent_signature: SignatureSet,
+    /// Signed by the child key
+    pub subkey_signature: SignatureSet,
+}
+
+/// Derivation of child keys from a SessionIdPair
+pub trait SubkeyDerivation {
+    /// Child keypair for `label` (e.g. `"chat"`). Always the same for the same key and label.
+    fn derive_subkey(&self, label: &str) -> Result<SessionIdPair>;
+    /// Proof linking this identity to the child for `label`
+    fn subkey_proof(&self, label: &str) -> Result<SubkeyProof>;
+}
+
+impl SubkeyDerivation for SessionIdPair {
+    fn derive_subkey(&self, label: &str) -> Result<SessionIdPair> {
+        let mut secret = SecretSessionKey::new([0; SECRET_KEY_SIZE]);
+        Hkdf::<Sha512>::new(Some(SUBKEY_SALT), self.secret.as_bytes())
+            .expand(label.as_bytes(), secret.expose_secret_mut())
+            .expect("valid HKDF-SHA512 output length");
+        session_id_pair_from_secret(&secret)
+    }
+    fn subkey_proof(&self, label: &str) -> Result<SubkeyProof> {
+        let subkey = self.derive_subkey(label)?;
+        let parent_id = self.get_id();
+        let subkey_id = subkey.get_id();
+        let payload = proof_payload(&parent_id, &subkey_id, label);
+        Ok(SubkeyProof {
+            label: label.to_string(),
+            subkey_id,
+            parent_signature: self.sign(payload.clone())?,
+            subkey_signature: subkey.sign(payload)?,
+        })
+    }
+}
+
+// the label is last so that the variable length field is unambiguous
+fn proof_payload<'a>(
+    parent: &'a SessionId,
+    subkey_id: &'a SessionId,
+    label: &'a str,
+) -> Vec<&'a [u8]> {
+    vec![
+        SUBKEY_PROOF_CONTEXT,
+        parent.as_ref(),
+        subkey_id.as_ref(),
+        label.as_bytes(),
+    ]
+}
+
+impl SubkeyProof {
+    /// Check that `subkey_id` is the child of `parent` for `label`
+    pub fn verify(&self, parent: &SessionId) -> Result<()> {
+        let payload = proof_payload(parent, &self.subkey_id, &self.label);
+        parent.verify(payload.clone(), &self.parent_signature)?;
+        self.subkey_id.verify(payload, &self.subkey_signature)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::new_session_id_pair;
+
+    #[test]
+    fn test_subkey() {
+        let parent = new_session_id_pair().unwrap();
+        let chat = parent.derive_subkey("chat").unwrap();
+        assert_eq!(
+            chat.get_id(),
+            parent.derive_subkey("chat").unwrap().get_id()
+        );
+        assert_ne!(
+            chat.get_id(),
+            parent.derive_subkey("presence").unwrap().get_id()
+        );
+        assert_ne!(chat.get_id(), parent.get_id());
+
+        let proof = parent.subkey_proof("chat").unwrap();
+        assert_eq!(proof.subkey_id, chat.get_id());
+        assert!(proof.verify(&parent.get_id()).is_ok());
+        assert!(proof.verify(&chat.get_id()).is_err());
+        let mut relabeled = proof.clone();
+        relabeled.label = "assets".to_string();
+        assert!(relabeled.verify(&parent.get_id()).is_err());
+    }
+}
@@ -0,0 +1,109 @@
+//! Time source of every expiry, not-before and issued-at check in the crate.
+//!
+//! Functions without an `_at` suffix read [`SystemClock`]. Each of them has an `_at`
+//! variant taking a [`Clock`], so embedders without `std::time`
+//! (e.g. `wasm32-unknown-unknown`) and tests supply their own time source.
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Source of the current time in seconds since UNIX epoch
+pub trait Clock: Send + Sync {
+    fn now(&self) -> u64;
+    /// Milliseconds since UNIX epoch. Defaults to whole seconds of [`Clock::now`].
+    fn now_ms(&self) -> u64 {
+        self.now().saturating_mul(1000)
+    }
+}
+
+/// `SystemTime::now()`
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0)
+    }
+    fn now_ms(&self) -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0)
+    }
+}
+
+/// Clock set by hand, for tests and simulations. Keeps milliseconds.
+#[derive(Debug, Default)]
+pub struct ManualClock(AtomicU64);
+
+impl ManualClock {
+    /// Clock at `now` seconds since UNIX epoch
+    pub fn new(now: u64) -> Self {
+        Self::from_ms(now.saturating_mul(1000))
+    }
+    /// Clock at `now_ms` milliseconds since UNIX epoch
+    pub fn from_ms(now_ms: u64) -> Self {
+        ManualClock(AtomicU64::new(now_ms))
+    }
+    pub fn set(&self, now: u64) {
+        self.set_ms(now.saturating_mul(1000));
+    }
+    pub fn set_ms(&self, now_ms: u64) {
+        self.0.store(now_ms, Ordering::Relaxed);
+    }
+    pub fn advance(&self, secs: u64) {
+        self.advance_ms(secs.saturating_mul(1000));
+    }
+    pub fn advance_ms(&self, ms: u64) {
+        self.0.fetch_add(ms, Ordering::Relaxed);
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> u64 {
+        self.now_ms() / 1000
+    }
+    fn now_ms(&self) -> u64 {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+impl<F: Fn() -> u64 + Send + Sync> Clock for F {
+    fn now(&self) -> u64 {
+        self()
+    }
+}
+
+impl<C: Clock + ?Sized> Clock for Arc<C> {
+    fn now(&self) -> u64 {
+        (**self).now()
+    }
+    fn now_ms(&self) -> u64 {
+        (**self).now_ms()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clock() {
+        let clock = Arc::new(ManualClock::new(100));
+        clock.advance(5);
+        assert_eq!(clock.now(), 105);
+        clock.set(7);
+        assert_eq!(Clock::now(&clock), 7);
+        assert_eq!(clock.now_ms(), 7000);
+        clock.advance_ms(1500);
+        assert_eq!((clock.now(), clock.now_ms()), (8, 8500));
+        clock.set(7);
+        assert_eq!((|| 42).now(), 42);
+        assert!(SystemClock.now_ms() / 1000 >= SystemClock.now() - 1);
+        let clock: &dyn Clock = &clock;
+        assert_eq!(clock.now(), 7);
+    }
+}
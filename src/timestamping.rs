@@ -0,0 +1,324 @@
+//! Trusted timestamps of signatures with Roughtime. Enabled with the `timestamping` feature.
+//!
+//! The Roughtime nonce is a hash of the SignatureSet, so a server response proves that the
+//! signature existed at the server's time. Servers sign with Ed25519, so no extra
+//! cryptography is needed. Implements the original (Google) Roughtime wire format;
+//! sending the request over UDP is up to the caller.
+use crate::errors::{self, Result, SessionIdError, SignatureErrorKind};
+use crate::{SessionId, SessionIdPublic, SignatureSet};
+use ed25519_dalek::{Digest, Sha512};
+
+const TIMESTAMP_CONTEXT: &[u8] = b"verse-session-id/timestamp/v1";
+const DELEGATION_CONTEXT: &[u8] = b"RoughTime v1 delegation signature--\0";
+const RESPONSE_CONTEXT: &[u8] = b"RoughTime v1 response signature\0";
+/// Size of a Roughtime request
+pub const ROUGHTIME_REQUEST_SIZE: usize = 1024;
+
+const fn tag(v: &[u8; 4]) -> u32 {
+    u32::from_le_bytes(*v)
+}
+const TAG_SIG: u32 = tag(b"SIG\0");
+const TAG_NONC: u32 = tag(b"NONC");
+const TAG_DELE: u32 = tag(b"DELE");
+const TAG_PATH: u32 = tag(b"PATH");
+const TAG_RADI: u32 = tag(b"RADI");
+const TAG_PUBK: u32 = tag(b"PUBK");
+const TAG_MIDP: u32 = tag(b"MIDP");
+const TAG_SREP: u32 = tag(b"SREP");
+const TAG_MINT: u32 = tag(b"MINT");
+const TAG_ROOT: u32 = tag(b"ROOT");
+const TAG_CERT: u32 = tag(b"CERT");
+const TAG_MAXT: u32 = tag(b"MAXT");
+const TAG_INDX: u32 = tag(b"INDX");
+const TAG_PAD: u32 = tag(b"PAD\xff");
+
+fn roughtime_error(msg: &str) -> SessionIdError {
+    SessionIdError::InvalidFormat(format!("roughtime: {}", msg))
+}
+
+/// Encode a Roughtime message. `fields` must be sorted by tag.
+fn encode(fields: &[(u32, &[u8])]) -> Vec<u8> {
+    let mut buf = (fields.len() as u32).to_le_bytes().to_vec();
+    let mut offset = 0u32;
+    for (_, v) in &fields[..fields.len().saturating_sub(1)] {
+        offset += v.len() as u32;
+        buf.extend_from_slice(&offset.to_le_bytes());
+    }
+    for (t, _) in fields {
+        buf.extend_from_slice(&t.to_le_bytes());
+    }
+    for (_, v) in fields {
+        buf.extend_from_slice(v);
+    }
+    buf
+}
+
+/// Decoded Roughtime message
+struct Message<'a>(Vec<(u32, &'a [u8])>);
+
+impl<'a> Message<'a> {
+    fn decode(bytes: &'a [u8]) -> Result<Self> {
+        let word = |i: usize| -> Result<u32> {
+            bytes
+                .get(i * 4..i * 4 + 4)
+                .map(|v| u32::from_le_bytes(v.try_into().unwrap()))
+                .ok_or_else(|| roughtime_error("truncated message"))
+        };
+        let n = word(0)? as usize;
+        if n == 0 || n > 64 {
+            return Err(roughtime_error("invalid number of tags"));
+        }
+        let values_start = 4 * (2 * n);
+        let values = bytes
+            .get(values_start..)
+            .ok_or_else(|| roughtime_error("truncated message"))?;
+        let mut fields = Vec::with_capacity(n);
+        let mut start = 0usize;
+        for i in 0..n {
+            let end = if i + 1 < n {
+                word(1 + i)? as usize
+            } else {
+                values.len()
+            };
+            let t = word(n + i)?;
+            if end < start || end > values.len() || end % 4 != 0 {
+                return Err(roughtime_error("invalid offset"));
+            }
+            if fields.last().is_some_and(|(prev, _)| *prev >= t) {
+                return Err(roughtime_error("tags not sorted"));
+            }
+            fields.push((t, &values[start..end]));
+            start = end;
+        }
+        Ok(Message(fields))
+    }
+    fn get(&self, t: u32) -> Result<&'a [u8]> {
+        self.0
+            .iter()
+            .find(|(k, _)| *k == t)
+            .map(|(_, v)| *v)
+            .ok_or_else(|| roughtime_error("missing tag"))
+    }
+    fn get_u64(&self, t: u32) -> Result<u64> {
+        let v: [u8; 8] = self
+            .get(t)?
+            .try_into()
+            .map_err(|_| roughtime_error("invalid u64"))?;
+        Ok(u64::from_le_bytes(v))
+    }
+}
+
+fn verify_ed25519(key: &[u8], context: &[u8], msg: &[u8], signature: &[u8]) -> Result<()> {
+    let key = ed25519_dalek::PublicKey::from_bytes(key)
+        .map_err(errors::signature(SignatureErrorKind::MalformedPublicKey))?;
+    let signature = ed25519_dalek::Signature::from_bytes(signature)
+        .map_err(errors::signature(SignatureErrorKind::MalformedSignature))?;
+    key.verify_strict(&[context, msg].concat(), &signature)
+        .map_err(errors::signature(SignatureErrorKind::VerificationFailed))
+}
+
+fn sha512(parts: &[&[u8]]) -> [u8; 64] {
+    let mut hasher = Sha512::new();
+    for p in parts {
+        hasher.update(p);
+    }
+    let mut hash = [0u8; 64];
+    hash.copy_from_slice(&hasher.finalize());
+    hash
+}
+
+/// Roughtime nonce committing to `signature`
+pub fn timestamp_nonce(signature: &SignatureSet) -> [u8; 64] {
+    sha512(&[TIMESTAMP_CONTEXT, &signature.to_bytes()])
+}
+
+/// Roughtime request to timestamp `signature`
+pub fn roughtime_request(signature: &SignatureSet) -> Vec<u8> {
+    let nonce = timestamp_nonce(signature);
+    // header of two tags plus the nonce
+    let pad = vec![0u8; ROUGHTIME_REQUEST_SIZE - 4 * 4 - nonce.len()];
+    encode(&[(TAG_NONC, &nonce), (TAG_PAD, &pad)])
+}
+
+/// Roughtime server response proving the time of a signature
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TimestampToken {
+    pub response: Vec<u8>,
+}
+
+/// Time attested by a [`TimestampToken`]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct AttestedTime {
+    /// Microseconds since UNIX epoch
+    pub midpoint: u64,
+    /// Uncertainty in microseconds
+    pub radius: u32,
+}
+
+impl TimestampToken {
+    pub fn new(response: Vec<u8>) -> Self {
+        TimestampToken { response }
+    }
+    /// Check the response of the server with long-term key `server_key` for `signature`
+    pub fn verify(&self, server_key: &[u8], signature: &SignatureSet) -> Result<AttestedTime> {
+        let response = Message::decode(&self.response)?;
+        let cert = Message::decode(response.get(TAG_CERT)?)?;
+        let dele_bytes = cert.get(TAG_DELE)?;
+        verify_ed25519(
+            server_key,
+            DELEGATION_CONTEXT,
+            dele_bytes,
+            cert.get(TAG_SIG)?,
+        )?;
+        let dele = Message::decode(dele_bytes)?;
+
+        let srep_bytes = response.get(TAG_SREP)?;
+        verify_ed25519(
+            dele.get(TAG_PUBK)?,
+            RESPONSE_CONTEXT,
+            srep_bytes,
+            response.get(TAG_SIG)?,
+        )?;
+        let srep = Message::decode(srep_bytes)?;
+
+        // Merkle path from our nonce to the signed root
+        let mut hash = sha512(&[&[0], &timestamp_nonce(signature)]);
+        let index: [u8; 4] = response
+            .get(TAG_INDX)?
+            .try_into()
+            .map_err(|_| roughtime_error("invalid index"))?;
+        let mut index = u32::from_le_bytes(index);
+        let path = response.get(TAG_PATH)?;
+        if path.len() % 64 != 0 {
+            return Err(roughtime_error("invalid path"));
+        }
+        for sibling in path.chunks_exact(64) {
+            let (l, r) = if index & 1 == 0 {
+                (&hash[..], sibling)
+            } else {
+                (sibling, &hash[..])
+            };
+            hash = sha512(&[&[1], l, r]);
+            index >>= 1;
+        }
+        if index != 0 || srep.get(TAG_ROOT)? != hash {
+            return Err(SessionIdError::Signature(
+                SignatureErrorKind::VerificationFailed,
+            ));
+        }
+
+        let midpoint = srep.get_u64(TAG_MIDP)?;
+        if midpoint < dele.get_u64(TAG_MINT)? || midpoint > dele.get_u64(TAG_MAXT)? {
+            return Err(SessionIdError::InvalidClaim("midpoint"));
+        }
+        let radius: [u8; 4] = srep
+            .get(TAG_RADI)?
+            .try_into()
+            .map_err(|_| roughtime_error("invalid radius"))?;
+        Ok(AttestedTime {
+            midpoint,
+            radius: u32::from_le_bytes(radius),
+        })
+    }
+}
+
+/// Verify `signature` over `payload` by `session_id` and the time it was timestamped
+pub fn verify_with_timestamp<P>(
+    session_id: &SessionId,
+    payload: P,
+    signature: &SignatureSet,
+    token: &TimestampToken,
+    server_key: &[u8],
+) -> Result<AttestedTime>
+where
+    P: IntoIterator,
+    P::Item: AsRef<[u8]>,
+{
+    session_id.verify(payload, signature)?;
+    token.verify(server_key, signature)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{new_session_id_pair, ISessionIdPair};
+
+    fn leaf(nonce: &[u8]) -> [u8; 64] {
+        sha512(&[&[0], nonce])
+    }
+
+    // server answering a batch of two requests
+    fn respond(nonces: [[u8; 64]; 2], index: usize) -> (Vec<u8>, Vec<u8>) {
+        let root_key = new_session_id_pair().unwrap();
+        let online_key = new_session_id_pair().unwrap();
+        let leaves = [leaf(&nonces[0]), leaf(&nonces[1])];
+        let root = sha512(&[&[1], &leaves[0], &leaves[1]]);
+
+        let midp = 1_700_000_000_000_000u64.to_le_bytes();
+        let srep = encode(&[
+            (TAG_RADI, &1_000_000u32.to_le_bytes()),
+            (TAG_MIDP, &midp),
+            (TAG_ROOT, &root),
+        ]);
+        let dele = encode(&[
+            (TAG_PUBK, online_key.public.as_bytes()),
+            (TAG_MINT, &0u64.to_le_bytes()),
+            (TAG_MAXT, &u64::MAX.to_le_bytes()),
+        ]);
+        let cert_sig =
+            ed25519_dalek::Signer::sign(&root_key, &[DELEGATION_CONTEXT, &dele].concat())
+                .to_bytes();
+        let cert = encode(&[(TAG_SIG, &cert_sig), (TAG_DELE, &dele)]);
+        let sig = ed25519_dalek::Signer::sign(&online_key, &[RESPONSE_CONTEXT, &srep].concat())
+            .to_bytes();
+        let response = encode(&[
+            (TAG_SIG, &sig),
+            (TAG_PATH, &leaves[1 - index]),
+            (TAG_SREP, &srep),
+            (TAG_CERT, &cert),
+            (TAG_INDX, &(index as u32).to_le_bytes()),
+        ]);
+        (response, root_key.public.as_bytes().to_vec())
+    }
+
+    #[test]
+    fn test_roughtime_request() {
+        let pair = new_session_id_pair().unwrap();
+        let sig = pair.sign(vec![b"world"]).unwrap();
+        let req = roughtime_request(&sig);
+        assert_eq!(req.len(), ROUGHTIME_REQUEST_SIZE);
+        let msg = Message::decode(&req).unwrap();
+        assert_eq!(msg.get(TAG_NONC).unwrap(), timestamp_nonce(&sig));
+    }
+
+    #[test]
+    fn test_timestamp_token() {
+        let pair = new_session_id_pair().unwrap();
+        let sig = pair.sign(vec![b"world ownership"]).unwrap();
+        let other = pair.sign(vec![b"other request"]).unwrap();
+        for index in 0..2 {
+            let mut nonces = [timestamp_nonce(&other), timestamp_nonce(&other)];
+            nonces[index] = timestamp_nonce(&sig);
+            let (response, server_key) = respond(nonces, index);
+            let token = TimestampToken::new(response);
+
+            let t = verify_with_timestamp(
+                &pair.get_id(),
+                [b"world ownership"],
+                &sig,
+                &token,
+                &server_key,
+            )
+            .unwrap();
+            assert_eq!(t.midpoint, 1_700_000_000_000_000);
+            assert_eq!(t.radius, 1_000_000);
+
+            assert!(token.verify(&server_key, &other).is_err());
+            assert!(token.verify(&[9; 32], &sig).is_err());
+            let mut tampered = token.clone();
+            let n = tampered.response.len();
+            tampered.response[n - 1] ^= 1;
+            assert!(tampered.verify(&server_key, &sig).is_err());
+        }
+    }
+}
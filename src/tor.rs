@@ -0,0 +1,150 @@
+//! Tor v3 onion service keys. Enabled with the `tor` feature.
+//!
+//! An onion service identity is an Ed25519 key, so a SessionId is also an onion address
+//! (rend-spec-v3 §6) and a SessionIdPair can be written as the `hs_ed25519_secret_key` and
+//! `hs_ed25519_public_key` files of a `HiddenServiceDir`.
+//!
+//! Tor stores only the expanded secret key (SHA-512 of the seed), which cannot be turned back
+//! into a seed, so keys generated by Tor cannot be imported as a SessionIdPair.
+use crate::errors::{self, Result, SessionIdError};
+use crate::{base32, ISessionIdPair, SessionId, SessionIdPair, SESSION_ID_SIZE};
+use ed25519_dalek::ExpandedSecretKey;
+use sha3::{Digest, Sha3_256};
+use zeroize::Zeroizing;
+
+const ONION_VERSION: u8 = 3;
+const ONION_SUFFIX: &str = ".onion";
+const CHECKSUM_CONTEXT: &[u8] = b".onion checksum";
+const SECRET_KEY_HEADER: &[u8; 32] = b"== ed25519v1-secret: type0 ==\0\0\0";
+const PUBLIC_KEY_HEADER: &[u8; 32] = b"== ed25519v1-public: type0 ==\0\0\0";
+/// Size of an `hs_ed25519_secret_key` file
+pub const TOR_SECRET_KEY_FILE_SIZE: usize = 32 + 64;
+/// Size of an `hs_ed25519_public_key` file
+pub const TOR_PUBLIC_KEY_FILE_SIZE: usize = 32 + SESSION_ID_SIZE;
+
+fn tor_error(msg: &str) -> SessionIdError {
+    SessionIdError::InvalidFormat(format!("tor: {}", msg))
+}
+
+fn onion_checksum(public_key: &[u8]) -> [u8; 2] {
+    let hash = Sha3_256::new()
+        .chain_update(CHECKSUM_CONTEXT)
+        .chain_update(public_key)
+        .chain_update([ONION_VERSION])
+        .finalize();
+    [hash[0], hash[1]]
+}
+
+impl SessionId {
+    /// v3 onion address, e.g. `...d.onion`
+    pub fn to_onion_address(&self) -> String {
+        let mut data = Vec::with_capacity(SESSION_ID_SIZE + 3);
+        data.extend_from_slice(self.as_raw());
+        data.extend_from_slice(&onion_checksum(self.as_raw()));
+        data.push(ONION_VERSION);
+        base32::encode(data).to_lowercase() + ONION_SUFFIX
+    }
+    /// Parse a v3 onion address, with or without the `.onion` suffix
+    pub fn from_onion_address(s: &str) -> Result<Self> {
+        let s = s.strip_suffix(ONION_SUFFIX).unwrap_or(s);
+        let data = base32::decode(&s.to_ascii_uppercase())?;
+        if data.len() != SESSION_ID_SIZE + 3 {
+            return Err(tor_error("invalid onion address length"));
+        }
+        let (public_key, rest) = data.split_at(SESSION_ID_SIZE);
+        if rest[2] != ONION_VERSION {
+            return Err(tor_error("unsupported onion address version"));
+        }
+        if rest[..2] != onion_checksum(public_key) {
+            return Err(tor_error("checksum mismatch"));
+        }
+        SessionId::try_from(public_key)
+    }
+    /// Contents of an `hs_ed25519_public_key` file
+    pub fn to_tor_public_key_file(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(TOR_PUBLIC_KEY_FILE_SIZE);
+        buf.extend_from_slice(PUBLIC_KEY_HEADER);
+        buf.extend_from_slice(self.as_raw());
+        buf
+    }
+    pub fn from_tor_public_key_file(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() != TOR_PUBLIC_KEY_FILE_SIZE {
+            return Err(errors::invalid_length(
+                TOR_PUBLIC_KEY_FILE_SIZE,
+                bytes.len(),
+            ));
+        }
+        let (header, public_key) = bytes.split_at(PUBLIC_KEY_HEADER.len());
+        if header != PUBLIC_KEY_HEADER {
+            return Err(tor_error("not an hs_ed25519_public_key file"));
+        }
+        SessionId::try_from(public_key)
+    }
+}
+
+/// Export of a SessionIdPair as an onion service identity
+pub trait TorOnionKeys {
+    /// Contents of an `hs_ed25519_secret_key` file. Anyone holding it can impersonate the
+    /// onion service and sign as this session ID.
+    fn to_tor_secret_key_file(&self) -> Zeroizing<Vec<u8>>;
+    /// v3 onion address of this session ID
+    fn onion_address(&self) -> String;
+}
+
+impl TorOnionKeys for SessionIdPair {
+    fn to_tor_secret_key_file(&self) -> Zeroizing<Vec<u8>> {
+        let expanded = Zeroizing::new(ExpandedSecretKey::from(&self.secret).to_bytes());
+        let mut buf = Zeroizing::new(Vec::with_capacity(TOR_SECRET_KEY_FILE_SIZE));
+        buf.extend_from_slice(SECRET_KEY_HEADER);
+        buf.extend_from_slice(&expanded[..]);
+        buf
+    }
+    fn onion_address(&self) -> String {
+        self.get_id().to_onion_address()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::new_session_id_pair;
+    use ed25519_dalek::{PublicKey, Verifier};
+
+    #[test]
+    fn test_onion_address() {
+        let pair = new_session_id_pair().unwrap();
+        let address = pair.onion_address();
+        assert_eq!(address.len(), 56 + ONION_SUFFIX.len());
+        // the version byte makes every v3 address end in 'd'
+        assert!(address.ends_with("d.onion"));
+        assert_eq!(
+            SessionId::from_onion_address(&address).unwrap(),
+            pair.get_id()
+        );
+        assert_eq!(
+            SessionId::from_onion_address(&address.to_uppercase()[..56]).unwrap(),
+            pair.get_id()
+        );
+        let mut typo = address.clone().into_bytes();
+        typo[0] = if typo[0] == b'a' { b'b' } else { b'a' };
+        let typo = String::from_utf8(typo).unwrap();
+        assert!(SessionId::from_onion_address(&typo).is_err());
+        assert!(SessionId::from_onion_address(&address[1..]).is_err());
+
+        let public = pair.get_id().to_tor_public_key_file();
+        assert_eq!(
+            SessionId::from_tor_public_key_file(&public).unwrap(),
+            pair.get_id()
+        );
+        assert!(SessionId::from_tor_public_key_file(&public[1..]).is_err());
+
+        // Tor signs with the expanded key; the signatures verify under the SessionId
+        let secret = pair.to_tor_secret_key_file();
+        assert_eq!(secret.len(), TOR_SECRET_KEY_FILE_SIZE);
+        assert_eq!(&secret[..32], SECRET_KEY_HEADER);
+        let expanded = ExpandedSecretKey::from_bytes(&secret[32..]).unwrap();
+        let public_key = PublicKey::from_bytes(pair.get_id().as_raw()).unwrap();
+        let sig = expanded.sign(b"descriptor", &public_key);
+        assert!(public_key.verify(b"descriptor", &sig).is_ok());
+    }
+}
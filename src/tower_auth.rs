@@ -0,0 +1,343 @@
+//! tower middleware. Enabled with the `tower` feature.
+//!
+//! [`SessionAuthLayer`] verifies HTTP Message Signatures added by [`sign_http_request`]
+//! and inserts the signer's [`SessionId`] into the request extensions.
+//! Requests without a valid signature, or signed too long ago or in the future, are rejected
+//! with 401 and never reach the inner service.
+//!
+//! When the request has a `Content-Digest` header, the inner service receives the body as
+//! [`DigestVerifiedBody`], which fails at the end of the stream if the body does not match.
+//! Require `content-digest` with [`SessionAuthLayer::with_components`] to reject unsigned
+//! bodies.
+//!
+//! [`sign_http_request`]: crate::sign_http_request
+use crate::http_signature::ContentDigestVerifier;
+use crate::time::{Clock, SystemClock};
+use crate::{verify_http_request_at, SessionId, DEFAULT_HTTP_COMPONENTS, DEFAULT_HTTP_MAX_AGE};
+use bytes::{Buf, Bytes};
+use http::{Request, Response, StatusCode};
+use http_body::{Body, Frame, SizeHint};
+use pin_project_lite::pin_project;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{ready, Context, Poll};
+use tower_layer::Layer;
+use tower_service::Service;
+
+/// Layer applying [`SessionAuth`]
+#[derive(Clone)]
+pub struct SessionAuthLayer {
+    components: Arc<[String]>,
+    max_age: u64,
+    clock: Arc<dyn Clock>,
+}
+
+impl SessionAuthLayer {
+    /// Require the signature to cover [`DEFAULT_HTTP_COMPONENTS`]
+    pub fn new() -> Self {
+        Self::with_components(DEFAULT_HTTP_COMPONENTS)
+    }
+    /// Require the signature to cover `components`
+    pub fn with_components(components: &[&str]) -> Self {
+        SessionAuthLayer {
+            components: components.iter().map(|v| v.to_string()).collect(),
+            max_age: DEFAULT_HTTP_MAX_AGE,
+            clock: Arc::new(SystemClock),
+        }
+    }
+    /// Reject signatures older than `max_age` seconds (default [`DEFAULT_HTTP_MAX_AGE`])
+    pub fn with_max_age(mut self, max_age: u64) -> Self {
+        self.max_age = max_age;
+        self
+    }
+    /// Check freshness against `clock` (default [`SystemClock`])
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Arc::new(clock);
+        self
+    }
+}
+
+impl fmt::Debug for SessionAuthLayer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SessionAuthLayer")
+            .field("components", &self.components)
+            .field("max_age", &self.max_age)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Default for SessionAuthLayer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S> Layer<S> for SessionAuthLayer {
+    type Service = SessionAuth<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        SessionAuth {
+            inner,
+            components: self.components.clone(),
+            max_age: self.max_age,
+            clock: self.clock.clone(),
+        }
+    }
+}
+
+/// Service verifying the request signature before calling the inner service
+#[derive(Clone)]
+pub struct SessionAuth<S> {
+    inner: S,
+    components: Arc<[String]>,
+    max_age: u64,
+    clock: Arc<dyn Clock>,
+}
+
+impl<S: fmt::Debug> fmt::Debug for SessionAuth<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SessionAuth")
+            .field("inner", &self.inner)
+            .field("components", &self.components)
+            .field("max_age", &self.max_age)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<S, ReqBody, ResBody> Service<Request<ReqBody>> for SessionAuth<S>
+where
+    S: Service<Request<DigestVerifiedBody<ReqBody>>, Response = Response<ResBody>>,
+    ResBody: Default,
+{
+    type Response = Response<ResBody>;
+    type Error = S::Error;
+    type Future = SessionAuthFuture<S::Future, ResBody>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<ReqBody>) -> Self::Future {
+        let components: Vec<&str> = self.components.iter().map(String::as_str).collect();
+        let verified = verify_http_request_at(&request, &components, self.max_age, &*self.clock)
+            .and_then(|v| Ok((v, ContentDigestVerifier::new(request.headers())?)));
+        match verified {
+            Ok((session_id, verifier)) => {
+                let mut request = request.map(|inner| DigestVerifiedBody { inner, verifier });
+                request.extensions_mut().insert::<SessionId>(session_id);
+                SessionAuthFuture::Inner {
+                    future: self.inner.call(request),
+                }
+            }
+            Err(_) => {
+                let mut response = Response::new(ResBody::default());
+                *response.status_mut() = StatusCode::UNAUTHORIZED;
+                SessionAuthFuture::Unauthorized {
+                    response: Some(response),
+                }
+            }
+        }
+    }
+}
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+pin_project! {
+    /// Request body passed on by [`SessionAuth`]. Yields an error instead of the end of the
+    /// stream when the body does not match the `Content-Digest` header.
+    pub struct DigestVerifiedBody<B> {
+        #[pin]
+        inner: B,
+        verifier: Option<ContentDigestVerifier>,
+    }
+}
+
+impl<B> DigestVerifiedBody<B> {
+    pub fn into_inner(self) -> B {
+        self.inner
+    }
+}
+
+impl<B: fmt::Debug> fmt::Debug for DigestVerifiedBody<B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DigestVerifiedBody")
+            .field("inner", &self.inner)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<B> Body for DigestVerifiedBody<B>
+where
+    B: Body,
+    B::Error: Into<BoxError>,
+{
+    type Data = Bytes;
+    type Error = BoxError;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let this = self.project();
+        match ready!(this.inner.poll_frame(cx)) {
+            Some(Ok(frame)) => {
+                let frame = frame.map_data(|mut data| data.copy_to_bytes(data.remaining()));
+                if let (Some(verifier), Some(data)) = (this.verifier.as_mut(), frame.data_ref()) {
+                    verifier.update(data);
+                }
+                Poll::Ready(Some(Ok(frame)))
+            }
+            Some(Err(err)) => Poll::Ready(Some(Err(err.into()))),
+            None => match this.verifier.take().map(ContentDigestVerifier::finish) {
+                Some(Err(err)) => Poll::Ready(Some(Err(err.into()))),
+                _ => Poll::Ready(None),
+            },
+        }
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.verifier.is_none() && self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        self.inner.size_hint()
+    }
+}
+
+pin_project! {
+    /// Response future of [`SessionAuth`]
+    #[project = SessionAuthFutureProj]
+    pub enum SessionAuthFuture<F, B> {
+        Inner { #[pin] future: F },
+        Unauthorized { response: Option<Response<B>> },
+    }
+}
+
+impl<F, B, E> Future for SessionAuthFuture<F, B>
+where
+    F: Future<Output = Result<Response<B>, E>>,
+{
+    type Output = Result<Response<B>, E>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        match self.project() {
+            SessionAuthFutureProj::Inner { future } => future.poll(cx),
+            SessionAuthFutureProj::Unauthorized { response } => {
+                Poll::Ready(Ok(response.take().expect("polled after completion")))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        content_digest, new_session_id_pair, sign_http_request, sign_http_request_at,
+        ISessionIdPair, ManualClock,
+    };
+    use http_body_util::{BodyExt, Full};
+    use std::convert::Infallible;
+    use tower::{service_fn, ServiceExt};
+
+    async fn echo(
+        request: Request<DigestVerifiedBody<Full<Bytes>>>,
+    ) -> Result<Response<String>, Infallible> {
+        let sid = *request.extensions().get::<SessionId>().unwrap();
+        Ok(
+            match request.into_body().collect().await.map(|v| v.to_bytes()) {
+                Ok(body) if body.is_empty() => Response::new(sid.to_string()),
+                Ok(body) => Response::new(format!("{}:{:?}", sid, body)),
+                Err(_) => {
+                    let mut response = Response::default();
+                    *response.status_mut() = StatusCode::BAD_REQUEST;
+                    response
+                }
+            },
+        )
+    }
+
+    fn request() -> Request<Full<Bytes>> {
+        Request::get("https://example.com/rooms?id=1")
+            .header("x-world", "1")
+            .body(Full::default())
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_session_auth_layer() {
+        let kp = new_session_id_pair().unwrap();
+        let mut signed = request();
+        sign_http_request(&kp, &mut signed, DEFAULT_HTTP_COMPONENTS).unwrap();
+
+        let svc = SessionAuthLayer::new().layer(service_fn(echo));
+        let res = svc.clone().oneshot(signed.clone()).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.into_body(), kp.get_id().to_string());
+
+        let res = svc.clone().oneshot(request()).await.unwrap();
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+
+        let mut tampered = signed.clone();
+        *tampered.uri_mut() = "https://example.com/rooms?id=2".parse().unwrap();
+        let res = svc.oneshot(tampered).await.unwrap();
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+
+        // x-world is required but not covered
+        let svc =
+            SessionAuthLayer::with_components(&["@method", "x-world"]).layer(service_fn(echo));
+        let res = svc.oneshot(signed).await.unwrap();
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_session_auth_layer_freshness() {
+        let kp = new_session_id_pair().unwrap();
+        let clock = Arc::new(ManualClock::new(1_700_000_000));
+        let mut signed = request();
+        sign_http_request_at(&kp, &mut signed, DEFAULT_HTTP_COMPONENTS, &clock).unwrap();
+
+        let svc = SessionAuthLayer::new()
+            .with_clock(clock.clone())
+            .layer(service_fn(echo));
+        let res = svc.clone().oneshot(signed.clone()).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        clock.advance(600);
+        let res = svc.oneshot(signed.clone()).await.unwrap();
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+        let svc = SessionAuthLayer::new()
+            .with_max_age(3600)
+            .with_clock(clock)
+            .layer(service_fn(echo));
+        let res = svc.oneshot(signed).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_session_auth_layer_content_digest() {
+        let kp = new_session_id_pair().unwrap();
+        let mut components = DEFAULT_HTTP_COMPONENTS.to_vec();
+        components.push("content-digest");
+        let signed = |body: &'static str| {
+            let mut request = Request::post("https://example.com/rooms")
+                .header("content-digest", content_digest(b"hello"))
+                .body(Full::from(body))
+                .unwrap();
+            sign_http_request(&kp, &mut request, &components).unwrap();
+            request
+        };
+
+        let svc = SessionAuthLayer::with_components(&components).layer(service_fn(echo));
+        let res = svc.clone().oneshot(signed("hello")).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.into_body(), format!("{}:b\"hello\"", kp.get_id()));
+        let res = svc.clone().oneshot(signed("hellp")).await.unwrap();
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+        // a body without content-digest is rejected when it is required
+        let res = svc.oneshot(request()).await.unwrap();
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+    }
+}
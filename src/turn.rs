@@ -0,0 +1,138 @@
+//! Ephemeral TURN credentials. Enabled with the `turn` feature.
+//!
+//! Follows the TURN REST API convention understood by coturn (`use-auth-secret`):
+//! the username is `<expiry>:<base64url SessionId>` and the password is
+//! base64(HMAC-SHA1(shared secret, username)).
+use crate::base64url;
+use crate::errors::{Result, SessionIdError, SignatureErrorKind};
+use crate::time::{Clock, SystemClock};
+use crate::SessionId;
+use hmac::{Hmac, Mac};
+use sha1::Sha1;
+
+type HmacSha1 = Hmac<Sha1>;
+
+/// Time-limited TURN username/password pair
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TurnCredentials {
+    pub username: String,
+    pub password: String,
+    /// Expiration time (seconds since UNIX epoch)
+    pub expires_at: u64,
+}
+
+fn password(shared_secret: &[u8], username: &str) -> HmacSha1 {
+    // HMAC accepts keys of any length
+    let mut mac = HmacSha1::new_from_slice(shared_secret).expect("any key length");
+    mac.update(username.as_bytes());
+    mac
+}
+
+impl TurnCredentials {
+    /// Credentials for `session_id` valid for `ttl` seconds
+    pub fn generate(session_id: &SessionId, shared_secret: &[u8], ttl: u64) -> Self {
+        Self::generate_at(session_id, shared_secret, ttl, &SystemClock)
+    }
+    /// Credentials for `session_id` valid for `ttl` seconds from the time of `clock`
+    pub fn generate_at(
+        session_id: &SessionId,
+        shared_secret: &[u8],
+        ttl: u64,
+        clock: &dyn Clock,
+    ) -> Self {
+        let expires_at = clock.now().saturating_add(ttl);
+        let username = format!("{}:{}", expires_at, base64url::encode(session_id));
+        let password = base64::encode(password(shared_secret, &username).finalize().into_bytes());
+        TurnCredentials {
+            username,
+            password,
+            expires_at,
+        }
+    }
+    /// Check credentials on the relay side. Returns the SessionId they were issued to.
+    pub fn verify(username: &str, password: &str, shared_secret: &[u8]) -> Result<SessionId> {
+        Self::verify_at(username, password, shared_secret, &SystemClock)
+    }
+    /// Check credentials at the time of `clock`
+    pub fn verify_at(
+        username: &str,
+        password_b64: &str,
+        shared_secret: &[u8],
+        clock: &dyn Clock,
+    ) -> Result<SessionId> {
+        let (expires_at, session_id) = username
+            .split_once(':')
+            .ok_or_else(|| SessionIdError::InvalidFormat("turn: invalid username".to_string()))?;
+        let expires_at: u64 = expires_at
+            .parse()
+            .map_err(|_| SessionIdError::InvalidClaim("expires_at"))?;
+        let session_id = SessionId::try_from(base64url::decode(session_id)?)?;
+        password(shared_secret, username)
+            .verify_slice(&base64::decode(password_b64)?)
+            .map_err(|_| SessionIdError::Signature(SignatureErrorKind::VerificationFailed))?;
+        if clock.now() >= expires_at {
+            return Err(SessionIdError::Expired);
+        }
+        Ok(session_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ManualClock;
+
+    #[test]
+    fn test_turn_credentials() {
+        let sid = SessionId::from([7; 32]);
+        let c = TurnCredentials::generate_at(
+            &sid,
+            b"relay-secret",
+            100_000_000,
+            &ManualClock::new(1_600_000_000),
+        );
+        assert_eq!(
+            c.username,
+            "1700000000:BwcHBwcHBwcHBwcHBwcHBwcHBwcHBwcHBwcHBwcHBwc"
+        );
+        assert_eq!(c.password, "R9EKyUb2Vbni6lTM7CT9wniczOk=");
+
+        let res = TurnCredentials::verify_at(
+            &c.username,
+            &c.password,
+            b"relay-secret",
+            &ManualClock::new(1600000000),
+        );
+        assert_eq!(res.unwrap(), sid);
+        assert!(matches!(
+            TurnCredentials::verify_at(
+                &c.username,
+                &c.password,
+                b"relay-secret",
+                &ManualClock::new(1700000000)
+            ),
+            Err(SessionIdError::Expired)
+        ));
+        assert!(TurnCredentials::verify_at(
+            &c.username,
+            &c.password,
+            b"other",
+            &ManualClock::new(1600000000)
+        )
+        .is_err());
+        let forged = c.username.replace("1700000000", "1800000000");
+        assert!(TurnCredentials::verify_at(
+            &forged,
+            &c.password,
+            b"relay-secret",
+            &ManualClock::new(1600000000)
+        )
+        .is_err());
+
+        let c = TurnCredentials::generate(&sid, b"relay-secret", 60);
+        assert_eq!(
+            TurnCredentials::verify(&c.username, &c.password, b"relay-secret").unwrap(),
+            sid
+        );
+    }
+}
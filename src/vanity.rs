@@ -0,0 +1,83 @@
+//! Vanity session ID generation.
+//!
+//! Grinds random keypairs until the ID matches a predicate. Every base64 character
+//! of a prefix multiplies the expected work by 64, so 4-5 characters is the practical limit.
+use crate::errors::Result;
+use crate::{new_session_id_pair, ISessionIdPair, SessionId, SessionIdPair};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+/// Generate a keypair whose ID satisfies `predicate`, using `parallelism` threads.
+/// Blocks until a match is found.
+pub fn generate_vanity<F>(predicate: F, parallelism: usize) -> Result<SessionIdPair>
+where
+    F: Fn(&SessionId) -> bool + Sync,
+{
+    let cancel = AtomicBool::new(false);
+    Ok(generate_vanity_cancellable(predicate, parallelism, &cancel)?.expect("never cancelled"))
+}
+
+/// Like [`generate_vanity`], but returns `None` once `cancel` is set
+pub fn generate_vanity_cancellable<F>(
+    predicate: F,
+    parallelism: usize,
+    cancel: &AtomicBool,
+) -> Result<Option<SessionIdPair>>
+where
+    F: Fn(&SessionId) -> bool + Sync,
+{
+    let done = AtomicBool::new(false);
+    let found: Mutex<Option<Result<SessionIdPair>>> = Mutex::new(None);
+    let worker = || {
+        while !done.load(Ordering::Relaxed) && !cancel.load(Ordering::Relaxed) {
+            match new_session_id_pair() {
+                Ok(pair) if !predicate(&pair.get_id()) => continue,
+                res => {
+                    let mut found = found.lock().unwrap();
+                    if found.is_none() {
+                        *found = Some(res);
+                    }
+                    done.store(true, Ordering::Relaxed);
+                }
+            }
+        }
+    };
+    if parallelism <= 1 {
+        worker();
+    } else {
+        std::thread::scope(|s| {
+            for _ in 0..parallelism {
+                s.spawn(worker);
+            }
+        });
+    }
+    found.into_inner().unwrap().transpose()
+}
+
+/// Predicate matching IDs whose string form (base64) starts with `prefix`
+///
+/// There is no base58 counterpart: the crate never renders a SessionId in base58.
+/// The only base58 form it touches is the libp2p PeerId, whose first eight characters
+/// (`12D3KooW`) are fixed by the multihash header. To grind on that, pass a closure
+/// over `SessionId::to_libp2p_peer_id` (feature `libp2p`) to [`generate_vanity`].
+pub fn base64_prefix(prefix: &str) -> impl Fn(&SessionId) -> bool + Sync + '_ {
+    move |id| id.to_string().starts_with(prefix)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_vanity() {
+        let pair = generate_vanity(base64_prefix("A"), 2).unwrap();
+        assert!(pair.get_id().to_string().starts_with('A'));
+        let pair = generate_vanity(|id| id.as_raw()[0] == 0x42, 1).unwrap();
+        assert_eq!(pair.get_id().as_raw()[0], 0x42);
+
+        let cancel = AtomicBool::new(true);
+        assert!(generate_vanity_cancellable(|_| false, 4, &cancel)
+            .unwrap()
+            .is_none());
+    }
+}
@@ -0,0 +1,148 @@
+use crate::errors::Result;
+use crate::session_id_pair::{prehash, verify_prehashed};
+use crate::{SessionId, SignatureSet, SIGNATURE_SIZE};
+use ed25519_dalek::Digest;
+use lru::LruCache;
+use std::num::NonZeroUsize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+#[derive(Eq, PartialEq, Hash)]
+struct CacheKey {
+    session_id: SessionId,
+    // SHA-512 of salt and payload, which is what the signature covers.
+    payload_hash: [u8; 64],
+    signature: [u8; SIGNATURE_SIZE],
+}
+
+/// LRU cache of successful signature verifications.
+/// Re-verifying an identical signed message skips the curve math.
+/// Failed verifications are never cached.
+pub struct VerifyCache {
+    cache: Mutex<LruCache<CacheKey, ()>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl VerifyCache {
+    /// Create a cache holding at most `capacity` entries
+    pub fn new(capacity: usize) -> Self {
+        VerifyCache {
+            cache: Mutex::new(LruCache::new(
+                NonZeroUsize::new(capacity).unwrap_or(NonZeroUsize::MIN),
+            )),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+    /// Verify signature, consulting the cache first
+    pub fn verify<P>(&self, session_id: &SessionId, payload: P, sigset: &SignatureSet) -> Result<()>
+    where
+        P: IntoIterator,
+        P::Item: AsRef<[u8]>,
+    {
+        let hasher = prehash(&sigset.salt, payload);
+        let mut payload_hash = [0u8; 64];
+        payload_hash.copy_from_slice(&hasher.clone().finalize());
+        let key = CacheKey {
+            session_id: *session_id,
+            payload_hash,
+            signature: sigset.signature,
+        };
+        if self.cache.lock().unwrap().get(&key).is_some() {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return Ok(());
+        }
+        self.misses.fetch_add(1, Ordering::Relaxed);
+
+        verify_prehashed(session_id, hasher, sigset)?;
+        self.cache.lock().unwrap().put(key, ());
+        Ok(())
+    }
+    /// Number of verifications answered from the cache
+    pub fn hits(&self) -> u64 {
+        self.hits.load(Ordering::Relaxed)
+    }
+    /// Number of verifications that required the curve math
+    pub fn misses(&self) -> u64 {
+        self.misses.load(Ordering::Relaxed)
+    }
+    /// Number of cached entries
+    pub fn len(&self) -> usize {
+        self.cache.lock().unwrap().len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    /// Remove all entries and reset the counters
+    pub fn clear(&self) {
+        self.cache.lock().unwrap().clear();
+        self.hits.store(0, Ordering::Relaxed);
+        self.misses.store(0, Ordering::Relaxed);
+    }
+}
+
+impl Default for VerifyCache {
+    fn default() -> Self {
+        VerifyCache::new(1024)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{new_session_id_pair, ISessionIdPair};
+
+    #[test]
+    fn test_verify_cache() {
+        let cache = VerifyCache::new(2);
+        let kp = new_session_id_pair().unwrap();
+        let sid = kp.get_id();
+        let ss = kp
+            .sign(vec!["1234".as_bytes(), "testdata".as_bytes()])
+            .unwrap();
+
+        assert!(cache
+            .verify(&sid, vec!["1234".as_bytes(), "testdata".as_bytes()], &ss)
+            .is_ok());
+        assert_eq!((cache.hits(), cache.misses()), (0, 1));
+        assert!(cache
+            .verify(&sid, vec!["1234".as_bytes(), "testdata".as_bytes()], &ss)
+            .is_ok());
+        assert_eq!((cache.hits(), cache.misses()), (1, 1));
+        assert_eq!(cache.len(), 1);
+
+        // failures are not cached
+        assert!(cache
+            .verify(&sid, vec!["0234".as_bytes(), "testdata".as_bytes()], &ss)
+            .is_err());
+        assert!(cache
+            .verify(&sid, vec!["0234".as_bytes(), "testdata".as_bytes()], &ss)
+            .is_err());
+        assert_eq!((cache.hits(), cache.misses()), (1, 3));
+        assert_eq!(cache.len(), 1);
+
+        let other = new_session_id_pair().unwrap().get_id();
+        assert!(cache
+            .verify(&other, vec!["1234".as_bytes(), "testdata".as_bytes()], &ss)
+            .is_err());
+
+        cache.clear();
+        assert!(cache.is_empty());
+        assert_eq!((cache.hits(), cache.misses()), (0, 0));
+    }
+
+    #[test]
+    fn test_verify_cache_eviction() {
+        let cache = VerifyCache::new(1);
+        let kp = new_session_id_pair().unwrap();
+        let sid = kp.get_id();
+        let ss0 = kp.sign(vec!["a".as_bytes()]).unwrap();
+        let ss1 = kp.sign(vec!["b".as_bytes()]).unwrap();
+
+        assert!(cache.verify(&sid, vec!["a".as_bytes()], &ss0).is_ok());
+        assert!(cache.verify(&sid, vec!["b".as_bytes()], &ss1).is_ok());
+        assert!(cache.verify(&sid, vec!["a".as_bytes()], &ss0).is_ok());
+        assert_eq!((cache.hits(), cache.misses()), (0, 3));
+    }
+}
@@ -0,0 +1,362 @@
+//! Versioned signature wire format with an optional metadata header.
+//!
+//! Version 1 is the legacy 72-byte [`SignatureSet`]. Version 2 prefixes it with a
+//! version byte, a flags byte and the present optional fields:
+//!
+//! | field | size |
+//! |-------|------|
+//! | version (`2`) | 1 |
+//! | flags | 1 |
+//! | algorithm id | 1, if flag `0x01` |
+//! | timestamp (seconds since UNIX epoch, LE) | 8, if flag `0x02` |
+//! | context label (u16 LE length + UTF-8) | 2 + n, if flag `0x04` |
+//! | signature set | 72 |
+//!
+//! A version 2 encoding is never 72 bytes long, so the length tells the versions apart.
+//! The header is covered by the signature.
+use crate::encoding::Reader;
+use crate::errors::{Result, SessionIdError};
+use crate::time::{Clock, SystemClock};
+use crate::{
+    ISessionIdPair, SessionId, SessionIdPair, SessionIdPublic, SignatureSet, SIGNATURE_SET_SIZE,
+};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+
+const VERSIONED_SIGNATURE_CONTEXT: &[u8] = b"verse-session-id/signature/v2";
+const VERSION_2: u8 = 2;
+const FLAG_ALGORITHM: u8 = 0x01;
+const FLAG_TIMESTAMP: u8 = 0x02;
+const FLAG_CONTEXT: u8 = 0x04;
+
+fn format_error(msg: &str) -> SessionIdError {
+    SessionIdError::InvalidFormat(format!("signature: {}", msg))
+}
+
+/// Signature algorithm identifier
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub enum SignatureAlgorithm {
+    /// ED25519 over the salted payload, as produced by [`ISessionIdPair::sign`]
+    Ed25519Salted,
+}
+
+impl SignatureAlgorithm {
+    pub fn id(&self) -> u8 {
+        match self {
+            SignatureAlgorithm::Ed25519Salted => 1,
+        }
+    }
+    pub fn from_id(id: u8) -> Result<Self> {
+        match id {
+            1 => Ok(SignatureAlgorithm::Ed25519Salted),
+            _ => Err(format_error("unknown algorithm")),
+        }
+    }
+}
+
+/// Optional fields of a version 2 signature.
+/// Always encodable: the context length is checked by [`with_context`](Self::with_context).
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct SignatureMetadata {
+    algorithm: Option<SignatureAlgorithm>,
+    timestamp: Option<u64>,
+    context: Option<String>,
+}
+
+impl SignatureMetadata {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn algorithm(&self) -> Option<SignatureAlgorithm> {
+        self.algorithm
+    }
+    /// Signing time (seconds since UNIX epoch)
+    pub fn timestamp(&self) -> Option<u64> {
+        self.timestamp
+    }
+    /// Application defined label of what was signed
+    pub fn context(&self) -> Option<&str> {
+        self.context.as_deref()
+    }
+    pub fn with_algorithm(mut self, algorithm: SignatureAlgorithm) -> Self {
+        self.algorithm = Some(algorithm);
+        self
+    }
+    pub fn with_timestamp(mut self, timestamp: u64) -> Self {
+        self.timestamp = Some(timestamp);
+        self
+    }
+    /// Timestamp of the current time
+    pub fn timestamped(self) -> Self {
+        self.timestamped_at(&SystemClock)
+    }
+    /// Timestamp of the time of `clock`
+    pub fn timestamped_at(self, clock: &dyn Clock) -> Self {
+        self.with_timestamp(clock.now())
+    }
+    /// Fails when `context` is longer than `u16::MAX` bytes
+    pub fn with_context(mut self, context: &str) -> Result<Self> {
+        if context.len() > u16::MAX as usize {
+            return Err(SessionIdError::InvalidArgument("context too long"));
+        }
+        self.context = Some(context.to_string());
+        Ok(self)
+    }
+
+    fn header(&self) -> Vec<u8> {
+        let mut buf = vec![VERSION_2, 0];
+        if let Some(algorithm) = self.algorithm {
+            buf[1] |= FLAG_ALGORITHM;
+            buf.push(algorithm.id());
+        }
+        if let Some(timestamp) = self.timestamp {
+            buf[1] |= FLAG_TIMESTAMP;
+            buf.extend_from_slice(&timestamp.to_le_bytes());
+        }
+        if let Some(context) = &self.context {
+            buf[1] |= FLAG_CONTEXT;
+            // at most u16::MAX bytes, see `with_context`
+            buf.extend_from_slice(&(context.len() as u16).to_le_bytes());
+            buf.extend_from_slice(context.as_bytes());
+        }
+        buf
+    }
+    fn read_header(r: &mut Reader) -> Result<Self> {
+        if r.read(1)?[0] != VERSION_2 {
+            return Err(format_error("unsupported version"));
+        }
+        let flags = r.read(1)?[0];
+        if flags & !(FLAG_ALGORITHM | FLAG_TIMESTAMP | FLAG_CONTEXT) != 0 {
+            return Err(format_error("unknown flags"));
+        }
+        let mut metadata = SignatureMetadata::new();
+        if flags & FLAG_ALGORITHM != 0 {
+            metadata.algorithm = Some(SignatureAlgorithm::from_id(r.read(1)?[0])?);
+        }
+        if flags & FLAG_TIMESTAMP != 0 {
+            metadata.timestamp = Some(u64::from_le_bytes(r.read_array()?));
+        }
+        if flags & FLAG_CONTEXT != 0 {
+            metadata.context = Some(r.read_str()?);
+        }
+        Ok(metadata)
+    }
+}
+
+/// Signature in either wire format version
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub enum VersionedSignatureSet {
+    /// Legacy 72-byte signature set over the bare payload
+    V1(SignatureSet),
+    /// Signature set over the metadata header and the payload
+    V2 {
+        metadata: SignatureMetadata,
+        signature: SignatureSet,
+    },
+}
+
+impl VersionedSignatureSet {
+    /// Sign `payload` in the version 2 format
+    pub fn sign<P>(pair: &SessionIdPair, metadata: SignatureMetadata, payload: P) -> Result<Self>
+    where
+        P: IntoIterator,
+        P::Item: AsRef<[u8]>,
+    {
+        let header = metadata.header();
+        let signature = pair.sign(signed_payload(&header, payload))?;
+        Ok(VersionedSignatureSet::V2 {
+            metadata,
+            signature,
+        })
+    }
+    pub fn verify<P>(&self, session_id: &SessionId, payload: P) -> Result<()>
+    where
+        P: IntoIterator,
+        P::Item: AsRef<[u8]>,
+    {
+        match self {
+            VersionedSignatureSet::V1(signature) => session_id.verify(payload, signature),
+            VersionedSignatureSet::V2 {
+                metadata,
+                signature,
+            } => {
+                let header = metadata.header();
+                session_id.verify(signed_payload(&header, payload), signature)
+            }
+        }
+    }
+    pub fn version(&self) -> u8 {
+        match self {
+            VersionedSignatureSet::V1(_) => 1,
+            VersionedSignatureSet::V2 { .. } => VERSION_2,
+        }
+    }
+    /// `None` for version 1
+    pub fn metadata(&self) -> Option<&SignatureMetadata> {
+        match self {
+            VersionedSignatureSet::V1(_) => None,
+            VersionedSignatureSet::V2 { metadata, .. } => Some(metadata),
+        }
+    }
+    pub fn signature_set(&self) -> &SignatureSet {
+        match self {
+            VersionedSignatureSet::V1(signature) => signature,
+            VersionedSignatureSet::V2 { signature, .. } => signature,
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = match self {
+            VersionedSignatureSet::V1(_) => Vec::with_capacity(SIGNATURE_SET_SIZE),
+            VersionedSignatureSet::V2 { metadata, .. } => metadata.header(),
+        };
+        buf.extend_from_slice(&self.signature_set().to_bytes());
+        buf
+    }
+    /// Parses both the legacy 72-byte form and version 2
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() == SIGNATURE_SET_SIZE {
+            return Ok(VersionedSignatureSet::V1(SignatureSet::try_from(bytes)?));
+        }
+        let mut r = Reader(bytes);
+        let metadata = SignatureMetadata::read_header(&mut r)?;
+        let signature = SignatureSet::from_bytes(&r.read_array::<SIGNATURE_SET_SIZE>()?);
+        if !r.0.is_empty() {
+            return Err(format_error("trailing bytes"));
+        }
+        Ok(VersionedSignatureSet::V2 {
+            metadata,
+            signature,
+        })
+    }
+}
+
+/// Item of the signed payload: the context and header, then the caller's parts
+enum Part<'a, T> {
+    Prefix(&'a [u8]),
+    Payload(T),
+}
+
+impl<T: AsRef<[u8]>> AsRef<[u8]> for Part<'_, T> {
+    fn as_ref(&self) -> &[u8] {
+        match self {
+            Part::Prefix(v) => v,
+            Part::Payload(v) => v.as_ref(),
+        }
+    }
+}
+
+fn signed_payload<P>(header: &[u8], payload: P) -> impl Iterator<Item = Part<'_, P::Item>>
+where
+    P: IntoIterator,
+    P::Item: AsRef<[u8]>,
+{
+    [VERSIONED_SIGNATURE_CONTEXT, header]
+        .into_iter()
+        .map(Part::Prefix)
+        .chain(payload.into_iter().map(Part::Payload))
+}
+
+impl From<SignatureSet> for VersionedSignatureSet {
+    fn from(v: SignatureSet) -> Self {
+        VersionedSignatureSet::V1(v)
+    }
+}
+impl TryFrom<&[u8]> for VersionedSignatureSet {
+    type Error = SessionIdError;
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        Self::from_bytes(value)
+    }
+}
+/// Base64, like [`SignatureSet`]
+impl fmt::Display for VersionedSignatureSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", base64::encode(self.to_bytes()))
+    }
+}
+impl std::str::FromStr for VersionedSignatureSet {
+    type Err = SessionIdError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_bytes(&base64::decode(s)?)
+    }
+}
+impl Serialize for VersionedSignatureSet {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+impl<'de> Deserialize<'de> for VersionedSignatureSet {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::new_session_id_pair;
+
+    #[test]
+    fn test_versioned_signature_set() {
+        let pair = new_session_id_pair().unwrap();
+        let id = pair.get_id();
+
+        // legacy signatures still parse and verify
+        let legacy = pair.sign(vec![b"data"]).unwrap();
+        let v1: VersionedSignatureSet = legacy.to_string().parse().unwrap();
+        assert_eq!(v1.version(), 1);
+        assert!(v1.metadata().is_none());
+        assert!(v1.verify(&id, vec![b"data"]).is_ok());
+
+        let metadata = SignatureMetadata::new()
+            .with_algorithm(SignatureAlgorithm::Ed25519Salted)
+            .with_timestamp(1_700_000_000)
+            .with_context("chat")
+            .unwrap();
+        let v2 = VersionedSignatureSet::sign(&pair, metadata.clone(), vec![b"data"]).unwrap();
+        assert_eq!(v2.to_bytes().len(), 1 + 1 + 1 + 8 + 2 + 4 + 72);
+        let parsed: VersionedSignatureSet = v2.to_string().parse().unwrap();
+        assert_eq!(parsed, v2);
+        assert_eq!(parsed.metadata(), Some(&metadata));
+        assert!(parsed.verify(&id, vec![b"data"]).is_ok());
+        assert!(parsed.verify(&id, [b"other"]).is_err());
+
+        // the header is signed
+        let tampered = VersionedSignatureSet::V2 {
+            metadata: metadata.with_timestamp(1),
+            signature: *parsed.signature_set(),
+        };
+        assert!(tampered.verify(&id, vec![b"data"]).is_err());
+        // a v2 signature is not a valid legacy one
+        assert!(VersionedSignatureSet::V1(*parsed.signature_set())
+            .verify(&id, vec![b"data"])
+            .is_err());
+
+        let empty = VersionedSignatureSet::sign(&pair, SignatureMetadata::new(), [b""; 0]).unwrap();
+        assert_eq!(empty.to_bytes().len(), 74);
+        let json = serde_json::to_string(&empty).unwrap();
+        assert_eq!(
+            serde_json::from_str::<VersionedSignatureSet>(&json).unwrap(),
+            empty
+        );
+
+        let mut bytes = empty.to_bytes();
+        bytes[1] = 0x80;
+        assert!(VersionedSignatureSet::from_bytes(&bytes).is_err());
+        assert!(VersionedSignatureSet::from_bytes(&bytes[..73]).is_err());
+
+        let long = "x".repeat(u16::MAX as usize);
+        let metadata = SignatureMetadata::new().with_context(&long).unwrap();
+        assert_eq!(metadata.context(), Some(long.as_str()));
+        let signed = VersionedSignatureSet::sign(&pair, metadata, [b"data"]).unwrap();
+        assert_eq!(
+            signed.to_string().parse::<VersionedSignatureSet>().unwrap(),
+            signed
+        );
+        assert!(SignatureMetadata::new()
+            .with_context(&(long + "x"))
+            .is_err());
+    }
+}
@@ -0,0 +1,205 @@
+//! Browser persistence of the keypair. Enabled with the `wasm` feature.
+//!
+//! Keys are stored in the `verse-session-id` IndexedDB database, falling back to
+//! `localStorage` where IndexedDB is unavailable (e.g. some private browsing modes).
+//! With a passphrase the stored secret is encrypted (see [`SecretSessionKey::encrypt_with_passphrase`]);
+//! otherwise it is the raw 32-byte seed, protected only by the browser's origin isolation.
+use crate::errors::{Result, SessionIdError};
+use crate::{SecretSessionKey, SessionIdPair, SessionIdPairSecret, SECRET_KEY_SIZE};
+use js_sys::{Promise, Uint8Array};
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{IdbDatabase, IdbOpenDbRequest, IdbRequest, IdbTransactionMode, Storage};
+use zeroize::Zeroizing;
+
+const DB_NAME: &str = "verse-session-id";
+const DB_VERSION: u32 = 1;
+const STORE_NAME: &str = "keys";
+const LOCAL_STORAGE_PREFIX: &str = "verse-session-id/";
+
+fn js_error(e: JsValue) -> SessionIdError {
+    SessionIdError::Keystore(format!("{:?}", e))
+}
+
+fn encode_secret(pair: &SessionIdPair, passphrase: Option<&str>) -> Result<Zeroizing<Vec<u8>>> {
+    let secret = pair.secret_key();
+    Ok(Zeroizing::new(match passphrase {
+        Some(passphrase) => secret.encrypt_with_passphrase(passphrase)?,
+        None => secret.expose_secret().to_vec(),
+    }))
+}
+
+fn decode_secret(stored: &[u8], passphrase: Option<&str>) -> Result<SessionIdPair> {
+    let secret = match passphrase {
+        Some(passphrase) if stored.len() != SECRET_KEY_SIZE => {
+            SecretSessionKey::decrypt_with_passphrase(stored, passphrase)?
+        }
+        None if stored.len() == SECRET_KEY_SIZE => {
+            let mut secret = SecretSessionKey::new([0; SECRET_KEY_SIZE]);
+            secret.expose_secret_mut().copy_from_slice(stored);
+            secret
+        }
+        Some(_) => {
+            return Err(SessionIdError::InvalidArgument(
+                "stored key is not encrypted",
+            ))
+        }
+        None => return Err(SessionIdError::Required),
+    };
+    secret.to_session_id_pair()
+}
+
+// resolves with `request.result` on success
+async fn wait(request: &IdbRequest) -> Result<JsValue> {
+    let promise = Promise::new(&mut |resolve, reject| {
+        let req = request.clone();
+        let on_success = Closure::once_into_js(move || {
+            let _ = resolve.call1(&JsValue::NULL, &req.result().unwrap_or(JsValue::UNDEFINED));
+        });
+        let req = request.clone();
+        let on_error = Closure::once_into_js(move || {
+            let error = req.error().ok().flatten().map(JsValue::from);
+            let _ = reject.call1(&JsValue::NULL, &error.unwrap_or(JsValue::UNDEFINED));
+        });
+        request.set_onsuccess(Some(on_success.unchecked_ref()));
+        request.set_onerror(Some(on_error.unchecked_ref()));
+    });
+    JsFuture::from(promise).await.map_err(js_error)
+}
+
+async fn open_db() -> Result<IdbDatabase> {
+    let factory = web_sys::window()
+        .ok_or(SessionIdError::Keystore("no window".to_string()))?
+        .indexed_db()
+        .map_err(js_error)?
+        .ok_or(SessionIdError::Keystore(
+            "IndexedDB is unavailable".to_string(),
+        ))?;
+    let request: IdbOpenDbRequest = factory
+        .open_with_u32(DB_NAME, DB_VERSION)
+        .map_err(js_error)?;
+    let req = request.clone();
+    let on_upgrade = Closure::once_into_js(move || {
+        if let Ok(db) = req.result().and_then(|v| v.dyn_into::<IdbDatabase>()) {
+            if !db.object_store_names().contains(STORE_NAME) {
+                let _ = db.create_object_store(STORE_NAME);
+            }
+        }
+    });
+    request.set_onupgradeneeded(Some(on_upgrade.unchecked_ref()));
+    wait(&request).await?.dyn_into().map_err(js_error)
+}
+
+fn local_storage() -> Result<Storage> {
+    web_sys::window()
+        .ok_or(SessionIdError::Keystore("no window".to_string()))?
+        .local_storage()
+        .map_err(js_error)?
+        .ok_or(SessionIdError::Keystore(
+            "localStorage is unavailable".to_string(),
+        ))
+}
+
+/// Store `pair` in `localStorage` under `name`, encrypted if `passphrase` is given
+pub fn persist_to_local_storage(
+    name: &str,
+    pair: &SessionIdPair,
+    passphrase: Option<&str>,
+) -> Result<()> {
+    let stored = encode_secret(pair, passphrase)?;
+    local_storage()?
+        .set_item(
+            &format!("{}{}", LOCAL_STORAGE_PREFIX, name),
+            &base64::encode(&*stored),
+        )
+        .map_err(js_error)
+}
+
+/// Keypair stored by [`persist_to_local_storage`]. `None` if there is none.
+pub fn load_from_local_storage(
+    name: &str,
+    passphrase: Option<&str>,
+) -> Result<Option<SessionIdPair>> {
+    let Some(stored) = local_storage()?
+        .get_item(&format!("{}{}", LOCAL_STORAGE_PREFIX, name))
+        .map_err(js_error)?
+    else {
+        return Ok(None);
+    };
+    let stored = Zeroizing::new(base64::decode(stored)?);
+    decode_secret(&stored, passphrase).map(Some)
+}
+
+/// Store `pair` in IndexedDB under `name`, encrypted if `passphrase` is given.
+/// Falls back to `localStorage` if IndexedDB cannot be opened.
+pub async fn persist_to_indexeddb(
+    name: &str,
+    pair: &SessionIdPair,
+    passphrase: Option<&str>,
+) -> Result<()> {
+    let db = match open_db().await {
+        Ok(db) => db,
+        Err(_) => return persist_to_local_storage(name, pair, passphrase),
+    };
+    let stored = encode_secret(pair, passphrase)?;
+    let request = db
+        .transaction_with_str_and_mode(STORE_NAME, IdbTransactionMode::Readwrite)
+        .and_then(|tx| tx.object_store(STORE_NAME))
+        .and_then(|store| {
+            store.put_with_key(&Uint8Array::from(&stored[..]), &JsValue::from_str(name))
+        })
+        .map_err(js_error)?;
+    wait(&request).await?;
+    db.close();
+    Ok(())
+}
+
+/// Keypair stored by [`persist_to_indexeddb`], looking in `localStorage` as well.
+/// `None` if there is none.
+pub async fn load_from_indexeddb(
+    name: &str,
+    passphrase: Option<&str>,
+) -> Result<Option<SessionIdPair>> {
+    let db = match open_db().await {
+        Ok(db) => db,
+        Err(_) => return load_from_local_storage(name, passphrase),
+    };
+    let request = db
+        .transaction_with_str(STORE_NAME)
+        .and_then(|tx| tx.object_store(STORE_NAME))
+        .and_then(|store| store.get(&JsValue::from_str(name)))
+        .map_err(js_error)?;
+    let value = wait(&request).await?;
+    db.close();
+    if value.is_undefined() {
+        return load_from_local_storage(name, passphrase);
+    }
+    let stored = Zeroizing::new(value.dyn_into::<Uint8Array>().map_err(js_error)?.to_vec());
+    decode_secret(&stored, passphrase).map(Some)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{new_session_id_pair, ISessionIdPair};
+
+    #[test]
+    fn test_stored_secret() {
+        let pair = new_session_id_pair().unwrap();
+        let plain = encode_secret(&pair, None).unwrap();
+        assert_eq!(plain.len(), SECRET_KEY_SIZE);
+        assert_eq!(decode_secret(&plain, None).unwrap().get_id(), pair.get_id());
+        assert!(decode_secret(&plain, Some("pw")).is_err());
+
+        let encrypted = pair.secret_key().encrypt_with_passphrase("pw").unwrap();
+        assert!(matches!(
+            decode_secret(&encrypted, None),
+            Err(SessionIdError::Required)
+        ));
+        assert_eq!(
+            decode_secret(&encrypted, Some("pw")).unwrap().get_id(),
+            pair.get_id()
+        );
+    }
+}
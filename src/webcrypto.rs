@@ -0,0 +1,216 @@
+//! Conversions to and from WebCrypto Ed25519 `CryptoKey`s. Enabled with the `wasm` feature.
+//!
+//! Public keys go through the `raw` format, private keys are imported as `pkcs8` and
+//! exported as `jwk`. [`WebCryptoSigner`] signs with a key that never leaves WebCrypto,
+//! producing plain Ed25519 signatures (see [`SessionId::verify_webcrypto`]).
+use crate::errors::{self, Result, SessionIdError, SignatureErrorKind};
+use crate::{
+    base64url, session_id_pair_from_bytes, ISessionIdPair, SecretSessionKey, SessionId,
+    SessionIdPair, SessionIdPairSecret, SECRET_KEY_SIZE, SIGNATURE_SIZE,
+};
+use js_sys::{Array, ArrayBuffer, Object, Promise, Reflect, Uint8Array};
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{Crypto, CryptoKey, SubtleCrypto};
+use zeroize::Zeroizing;
+
+const ALGORITHM: &str = "Ed25519";
+/// PKCS#8 `PrivateKeyInfo` header of an Ed25519 key (RFC 8410), followed by the seed
+const PKCS8_PREFIX: [u8; 16] = [
+    0x30, 0x2e, 0x02, 0x01, 0x00, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70, 0x04, 0x22, 0x04, 0x20,
+];
+
+fn js_error(e: JsValue) -> SessionIdError {
+    SessionIdError::InvalidFormat(format!("webcrypto: {:?}", e))
+}
+
+fn pkcs8_der(secret: &SecretSessionKey) -> Zeroizing<Vec<u8>> {
+    Zeroizing::new([&PKCS8_PREFIX[..], secret.expose_secret()].concat())
+}
+
+// `d` and `x` members of an OKP JWK
+fn pair_from_jwk(d: &str, x: &str) -> Result<SessionIdPair> {
+    let seed = Zeroizing::new(base64url::decode(d)?);
+    if seed.len() != SECRET_KEY_SIZE {
+        return Err(errors::invalid_length(SECRET_KEY_SIZE, seed.len()));
+    }
+    let mut bytes = Zeroizing::new(seed.to_vec());
+    bytes.extend_from_slice(&base64url::decode(x)?);
+    session_id_pair_from_bytes(&bytes)
+}
+
+// `crypto.subtle` of the window or worker
+fn subtle() -> Result<SubtleCrypto> {
+    let crypto: Crypto = Reflect::get(&js_sys::global(), &JsValue::from_str("crypto"))
+        .and_then(|v| v.dyn_into())
+        .map_err(js_error)?;
+    Ok(crypto.subtle())
+}
+
+async fn call(promise: std::result::Result<Promise, JsValue>) -> Result<JsValue> {
+    JsFuture::from(promise.map_err(js_error)?)
+        .await
+        .map_err(js_error)
+}
+
+fn usages(usages: &[&str]) -> Array {
+    usages.iter().map(|u| JsValue::from_str(u)).collect()
+}
+
+fn get_string(object: &JsValue, key: &str) -> Result<String> {
+    Reflect::get(object, &JsValue::from_str(key))
+        .map_err(js_error)?
+        .as_string()
+        .ok_or_else(|| SessionIdError::InvalidFormat(format!("webcrypto: jwk without {}", key)))
+}
+
+impl SessionId {
+    /// WebCrypto Ed25519 public key usable with `crypto.subtle.verify`
+    pub async fn to_crypto_key(&self) -> Result<CryptoKey> {
+        let data = Uint8Array::from(self.as_ref());
+        call(subtle()?.import_key_with_str("raw", &data, ALGORITHM, true, &usages(&["verify"])))
+            .await?
+            .dyn_into()
+            .map_err(js_error)
+    }
+    /// Session ID of a WebCrypto Ed25519 public key
+    pub async fn from_crypto_key(public_key: &CryptoKey) -> Result<SessionId> {
+        let raw: ArrayBuffer = call(subtle()?.export_key("raw", public_key))
+            .await?
+            .dyn_into()
+            .map_err(js_error)?;
+        SessionId::try_from(&Uint8Array::new(&raw).to_vec()[..])
+    }
+    /// Verify a plain Ed25519 signature, as created by [`WebCryptoSigner::sign`]
+    pub fn verify_webcrypto(&self, message: &[u8], signature: &[u8]) -> Result<()> {
+        let pk = ed25519_dalek::PublicKey::from_bytes(self.as_ref())
+            .map_err(errors::signature(SignatureErrorKind::MalformedPublicKey))?;
+        let signature = ed25519_dalek::Signature::from_bytes(signature)
+            .map_err(errors::signature(SignatureErrorKind::MalformedSignature))?;
+        pk.verify_strict(message, &signature)
+            .map_err(errors::signature(SignatureErrorKind::VerificationFailed))
+    }
+}
+
+/// WebCrypto Ed25519 private key of `pair`, usable with `crypto.subtle.sign`.
+/// With `extractable` false the secret cannot be read back from JS.
+pub async fn session_id_pair_to_crypto_key(
+    pair: &SessionIdPair,
+    extractable: bool,
+) -> Result<CryptoKey> {
+    let der = pkcs8_der(&pair.secret_key());
+    let data = Uint8Array::from(&der[..]);
+    let key = call(subtle()?.import_key_with_str(
+        "pkcs8",
+        &data,
+        ALGORITHM,
+        extractable,
+        &usages(&["sign"]),
+    ))
+    .await;
+    // do not leave the seed in the JS heap
+    data.fill(0, 0, data.length());
+    key?.dyn_into().map_err(js_error)
+}
+
+/// Keypair of an extractable WebCrypto Ed25519 private key
+pub async fn session_id_pair_from_crypto_key(private_key: &CryptoKey) -> Result<SessionIdPair> {
+    if !private_key.extractable() {
+        return Err(SessionIdError::InvalidArgument(
+            "crypto key is not extractable",
+        ));
+    }
+    let jwk = call(subtle()?.export_key("jwk", private_key)).await?;
+    let d = Zeroizing::new(get_string(&jwk, "d")?);
+    pair_from_jwk(&d, &get_string(&jwk, "x")?)
+}
+
+/// Signer backed by a WebCrypto private key, which may be non-extractable
+pub struct WebCryptoSigner {
+    private_key: CryptoKey,
+    session_id: SessionId,
+}
+
+impl WebCryptoSigner {
+    /// Signer of a key pair held by WebCrypto, e.g. from `crypto.subtle.generateKey`
+    pub async fn new(private_key: CryptoKey, public_key: &CryptoKey) -> Result<Self> {
+        Ok(WebCryptoSigner {
+            session_id: SessionId::from_crypto_key(public_key).await?,
+            private_key,
+        })
+    }
+    /// New non-extractable key pair generated by WebCrypto
+    pub async fn generate() -> Result<Self> {
+        let pair: Object =
+            call(subtle()?.generate_key_with_str(ALGORITHM, false, &usages(&["sign", "verify"])))
+                .await?
+                .dyn_into()
+                .map_err(js_error)?;
+        let get_key = |name: &str| -> Result<CryptoKey> {
+            Reflect::get(&pair, &JsValue::from_str(name))
+                .and_then(|v| v.dyn_into())
+                .map_err(js_error)
+        };
+        Self::new(get_key("privateKey")?, &get_key("publicKey")?).await
+    }
+    /// Move `pair` into WebCrypto as a non-extractable key
+    pub async fn from_session_id_pair(pair: &SessionIdPair) -> Result<Self> {
+        Ok(WebCryptoSigner {
+            private_key: session_id_pair_to_crypto_key(pair, false).await?,
+            session_id: pair.get_id(),
+        })
+    }
+    pub fn session_id(&self) -> SessionId {
+        self.session_id
+    }
+    pub fn crypto_key(&self) -> &CryptoKey {
+        &self.private_key
+    }
+    /// Plain Ed25519 signature of `message` by `crypto.subtle.sign`
+    pub async fn sign(&self, message: &[u8]) -> Result<[u8; SIGNATURE_SIZE]> {
+        let signature: ArrayBuffer =
+            call(subtle()?.sign_with_str_and_u8_array(ALGORITHM, &self.private_key, message))
+                .await?
+                .dyn_into()
+                .map_err(js_error)?;
+        let signature = Uint8Array::new(&signature).to_vec();
+        signature
+            .try_into()
+            .map_err(|_| SessionIdError::Signature(SignatureErrorKind::SigningFailed))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::new_session_id_pair;
+    use ed25519_dalek::Signer;
+
+    #[test]
+    fn test_webcrypto_key_encoding() {
+        let pair = new_session_id_pair().unwrap();
+        let der = pkcs8_der(&pair.secret_key());
+        assert_eq!(der.len(), 48);
+        // openssl genpkey -algorithm ed25519 -outform der
+        assert!(base64::encode(&der[..]).starts_with("MC4CAQAwBQYDK2VwBCIEI"));
+        assert_eq!(&der[16..], pair.secret.as_bytes());
+
+        let d = base64url::encode(pair.secret.as_bytes());
+        let x = base64url::encode(pair.get_id());
+        assert_eq!(pair_from_jwk(&d, &x).unwrap().get_id(), pair.get_id());
+        let other = base64url::encode(new_session_id_pair().unwrap().get_id());
+        assert!(pair_from_jwk(&d, &other).is_err());
+        assert!(pair_from_jwk(&x[..8], &x).is_err());
+
+        let signature = Signer::sign(&pair, b"data").to_bytes();
+        assert!(pair.get_id().verify_webcrypto(b"data", &signature).is_ok());
+        assert!(pair
+            .get_id()
+            .verify_webcrypto(b"other", &signature)
+            .is_err());
+        assert!(pair
+            .get_id()
+            .verify_webcrypto(b"data", &signature[1..])
+            .is_err());
+    }
+}
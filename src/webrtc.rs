@@ -0,0 +1,122 @@
+//! WebRTC identity binding.
+//!
+//! Signed statements tying a SessionId to its DTLS certificate fingerprint,
+//! so data channels can be authenticated against Verse identities,
+//! and signed SDP offers/answers, so the signaling server cannot tamper with them.
+use crate::errors::{Result, SessionIdError};
+use crate::{ISessionIdPair, SessionId, SessionIdPair, SessionIdPublic, SignatureSet};
+
+const DTLS_FINGERPRINT_CONTEXT: &[u8] = b"verse-session-id/dtls-fingerprint/v1";
+const SDP_CONTEXT: &[u8] = b"verse-session-id/sdp/v1";
+
+/// WebRTC signing with a SessionIdPair
+pub trait WebRtcSigner {
+    /// Sign the DTLS certificate fingerprint (raw digest bytes)
+    fn bind_dtls_fingerprint(&self, fingerprint: &[u8]) -> Result<SignatureSet>;
+    /// Sign an SDP offer/answer after [`canonicalize_sdp`]
+    fn sign_sdp(&self, sdp: &str) -> Result<SignatureSet>;
+}
+
+impl WebRtcSigner for SessionIdPair {
+    fn bind_dtls_fingerprint(&self, fingerprint: &[u8]) -> Result<SignatureSet> {
+        self.sign([DTLS_FINGERPRINT_CONTEXT, fingerprint])
+    }
+    fn sign_sdp(&self, sdp: &str) -> Result<SignatureSet> {
+        self.sign([SDP_CONTEXT, canonicalize_sdp(sdp).as_bytes()])
+    }
+}
+
+impl SessionId {
+    /// Verify that `fingerprint` was bound to this session ID
+    pub fn verify_dtls_fingerprint(&self, fingerprint: &[u8], sigset: &SignatureSet) -> Result<()> {
+        self.verify([DTLS_FINGERPRINT_CONTEXT, fingerprint], sigset)
+    }
+    /// Verify an SDP offer/answer signed by this session ID
+    pub fn verify_sdp(&self, sdp: &str, sigset: &SignatureSet) -> Result<()> {
+        self.verify([SDP_CONTEXT, canonicalize_sdp(sdp).as_bytes()], sigset)
+    }
+}
+
+/// Canonical form of an SDP blob: CRLF line endings, no surrounding whitespace
+/// on each line and no empty lines. Survives signaling paths that rewrite line endings.
+pub fn canonicalize_sdp(sdp: &str) -> String {
+    let mut buf = String::with_capacity(sdp.len() + 16);
+    for line in sdp.lines().map(str::trim).filter(|v| !v.is_empty()) {
+        buf.push_str(line);
+        buf.push_str("\r\n");
+    }
+    buf
+}
+
+/// Parse the value of an SDP `a=fingerprint` attribute (e.g. `sha-256 AB:CD:...`).
+/// Returns the hash function name (lowercase) and the digest bytes.
+pub fn parse_sdp_fingerprint(value: &str) -> Result<(String, Vec<u8>)> {
+    let invalid = || SessionIdError::InvalidFormat("sdp: invalid fingerprint".to_string());
+    let value = value.trim();
+    let value = value.strip_prefix("a=fingerprint:").unwrap_or(value);
+    let (hash, digest) = value.split_once(' ').ok_or_else(invalid)?;
+    let digest = digest
+        .trim()
+        .split(':')
+        .map(|v| match v.len() {
+            2 => u8::from_str_radix(v, 16).map_err(|_| invalid()),
+            _ => Err(invalid()),
+        })
+        .collect::<Result<Vec<u8>>>()?;
+    Ok((hash.to_ascii_lowercase(), digest))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::new_session_id_pair;
+
+    #[test]
+    fn test_dtls_fingerprint() {
+        let kp = new_session_id_pair().unwrap();
+        let (hash, fingerprint) = parse_sdp_fingerprint(
+            "sha-256 19:E2:1C:3B:4B:9F:81:E6:B8:5C:F4:A5:A8:D8:73:04:BB:05:2F:70:9F:04:A9:0E:05:E9:26:33:E8:70:88:A2",
+        )
+        .unwrap();
+        assert_eq!(hash, "sha-256");
+        assert_eq!(fingerprint.len(), 32);
+        assert_eq!(fingerprint[0], 0x19);
+
+        let sig = kp.bind_dtls_fingerprint(&fingerprint).unwrap();
+        assert!(kp
+            .get_id()
+            .verify_dtls_fingerprint(&fingerprint, &sig)
+            .is_ok());
+        let mut other = fingerprint.clone();
+        other[31] ^= 1;
+        assert!(kp.get_id().verify_dtls_fingerprint(&other, &sig).is_err());
+        // not interchangeable with a plain signature
+        let plain = kp.sign(vec![&fingerprint]).unwrap();
+        assert!(kp
+            .get_id()
+            .verify_dtls_fingerprint(&fingerprint, &plain)
+            .is_err());
+
+        assert!(parse_sdp_fingerprint("sha-256").is_err());
+        assert!(parse_sdp_fingerprint("sha-256 1:E2").is_err());
+        assert!(parse_sdp_fingerprint("a=fingerprint:SHA-256 AB:CD").is_ok());
+    }
+
+    #[test]
+    fn test_sdp() {
+        let kp = new_session_id_pair().unwrap();
+        let sdp = "v=0\r\no=- 4611731400430051336 2 IN IP4 127.0.0.1\r\ns=-\r\nt=0 0\r\nm=application 9 UDP/DTLS/SCTP webrtc-datachannel\r\na=fingerprint:sha-256 19:E2:1C\r\n";
+        let sig = kp.sign_sdp(sdp).unwrap();
+        assert!(kp.get_id().verify_sdp(sdp, &sig).is_ok());
+
+        // line ending changes are tolerated
+        let lf = sdp.replace("\r\n", "\n");
+        assert_eq!(canonicalize_sdp(&lf), sdp);
+        assert!(kp.get_id().verify_sdp(&lf, &sig).is_ok());
+
+        let tampered = sdp.replace("19:E2:1C", "19:E2:1D");
+        assert!(kp.get_id().verify_sdp(&tampered, &sig).is_err());
+        let other = new_session_id_pair().unwrap().get_id();
+        assert!(other.verify_sdp(sdp, &sig).is_err());
+    }
+}
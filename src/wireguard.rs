@@ -0,0 +1,77 @@
+//! WireGuard keys derived from session keys.
+//!
+//! WireGuard uses X25519 keys in standard base64, so a node's interface key is
+//! [`X25519KeyAgreement::to_x25519_secret`] and its peers' `PublicKey` entries follow from
+//! their SessionIds alone.
+use crate::errors::Result;
+use crate::{ISessionIdPair, SessionId, SessionIdPair, X25519KeyAgreement};
+use std::fmt;
+use zeroize::Zeroizing;
+
+impl SessionId {
+    /// WireGuard public key (`[Peer] PublicKey`)
+    pub fn to_wireguard_public_key(&self) -> Result<String> {
+        Ok(base64::encode(self.to_x25519_public()?))
+    }
+}
+
+/// Interface keys in WireGuard's base64 format. `Debug` does not print the private key.
+#[derive(Clone, Eq, PartialEq)]
+pub struct WireGuardKeys {
+    /// `[Interface] PrivateKey`
+    pub private_key: Zeroizing<String>,
+    pub public_key: String,
+}
+
+impl fmt::Debug for WireGuardKeys {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WireGuardKeys")
+            .field("private_key", &format_args!("<REDACTED>"))
+            .field("public_key", &self.public_key)
+            .finish()
+    }
+}
+
+/// Export of a SessionIdPair as WireGuard keys
+pub trait WireGuardKeyExport {
+    /// X25519 private and public key. Keep the private key as secret as the session key itself.
+    fn to_wireguard_keys(&self) -> Result<WireGuardKeys>;
+}
+
+impl WireGuardKeyExport for SessionIdPair {
+    fn to_wireguard_keys(&self) -> Result<WireGuardKeys> {
+        let secret = Zeroizing::new(self.to_x25519_secret());
+        Ok(WireGuardKeys {
+            private_key: Zeroizing::new(base64::encode(&secret[..])),
+            public_key: self.get_id().to_wireguard_public_key()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::new_session_id_pair;
+    use curve25519_dalek::constants::X25519_BASEPOINT;
+    use curve25519_dalek::scalar::Scalar;
+
+    #[test]
+    fn test_wireguard_keys() {
+        let pair = new_session_id_pair().unwrap();
+        let keys = pair.to_wireguard_keys().unwrap();
+        assert_eq!(keys.private_key.len(), 44);
+        assert_eq!(
+            keys.public_key,
+            pair.get_id().to_wireguard_public_key().unwrap()
+        );
+        assert!(!format!("{:?}", keys).contains(keys.private_key.as_str()));
+
+        // `wg pubkey` of the private key
+        let secret: [u8; 32] = base64::decode(keys.private_key.as_str())
+            .unwrap()
+            .try_into()
+            .unwrap();
+        let public = (X25519_BASEPOINT * Scalar::from_bits(secret)).to_bytes();
+        assert_eq!(base64::encode(public), keys.public_key);
+    }
+}
@@ -0,0 +1,223 @@
+//! WebSocket handshake authentication, as sans-io state machines.
+//!
+//! 1. The server sends a challenge frame carrying a random nonce.
+//! 2. The client replies with its SessionId and a SignatureSet over the server's SessionId
+//!    and the nonce, so the reply cannot be relayed to another server.
+//! 3. The server verifies the reply before the deadline.
+//!
+//! Frames are binary messages. Times are monotonic milliseconds from any epoch chosen
+//! by the caller, so the machines work with tungstenite as well as browser sockets.
+use crate::errors::{self, Result, SessionIdError};
+use crate::{
+    ISessionIdPair, SessionId, SessionIdPair, SessionIdPublic, SignatureSet, SESSION_ID_SIZE,
+    SIGNATURE_SET_SIZE,
+};
+
+const WS_AUTH_CONTEXT: &[u8] = b"verse-session-id/ws-auth/v1";
+const CHALLENGE_TAG: u8 = 1;
+const RESPONSE_TAG: u8 = 2;
+const NONCE_SIZE: usize = 32;
+/// Size of the challenge frame
+pub const WS_AUTH_CHALLENGE_SIZE: usize = 1 + NONCE_SIZE;
+/// Size of the response frame
+pub const WS_AUTH_RESPONSE_SIZE: usize = 1 + SESSION_ID_SIZE + SIGNATURE_SET_SIZE;
+
+fn unexpected_frame() -> SessionIdError {
+    SessionIdError::InvalidFormat("ws_auth: unexpected frame".to_string())
+}
+
+/// State of a [`WsAuthServer`]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum WsAuthState {
+    /// Waiting for the client's response
+    Pending,
+    /// The client proved ownership of the SessionId
+    Authenticated(SessionId),
+    /// Invalid response or deadline exceeded. The connection should be closed.
+    Failed,
+}
+
+/// Server side of the handshake
+#[derive(Clone, Debug)]
+pub struct WsAuthServer {
+    server_id: SessionId,
+    nonce: [u8; NONCE_SIZE],
+    deadline_ms: u64,
+    state: WsAuthState,
+}
+
+impl WsAuthServer {
+    /// Start a handshake for the server identified by `server_id`,
+    /// which must complete within `timeout_ms`
+    pub fn new(server_id: &SessionId, now_ms: u64, timeout_ms: u64) -> Result<Self> {
+        let mut nonce = [0u8; NONCE_SIZE];
+        getrandom::getrandom(&mut nonce)?;
+        Ok(WsAuthServer {
+            server_id: *server_id,
+            nonce,
+            deadline_ms: now_ms.saturating_add(timeout_ms),
+            state: WsAuthState::Pending,
+        })
+    }
+    /// Frame to send to the client
+    pub fn challenge_frame(&self) -> Vec<u8> {
+        let mut frame = Vec::with_capacity(WS_AUTH_CHALLENGE_SIZE);
+        frame.push(CHALLENGE_TAG);
+        frame.extend_from_slice(&self.nonce);
+        frame
+    }
+    /// Handle the client's response. Returns the authenticated SessionId.
+    /// Any error moves the server to [`WsAuthState::Failed`].
+    pub fn handle_frame(&mut self, frame: &[u8], now_ms: u64) -> Result<SessionId> {
+        if self.state != WsAuthState::Pending {
+            return Err(unexpected_frame());
+        }
+        let res = self.verify(frame, now_ms);
+        self.state = match res {
+            Ok(session_id) => WsAuthState::Authenticated(session_id),
+            Err(_) => WsAuthState::Failed,
+        };
+        res
+    }
+    fn verify(&self, frame: &[u8], now_ms: u64) -> Result<SessionId> {
+        if now_ms >= self.deadline_ms {
+            return Err(SessionIdError::Expired);
+        }
+        if frame.len() != WS_AUTH_RESPONSE_SIZE {
+            return Err(errors::invalid_length(WS_AUTH_RESPONSE_SIZE, frame.len()));
+        }
+        if frame[0] != RESPONSE_TAG {
+            return Err(unexpected_frame());
+        }
+        let (session_id, sig) = frame[1..].split_at(SESSION_ID_SIZE);
+        let session_id = SessionId::try_from(session_id)?;
+        let sig = SignatureSet::try_from(sig)?;
+        session_id.verify(
+            [WS_AUTH_CONTEXT, self.server_id.as_ref(), &self.nonce],
+            &sig,
+        )?;
+        Ok(session_id)
+    }
+    /// Fail the handshake if the deadline has passed. Returns true if it failed.
+    pub fn handle_timeout(&mut self, now_ms: u64) -> bool {
+        if self.state == WsAuthState::Pending && now_ms >= self.deadline_ms {
+            self.state = WsAuthState::Failed;
+        }
+        self.state == WsAuthState::Failed
+    }
+    /// When [`handle_timeout`](Self::handle_timeout) should be called
+    pub fn deadline_ms(&self) -> u64 {
+        self.deadline_ms
+    }
+    pub fn state(&self) -> WsAuthState {
+        self.state
+    }
+}
+
+/// Client side of the handshake
+pub struct WsAuthClient<'a> {
+    pair: &'a SessionIdPair,
+    server_id: SessionId,
+    responded: bool,
+}
+
+impl<'a> WsAuthClient<'a> {
+    /// Authenticate to the server expected to be `server_id`
+    pub fn new(pair: &'a SessionIdPair, server_id: &SessionId) -> Self {
+        WsAuthClient {
+            pair,
+            server_id: *server_id,
+            responded: false,
+        }
+    }
+    /// Handle the server's challenge. Returns the frame to send back.
+    pub fn handle_frame(&mut self, frame: &[u8]) -> Result<Vec<u8>> {
+        if self.responded || frame.first() != Some(&CHALLENGE_TAG) {
+            return Err(unexpected_frame());
+        }
+        if frame.len() != WS_AUTH_CHALLENGE_SIZE {
+            return Err(errors::invalid_length(WS_AUTH_CHALLENGE_SIZE, frame.len()));
+        }
+        let sig = self
+            .pair
+            .sign([WS_AUTH_CONTEXT, self.server_id.as_ref(), &frame[1..]])?;
+        self.responded = true;
+
+        let mut res = Vec::with_capacity(WS_AUTH_RESPONSE_SIZE);
+        res.push(RESPONSE_TAG);
+        res.extend_from_slice(self.pair.get_id().as_ref());
+        res.extend_from_slice(&sig.to_bytes());
+        Ok(res)
+    }
+    /// True once the challenge has been answered
+    pub fn is_responded(&self) -> bool {
+        self.responded
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::new_session_id_pair;
+
+    #[test]
+    fn test_ws_auth() {
+        let kp = new_session_id_pair().unwrap();
+        let server_id = new_session_id_pair().unwrap().get_id();
+        let mut server = WsAuthServer::new(&server_id, 1000, 5000).unwrap();
+        let mut client = WsAuthClient::new(&kp, &server_id);
+
+        let challenge = server.challenge_frame();
+        assert_eq!(challenge.len(), WS_AUTH_CHALLENGE_SIZE);
+        let response = client.handle_frame(&challenge).unwrap();
+        assert!(client.is_responded());
+        assert!(client.handle_frame(&challenge).is_err());
+
+        assert!(!server.handle_timeout(2000));
+        assert_eq!(server.handle_frame(&response, 2000).unwrap(), kp.get_id());
+        assert_eq!(server.state(), WsAuthState::Authenticated(kp.get_id()));
+        assert!(server.handle_frame(&response, 2000).is_err());
+        assert!(!server.handle_timeout(10000));
+    }
+
+    #[test]
+    fn test_ws_auth_failure() {
+        let kp = new_session_id_pair().unwrap();
+        let server_id = new_session_id_pair().unwrap().get_id();
+
+        // deadline
+        let mut server = WsAuthServer::new(&server_id, 1000, 5000).unwrap();
+        let response = WsAuthClient::new(&kp, &server_id)
+            .handle_frame(&server.challenge_frame())
+            .unwrap();
+        assert!(matches!(
+            server.handle_frame(&response, 6000),
+            Err(SessionIdError::Expired)
+        ));
+        assert_eq!(server.state(), WsAuthState::Failed);
+
+        let mut server = WsAuthServer::new(&server_id, 1000, 5000).unwrap();
+        assert!(server.handle_timeout(6000));
+        assert_eq!(server.state(), WsAuthState::Failed);
+
+        // replayed response to another challenge
+        let mut server = WsAuthServer::new(&server_id, 1000, 5000).unwrap();
+        assert!(server.handle_frame(&response, 2000).is_err());
+        assert_eq!(server.state(), WsAuthState::Failed);
+
+        // a malicious server relays another server's challenge
+        let other_id = new_session_id_pair().unwrap().get_id();
+        let mut other = WsAuthServer::new(&other_id, 1000, 5000).unwrap();
+        let relayed = WsAuthClient::new(&kp, &server_id)
+            .handle_frame(&other.challenge_frame())
+            .unwrap();
+        assert!(other.handle_frame(&relayed, 2000).is_err());
+        assert_eq!(other.state(), WsAuthState::Failed);
+
+        let mut server = WsAuthServer::new(&server_id, 1000, 5000).unwrap();
+        assert!(server.handle_frame(&[RESPONSE_TAG], 2000).is_err());
+        assert!(WsAuthClient::new(&kp, &server_id)
+            .handle_frame(&response)
+            .is_err());
+    }
+}
@@ -0,0 +1,82 @@
+//! X25519 keys derived from session keys.
+//!
+//! The secret is the clamped scalar of the Ed25519 secret key (SHA-512 of the seed),
+//! and the public key is the Montgomery form of the SessionId, as in libsodium's
+//! `crypto_sign_ed25519_*_to_curve25519`.
+use crate::errors::{Result, SessionIdError, SignatureErrorKind};
+use crate::{SessionId, SessionIdPair};
+use curve25519_dalek::edwards::CompressedEdwardsY;
+use curve25519_dalek::montgomery::MontgomeryPoint;
+use curve25519_dalek::scalar::Scalar;
+use ed25519_dalek::{Digest, Sha512};
+
+/// Size of X25519 keys and shared secrets
+pub const X25519_KEY_SIZE: usize = 32;
+
+const MALFORMED_PUBLIC_KEY: SessionIdError =
+    SessionIdError::Signature(SignatureErrorKind::MalformedPublicKey);
+
+impl SessionId {
+    /// X25519 public key of this session ID
+    pub fn to_x25519_public(&self) -> Result<[u8; X25519_KEY_SIZE]> {
+        let point = CompressedEdwardsY::from_slice(self.as_ref())
+            .decompress()
+            .ok_or(MALFORMED_PUBLIC_KEY)?;
+        Ok(point.to_montgomery().to_bytes())
+    }
+}
+
+/// X25519 key agreement with a SessionIdPair
+pub trait X25519KeyAgreement {
+    /// X25519 secret key. Keep it as secret as the session key itself.
+    fn to_x25519_secret(&self) -> [u8; X25519_KEY_SIZE];
+    /// X25519 shared secret with the owner of `their_id`
+    fn diffie_hellman(&self, their_id: &SessionId) -> Result<[u8; X25519_KEY_SIZE]>;
+}
+
+impl X25519KeyAgreement for SessionIdPair {
+    fn to_x25519_secret(&self) -> [u8; X25519_KEY_SIZE] {
+        let hash = Sha512::digest(self.secret.as_bytes());
+        let mut secret = [0u8; X25519_KEY_SIZE];
+        secret.copy_from_slice(&hash[..X25519_KEY_SIZE]);
+        secret[0] &= 248;
+        secret[31] &= 127;
+        secret[31] |= 64;
+        secret
+    }
+    fn diffie_hellman(&self, their_id: &SessionId) -> Result<[u8; X25519_KEY_SIZE]> {
+        let scalar = Scalar::from_bits(self.to_x25519_secret());
+        let shared = (MontgomeryPoint(their_id.to_x25519_public()?) * scalar).to_bytes();
+        // low order points
+        if shared == [0u8; X25519_KEY_SIZE] {
+            return Err(MALFORMED_PUBLIC_KEY);
+        }
+        Ok(shared)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{new_session_id_pair, ISessionIdPair};
+
+    #[test]
+    fn test_x25519() {
+        let a = new_session_id_pair().unwrap();
+        let b = new_session_id_pair().unwrap();
+        let public = (MontgomeryPoint(curve25519_dalek::constants::X25519_BASEPOINT.0)
+            * Scalar::from_bits(a.to_x25519_secret()))
+        .to_bytes();
+        assert_eq!(a.get_id().to_x25519_public().unwrap(), public);
+
+        let ab = a.diffie_hellman(&b.get_id()).unwrap();
+        let ba = b.diffie_hellman(&a.get_id()).unwrap();
+        assert_eq!(ab, ba);
+        assert_ne!(ab, a.diffie_hellman(&a.get_id()).unwrap());
+
+        // identity point
+        let mut identity = [0u8; 32];
+        identity[0] = 1;
+        assert!(a.diffie_hellman(&SessionId::from(identity)).is_err());
+    }
+}
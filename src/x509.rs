@@ -0,0 +1,212 @@
+//! Self-signed X.509 certificates of session keys. Enabled with the `x509` feature.
+//!
+//! The certificate's public key is the SessionId itself, so the same identity can
+//! terminate TLS or DTLS and peers can pin it. Encoded by hand as DER (RFC 5280, RFC 8410).
+use crate::base64url;
+use crate::der::{self, sequence, tlv, TAG_INTEGER, TAG_OCTET_STRING, TAG_OID};
+use crate::errors::{Result, SessionIdError};
+use crate::time::{Clock, SystemClock};
+use crate::{ISessionIdPair, SessionId, SessionIdPair};
+use ed25519_dalek::Signer;
+use std::net::IpAddr;
+
+/// id-at-commonName (2.5.4.3)
+const OID_COMMON_NAME: &[u8] = &[0x55, 0x04, 0x03];
+/// id-ce-subjectAltName (2.5.29.17)
+const OID_SUBJECT_ALT_NAME: &[u8] = &[0x55, 0x1d, 0x11];
+/// 9999-12-31T23:59:59Z, "no well-defined expiration date" (RFC 5280 4.1.2.5)
+const NO_EXPIRATION: u64 = 253402300799;
+
+/// DER encoded certificate and its PKCS#8 private key
+#[derive(Clone)]
+pub struct SelfSignedCertificate {
+    pub cert_der: Vec<u8>,
+    /// PKCS#8 `PrivateKeyInfo` (RFC 8410). Keep it as secret as the session key.
+    pub key_der: Vec<u8>,
+}
+
+/// X.509 certificate generation with a SessionIdPair
+pub trait X509Signer {
+    /// Certificate for `san` (DNS names or IP addresses), valid from an hour ago without expiration
+    fn to_self_signed_cert(&self, san: &[&str]) -> Result<SelfSignedCertificate> {
+        self.to_self_signed_cert_at(san, &SystemClock)
+    }
+    /// Like [`to_self_signed_cert`](Self::to_self_signed_cert), valid from an hour before the
+    /// time of `clock`
+    fn to_self_signed_cert_at(
+        &self,
+        san: &[&str],
+        clock: &dyn Clock,
+    ) -> Result<SelfSignedCertificate>;
+}
+
+// (year, month, day) of days since 1970-01-01
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    (yoe + era * 400 + i64::from(m <= 2), m, d)
+}
+
+fn time(unix: u64) -> Vec<u8> {
+    let secs = unix % 86400;
+    let (y, m, d) = civil_from_days((unix / 86400) as i64);
+    let hms = format!("{:02}{:02}{:02}Z", secs / 3600, secs / 60 % 60, secs % 60);
+    // UTCTime through 2049, GeneralizedTime after (RFC 5280 4.1.2.5)
+    if y < 2050 {
+        let s = format!("{:02}{:02}{:02}{}", y % 100, m, d, hms);
+        tlv(der::TAG_UTC_TIME, s.as_bytes())
+    } else {
+        let s = format!("{:04}{:02}{:02}{}", y, m, d, hms);
+        tlv(der::TAG_GENERALIZED_TIME, s.as_bytes())
+    }
+}
+
+fn subject_alt_name(san: &[&str]) -> Vec<u8> {
+    let names: Vec<u8> = san
+        .iter()
+        .flat_map(|name| match name.parse::<IpAddr>() {
+            // [7] iPAddress
+            Ok(IpAddr::V4(ip)) => tlv(0x87, &ip.octets()),
+            Ok(IpAddr::V6(ip)) => tlv(0x87, &ip.octets()),
+            // [2] dNSName
+            Err(_) => tlv(0x82, name.as_bytes()),
+        })
+        .collect();
+    sequence(&[
+        &tlv(TAG_OID, OID_SUBJECT_ALT_NAME),
+        &tlv(TAG_OCTET_STRING, &tlv(der::TAG_SEQUENCE, &names)),
+    ])
+}
+
+impl X509Signer for SessionIdPair {
+    fn to_self_signed_cert_at(
+        &self,
+        san: &[&str],
+        clock: &dyn Clock,
+    ) -> Result<SelfSignedCertificate> {
+        let id = self.get_id();
+        let mut serial = [0u8; 16];
+        getrandom::getrandom(&mut serial)?;
+        // positive and minimally encoded
+        serial[0] = (serial[0] & 0x7f) | 0x40;
+
+        let name = sequence(&[&tlv(
+            der::TAG_SET,
+            &sequence(&[
+                &tlv(TAG_OID, OID_COMMON_NAME),
+                &tlv(der::TAG_UTF8_STRING, base64url::encode(id).as_bytes()),
+            ]),
+        )]);
+        let mut items: Vec<Vec<u8>> = vec![
+            // [0] version v3
+            tlv(0xa0, &tlv(TAG_INTEGER, &[2])),
+            tlv(TAG_INTEGER, &serial),
+            der::ed25519_algorithm(),
+            name.clone(),
+            sequence(&[
+                &time(clock.now().saturating_sub(3600)),
+                &time(NO_EXPIRATION),
+            ]),
+            name,
+            id.to_spki_der(),
+        ];
+        if !san.is_empty() {
+            // [3] extensions
+            items.push(tlv(0xa3, &sequence(&[&subject_alt_name(san)])));
+        }
+        let items: Vec<&[u8]> = items.iter().map(|v| v.as_slice()).collect();
+        let tbs = sequence(&items);
+        let signature = Signer::sign(self, &tbs).to_bytes();
+        let cert_der = sequence(&[
+            &tbs,
+            &der::ed25519_algorithm(),
+            &der::bit_string(&signature),
+        ]);
+
+        let key_der = sequence(&[
+            &tlv(TAG_INTEGER, &[0]),
+            &der::ed25519_algorithm(),
+            &tlv(
+                TAG_OCTET_STRING,
+                &tlv(TAG_OCTET_STRING, self.secret.as_bytes()),
+            ),
+        ]);
+        Ok(SelfSignedCertificate { cert_der, key_der })
+    }
+}
+
+fn x509_error(msg: &str) -> SessionIdError {
+    SessionIdError::InvalidFormat(format!("x509: {}", msg))
+}
+
+impl SessionId {
+    /// SessionId of the Ed25519 key of a DER certificate. Does not check the signature.
+    pub fn from_x509_cert(cert_der: &[u8]) -> Result<SessionId> {
+        let truncated = || x509_error("truncated certificate");
+        let (_, cert, _) = der::read_tlv(cert_der).ok_or_else(truncated)?;
+        let (_, tbs, _) = der::read_tlv(cert).ok_or_else(truncated)?;
+        let mut rest = tbs;
+        // skip [0] version, serial, signature, issuer, validity and subject
+        let mut fields = 0;
+        while fields < 5 {
+            let (tag, _, next) = der::read_tlv(rest).ok_or_else(truncated)?;
+            if !(fields == 0 && tag == 0xa0) {
+                fields += 1;
+            }
+            rest = next;
+        }
+        let (_, spki, _) = der::read_tlv(rest).ok_or_else(truncated)?;
+        SessionId::from_spki_der(&der::tlv(der::TAG_SEQUENCE, spki))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::new_session_id_pair;
+
+    #[test]
+    fn test_time() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(19723), (2024, 1, 1));
+        assert_eq!(time(1709210096), tlv(der::TAG_UTC_TIME, b"240229123456Z"));
+        assert_eq!(
+            time(NO_EXPIRATION),
+            tlv(der::TAG_GENERALIZED_TIME, b"99991231235959Z")
+        );
+    }
+
+    #[test]
+    fn test_self_signed_cert() {
+        let pair = new_session_id_pair().unwrap();
+        let cert = pair
+            .to_self_signed_cert(&["node1.example.com", "127.0.0.1"])
+            .unwrap();
+        // the key is embedded verbatim
+        let spki = pair.get_id().to_spki_der();
+        assert!(cert.cert_der.windows(spki.len()).any(|w| w == spki));
+        assert_eq!(cert.key_der.len(), 48);
+        assert_eq!(&cert.key_der[16..], pair.secret.as_bytes());
+
+        assert_eq!(
+            SessionId::from_x509_cert(&cert.cert_der).unwrap(),
+            pair.get_id()
+        );
+        assert!(SessionId::from_x509_cert(&cert.cert_der[..100]).is_err());
+
+        // the signature covers the TBS certificate
+        let (_, body, rest) = der::read_tlv(&cert.cert_der).unwrap();
+        assert!(rest.is_empty());
+        let (_, _, after_tbs) = der::read_tlv(body).unwrap();
+        let tbs = &body[..body.len() - after_tbs.len()];
+        let signature = &cert.cert_der[cert.cert_der.len() - 64..];
+        let signature = ed25519_dalek::Signature::from_bytes(signature).unwrap();
+        assert!(pair.public.verify_strict(tbs, &signature).is_ok());
+    }
+}